@@ -1,17 +1,135 @@
-use crate::{Error, Result, Storage};
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, Error, GetReader, ObjectMeta, Page, PathId,
+    Presign, PresignMethod, PresignedRequest, PutOptions, Result, Storage, StoragePath,
+};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use futures::stream::{self, BoxStream, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::header::HeaderMap;
 use reqwest::{Client, StatusCode};
 use secrecy::{ExposeSecret, SecretString};
+use sha2::Sha256;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// Azure Blob Storage adapter using SAS token authentication.
+///
+/// Construct it with a SAS token issued elsewhere, or with a key to sign
+/// its own short-lived SAS tokens and [presign](Presign) URLs for single
+/// blobs: the storage account key, or a [`UserDelegationKey`] obtained with
+/// a Microsoft Entra ID token.
 #[derive(Clone, Debug)]
-pub struct AzureStorage {
+pub struct AzureStorage<Id = String> {
     client: Client,
     account: String,
     container: String,
-    sas_token: SecretString,
+    credential: Credential,
     base_url: String,
+    ids: PhantomData<fn() -> Id>,
+}
+
+/// How requests to Azure are authorized.
+#[derive(Clone, Debug)]
+enum Credential {
+    /// A SAS token, without the leading `?`, sent with every request.
+    SasToken(SecretString),
+    /// The Base64 storage account key, used to sign service SAS tokens.
+    AccountKey(SecretString),
+    /// A user delegation key, used to sign user delegation SAS tokens.
+    UserDelegation(UserDelegationKey),
+}
+
+/// A key from Get User Delegation Key that signs user delegation SAS tokens
+/// in place of the account key.
+///
+/// Tokens it signs act with the Entra ID identity that requested it, so
+/// that identity needs a role granting the blob permissions, and they stop
+/// working when the key expires or is revoked.
+#[derive(Clone, Debug)]
+pub struct UserDelegationKey {
+    object_id: String,
+    tenant_id: String,
+    start: String,
+    expiry: String,
+    service: String,
+    version: String,
+    /// Base64 key used to sign tokens.
+    value: SecretString,
+}
+
+impl UserDelegationKey {
+    /// Request a key valid until `expiry` (at most seven days ahead) from
+    /// the Blob service at `endpoint`, such as
+    /// `https://myaccount.blob.core.windows.net`, authorized by an Entra ID
+    /// `access_token` for `https://storage.azure.com/`.
+    pub async fn fetch(endpoint: &str, access_token: &str, expiry: SystemTime) -> Result<Self> {
+        let url = format!(
+            "{}/?restype=service&comp=userdelegationkey",
+            endpoint.trim_end_matches('/')
+        );
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+             <KeyInfo><Start>{}</Start><Expiry>{}</Expiry></KeyInfo>",
+            crate::time::format_iso8601(SystemTime::now()),
+            crate::time::format_iso8601(expiry)
+        );
+
+        let response = Client::new()
+            .post(&url)
+            .bearer_auth(access_token)
+            .header("x-ms-version", <AzureStorage>::SAS_VERSION)
+            .header("Content-Type", "application/xml")
+            .body(body)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after = crate::http::retry_after(response.headers());
+            let message = format!("Azure user delegation key request failed: {status}");
+            return Err(match status {
+                StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+                    Error::PermissionDenied(message)
+                }
+                _ => crate::http::status_error(status, retry_after, &url, message),
+            });
+        }
+
+        let xml = response.text().await.map_err(crate::http::request_error)?;
+        Self::from_xml(&xml)
+    }
+
+    /// Parse the body of a Get User Delegation Key response.
+    fn from_xml(xml: &str) -> Result<Self> {
+        let field = |tag: &str| {
+            xml_elements(xml, tag)
+                .first()
+                .map(|value| xml_unescape(value))
+                .ok_or_else(|| Error::Generic(format!("Azure user delegation key has no {tag}")))
+        };
+        let value = field("Value")?;
+        BASE64
+            .decode(&value)
+            .map_err(|_| Error::Generic("Azure user delegation key is not valid Base64".into()))?;
+        Ok(Self {
+            object_id: field("SignedOid")?,
+            tenant_id: field("SignedTid")?,
+            start: field("SignedStart")?,
+            expiry: field("SignedExpiry")?,
+            service: field("SignedService")?,
+            version: field("SignedVersion")?,
+            value: SecretString::from(value),
+        })
+    }
+
+    /// When the key, and every token signed with it, expires.
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        crate::time::parse_rfc3339(&self.expiry)
+    }
 }
 
 impl AzureStorage {
@@ -24,16 +142,13 @@ impl AzureStorage {
         sas_token: impl Into<String>,
     ) -> Self {
         let account = account.into();
-        let container = container.into();
-        let base_url = format!("https://{}.blob.core.windows.net/{}", account, container);
-
-        Self {
-            client: Client::new(),
+        let base_url = Self::default_endpoint(&account);
+        Self::build(
             account,
-            container,
-            sas_token: SecretString::from(sas_token.into()),
-            base_url,
-        }
+            container.into(),
+            Credential::SasToken(SecretString::from(sas_token.into())),
+            &base_url,
+        )
     }
 
     /// Create a new Azure Blob Storage adapter with custom endpoint (for emulators).
@@ -42,61 +157,527 @@ impl AzureStorage {
         container: impl Into<String>,
         sas_token: impl Into<String>,
         endpoint: impl Into<String>,
+    ) -> Self {
+        Self::build(
+            account.into(),
+            container.into(),
+            Credential::SasToken(SecretString::from(sas_token.into())),
+            &endpoint.into(),
+        )
+    }
+
+    /// Create a new Azure Blob Storage adapter that authorizes with the
+    /// storage account key, as shown in the Azure portal (Base64).
+    ///
+    /// The key never leaves the process: requests carry SAS tokens signed
+    /// with it, and [`presign`](Presign::presign) can hand out URLs scoped
+    /// to a single blob.
+    pub fn with_account_key(
+        account: impl Into<String>,
+        container: impl Into<String>,
+        account_key: impl AsRef<str>,
+    ) -> Result<Self> {
+        let account = account.into();
+        let base_url = Self::default_endpoint(&account);
+        Ok(Self::build(
+            account,
+            container.into(),
+            Self::account_key(account_key.as_ref())?,
+            &base_url,
+        ))
+    }
+
+    /// Like [`with_account_key`](Self::with_account_key), with a custom
+    /// endpoint (for emulators such as Azurite).
+    pub fn with_account_key_and_endpoint(
+        account: impl Into<String>,
+        container: impl Into<String>,
+        account_key: impl AsRef<str>,
+        endpoint: impl Into<String>,
+    ) -> Result<Self> {
+        Ok(Self::build(
+            account.into(),
+            container.into(),
+            Self::account_key(account_key.as_ref())?,
+            &endpoint.into(),
+        ))
+    }
+
+    /// Create a new Azure Blob Storage adapter that authorizes with a
+    /// [`UserDelegationKey`], for accounts where Entra ID is used instead of
+    /// the account key.
+    ///
+    /// Like an account key, it signs SAS tokens for requests and
+    /// [`presign`](Presign::presign); those stop working once the key
+    /// expires, so build a new adapter with a fresh key before then.
+    pub fn with_user_delegation_key(
+        account: impl Into<String>,
+        container: impl Into<String>,
+        key: UserDelegationKey,
     ) -> Self {
         let account = account.into();
-        let container = container.into();
-        let endpoint = endpoint.into();
+        let base_url = Self::default_endpoint(&account);
+        Self::build(
+            account,
+            container.into(),
+            Credential::UserDelegation(key),
+            &base_url,
+        )
+    }
+
+    /// Like [`with_user_delegation_key`](Self::with_user_delegation_key),
+    /// with a custom endpoint (for emulators such as Azurite).
+    pub fn with_user_delegation_key_and_endpoint(
+        account: impl Into<String>,
+        container: impl Into<String>,
+        key: UserDelegationKey,
+        endpoint: impl Into<String>,
+    ) -> Self {
+        Self::build(
+            account.into(),
+            container.into(),
+            Credential::UserDelegation(key),
+            &endpoint.into(),
+        )
+    }
+
+    /// Address items by [`StoragePath`] instead of `String`.
+    pub fn with_path_ids(self) -> AzureStorage<StoragePath> {
+        AzureStorage {
+            client: self.client,
+            account: self.account,
+            container: self.container,
+            credential: self.credential,
+            base_url: self.base_url,
+            ids: PhantomData,
+        }
+    }
+}
+
+impl<Id: PathId> AzureStorage<Id> {
+    fn default_endpoint(account: &str) -> String {
+        format!("https://{}.blob.core.windows.net", account)
+    }
+
+    fn build(account: String, container: String, credential: Credential, endpoint: &str) -> Self {
         let base_url = format!("{}/{}", endpoint.trim_end_matches('/'), container);
 
         Self {
             client: Client::new(),
             account,
             container,
-            sas_token: SecretString::from(sas_token.into()),
+            credential,
             base_url,
+            ids: PhantomData,
+        }
+    }
+
+    fn account_key(account_key: &str) -> Result<Credential> {
+        BASE64
+            .decode(account_key.trim())
+            .map(|_| Credential::AccountKey(SecretString::from(account_key.trim())))
+            .map_err(|_| Error::Generic("Azure account key is not valid Base64".to_string()))
+    }
+
+    /// SAS version used for tokens the adapter signs.
+    const SAS_VERSION: &str = "2022-11-02";
+
+    /// How long the SAS tokens the adapter signs for its own requests stay
+    /// valid.
+    const REQUEST_SAS_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+    /// Query string authorizing a request to the container.
+    fn sas_query(&self) -> Result<String> {
+        match &self.credential {
+            Credential::SasToken(token) => Ok(token.expose_secret().to_string()),
+            Credential::AccountKey(_) => self.sign_sas(
+                None,
+                "racwdl",
+                SystemTime::now() + Self::REQUEST_SAS_LIFETIME,
+            ),
+            Credential::UserDelegation(key) => {
+                // Tokens cannot outlive the key that signs them.
+                let mut expiry = SystemTime::now() + Self::REQUEST_SAS_LIFETIME;
+                if let Some(key_expiry) = key.expires_at() {
+                    expiry = expiry.min(key_expiry);
+                }
+                self.sign_sas(None, "racwdl", expiry)
+            }
+        }
+    }
+
+    /// Sign a SAS for `blob_name`, or for the whole container if `None`,
+    /// granting `permissions` until `expiry`: a service SAS with the account
+    /// key, or a user delegation SAS with a user delegation key.
+    fn sign_sas(
+        &self,
+        blob_name: Option<&str>,
+        permissions: &str,
+        expiry: SystemTime,
+    ) -> Result<String> {
+        let (key, delegation) = match &self.credential {
+            Credential::AccountKey(key) => (key, None),
+            Credential::UserDelegation(delegation) => (&delegation.value, Some(delegation)),
+            Credential::SasToken(_) => {
+                return Err(Error::Generic(
+                    "signing SAS tokens needs an Azure account key or user delegation key"
+                        .to_string(),
+                ));
+            }
+        };
+        let key = BASE64
+            .decode(key.expose_secret())
+            .map_err(|_| Error::Generic("Azure signing key is not valid Base64".to_string()))?;
+
+        let expiry = crate::time::format_iso8601(expiry);
+        // The signature covers the blob name itself, not its URL encoding.
+        let (resource, canonical) = match blob_name {
+            Some(blob) => (
+                "b",
+                format!("/blob/{}/{}/{}", self.account, self.container, blob),
+            ),
+            None => ("c", format!("/blob/{}/{}", self.account, self.container)),
+        };
+        // Emulators are usually served over plain HTTP.
+        let protocol = if self.base_url.starts_with("https://") {
+            "https"
+        } else {
+            "https,http"
+        };
+
+        let string_to_sign = match delegation {
+            None => Self::sas_string_to_sign(permissions, &expiry, &canonical, protocol, resource),
+            Some(delegation) => Self::user_delegation_string_to_sign(
+                permissions,
+                &expiry,
+                &canonical,
+                delegation,
+                protocol,
+                resource,
+            ),
+        };
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key)
+            .map_err(|e| Error::Generic(format!("invalid Azure signing key: {e}")))?;
+        mac.update(string_to_sign.as_bytes());
+        let signature = BASE64.encode(mac.finalize().into_bytes());
+
+        let mut sas = format!(
+            "sv={}&spr={}&se={}&sr={}&sp={}",
+            Self::SAS_VERSION,
+            urlencoding::encode(protocol),
+            urlencoding::encode(&expiry),
+            resource,
+            permissions,
+        );
+        if let Some(delegation) = delegation {
+            sas.push_str(&format!(
+                "&skoid={}&sktid={}&skt={}&ske={}&sks={}&skv={}",
+                urlencoding::encode(&delegation.object_id),
+                urlencoding::encode(&delegation.tenant_id),
+                urlencoding::encode(&delegation.start),
+                urlencoding::encode(&delegation.expiry),
+                urlencoding::encode(&delegation.service),
+                urlencoding::encode(&delegation.version),
+            ));
+        }
+        sas.push_str(&format!("&sig={}", urlencoding::encode(&signature)));
+        Ok(sas)
+    }
+
+    /// The string a service SAS signs, for SAS versions 2020-12-06 and later.
+    ///
+    /// Fields the adapter never sets (start time, stored access policy, IP
+    /// range, snapshot, encryption scope and response header overrides) are
+    /// left empty.
+    fn sas_string_to_sign(
+        permissions: &str,
+        expiry: &str,
+        canonical_resource: &str,
+        protocol: &str,
+        resource: &str,
+    ) -> String {
+        [
+            permissions,
+            "",
+            expiry,
+            canonical_resource,
+            "",
+            "",
+            protocol,
+            Self::SAS_VERSION,
+            resource,
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+        ]
+        .join("\n")
+    }
+
+    /// The string a user delegation SAS signs, for SAS versions 2020-12-06
+    /// and later.
+    ///
+    /// Fields the adapter never sets (start time, authorized and
+    /// unauthorized object IDs, correlation ID, IP range, snapshot,
+    /// encryption scope and response header overrides) are left empty.
+    fn user_delegation_string_to_sign(
+        permissions: &str,
+        expiry: &str,
+        canonical_resource: &str,
+        key: &UserDelegationKey,
+        protocol: &str,
+        resource: &str,
+    ) -> String {
+        [
+            permissions,
+            "",
+            expiry,
+            canonical_resource,
+            &key.object_id,
+            &key.tenant_id,
+            &key.start,
+            &key.expiry,
+            &key.service,
+            &key.version,
+            "",
+            "",
+            "",
+            "",
+            protocol,
+            Self::SAS_VERSION,
+            resource,
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+        ]
+        .join("\n")
+    }
+
+    /// Path of `blob` below the container URL, with each segment
+    /// percent-encoded.
+    fn blob_path(blob: &str) -> String {
+        blob.split('/')
+            .map(urlencoding::encode)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Name of the blob for item `id`, see [`StoragePath`].
+    /// URLs resolve `.` and `..` segments away, so names with them are
+    /// rejected.
+    fn blob_name(id: &str) -> Result<String> {
+        let name = crate::path::object_key(id)?;
+        if name
+            .split('/')
+            .any(|segment| segment == "." || segment == "..")
+        {
+            return Err(Error::InvalidId(format!(
+                "blob names cannot have dot segments: {id}"
+            )));
         }
+        Ok(name)
     }
 
-    fn blob_url(&self, blob_name: &str) -> String {
-        format!(
+    /// URL of the blob for item `id`.
+    fn blob_url(&self, id: &str) -> Result<String> {
+        Ok(format!(
             "{}/{}?{}",
             self.base_url,
-            blob_name,
-            self.sas_token.expose_secret()
-        )
+            Self::blob_path(&Self::blob_name(id)?),
+            self.sas_query()?
+        ))
+    }
+
+    /// Block size for uploads, and the most an upload holds in memory at once.
+    const BLOCK_SIZE: usize = 8 * 1024 * 1024;
+
+    /// Upload `first_block` followed by the rest of `input` as staged blocks,
+    /// then commit them with Put Block List.
+    ///
+    /// Blocks that are never committed are discarded by Azure after a week.
+    async fn put_blocks<R: AsyncRead + Send + Unpin>(
+        &self,
+        blob_name: &str,
+        mut input: R,
+        first_block: Vec<u8>,
+        options: &PutOptions,
+    ) -> Result<()> {
+        let url = self.blob_url(blob_name)?;
+        let mut block_ids = Vec::new();
+        let mut data = first_block;
+
+        loop {
+            // Block IDs must be valid Base64 of equal length; zero-padded
+            // decimal digits are both.
+            let block_id = format!("{:032}", block_ids.len());
+            let response = self
+                .client
+                .put(format!("{url}&comp=block&blockid={block_id}"))
+                .header("Content-Length", data.len().to_string())
+                .body(std::mem::take(&mut data))
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+            if !response.status().is_success() {
+                return Err(self.response_error(&response, blob_name));
+            }
+            block_ids.push(block_id);
+
+            if crate::io::fill_chunk(&mut input, &mut data, Self::BLOCK_SIZE).await? == 0 {
+                break;
+            }
+        }
+
+        let mut block_list = String::from(r#"<?xml version="1.0" encoding="utf-8"?><BlockList>"#);
+        for block_id in &block_ids {
+            block_list.push_str(&format!("<Latest>{block_id}</Latest>"));
+        }
+        block_list.push_str("</BlockList>");
+
+        let mut request = self
+            .client
+            .put(format!("{url}&comp=blocklist"))
+            .header("Content-Length", block_list.len().to_string())
+            .body(block_list);
+        request = Self::with_attributes(request, options);
+        if let Some(etag) = &options.if_match {
+            request = request.header("If-Match", etag);
+        }
+        if let Some(etag) = &options.if_none_match {
+            request = request.header("If-None-Match", etag);
+        }
+
+        let response = request.send().await.map_err(crate::http::request_error)?;
+
+        self.check_commit_status(&response, blob_name, options)
+    }
+
+    /// Add the content attributes in `options` to a request that commits a
+    /// blob.
+    fn with_attributes(
+        mut request: reqwest::RequestBuilder,
+        options: &PutOptions,
+    ) -> reqwest::RequestBuilder {
+        if let Some(content_type) = &options.content_type {
+            request = request.header("x-ms-blob-content-type", content_type);
+        }
+        if let Some(cache_control) = &options.cache_control {
+            request = request.header("x-ms-blob-cache-control", cache_control);
+        }
+        if let Some(disposition) = &options.content_disposition {
+            request = request.header("x-ms-blob-content-disposition", disposition);
+        }
+        for (key, value) in &options.user_metadata {
+            request = request.header(format!("x-ms-meta-{key}"), value);
+        }
+        request
+    }
+
+    /// Map the status of a request that commits a blob.
+    fn check_commit_status(
+        &self,
+        response: &reqwest::Response,
+        blob_name: &str,
+        options: &PutOptions,
+    ) -> Result<()> {
+        let status = response.status();
+        // `If-None-Match: *` on an existing blob is reported as 409 BlobAlreadyExists.
+        if status == StatusCode::CONFLICT && options.if_none_match.is_some() {
+            return Err(Error::PreconditionFailed(blob_name.to_string()));
+        }
+        if !status.is_success() {
+            return Err(self.response_error(response, blob_name));
+        }
+        Ok(())
+    }
+
+    /// Send a GET for blob `blob_name` and check the response status.
+    async fn download(&self, blob_name: &str) -> Result<reqwest::Response> {
+        let url = self.blob_url(blob_name)?;
+
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        if !response.status().is_success() {
+            return Err(self.response_error(&response, blob_name));
+        }
+        Ok(response)
     }
 
-    fn map_status_error(&self, status: StatusCode, blob_name: &str) -> Error {
-        match status {
-            StatusCode::NOT_FOUND => Error::NotFound(blob_name.to_string()),
-            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
-                Error::PermissionDenied("Azure authentication failed".to_string())
+    /// Error for a failed response about `blob_name`.
+    fn response_error(&self, response: &reqwest::Response, blob_name: &str) -> Error {
+        self.map_status_error(response.status(), response.headers(), blob_name)
+    }
+
+    /// Classify a failed status, using the `x-ms-error-code` header where it
+    /// is more specific than the status alone.
+    fn map_status_error(&self, status: StatusCode, headers: &HeaderMap, blob_name: &str) -> Error {
+        let retry_after = crate::http::retry_after(headers);
+        let code = headers
+            .get("x-ms-error-code")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        let message = match code {
+            "" => format!("Azure Blob Storage error: {status}"),
+            code => format!("Azure Blob Storage error: {status} ({code})"),
+        };
+        match code {
+            "BlobAlreadyExists" => Error::AlreadyExists(blob_name.to_string()),
+            "ServerBusy" => Error::RateLimited {
+                message,
+                retry_after,
+            },
+            "OperationTimedOut" => Error::Timeout(message),
+            "InvalidResourceName" | "OutOfRangeInput" => Error::InvalidId(blob_name.to_string()),
+            "AccountIsDisabled" | "InsufficientAccountPermissions" => {
+                Error::PermissionDenied(message)
             }
-            _ => Error::Generic(format!("Azure Blob Storage error: {}", status)),
+            _ => match status {
+                StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+                    Error::PermissionDenied("Azure authentication failed".to_string())
+                }
+                _ => crate::http::status_error(status, retry_after, blob_name, message),
+            },
         }
     }
 }
 
-impl Storage for AzureStorage {
-    type Id = String;
+impl<Id: PathId> Storage for AzureStorage<Id> {
+    type Id = Id;
 
     async fn exists(&self, id: &Self::Id) -> Result<bool> {
-        let url = self.blob_url(id);
+        let url = self.blob_url(id.as_ref())?;
 
         let response = self
             .client
             .head(&url)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         Ok(response.status().is_success())
     }
 
     async fn folder_exists(&self, id: &Self::Id) -> Result<bool> {
+        // The container itself is the root folder.
+        if id.as_ref().is_empty() || id.as_ref() == "/" {
+            return Ok(true);
+        }
+
         // In Azure Blob Storage, folders don't exist as entities - they're just prefixes
         // Check if any blobs exist with this prefix
-        let mut prefix = id.clone();
+        let mut prefix = Self::blob_name(id.as_ref())?;
         if !prefix.ends_with('/') {
             prefix.push('/');
         }
@@ -105,7 +686,7 @@ impl Storage for AzureStorage {
             "{}?restype=container&comp=list&prefix={}&maxresults=1&{}",
             self.base_url,
             urlencoding::encode(&prefix),
-            self.sas_token.expose_secret()
+            self.sas_query()?
         );
 
         let response = self
@@ -113,55 +694,107 @@ impl Storage for AzureStorage {
             .get(&url)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         if !response.status().is_success() {
-            return Err(self.map_status_error(response.status(), &prefix));
+            return Err(self.response_error(&response, &prefix));
         }
 
-        let body = response
-            .text()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+        let body = response.text().await.map_err(crate::http::request_error)?;
 
         // Check if the XML response contains any blobs
         Ok(body.contains("<Blob>"))
     }
 
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let url = self.blob_url(id.as_ref())?;
+
+        let response = self
+            .client
+            .head(&url)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        if !response.status().is_success() {
+            return Err(self.response_error(&response, id.as_ref()));
+        }
+
+        let headers = response.headers();
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+
+        // Custom metadata is returned as `x-ms-meta-<name>` headers.
+        let metadata = headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let key = name.as_str().strip_prefix("x-ms-meta-")?;
+                Some((key.to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+
+        Ok(ObjectMeta {
+            size: header("content-length")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            last_modified: header("last-modified")
+                .as_deref()
+                .and_then(crate::time::parse_http_date),
+            etag: header("etag"),
+            content_type: header("content-type"),
+            cache_control: header("cache-control"),
+            content_disposition: header("content-disposition"),
+            metadata,
+        })
+    }
+
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
         &self,
         id: Self::Id,
-        mut input: R,
+        input: R,
         len: Option<u64>,
     ) -> Result<()> {
-        let url = self.blob_url(&id);
+        self.put_with(id, input, len, PutOptions::default()).await
+    }
+
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        mut input: R,
+        _len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        let url = self.blob_url(id.as_ref())?;
 
-        // Read all data into memory
+        // Read one block; anything that fits goes up in a single Put Blob.
         let mut data = Vec::new();
-        tokio::io::copy(&mut input, &mut data)
-            .await
-            .map_err(|e| Error::Io(e))?;
+        if crate::io::fill_chunk(&mut input, &mut data, Self::BLOCK_SIZE).await? == Self::BLOCK_SIZE
+        {
+            return self.put_blocks(id.as_ref(), input, data, &options).await;
+        }
 
         let mut request = self
             .client
             .put(&url)
             .header("x-ms-blob-type", "BlockBlob")
+            .header("Content-Length", data.len().to_string())
             .body(data);
+        request = Self::with_attributes(request, &options);
 
-        if let Some(len) = len {
-            request = request.header("Content-Length", len.to_string());
+        if let Some(etag) = &options.if_match {
+            request = request.header("If-Match", etag);
         }
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
-
-        if !response.status().is_success() {
-            return Err(self.map_status_error(response.status(), &id));
+        if let Some(etag) = &options.if_none_match {
+            request = request.header("If-None-Match", etag);
         }
 
-        Ok(())
+        let response = request.send().await.map_err(crate::http::request_error)?;
+
+        self.check_commit_status(&response, id.as_ref(), &options)
     }
 
     async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
@@ -169,24 +802,13 @@ impl Storage for AzureStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
-        let url = self.blob_url(id);
-
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
-
-        if !response.status().is_success() {
-            return Err(self.map_status_error(response.status(), id));
-        }
+        let response = self.download(id.as_ref()).await?;
 
         let mut stream = response.bytes_stream();
         let mut total_bytes = 0u64;
 
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| Error::Connection(Box::new(e)))?;
+            let chunk = chunk.map_err(crate::http::request_error)?;
             output.write_all(&chunk).await?;
             total_bytes += chunk.len() as u64;
         }
@@ -195,35 +817,405 @@ impl Storage for AzureStorage {
         Ok(total_bytes)
     }
 
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        Ok(crate::io::response_reader(
+            self.download(id.as_ref()).await?,
+        ))
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        if range.is_empty() {
+            return Ok(0);
+        }
+
+        let url = self.blob_url(id.as_ref())?;
+
+        let response = self
+            .client
+            .get(&url)
+            .header("x-ms-range", crate::io::range_header(&range))
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        match response.status() {
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(0),
+            status if status.is_success() => {
+                crate::io::write_range_response(response, range, output).await
+            }
+            _ => Err(self.response_error(&response, id.as_ref())),
+        }
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        self.copy_blob(src.as_ref(), dst.as_ref()).await
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        // Blob Storage has no rename; copy server-side then remove the source.
+        self.copy_blob(src.as_ref(), dst.as_ref()).await?;
+        self.delete(src).await
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
-        let url = self.blob_url(id);
+        let url = self.blob_url(id.as_ref())?;
 
         let response = self
             .client
             .delete(&url)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         // 202 Accepted or 404 Not Found are both OK (idempotent delete)
         if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
             Ok(())
         } else {
-            Err(self.map_status_error(response.status(), id))
+            Err(self.response_error(&response, id.as_ref()))
+        }
+    }
+
+    async fn delete_many(&self, ids: Vec<Self::Id>) -> Result<BatchResult<Self::Id>> {
+        let mut result = BatchResult::new();
+        let mut ids_by_blob: std::collections::HashMap<String, Vec<Id>> =
+            std::collections::HashMap::new();
+        for id in ids {
+            match Self::blob_name(id.as_ref()) {
+                Ok(blob) => ids_by_blob.entry(blob).or_default().push(id),
+                Err(e) => result.failed.push((id, e)),
+            }
         }
+
+        // Report the outcome under the caller's ids, not the blob names.
+        let blobs: Vec<String> = ids_by_blob.keys().cloned().collect();
+        let mut deleted = BatchResult::new();
+        for batch in blobs.chunks(Self::DELETE_BATCH_SIZE) {
+            self.delete_batch(batch, &mut deleted).await;
+        }
+        for blob in deleted.succeeded {
+            result
+                .succeeded
+                .extend(ids_by_blob.remove(&blob).unwrap_or_default());
+        }
+        for (blob, error) in deleted.failed {
+            result.fail_all(ids_by_blob.remove(&blob).unwrap_or_default(), &error);
+        }
+        Ok(result)
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        // Folders are only blob name prefixes, so removing the blobs removes the folder.
+        let prefix = crate::delete_folder_prefix(folder.as_ref())?;
+        let entries = self.list_blobs(Some(&prefix)).await?;
+        let ids = entries.into_iter().map(|entry| entry.id).collect();
+        self.delete_many(ids).await?.into_result()
     }
 
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
-        let prefix_str = prefix.map(|s| s.as_str()).unwrap_or("");
+        let entries = self.list_blobs(prefix.map(AsRef::as_ref)).await?;
+        Ok(Box::pin(stream::iter(
+            entries.into_iter().map(|entry| Ok(entry.id)),
+        )))
+    }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        let entries = self.list_blobs(prefix.map(AsRef::as_ref)).await?;
+        Ok(Box::pin(stream::iter(entries.into_iter().map(Ok))))
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        // Azure caps a page at 5000 blobs.
+        let max_results = page_size.clamp(1, 5000);
+        let marker = continuation.map(|t| t.as_str().to_string());
+        let (entries, next) = self
+            .list_blobs_page(prefix.map(AsRef::as_ref), Some(max_results), marker)
+            .await?;
+
+        Ok(Page {
+            ids: entries.into_iter().map(|entry| entry.id).collect(),
+            next: next.map(ContinuationToken::new),
+        })
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        let prefix = crate::dir_prefix(folder.map(AsRef::as_ref));
+        let key_prefix = crate::path::object_prefix(&prefix);
+        let mut listing = DirListing::<Id>::default();
+        let mut marker: Option<String> = None;
+
+        loop {
+            let mut url = format!(
+                "{}?restype=container&comp=list&delimiter=%2F&{}",
+                self.base_url,
+                self.sas_query()?
+            );
+            if !key_prefix.is_empty() {
+                url.push_str(&format!("&prefix={}", urlencoding::encode(&key_prefix)));
+            }
+            if let Some(m) = &marker {
+                url.push_str(&format!("&marker={}", urlencoding::encode(m)));
+            }
+
+            let response = self
+                .client
+                .get(&url)
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            if !response.status().is_success() {
+                return Err(self.response_error(&response, &format!("list directory: {}", prefix)));
+            }
+
+            let body = response.text().await.map_err(crate::http::request_error)?;
+
+            for blob in xml_elements(&body, "Blob") {
+                if let Some(name) = xml_elements(blob, "Name").first() {
+                    listing
+                        .files
+                        .push(crate::path::object_id(&xml_unescape(name))?);
+                }
+            }
+            for blob_prefix in xml_elements(&body, "BlobPrefix") {
+                if let Some(name) = xml_elements(blob_prefix, "Name").first() {
+                    let name = xml_unescape(name);
+                    let folder = name.strip_suffix('/').unwrap_or(&name);
+                    if let Ok(id) = crate::path::object_id(folder) {
+                        listing.prefixes.push(id);
+                    }
+                }
+            }
+
+            marker = xml_elements(&body, "NextMarker")
+                .first()
+                .map(|m| xml_unescape(m))
+                .filter(|m| !m.is_empty());
+            if marker.is_none() {
+                break;
+            }
+        }
+
+        // Names with empty segments escape into names outside the folder,
+        // see `StoragePath`.
+        listing.files.retain(|id| id.as_ref().starts_with(&prefix));
+        listing
+            .prefixes
+            .retain(|id| id.as_ref().starts_with(&prefix));
+        Ok(listing.normalize())
+    }
+}
+
+impl<Id: PathId> AzureStorage<Id> {
+    /// Server-side copy using the Copy Blob operation.
+    ///
+    /// Copies within an account usually complete synchronously; otherwise the
+    /// destination is polled until the copy leaves the `pending` state.
+    async fn copy_blob(&self, src: &str, dst: &str) -> Result<()> {
+        let url = self.blob_url(dst)?;
+
+        let response = self
+            .client
+            .put(&url)
+            .header("x-ms-copy-source", self.blob_url(src)?)
+            .header("Content-Length", "0")
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Err(Error::NotFound(src.to_string()));
+        }
+        if !status.is_success() {
+            return Err(self.response_error(&response, dst));
+        }
+
+        let mut copy_status = Self::copy_status(&response);
+        while copy_status.as_deref() == Some("pending") {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+            let response = self
+                .client
+                .head(&url)
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            if !response.status().is_success() {
+                return Err(self.response_error(&response, dst));
+            }
+            copy_status = Self::copy_status(&response);
+        }
+
+        match copy_status.as_deref() {
+            None | Some("success") => Ok(()),
+            Some(other) => Err(Error::Generic(format!(
+                "Azure copy of {} to {} ended with status: {}",
+                src, dst, other
+            ))),
+        }
+    }
+
+    fn copy_status(response: &reqwest::Response) -> Option<String> {
+        response
+            .headers()
+            .get("x-ms-copy-status")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+    }
+
+    /// List every blob whose id starts with `prefix` with List Blobs,
+    /// following `NextMarker` across pages.
+    async fn list_blobs(&self, prefix: Option<&str>) -> Result<Vec<Entry<Id>>> {
+        let mut entries = Vec::new();
+        let mut marker: Option<String> = None;
 
-        // Build list blobs URL
+        loop {
+            let (page, next) = self.list_blobs_page(prefix, None, marker.take()).await?;
+            entries.extend(page);
+            match next {
+                Some(next) => marker = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Most subrequests a single Blob Batch request may carry.
+    const DELETE_BATCH_SIZE: usize = 256;
+
+    /// Delete `blobs` with one Blob Batch request, recording the outcome for
+    /// each blob.
+    async fn delete_batch(&self, blobs: &[String], result: &mut BatchResult<String>) {
+        // Subrequests address blobs by path, without the scheme and host.
+        let container_path = self
+            .base_url
+            .split_once("://")
+            .and_then(|(_, rest)| rest.find('/').map(|i| &rest[i..]))
+            .unwrap_or("");
+        let boundary = format!(
+            "batch_{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        );
+        let sas = match self.sas_query() {
+            Ok(sas) => sas,
+            Err(error) => return result.fail_all(blobs.iter().cloned(), &error),
+        };
+
+        let mut body = String::new();
+        for (i, blob) in blobs.iter().enumerate() {
+            body.push_str(&format!(
+                "--{boundary}\r\n\
+                 Content-Type: application/http\r\n\
+                 Content-Transfer-Encoding: binary\r\n\
+                 Content-ID: {i}\r\n\r\n\
+                 DELETE {container_path}/{}?{} HTTP/1.1\r\n\
+                 Content-Length: 0\r\n\r\n",
+                Self::blob_path(blob),
+                sas
+            ));
+        }
+        body.push_str(&format!("--{boundary}--\r\n"));
+
+        let url = format!("{}?restype=container&comp=batch&{sas}", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .header("x-ms-version", "2020-10-02")
+            .header(
+                "Content-Type",
+                format!("multipart/mixed; boundary={boundary}"),
+            )
+            .body(body)
+            .send()
+            .await;
+        let response = match response {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                let error = self.response_error(&response, "batch delete");
+                return result.fail_all(blobs.iter().cloned(), &error);
+            }
+            Err(e) => {
+                return result.fail_all(blobs.iter().cloned(), &crate::http::request_error(e));
+            }
+        };
+
+        let response_boundary = response
+            .headers()
+            .get("Content-Type")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split_once("boundary="))
+            .map(|(_, b)| b.trim_matches('"').to_string());
+        let text = match response.text().await {
+            Ok(text) => text,
+            Err(e) => {
+                return result.fail_all(blobs.iter().cloned(), &crate::http::request_error(e));
+            }
+        };
+
+        let mut statuses = response_boundary
+            .map(|b| parse_batch_statuses(&text, &b))
+            .unwrap_or_default();
+        for (i, blob) in blobs.iter().enumerate() {
+            match statuses.remove(&i) {
+                // 202 Accepted or 404 Not Found are both OK (idempotent delete)
+                Some(status) if status.is_success() || status == StatusCode::NOT_FOUND => {
+                    result.succeeded.push(blob.clone());
+                }
+                Some(status) => {
+                    result.failed.push((
+                        blob.clone(),
+                        self.map_status_error(status, &HeaderMap::new(), blob),
+                    ));
+                }
+                None => result.failed.push((
+                    blob.clone(),
+                    Error::Generic("missing from Azure batch response".to_string()),
+                )),
+            }
+        }
+    }
+
+    /// Fetch one page of List Blobs for the blobs whose ids start with
+    /// `prefix`, returning them and the marker of the next page, if any.
+    async fn list_blobs_page(
+        &self,
+        prefix: Option<&str>,
+        max_results: Option<usize>,
+        marker: Option<String>,
+    ) -> Result<(Vec<Entry<Id>>, Option<String>)> {
+        let prefix = prefix.unwrap_or("");
         let mut url = format!(
             "{}?restype=container&comp=list&{}",
             self.base_url,
-            self.sas_token.expose_secret()
+            self.sas_query()?
         );
-        if !prefix_str.is_empty() {
-            url.push_str(&format!("&prefix={}", urlencoding::encode(prefix_str)));
+        if !prefix.is_empty() {
+            let key_prefix = crate::path::object_prefix(prefix);
+            url.push_str(&format!("&prefix={}", urlencoding::encode(&key_prefix)));
+        }
+        if let Some(max) = max_results {
+            url.push_str(&format!("&maxresults={}", max));
+        }
+        if let Some(m) = &marker {
+            url.push_str(&format!("&marker={}", urlencoding::encode(m)));
         }
 
         let response = self
@@ -231,71 +1223,325 @@ impl Storage for AzureStorage {
             .get(&url)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         if !response.status().is_success() {
-            return Err(self.map_status_error(
-                response.status(),
-                &format!("list with prefix: {}", prefix_str),
-            ));
+            return Err(self.response_error(&response, &format!("list with prefix: {}", prefix)));
         }
 
-        let body = response
-            .text()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+        let body = response.text().await.map_err(crate::http::request_error)?;
+
+        let mut entries = Vec::new();
+        for blob in xml_elements(&body, "Blob") {
+            let property = |tag: &str| xml_elements(blob, tag).first().map(|v| xml_unescape(v));
+            let Some(name) = property("Name") else {
+                continue;
+            };
+            let id: Id = crate::path::object_id(&name)?;
+            if !id.as_ref().starts_with(prefix) {
+                continue;
+            }
+            entries.push(Entry {
+                id,
+                size: property("Content-Length")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+                modified: property("Last-Modified")
+                    .as_deref()
+                    .and_then(crate::time::parse_http_date),
+                etag: property("Etag"),
+                is_dir: false,
+            });
+        }
 
-        // Parse XML response
-        let blob_names = self.parse_list_response(&body)?;
+        let next = xml_elements(&body, "NextMarker")
+            .first()
+            .map(|m| xml_unescape(m))
+            .filter(|m| !m.is_empty());
 
-        Ok(Box::pin(stream::iter(blob_names.into_iter().map(Ok))))
+        Ok((entries, next))
     }
 }
 
-impl AzureStorage {
-    /// Parse Azure Blob Storage XML list response.
-    /// This is a simple parser - for production use, consider using a proper XML library.
-    fn parse_list_response(&self, xml: &str) -> Result<Vec<String>> {
-        let mut blob_names = Vec::new();
-
-        // Simple XML parsing - look for <Name>...</Name> tags within <Blob> sections
-        let mut in_blob = false;
-        let mut capturing_name = false;
-        let mut current_name = String::new();
-
-        for line in xml.lines() {
-            let trimmed = line.trim();
-
-            if trimmed.starts_with("<Blob>") {
-                in_blob = true;
-            } else if trimmed.starts_with("</Blob>") {
-                in_blob = false;
-            } else if in_blob {
-                if trimmed.starts_with("<Name>") {
-                    capturing_name = true;
-                    // Extract name between tags
-                    if let Some(start) = trimmed.find("<Name>") {
-                        if let Some(end) = trimmed.find("</Name>") {
-                            let name = &trimmed[start + 6..end];
-                            blob_names.push(name.to_string());
-                            capturing_name = false;
-                        } else {
-                            current_name = trimmed[start + 6..].to_string();
-                        }
-                    }
-                } else if capturing_name && trimmed.ends_with("</Name>") {
-                    if let Some(end) = trimmed.find("</Name>") {
-                        current_name.push_str(&trimmed[..end]);
-                        blob_names.push(current_name.clone());
-                        current_name.clear();
-                        capturing_name = false;
-                    }
-                } else if capturing_name {
-                    current_name.push_str(trimmed);
-                }
+/// Presigned URLs carry a SAS scoped to the one blob, so they need an adapter
+/// constructed with [`AzureStorage::with_account_key`] (service SAS) or
+/// [`AzureStorage::with_user_delegation_key`] (user delegation SAS); adapters
+/// holding a SAS token return [`Error::Unsupported`]. Uploads must send the
+/// returned `x-ms-blob-type` header and are limited to a single Put Blob
+/// (5000 MiB).
+impl<Id: PathId> Presign for AzureStorage<Id> {
+    async fn presign(
+        &self,
+        id: &Self::Id,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest> {
+        let blob = Self::blob_name(id.as_ref())?;
+        if matches!(self.credential, Credential::SasToken(_)) {
+            return Err(Error::Unsupported(
+                "presigning Azure URLs needs an adapter constructed with an account key \
+                 or user delegation key"
+                    .to_string(),
+            ));
+        }
+
+        let expires_at = SystemTime::now()
+            .checked_add(expires_in)
+            .ok_or_else(|| Error::Generic("invalid presign expiry".to_string()))?;
+        let (permissions, headers) = match method {
+            PresignMethod::Get => ("r", Vec::new()),
+            PresignMethod::Put => (
+                "cw",
+                vec![("x-ms-blob-type".to_string(), "BlockBlob".to_string())],
+            ),
+        };
+        let sas = self.sign_sas(Some(&blob), permissions, expires_at)?;
+
+        Ok(PresignedRequest {
+            method,
+            url: format!("{}/{}?{}", self.base_url, Self::blob_path(&blob), sas),
+            headers,
+            expires_at,
+        })
+    }
+}
+
+/// Status of each subresponse in a Blob Batch response, keyed by the
+/// `Content-ID` of its subrequest.
+fn parse_batch_statuses(
+    body: &str,
+    boundary: &str,
+) -> std::collections::HashMap<usize, StatusCode> {
+    let delimiter = format!("--{boundary}");
+    let mut statuses = std::collections::HashMap::new();
+    for part in body.split(delimiter.as_str()) {
+        let mut content_id = None;
+        let mut status = None;
+        for line in part.lines() {
+            let line = line.trim();
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("Content-ID")
+            {
+                content_id = value.trim().parse::<usize>().ok();
+            }
+            if status.is_none() && line.starts_with("HTTP/") {
+                status = line
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|code| code.parse::<u16>().ok())
+                    .and_then(|code| StatusCode::from_u16(code).ok());
             }
         }
+        if let (Some(id), Some(status)) = (content_id, status) {
+            statuses.insert(id, status);
+        }
+    }
+    statuses
+}
+
+/// Contents of every `<tag>...</tag>` element in `xml`, outermost first.
+///
+/// Enough for the flat List Blobs response; not a general XML parser.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut out = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        let Some(end) = after.find(&close) else {
+            break;
+        };
+        out.push(&after[..end]);
+        rest = &after[end + close.len()..];
+    }
+    out
+}
+
+/// Decode the predefined XML entities.
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
 
-        Ok(blob_names)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_batch_statuses() {
+        let body = "--batchresponse_1\r\n\
+                    Content-Type: application/http\r\n\
+                    Content-ID: 0\r\n\r\n\
+                    HTTP/1.1 202 Accepted\r\n\
+                    x-ms-request-id: a\r\n\r\n\
+                    --batchresponse_1\r\n\
+                    Content-Type: application/http\r\n\
+                    Content-ID: 1\r\n\r\n\
+                    HTTP/1.1 403 This request is not authorized\r\n\r\n\
+                    --batchresponse_1--\r\n";
+
+        let statuses = parse_batch_statuses(body, "batchresponse_1");
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[&0], StatusCode::ACCEPTED);
+        assert_eq!(statuses[&1], StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_sign_sas() {
+        let storage = AzureStorage::with_account_key(
+            "myaccount",
+            "mycontainer",
+            BASE64.encode(b"not a real account key"),
+        )
+        .unwrap();
+        let expiry = std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let sas = storage.sign_sas(Some("dir/file.txt"), "r", expiry).unwrap();
+        assert_eq!(
+            sas,
+            "sv=2022-11-02&spr=https&se=2023-11-14T22%3A13%3A20Z&sr=b&sp=r\
+             &sig=DD8Kqr3SxnO%2B5Os15gSzJRTA3i4hN7SnzSirhPYb2EM%3D"
+        );
+
+        let sas = storage.sign_sas(None, "racwdl", expiry).unwrap();
+        assert!(sas.contains("&sr=c&sp=racwdl&sig="));
+    }
+
+    #[test]
+    fn test_sign_user_delegation_sas() {
+        let key = UserDelegationKey::from_xml(&format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><UserDelegationKey>\
+             <SignedOid>oid</SignedOid><SignedTid>tid</SignedTid>\
+             <SignedStart>2023-11-14T00:00:00Z</SignedStart>\
+             <SignedExpiry>2023-11-15T00:00:00Z</SignedExpiry>\
+             <SignedService>b</SignedService><SignedVersion>2022-11-02</SignedVersion>\
+             <Value>{}</Value></UserDelegationKey>",
+            BASE64.encode(b"delegation key")
+        ))
+        .unwrap();
+        assert_eq!(
+            key.expires_at(),
+            Some(std::time::UNIX_EPOCH + Duration::from_secs(1_700_006_400))
+        );
+        let storage = AzureStorage::with_user_delegation_key("myaccount", "mycontainer", key);
+        let expiry = std::time::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let sas = storage.sign_sas(Some("a b/c.txt"), "r", expiry).unwrap();
+        let string_to_sign = "r\n\n2023-11-14T22:13:20Z\n/blob/myaccount/mycontainer/a b/c.txt\n\
+                              oid\ntid\n2023-11-14T00:00:00Z\n2023-11-15T00:00:00Z\nb\n2022-11-02\n\
+                              \n\n\n\nhttps\n2022-11-02\nb\n\n\n\n\n\n\n";
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"delegation key").unwrap();
+        mac.update(string_to_sign.as_bytes());
+        let signature = BASE64.encode(mac.finalize().into_bytes());
+        assert_eq!(
+            sas,
+            format!(
+                "sv=2022-11-02&spr=https&se=2023-11-14T22%3A13%3A20Z&sr=b&sp=r\
+                 &skoid=oid&sktid=tid&skt=2023-11-14T00%3A00%3A00Z&ske=2023-11-15T00%3A00%3A00Z\
+                 &sks=b&skv=2022-11-02&sig={}",
+                urlencoding::encode(&signature)
+            )
+        );
+
+        // Requests are signed too, and never outlive the key
+        let query = storage.sas_query().unwrap();
+        assert!(query.contains("&se=2023-11-15T00%3A00%3A00Z&sr=c&"));
+    }
+
+    #[test]
+    fn test_user_delegation_key_needs_every_field() {
+        let result = UserDelegationKey::from_xml(
+            "<UserDelegationKey><Value>a2V5</Value></UserDelegationKey>",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_account_key_must_be_base64() {
+        assert!(AzureStorage::with_account_key("a", "c", "not base64!").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_presign_needs_account_key() {
+        let storage = AzureStorage::new("myaccount", "mycontainer", "sv=x&sig=y");
+        let result = storage
+            .presign(
+                &"file.txt".to_string(),
+                PresignMethod::Get,
+                Duration::from_secs(60),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+    }
+
+    #[tokio::test]
+    async fn test_presign_put() {
+        let storage = AzureStorage::with_account_key_and_endpoint(
+            "devstoreaccount1",
+            "mycontainer",
+            BASE64.encode(b"key"),
+            "http://127.0.0.1:10000/devstoreaccount1",
+        )
+        .unwrap();
+        let request = storage
+            .presign(
+                &"up/file.txt".to_string(),
+                PresignMethod::Put,
+                Duration::from_secs(600),
+            )
+            .await
+            .unwrap();
+
+        assert!(request.url.starts_with(
+            "http://127.0.0.1:10000/devstoreaccount1/mycontainer/up/file.txt?sv=2022-11-02&spr=https%2Chttp&se="
+        ));
+        assert!(request.url.contains("&sr=b&sp=cw&sig="));
+        assert_eq!(
+            request.headers,
+            [("x-ms-blob-type".to_string(), "BlockBlob".to_string())]
+        );
+        assert!(request.expires_at > SystemTime::now());
+    }
+
+    #[tokio::test]
+    async fn test_presign_encodes_blob_path() {
+        let storage =
+            AzureStorage::with_account_key("myaccount", "mycontainer", BASE64.encode(b"key"))
+                .unwrap();
+        let request = storage
+            .presign(
+                &"my dir/a b#1?.txt".to_string(),
+                PresignMethod::Get,
+                Duration::from_secs(600),
+            )
+            .await
+            .unwrap();
+
+        let (url, query) = request.url.split_once('?').unwrap();
+        assert_eq!(
+            url,
+            "https://myaccount.blob.core.windows.net/mycontainer/my%20dir/a%20b%231%3F.txt"
+        );
+        // The signature covers the blob name as it is stored
+        let expiry = query
+            .split("&se=")
+            .nth(1)
+            .unwrap()
+            .split('&')
+            .next()
+            .unwrap();
+        let expiry = urlencoding::decode(expiry).unwrap();
+        let signed = storage
+            .sign_sas(
+                Some("my dir/a b#1?.txt"),
+                "r",
+                crate::time::parse_rfc3339(&expiry).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(query, signed);
     }
 }
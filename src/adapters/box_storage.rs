@@ -1,10 +1,13 @@
-use crate::{Error, Result, Storage};
+use crate::multi::{DriveItem, DriveTree};
+use crate::{DirListing, Error, GetReader, ObjectMeta, PutOptions, Result, Storage};
 use futures::stream::{self, BoxStream};
 use reqwest::header::AUTHORIZATION;
 use reqwest::{Client, StatusCode};
 use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// Box.com storage adapter using OAuth2 access tokens.
 ///
@@ -16,6 +19,10 @@ pub struct BoxStorage {
     access_token: SecretString,
     /// Parent folder ID for new uploads (default "0" = root)
     parent_folder_id: String,
+    /// Base URL of the content API
+    api_url: String,
+    /// Base URL of the upload API
+    upload_url: String,
 }
 
 impl BoxStorage {
@@ -29,6 +36,8 @@ impl BoxStorage {
             client: Client::new(),
             access_token: SecretString::from(access_token.into()),
             parent_folder_id: "0".to_string(),
+            api_url: Self::API_URL.to_string(),
+            upload_url: Self::UPLOAD_URL.to_string(),
         }
     }
 
@@ -38,9 +47,23 @@ impl BoxStorage {
             client: Client::new(),
             access_token: SecretString::from(access_token.into()),
             parent_folder_id: folder_id.into(),
+            api_url: Self::API_URL.to_string(),
+            upload_url: Self::UPLOAD_URL.to_string(),
         }
     }
 
+    /// Override the base URLs of the content and upload APIs (useful for
+    /// tests/mocks). Neither should end in a `/`.
+    pub fn with_base_urls(
+        mut self,
+        api_url: impl Into<String>,
+        upload_url: impl Into<String>,
+    ) -> Self {
+        self.api_url = api_url.into();
+        self.upload_url = upload_url.into();
+        self
+    }
+
     /// Get the parent folder ID used for new uploads.
     pub fn parent_folder_id(&self) -> &str {
         &self.parent_folder_id
@@ -57,7 +80,7 @@ impl BoxStorage {
     /// Note: This searches within the current parent folder only.
     /// If multiple folders have the same name, only the first is returned.
     pub async fn find_folder_by_name(&self, name: &str) -> Result<Option<String>> {
-        let url = format!("{}/folders/{}/items", Self::API_URL, self.parent_folder_id);
+        let url = format!("{}/folders/{}/items", self.api_url, self.parent_folder_id);
 
         let response = self
             .client
@@ -66,12 +89,10 @@ impl BoxStorage {
             .query(&[("limit", "1000"), ("fields", "id,type,name")])
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(self.map_error(status, &self.parent_folder_id, &body));
+            return Err(self.response_error(response, &self.parent_folder_id).await);
         }
 
         let items: BoxFolderItems = response
@@ -87,25 +108,112 @@ impl BoxStorage {
             .map(|item| item.id))
     }
 
-    fn map_error(&self, status: StatusCode, context: &str, body: &str) -> Error {
-        match status {
-            StatusCode::NOT_FOUND => Error::NotFound(context.to_string()),
-            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-                Error::PermissionDenied(format!("Box auth failed: {}", body))
+    /// Every item in the folder `folder_id`, following pages.
+    async fn folder_items(&self, folder_id: &str) -> Result<Vec<BoxFolderEntry>> {
+        let url = format!("{}/folders/{}/items", self.api_url, folder_id);
+
+        let mut entries = Vec::new();
+        let mut offset = 0u64;
+
+        loop {
+            let response = self
+                .client
+                .get(&url)
+                .header(AUTHORIZATION, self.auth_header())
+                .query(&[("fields", "id,type,name")])
+                .query(&[("limit", 1000), ("offset", offset)])
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            let status = response.status();
+            if !status.is_success() {
+                return Err(self.response_error(response, folder_id).await);
             }
-            StatusCode::CONFLICT => Error::Generic(format!("Box conflict: {}", body)),
-            StatusCode::TOO_MANY_REQUESTS => Error::Generic("Box rate limit exceeded".to_string()),
-            StatusCode::INSUFFICIENT_STORAGE => {
-                Error::Generic("Box storage quota exceeded".to_string())
+
+            let items: BoxFolderItems = response
+                .json()
+                .await
+                .map_err(|e| Error::Generic(format!("failed to parse Box response: {e}")))?;
+
+            let page_len = items.entries.len() as u64;
+            entries.extend(items.entries);
+
+            offset = items.offset + page_len;
+            if page_len == 0 || page_len < items.limit || offset >= items.total_count {
+                return Ok(entries);
             }
-            _ => Error::Generic(format!("Box error {}: {}", status, body)),
+        }
+    }
+
+    /// Send a GET for the content of file `id` and check the response status.
+    async fn download(&self, id: &str) -> Result<reqwest::Response> {
+        let url = format!("{}/files/{}/content", self.api_url, id);
+
+        let response = self
+            .client
+            .get(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        if !response.status().is_success() {
+            return Err(self.response_error(response, id).await);
+        }
+        Ok(response)
+    }
+
+    /// Error for a failed response about `context`, read from its body.
+    async fn response_error(&self, response: reqwest::Response, context: &str) -> Error {
+        let status = response.status();
+        let retry_after = crate::http::retry_after(response.headers());
+        let body = response.text().await.unwrap_or_default();
+        self.map_error(status, retry_after, context, &body)
+    }
+
+    /// Classify a failed status, using the `code` of Box's error body where
+    /// it is more specific than the status alone.
+    fn map_error(
+        &self,
+        status: StatusCode,
+        retry_after: Option<Duration>,
+        context: &str,
+        body: &str,
+    ) -> Error {
+        let code = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|error| Some(error.get("code")?.as_str()?.to_string()))
+            .unwrap_or_default();
+        match (status, code.as_str()) {
+            (_, "item_name_in_use") => Error::AlreadyExists(context.to_string()),
+            (_, "item_name_invalid" | "item_name_too_long") => {
+                Error::InvalidId(context.to_string())
+            }
+            (_, "storage_limit_exceeded") | (StatusCode::INSUFFICIENT_STORAGE, _) => {
+                Error::QuotaExceeded("Box storage quota exceeded".to_string())
+            }
+            (StatusCode::TOO_MANY_REQUESTS, _) => Error::RateLimited {
+                message: "Box rate limit exceeded".to_string(),
+                retry_after,
+            },
+            (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN, _) => {
+                Error::PermissionDenied(format!("Box auth failed: {}", body))
+            }
+            (StatusCode::CONFLICT, _) => Error::Conflict(format!("Box conflict: {}", body)),
+            _ => crate::http::status_error(
+                status,
+                retry_after,
+                context,
+                format!("Box error {}: {}", status, body),
+            ),
         }
     }
 
     /// Search for a file by name in the configured folder.
     /// Returns the file ID if found, None otherwise.
     async fn search_file_in_folder(&self, name: &str) -> Result<Option<String>> {
-        let url = format!("{}/folders/{}/items", Self::API_URL, self.parent_folder_id);
+        let url = format!("{}/folders/{}/items", self.api_url, self.parent_folder_id);
 
         let response = self
             .client
@@ -114,12 +222,10 @@ impl BoxStorage {
             .query(&[("limit", "1000"), ("fields", "id,type,name")])
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(self.map_error(status, &self.parent_folder_id, &body));
+            return Err(self.response_error(response, &self.parent_folder_id).await);
         }
 
         let items: BoxFolderItems = response
@@ -135,73 +241,100 @@ impl BoxStorage {
             .map(|item| item.id))
     }
 
-    /// Create a new file in the configured folder.
-    async fn create_file(&self, name: &str, data: Vec<u8>) -> Result<()> {
-        let url = format!("{}/files/content", Self::UPLOAD_URL);
-
-        // Box upload API uses multipart/form-data
-        let form = reqwest::multipart::Form::new()
-            .part(
-                "attributes",
-                reqwest::multipart::Part::text(format!(
-                    r#"{{"name":"{}","parent":{{"id":"{}"}}}}"#,
-                    name, self.parent_folder_id
-                ))
-                .mime_str("application/json")
-                .map_err(|e| Error::Generic(format!("invalid mime type: {e}")))?,
-            )
-            .part(
-                "file",
-                reqwest::multipart::Part::bytes(data)
-                    .file_name(name.to_string())
-                    .mime_str("application/octet-stream")
-                    .map_err(|e| Error::Generic(format!("invalid mime type: {e}")))?,
-            );
-
-        let response = self
-            .client
-            .post(&url)
-            .header(AUTHORIZATION, self.auth_header())
-            .multipart(form)
-            .send()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+    /// Upload `input` as a new file called `name` inside the folder
+    /// `parent_folder_id` and return the new file's ID.
+    ///
+    /// The name, folder and content go up in one multipart request. Fails if
+    /// the folder already contains an item called `name`; use
+    /// [`Storage::put`] to replace the content of an existing file.
+    pub async fn create<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        parent_folder_id: &str,
+        name: &str,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        let url = format!("{}/files/content", self.upload_url);
+        let attributes = serde_json::json!({ "name": name, "parent": { "id": parent_folder_id } });
+
+        let response = crate::io::send_streaming(input, |body| {
+            // Box upload API uses multipart/form-data
+            let form = reqwest::multipart::Form::new()
+                .part(
+                    "attributes",
+                    reqwest::multipart::Part::text(attributes.to_string())
+                        .mime_str("application/json")
+                        .map_err(|e| Error::Generic(format!("invalid mime type: {e}")))?,
+                )
+                .part(
+                    "file",
+                    Self::file_part(body, len)
+                        .file_name(name.to_string())
+                        .mime_str("application/octet-stream")
+                        .map_err(|e| Error::Generic(format!("invalid mime type: {e}")))?,
+                );
+
+            Ok(self
+                .client
+                .post(&url)
+                .header(AUTHORIZATION, self.auth_header())
+                .multipart(form))
+        })
+        .await?;
 
         match response.status() {
-            StatusCode::CREATED => Ok(()),
-            status => {
-                let body = response.text().await.unwrap_or_default();
-                Err(self.map_error(status, name, &body))
+            StatusCode::CREATED => {
+                let items: BoxFolderItems = response
+                    .json()
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to parse Box response: {e}")))?;
+                items
+                    .entries
+                    .into_iter()
+                    .next()
+                    .map(|item| item.id)
+                    .ok_or_else(|| Error::Generic("Box upload returned no file".to_string()))
             }
+            _ => Err(self.response_error(response, name).await),
+        }
+    }
+
+    /// Multipart file part streaming `body`, with its length when known.
+    fn file_part(body: reqwest::Body, len: Option<u64>) -> reqwest::multipart::Part {
+        match len {
+            Some(len) => reqwest::multipart::Part::stream_with_length(body, len),
+            None => reqwest::multipart::Part::stream(body),
         }
     }
 
     /// Update an existing file by ID.
-    async fn update_file(&self, file_id: &str, data: Vec<u8>) -> Result<()> {
-        let url = format!("{}/files/{}/content", Self::UPLOAD_URL, file_id);
+    async fn update_file<R: AsyncRead + Unpin>(
+        &self,
+        file_id: &str,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        let url = format!("{}/files/{}/content", self.upload_url, file_id);
 
-        let form = reqwest::multipart::Form::new().part(
-            "file",
-            reqwest::multipart::Part::bytes(data)
-                .mime_str("application/octet-stream")
-                .map_err(|e| Error::Generic(format!("invalid mime type: {e}")))?,
-        );
+        let response = crate::io::send_streaming(input, |body| {
+            let form = reqwest::multipart::Form::new().part(
+                "file",
+                Self::file_part(body, len)
+                    .mime_str("application/octet-stream")
+                    .map_err(|e| Error::Generic(format!("invalid mime type: {e}")))?,
+            );
 
-        let response = self
-            .client
-            .post(&url)
-            .header(AUTHORIZATION, self.auth_header())
-            .multipart(form)
-            .send()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            Ok(self
+                .client
+                .post(&url)
+                .header(AUTHORIZATION, self.auth_header())
+                .multipart(form))
+        })
+        .await?;
 
         match response.status() {
             StatusCode::OK | StatusCode::CREATED => Ok(()),
-            status => {
-                let body = response.text().await.unwrap_or_default();
-                Err(self.map_error(status, file_id, &body))
-            }
+            _ => Err(self.response_error(response, file_id).await),
         }
     }
 }
@@ -225,11 +358,58 @@ struct BoxFolderEntry {
     name: String,
 }
 
+#[derive(Deserialize)]
+struct BoxFileInfo {
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    modified_at: Option<String>,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    metadata: Option<BoxFileMetadata>,
+}
+
+/// The `metadata` field of a file, holding the metadata instances requested.
+#[derive(Deserialize)]
+struct BoxFileMetadata {
+    #[serde(default)]
+    global: Option<BoxGlobalMetadata>,
+}
+
+#[derive(Deserialize)]
+struct BoxGlobalMetadata {
+    /// The `global.properties` instance: free-form keys, plus `$`-prefixed
+    /// fields Box adds itself.
+    #[serde(default)]
+    properties: Option<HashMap<String, serde_json::Value>>,
+}
+
+impl BoxFileInfo {
+    /// User metadata from the `global.properties` instance.
+    fn user_metadata(&mut self) -> HashMap<String, String> {
+        let properties = self
+            .metadata
+            .take()
+            .and_then(|m| m.global)
+            .and_then(|g| g.properties)
+            .unwrap_or_default();
+        properties
+            .into_iter()
+            .filter(|(key, _)| !key.starts_with('$'))
+            .filter_map(|(key, value)| match value {
+                serde_json::Value::String(value) => Some((key, value)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 impl Storage for BoxStorage {
     type Id = String;
 
     async fn exists(&self, id: &Self::Id) -> Result<bool> {
-        let url = format!("{}/files/{}", Self::API_URL, id);
+        let url = format!("{}/files/{}", self.api_url, id);
 
         let response = self
             .client
@@ -237,21 +417,18 @@ impl Storage for BoxStorage {
             .header(AUTHORIZATION, self.auth_header())
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match response.status() {
             StatusCode::OK => Ok(true),
             StatusCode::NOT_FOUND => Ok(false),
-            status => {
-                let body = response.text().await.unwrap_or_default();
-                Err(self.map_error(status, id, &body))
-            }
+            _ => Err(self.response_error(response, id).await),
         }
     }
 
     async fn folder_exists(&self, id: &Self::Id) -> Result<bool> {
         // In Box, folders have their own endpoint
-        let url = format!("{}/folders/{}", Self::API_URL, id);
+        let url = format!("{}/folders/{}", self.api_url, id);
 
         let response = self
             .client
@@ -259,42 +436,97 @@ impl Storage for BoxStorage {
             .header(AUTHORIZATION, self.auth_header())
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match response.status() {
             StatusCode::OK => Ok(true),
             StatusCode::NOT_FOUND => Ok(false),
-            status => {
-                let body = response.text().await.unwrap_or_default();
-                Err(self.map_error(status, id, &body))
-            }
+            _ => Err(self.response_error(response, id).await),
         }
     }
 
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let url = format!("{}/files/{}", self.api_url, id);
+
+        let response = self
+            .client
+            .get(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .query(&[("fields", "size,modified_at,etag,metadata.global.properties")])
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(self.response_error(response, id).await);
+        }
+
+        let mut info: BoxFileInfo = response
+            .json()
+            .await
+            .map_err(|e| Error::Generic(format!("failed to parse Box response: {e}")))?;
+        let metadata = info.user_metadata();
+
+        Ok(ObjectMeta {
+            size: info.size,
+            last_modified: info
+                .modified_at
+                .as_deref()
+                .and_then(crate::time::parse_rfc3339),
+            etag: info.etag,
+            metadata,
+            ..Default::default()
+        })
+    }
+
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
         &self,
         id: Self::Id,
-        mut input: R,
-        _len: Option<u64>,
+        input: R,
+        len: Option<u64>,
     ) -> Result<()> {
-        // Read the entire content into memory
-        // Box API requires multipart/form-data upload
-        let mut data = Vec::new();
-        input
-            .read_to_end(&mut data)
-            .await
-            .map_err(|e| Error::Generic(format!("failed to read data: {e}")))?;
-
         // First, try to get file info by searching in folder
         let search_result = self.search_file_in_folder(&id).await?;
 
         if let Some(file_id) = search_result {
             // File exists, update it
-            self.update_file(&file_id, data).await
+            self.update_file(&file_id, input, len).await
         } else {
             // File doesn't exist, create new
-            self.create_file(&id, data).await
+            self.create(&self.parent_folder_id, &id, input, len)
+                .await
+                .map(drop)
+        }
+    }
+
+    /// User metadata is stored in the file's `global.properties` metadata
+    /// instance, replacing any earlier one; the other content attributes are
+    /// ignored. Preconditions are not supported.
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        if options.has_preconditions() {
+            return Err(Error::Unsupported(
+                "conditional writes are not supported by this backend".to_string(),
+            ));
+        }
+
+        let file_id = match self.search_file_in_folder(&id).await? {
+            Some(file_id) => {
+                self.update_file(&file_id, input, len).await?;
+                file_id
+            }
+            None => self.create(&self.parent_folder_id, &id, input, len).await?,
+        };
+        if options.has_attributes() {
+            self.set_attributes(&file_id, &options).await?;
         }
+        Ok(())
     }
 
     async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
@@ -302,26 +534,9 @@ impl Storage for BoxStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
-        let url = format!("{}/files/{}/content", Self::API_URL, id);
+        let response = self.download(id).await?;
 
-        let response = self
-            .client
-            .get(&url)
-            .header(AUTHORIZATION, self.auth_header())
-            .send()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(self.map_error(status, id, &body));
-        }
-
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+        let bytes = response.bytes().await.map_err(crate::http::request_error)?;
 
         let total_bytes = bytes.len() as u64;
 
@@ -338,8 +553,12 @@ impl Storage for BoxStorage {
         Ok(total_bytes)
     }
 
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        Ok(crate::io::response_reader(self.download(id).await?))
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
-        let url = format!("{}/files/{}", Self::API_URL, id);
+        let url = format!("{}/files/{}", self.api_url, id);
 
         let response = self
             .client
@@ -347,15 +566,29 @@ impl Storage for BoxStorage {
             .header(AUTHORIZATION, self.auth_header())
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match response.status() {
             StatusCode::NO_CONTENT => Ok(()),
             StatusCode::NOT_FOUND => Err(Error::NotFound(id.to_string())),
-            status => {
-                let body = response.text().await.unwrap_or_default();
-                Err(self.map_error(status, id, &body))
-            }
+            _ => Err(self.response_error(response, id).await),
+        }
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        let url = format!("{}/folders/{}?recursive=true", self.api_url, folder);
+
+        let response = self
+            .client
+            .delete(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        match response.status() {
+            StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => Ok(()),
+            _ => Err(self.response_error(response, folder).await),
         }
     }
 
@@ -365,7 +598,7 @@ impl Storage for BoxStorage {
         // If prefix is provided, we'll treat it as a folder ID
 
         let folder_id = prefix.unwrap_or(&self.parent_folder_id);
-        let url = format!("{}/folders/{}/items", Self::API_URL, folder_id);
+        let url = format!("{}/folders/{}/items", self.api_url, folder_id);
 
         let response = self
             .client
@@ -374,12 +607,10 @@ impl Storage for BoxStorage {
             .query(&[("limit", "1000"), ("fields", "id,type,name")])
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().await.unwrap_or_default();
-            return Err(self.map_error(status, folder_id, &body));
+            return Err(self.response_error(response, folder_id).await);
         }
 
         let items: BoxFolderItems = response
@@ -397,6 +628,113 @@ impl Storage for BoxStorage {
 
         Ok(Box::pin(stream::iter(file_ids)))
     }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        // Folder IDs take the place of paths; the root is the configured folder
+        let folder_id = folder.unwrap_or(&self.parent_folder_id);
+        let items = match self.folder_items(folder_id).await {
+            Err(Error::NotFound(_)) => return Ok(DirListing::default()),
+            items => items?,
+        };
+
+        let mut listing = DirListing::default();
+        for item in items {
+            match item.item_type.as_str() {
+                "folder" => listing.prefixes.push(item.id),
+                "file" => listing.files.push(item.id),
+                _ => {}
+            }
+        }
+
+        Ok(listing.normalize())
+    }
+}
+
+impl DriveTree for BoxStorage {
+    fn root_folder_id(&self) -> String {
+        self.parent_folder_id.clone()
+    }
+
+    async fn list_children(&self, parent_id: &str) -> Result<Vec<DriveItem>> {
+        Ok(self
+            .folder_items(parent_id)
+            .await?
+            .into_iter()
+            .filter(|item| item.item_type == "file" || item.item_type == "folder")
+            .map(|item| DriveItem {
+                is_folder: item.item_type == "folder",
+                id: item.id,
+                name: item.name,
+            })
+            .collect())
+    }
+
+    async fn create_folder(&self, parent_id: &str, name: &str) -> Result<String> {
+        let url = format!("{}/folders", self.api_url);
+
+        let response = self
+            .client
+            .post(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .json(&serde_json::json!({ "name": name, "parent": { "id": parent_id } }))
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        match response.status() {
+            StatusCode::CREATED => {
+                let folder: BoxFolderEntry = response
+                    .json()
+                    .await
+                    .map_err(|e| Error::Generic(format!("failed to parse Box response: {e}")))?;
+                Ok(folder.id)
+            }
+            _ => Err(self.response_error(response, name).await),
+        }
+    }
+
+    async fn create_file<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        parent_id: &str,
+        name: &str,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        self.create(parent_id, name, input, len).await
+    }
+
+    async fn set_attributes(&self, id: &str, options: &PutOptions) -> Result<()> {
+        if options.user_metadata.is_empty() {
+            return Ok(());
+        }
+        let url = format!("{}/files/{}/metadata/global/properties", self.api_url, id);
+
+        // An instance can only be created once, so drop the old one first.
+        let response = self
+            .client
+            .delete(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+        let status = response.status();
+        if !status.is_success() && status != StatusCode::NOT_FOUND {
+            return Err(self.response_error(response, id).await);
+        }
+
+        let response = self
+            .client
+            .post(&url)
+            .header(AUTHORIZATION, self.auth_header())
+            .json(&options.user_metadata)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+        match response.status() {
+            StatusCode::CREATED => Ok(()),
+            _ => Err(self.response_error(response, id).await),
+        }
+    }
 }
 
 #[cfg(test)]
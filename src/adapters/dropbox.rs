@@ -1,9 +1,13 @@
-use crate::{Error, Result, Storage};
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, Error, GetReader, ObjectMeta, Page, Result,
+    Storage,
+};
 use futures::stream::{self, BoxStream, StreamExt};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use reqwest::{Client, StatusCode};
 use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// Dropbox storage adapter using OAuth2 access tokens.
@@ -42,19 +46,414 @@ impl DropboxStorage {
         }
     }
 
+    /// Server-side copy or move via `/files/copy_v2` or `/files/move_v2`.
+    ///
+    /// Dropbox refuses to overwrite, so an existing destination is deleted
+    /// and the request retried once.
+    async fn relocate(&self, endpoint: &str, src: &str, dst: &str) -> Result<()> {
+        let from_path = Self::ensure_path_format(src);
+        let to_path = Self::ensure_path_format(dst);
+
+        let request_body = DropboxRelocationArg {
+            from_path: from_path.clone(),
+            to_path: to_path.clone(),
+            autorename: false,
+        };
+
+        let mut retried = false;
+        loop {
+            let response = self
+                .client
+                .post(&format!("{}/files/{}", Self::API_URL, endpoint))
+                .header(AUTHORIZATION, self.auth_header())
+                .header(CONTENT_TYPE, "application/json")
+                .json(&request_body)
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(());
+            }
+
+            let body = response.text().await.unwrap_or_default();
+            if status == StatusCode::CONFLICT {
+                if body.contains("from_lookup") && body.contains("not_found") {
+                    return Err(Error::NotFound(from_path));
+                }
+                if !retried && body.contains("to/conflict") {
+                    self.delete(&to_path).await?;
+                    retried = true;
+                    continue;
+                }
+            }
+            return Err(self.map_error(status, &from_path, &body));
+        }
+    }
+
+    /// Fetch every entry of `list_folder` for `path`, following the cursor.
+    async fn list_folder(&self, path: String, recursive: bool) -> Result<Vec<DropboxEntry>> {
+        let request_body = DropboxListFolderArg {
+            path: path.clone(),
+            recursive,
+            limit: None,
+        };
+
+        let response = self
+            .client
+            .post(&format!("{}/files/list_folder", Self::API_URL))
+            .header(AUTHORIZATION, self.auth_header())
+            .header(CONTENT_TYPE, "application/json")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            // A missing folder is reported as 409 with a path/not_found error.
+            if status == StatusCode::CONFLICT && body.contains("not_found") {
+                return Err(Error::NotFound(path));
+            }
+            return Err(self.map_error(status, &path, &body));
+        }
+
+        let list_result: DropboxListFolderResult = response
+            .json()
+            .await
+            .map_err(|e| Error::Generic(format!("Failed to parse list response: {}", e)))?;
+
+        let mut all_entries = list_result.entries;
+        let mut cursor = list_result.cursor;
+        let mut has_more = list_result.has_more;
+
+        // Continue fetching if there are more results
+        while has_more {
+            if let Some(ref c) = cursor {
+                let continue_body = DropboxListFolderContinueArg { cursor: c.clone() };
+
+                let continue_response = self
+                    .client
+                    .post(&format!("{}/files/list_folder/continue", Self::API_URL))
+                    .header(AUTHORIZATION, self.auth_header())
+                    .header(CONTENT_TYPE, "application/json")
+                    .json(&continue_body)
+                    .send()
+                    .await
+                    .map_err(crate::http::request_error)?;
+
+                if !continue_response.status().is_success() {
+                    break;
+                }
+
+                let continue_result: DropboxListFolderResult =
+                    continue_response.json().await.map_err(|e| {
+                        Error::Generic(format!("Failed to parse continue response: {}", e))
+                    })?;
+
+                all_entries.extend(continue_result.entries);
+                cursor = continue_result.cursor;
+                has_more = continue_result.has_more;
+            } else {
+                break;
+            }
+        }
+
+        Ok(all_entries)
+    }
+
+    /// Delete the file or folder at `path` via `/files/delete_v2`.
+    async fn delete_path(&self, path: String) -> Result<()> {
+        let request_body = DropboxPath { path: path.clone() };
+
+        let response = self
+            .client
+            .post(format!("{}/files/delete_v2", Self::API_URL))
+            .header(AUTHORIZATION, self.auth_header())
+            .header(CONTENT_TYPE, "application/json")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        match status {
+            StatusCode::OK => Ok(()),
+            StatusCode::NOT_FOUND => Ok(()), // Idempotent delete
+            _ => {
+                let body = response.text().await.unwrap_or_default();
+                // A missing path is reported as 409 with a path_lookup/not_found error.
+                if status == StatusCode::CONFLICT && body.contains("not_found") {
+                    return Ok(());
+                }
+                Err(self.map_error(status, &path, &body))
+            }
+        }
+    }
+
+    /// Most entries a single `/files/delete_batch` job may contain.
+    const DELETE_BATCH_SIZE: usize = 1000;
+
+    /// Delete `ids` with one `/files/delete_batch` job, polling it until it
+    /// completes, and record the outcome for each item.
+    async fn delete_batch(&self, ids: &[String], result: &mut BatchResult<String>) {
+        let request_body = DropboxDeleteBatchArg {
+            entries: ids
+                .iter()
+                .map(|id| DropboxPath {
+                    path: Self::ensure_path_format(id),
+                })
+                .collect(),
+        };
+
+        let mut status = match self.batch_request("delete_batch", &request_body).await {
+            Ok(status) => status,
+            Err(e) => return result.fail_all(ids.iter().cloned(), &e),
+        };
+        while status.tag == "async_job_id" || status.tag == "in_progress" {
+            let Some(job_id) = status.async_job_id.clone() else {
+                break;
+            };
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            let check = DropboxAsyncJobIdArg {
+                async_job_id: job_id,
+            };
+            status = match self.batch_request("delete_batch/check", &check).await {
+                // `in_progress` replies carry no job id, so keep the one we have.
+                Ok(next) => DropboxDeleteBatchStatus {
+                    async_job_id: next.async_job_id.or(status.async_job_id),
+                    ..next
+                },
+                Err(e) => return result.fail_all(ids.iter().cloned(), &e),
+            };
+        }
+
+        if status.tag != "complete" || status.entries.len() != ids.len() {
+            let error = Error::Generic(format!("Dropbox delete batch ended as {}", status.tag));
+            return result.fail_all(ids.iter().cloned(), &error);
+        }
+
+        // Entries come back in the order they were submitted.
+        for (id, entry) in ids.iter().zip(status.entries) {
+            let failure = entry.failure.map(|f| f.to_string()).unwrap_or_default();
+            // A missing path counts as deleted (idempotent delete).
+            if entry.tag == "success" || failure.contains("not_found") {
+                result.succeeded.push(id.clone());
+            } else {
+                let error = Self::tagged_error(&failure, id, None).unwrap_or_else(|| {
+                    Error::Generic(format!("Dropbox delete failed: {}", failure))
+                });
+                result.failed.push((id.clone(), error));
+            }
+        }
+    }
+
+    /// POST `body` to a `/files/delete_batch` endpoint and parse the job status.
+    async fn batch_request<A: Serialize>(
+        &self,
+        endpoint: &str,
+        body: &A,
+    ) -> Result<DropboxDeleteBatchStatus> {
+        let response = self
+            .client
+            .post(format!("{}/files/{}", Self::API_URL, endpoint))
+            .header(AUTHORIZATION, self.auth_header())
+            .header(CONTENT_TYPE, "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(self.map_error(status, endpoint, &body));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| Error::Generic(format!("Failed to parse delete batch response: {}", e)))
+    }
+
+    /// Chunk size for upload sessions, and the most an upload holds in
+    /// memory at once. Must be a multiple of 4 MiB.
+    const UPLOAD_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+    /// POST `body` to a content endpoint with a JSON `Dropbox-API-Arg`.
+    async fn content_request<A: Serialize>(
+        &self,
+        endpoint: &str,
+        path: &str,
+        arg: &A,
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response> {
+        let arg_json = serde_json::to_string(arg)
+            .map_err(|e| Error::Generic(format!("JSON serialization error: {}", e)))?;
+
+        let response = self
+            .client
+            .post(format!("{}/{}", Self::CONTENT_URL, endpoint))
+            .header(AUTHORIZATION, self.auth_header())
+            .header(CONTENT_TYPE, "application/octet-stream")
+            .header("Dropbox-API-Arg", arg_json)
+            .body(body)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if status.is_success() {
+            Ok(response)
+        } else {
+            let body = response.text().await.unwrap_or_default();
+            Err(self.map_error(status, path, &body))
+        }
+    }
+
+    /// Upload `first_chunk` followed by the rest of `input` through an
+    /// upload session, one chunk at a time.
+    async fn session_upload<R: AsyncRead + Send + Unpin>(
+        &self,
+        path: &str,
+        mut input: R,
+        first_chunk: Vec<u8>,
+    ) -> Result<()> {
+        let offset = first_chunk.len() as u64;
+        let started: DropboxSessionStartResult = self
+            .content_request(
+                "files/upload_session/start",
+                path,
+                &DropboxSessionStartArg { close: false },
+                first_chunk,
+            )
+            .await?
+            .json()
+            .await
+            .map_err(|e| Error::Generic(format!("JSON parse error: {}", e)))?;
+
+        let mut cursor = DropboxSessionCursor {
+            session_id: started.session_id,
+            offset,
+        };
+        let mut data = Vec::new();
+        loop {
+            // A short chunk is the last one and is sent with the commit.
+            let n = crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?;
+            if n < Self::UPLOAD_CHUNK_SIZE {
+                break;
+            }
+            self.content_request(
+                "files/upload_session/append_v2",
+                path,
+                &DropboxSessionAppendArg {
+                    cursor: &cursor,
+                    close: false,
+                },
+                std::mem::take(&mut data),
+            )
+            .await?;
+            cursor.offset += n as u64;
+        }
+
+        self.content_request(
+            "files/upload_session/finish",
+            path,
+            &DropboxSessionFinishArg {
+                cursor: &cursor,
+                commit: DropboxUploadArg::overwrite(path.to_string()),
+            },
+            data,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Request the content of the file at `path` and check the response status.
+    async fn download(&self, path: &str) -> Result<reqwest::Response> {
+        let path = Self::ensure_path_format(path);
+
+        let download_arg = DropboxPath { path: path.clone() };
+
+        let arg_json = serde_json::to_string(&download_arg)
+            .map_err(|e| Error::Generic(format!("JSON serialization error: {}", e)))?;
+
+        let response = self
+            .client
+            .post(&format!("{}/files/download", Self::CONTENT_URL))
+            .header(AUTHORIZATION, self.auth_header())
+            .header("Dropbox-API-Arg", arg_json)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(self.map_error(status, &path, &body));
+        }
+        Ok(response)
+    }
+
+    /// Classify a failed status. Dropbox reports most failures as `409` with
+    /// an `error_summary` such as `path/conflict/file/`, and rate limits with
+    /// a `retry_after` in the body.
     fn map_error(&self, status: StatusCode, path: &str, body: &str) -> Error {
+        let error = serde_json::from_str::<serde_json::Value>(body).ok();
+        let summary = error
+            .as_ref()
+            .and_then(|e| e.get("error_summary")?.as_str())
+            .unwrap_or_default();
+        let retry_after = error
+            .as_ref()
+            .and_then(|e| e.pointer("/error/retry_after")?.as_u64())
+            .map(Duration::from_secs);
+
         match status {
-            StatusCode::NOT_FOUND => Error::NotFound(path.to_string()),
+            StatusCode::CONFLICT => Self::tagged_error(summary, path, retry_after)
+                .unwrap_or_else(|| Error::Conflict(format!("Dropbox conflict: {}", body))),
             StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
                 Error::PermissionDenied(format!("Dropbox auth failed: {}", body))
             }
-            StatusCode::CONFLICT => Error::Generic(format!("Dropbox conflict: {}", body)),
-            StatusCode::TOO_MANY_REQUESTS => {
-                Error::Generic("Dropbox rate limit exceeded".to_string())
-            }
-            _ => Error::Generic(format!("Dropbox error {}: {}", status, body)),
+            StatusCode::TOO_MANY_REQUESTS => Error::RateLimited {
+                message: "Dropbox rate limit exceeded".to_string(),
+                retry_after,
+            },
+            _ => crate::http::status_error(
+                status,
+                retry_after,
+                path,
+                format!("Dropbox error {}: {}", status, body),
+            ),
         }
     }
+
+    /// Classify a Dropbox error by the tags in its summary or JSON form,
+    /// e.g. `path/insufficient_space/`.
+    fn tagged_error(tags: &str, path: &str, retry_after: Option<Duration>) -> Option<Error> {
+        let has = |tag: &str| tags.contains(tag);
+        let error = if has("not_found") {
+            Error::NotFound(path.to_string())
+        } else if has("insufficient_space") || has("insufficient_quota") {
+            Error::QuotaExceeded(format!("Dropbox quota exceeded: {tags}"))
+        } else if has("malformed_path") || has("disallowed_name") || has("too_long") {
+            Error::InvalidId(path.to_string())
+        } else if has("too_many_write_operations") || has("too_many_requests") {
+            Error::RateLimited {
+                message: format!("Dropbox rate limit exceeded: {tags}"),
+                retry_after,
+            }
+        } else if has("no_write_permission") || has("restricted_content") {
+            Error::PermissionDenied(format!("Dropbox denied access: {tags}"))
+        } else if has("conflict") {
+            Error::AlreadyExists(path.to_string())
+        } else {
+            return None;
+        };
+        Some(error)
+    }
 }
 
 #[derive(Serialize)]
@@ -62,6 +461,35 @@ struct DropboxPath {
     path: String,
 }
 
+#[derive(Serialize)]
+struct DropboxDeleteBatchArg {
+    entries: Vec<DropboxPath>,
+}
+
+#[derive(Serialize)]
+struct DropboxAsyncJobIdArg {
+    async_job_id: String,
+}
+
+/// Reply of `/files/delete_batch` and `/files/delete_batch/check`.
+#[derive(Deserialize)]
+struct DropboxDeleteBatchStatus {
+    #[serde(rename = ".tag")]
+    tag: String,
+    #[serde(default)]
+    async_job_id: Option<String>,
+    #[serde(default)]
+    entries: Vec<DropboxDeleteBatchEntry>,
+}
+
+#[derive(Deserialize)]
+struct DropboxDeleteBatchEntry {
+    #[serde(rename = ".tag")]
+    tag: String,
+    #[serde(default)]
+    failure: Option<serde_json::Value>,
+}
+
 #[derive(Serialize)]
 struct DropboxUploadArg {
     path: String,
@@ -70,6 +498,52 @@ struct DropboxUploadArg {
     mute: bool,
 }
 
+impl DropboxUploadArg {
+    fn overwrite(path: String) -> Self {
+        Self {
+            path,
+            mode: "overwrite".to_string(),
+            autorename: false,
+            mute: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DropboxSessionStartArg {
+    close: bool,
+}
+
+#[derive(Deserialize)]
+struct DropboxSessionStartResult {
+    session_id: String,
+}
+
+#[derive(Serialize)]
+struct DropboxSessionCursor {
+    session_id: String,
+    offset: u64,
+}
+
+#[derive(Serialize)]
+struct DropboxSessionAppendArg<'a> {
+    cursor: &'a DropboxSessionCursor,
+    close: bool,
+}
+
+#[derive(Serialize)]
+struct DropboxSessionFinishArg<'a> {
+    cursor: &'a DropboxSessionCursor,
+    commit: DropboxUploadArg,
+}
+
+#[derive(Serialize)]
+struct DropboxRelocationArg {
+    from_path: String,
+    to_path: String,
+    autorename: bool,
+}
+
 #[derive(Deserialize)]
 #[allow(dead_code)]
 struct DropboxMetadata {
@@ -77,12 +551,25 @@ struct DropboxMetadata {
     tag: String,
     name: String,
     path_display: Option<String>,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    server_modified: Option<String>,
+    #[serde(default)]
+    content_hash: Option<String>,
 }
 
 #[derive(Serialize)]
 struct DropboxListFolderArg {
     path: String,
     recursive: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct DropboxListFolderContinueArg {
+    cursor: String,
 }
 
 #[derive(Deserialize)]
@@ -97,6 +584,12 @@ struct DropboxEntry {
     #[serde(rename = ".tag")]
     tag: String,
     path_display: Option<String>,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    server_modified: Option<String>,
+    #[serde(default)]
+    content_hash: Option<String>,
 }
 
 impl Storage for DropboxStorage {
@@ -115,7 +608,7 @@ impl Storage for DropboxStorage {
             .json(&request_body)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         let status = response.status();
         match status {
@@ -141,7 +634,7 @@ impl Storage for DropboxStorage {
             .json(&request_body)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         let status = response.status();
         match status {
@@ -158,47 +651,76 @@ impl Storage for DropboxStorage {
         }
     }
 
-    async fn put<R: AsyncRead + Send + Sync + Unpin>(
-        &self,
-        id: Self::Id,
-        mut input: R,
-        _len: Option<u64>,
-    ) -> Result<()> {
-        let path = Self::ensure_path_format(&id);
-
-        // Read all data into memory
-        // For large files, Dropbox supports chunked uploads which could be implemented
-        let mut data = Vec::new();
-        tokio::io::copy(&mut input, &mut data).await?;
-
-        let upload_arg = DropboxUploadArg {
-            path: path.clone(),
-            mode: "overwrite".to_string(),
-            autorename: false,
-            mute: false,
-        };
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let path = Self::ensure_path_format(id);
 
-        let arg_json = serde_json::to_string(&upload_arg)
-            .map_err(|e| Error::Generic(format!("JSON serialization error: {}", e)))?;
+        let request_body = DropboxPath { path: path.clone() };
 
         let response = self
             .client
-            .post(&format!("{}/files/upload", Self::CONTENT_URL))
+            .post(&format!("{}/files/get_metadata", Self::API_URL))
             .header(AUTHORIZATION, self.auth_header())
-            .header(CONTENT_TYPE, "application/octet-stream")
-            .header("Dropbox-API-Arg", arg_json)
-            .body(data)
+            .header(CONTENT_TYPE, "application/json")
+            .json(&request_body)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         let status = response.status();
-        if status.is_success() {
-            Ok(())
-        } else {
+        if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
-            Err(self.map_error(status, &path, &body))
+            // Missing paths are reported as a 409 `path/not_found` API error.
+            if status == StatusCode::CONFLICT && body.contains("not_found") {
+                return Err(Error::NotFound(path));
+            }
+            return Err(self.map_error(status, &path, &body));
+        }
+
+        let metadata: DropboxMetadata = response
+            .json()
+            .await
+            .map_err(|e| Error::Generic(format!("Failed to parse metadata response: {}", e)))?;
+
+        if metadata.tag != "file" {
+            return Err(Error::NotFound(path));
+        }
+
+        Ok(ObjectMeta {
+            size: metadata.size,
+            last_modified: metadata
+                .server_modified
+                .as_deref()
+                .and_then(crate::time::parse_rfc3339),
+            etag: metadata.content_hash,
+            ..Default::default()
+        })
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        mut input: R,
+        _len: Option<u64>,
+    ) -> Result<()> {
+        let path = Self::ensure_path_format(&id);
+
+        // Read one chunk; anything that fits goes up in a single request,
+        // larger content through an upload session.
+        let mut data = Vec::new();
+        if crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?
+            == Self::UPLOAD_CHUNK_SIZE
+        {
+            return self.session_upload(&path, input, data).await;
         }
+
+        self.content_request(
+            "files/upload",
+            &path,
+            &DropboxUploadArg::overwrite(path.clone()),
+            data,
+        )
+        .await?;
+        Ok(())
     }
 
     async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
@@ -206,33 +728,13 @@ impl Storage for DropboxStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
-        let path = Self::ensure_path_format(id);
-
-        let download_arg = DropboxPath { path: path.clone() };
-
-        let arg_json = serde_json::to_string(&download_arg)
-            .map_err(|e| Error::Generic(format!("JSON serialization error: {}", e)))?;
-
-        let response = self
-            .client
-            .post(&format!("{}/files/download", Self::CONTENT_URL))
-            .header(AUTHORIZATION, self.auth_header())
-            .header("Dropbox-API-Arg", arg_json)
-            .send()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let body = response.text().await.unwrap_or_default();
-            return Err(self.map_error(status, &path, &body));
-        }
+        let response = self.download(id).await?;
 
         let mut stream = response.bytes_stream();
         let mut total_bytes = 0u64;
 
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| Error::Connection(Box::new(e)))?;
+            let chunk = chunk.map_err(crate::http::request_error)?;
             output.write_all(&chunk).await?;
             total_bytes += chunk.len() as u64;
         }
@@ -241,30 +743,35 @@ impl Storage for DropboxStorage {
         Ok(total_bytes)
     }
 
-    async fn delete(&self, id: &Self::Id) -> Result<()> {
-        let path = Self::ensure_path_format(id);
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        Ok(crate::io::response_reader(self.download(id).await?))
+    }
 
-        let request_body = DropboxPath { path: path.clone() };
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        self.relocate("copy_v2", src, &dst).await
+    }
 
-        let response = self
-            .client
-            .post(&format!("{}/files/delete_v2", Self::API_URL))
-            .header(AUTHORIZATION, self.auth_header())
-            .header(CONTENT_TYPE, "application/json")
-            .json(&request_body)
-            .send()
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        self.relocate("move_v2", src, &dst).await
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<()> {
+        self.delete_path(Self::ensure_path_format(id)).await
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        // Deleting a folder also deletes everything in it.
+        let folder = crate::delete_folder_prefix(folder)?;
+        self.delete_path(Self::ensure_path_format(folder.trim_end_matches('/')))
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+    }
 
-        let status = response.status();
-        match status {
-            StatusCode::OK => Ok(()),
-            StatusCode::NOT_FOUND => Ok(()), // Idempotent delete
-            _ => {
-                let body = response.text().await.unwrap_or_default();
-                Err(self.map_error(status, &path, &body))
-            }
+    async fn delete_many(&self, ids: Vec<Self::Id>) -> Result<BatchResult<Self::Id>> {
+        let mut result = BatchResult::new();
+        for batch in ids.chunks(Self::DELETE_BATCH_SIZE) {
+            self.delete_batch(batch, &mut result).await;
         }
+        Ok(result)
     }
 
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
@@ -273,80 +780,144 @@ impl Storage for DropboxStorage {
             _ => String::new(),
         };
 
-        let request_body = DropboxListFolderArg {
-            path: path.clone(),
-            recursive: true,
+        let all_entries = self.list_folder(path, true).await?;
+
+        // Filter only files (not folders) and extract paths
+        let file_paths: Vec<String> = all_entries
+            .into_iter()
+            .filter(|entry| entry.tag == "file")
+            .filter_map(|entry| entry.path_display)
+            .collect();
+
+        Ok(Box::pin(stream::iter(file_paths.into_iter().map(Ok))))
+    }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        let path = match prefix {
+            Some(p) if !p.is_empty() => Self::ensure_path_format(p),
+            _ => String::new(),
         };
 
-        let response = self
-            .client
-            .post(&format!("{}/files/list_folder", Self::API_URL))
+        let all_entries = self.list_folder(path, true).await?;
+
+        // Same items as list: files only, identified by their display path
+        let entries: Vec<Entry<String>> = all_entries
+            .into_iter()
+            .filter(|entry| entry.tag == "file")
+            .filter_map(|entry| {
+                Some(Entry {
+                    id: entry.path_display?,
+                    size: entry.size,
+                    modified: entry
+                        .server_modified
+                        .as_deref()
+                        .and_then(crate::time::parse_rfc3339),
+                    etag: entry.content_hash,
+                    is_dir: false,
+                })
+            })
+            .collect();
+
+        Ok(Box::pin(stream::iter(entries.into_iter().map(Ok))))
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        let path = match prefix {
+            Some(p) if !p.is_empty() => Self::ensure_path_format(p),
+            _ => String::new(),
+        };
+
+        // The token is the list_folder cursor, which remembers the path and
+        // page size of the first request.
+        let request = match continuation {
+            None => self
+                .client
+                .post(format!("{}/files/list_folder", Self::API_URL))
+                .json(&DropboxListFolderArg {
+                    path: path.clone(),
+                    recursive: true,
+                    limit: Some(page_size.clamp(1, 2000) as u32),
+                }),
+            Some(token) => self
+                .client
+                .post(format!("{}/files/list_folder/continue", Self::API_URL))
+                .json(&DropboxListFolderContinueArg {
+                    cursor: token.as_str().to_string(),
+                }),
+        };
+
+        let response = request
             .header(AUTHORIZATION, self.auth_header())
             .header(CONTENT_TYPE, "application/json")
-            .json(&request_body)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         let status = response.status();
         if !status.is_success() {
             let body = response.text().await.unwrap_or_default();
+            if status == StatusCode::CONFLICT && body.contains("not_found") {
+                return Ok(Page {
+                    ids: Vec::new(),
+                    next: None,
+                });
+            }
             return Err(self.map_error(status, &path, &body));
         }
 
-        let list_result: DropboxListFolderResult = response
+        let result: DropboxListFolderResult = response
             .json()
             .await
             .map_err(|e| Error::Generic(format!("Failed to parse list response: {}", e)))?;
 
-        let mut all_entries = list_result.entries;
-        let mut cursor = list_result.cursor;
-        let mut has_more = list_result.has_more;
-
-        // Continue fetching if there are more results
-        while has_more {
-            if let Some(ref c) = cursor {
-                #[derive(Serialize)]
-                struct ContinueArg {
-                    cursor: String,
-                }
-
-                let continue_body = ContinueArg { cursor: c.clone() };
+        // Folders count towards Dropbox's limit, so a page may hold fewer files
+        let ids = result
+            .entries
+            .into_iter()
+            .filter(|entry| entry.tag == "file")
+            .filter_map(|entry| entry.path_display)
+            .collect();
+        let next = result
+            .cursor
+            .filter(|_| result.has_more)
+            .map(ContinuationToken::new);
 
-                let continue_response = self
-                    .client
-                    .post(&format!("{}/files/list_folder/continue", Self::API_URL))
-                    .header(AUTHORIZATION, self.auth_header())
-                    .header(CONTENT_TYPE, "application/json")
-                    .json(&continue_body)
-                    .send()
-                    .await
-                    .map_err(|e| Error::Connection(Box::new(e)))?;
+        Ok(Page { ids, next })
+    }
 
-                if !continue_response.status().is_success() {
-                    break;
-                }
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        let path = match folder.map(|f| f.trim_matches('/')) {
+            Some(f) if !f.is_empty() => Self::ensure_path_format(f),
+            _ => String::new(),
+        };
 
-                let continue_result: DropboxListFolderResult =
-                    continue_response.json().await.map_err(|e| {
-                        Error::Generic(format!("Failed to parse continue response: {}", e))
-                    })?;
+        let entries = match self.list_folder(path, false).await {
+            Ok(entries) => entries,
+            Err(Error::NotFound(_)) => return Ok(DirListing::default()),
+            Err(e) => return Err(e),
+        };
 
-                all_entries.extend(continue_result.entries);
-                cursor = continue_result.cursor;
-                has_more = continue_result.has_more;
-            } else {
-                break;
+        let mut listing = DirListing::default();
+        for entry in entries {
+            let Some(path) = entry.path_display else {
+                continue;
+            };
+            let id = path.trim_start_matches('/').to_string();
+            match entry.tag.as_str() {
+                "folder" => listing.prefixes.push(id),
+                "file" => listing.files.push(id),
+                _ => {}
             }
         }
 
-        // Filter only files (not folders) and extract paths
-        let file_paths: Vec<String> = all_entries
-            .into_iter()
-            .filter(|entry| entry.tag == "file")
-            .filter_map(|entry| entry.path_display)
-            .collect();
-
-        Ok(Box::pin(stream::iter(file_paths.into_iter().map(Ok))))
+        Ok(listing.normalize())
     }
 }
@@ -1,16 +1,17 @@
-use crate::{Error, Result, Storage};
+use crate::{DirListing, Error, ObjectMeta, PathId, Result, Storage, StoragePath};
 use futures::stream::{self, BoxStream};
 use secrecy::{ExposeSecret, SecretString};
+use std::marker::PhantomData;
 use std::path::PathBuf;
 use std::sync::Arc;
-use suppaftp::AsyncFtpStream;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use suppaftp::{AsyncFtpStream, FtpError};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::sync::Mutex;
 
 /// FTP storage adapter using suppaftp.
 ///
 /// Supports username/password authentication.
-pub struct FtpStorage {
+pub struct FtpStorage<Id = String> {
     host: String,
     port: u16,
     username: String,
@@ -19,9 +20,10 @@ pub struct FtpStorage {
     // FTP connection wrapped in Arc<Mutex> for thread-safe access
     // In production, consider connection pooling
     stream: Arc<Mutex<AsyncFtpStream>>,
+    ids: PhantomData<fn() -> Id>,
 }
 
-impl std::fmt::Debug for FtpStorage {
+impl<Id> std::fmt::Debug for FtpStorage<Id> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FtpStorage")
             .field("host", &self.host)
@@ -80,15 +82,16 @@ impl FtpStorage {
         stream
             .transfer_type(suppaftp::types::FileType::Binary)
             .await
-            .map_err(|e| Error::Generic(format!("Failed to set binary mode: {}", e)))?;
+            .map_err(|e| Self::ftp_error("Failed to set binary mode", e))?;
 
         // Change to base directory if specified
         if let Some(ref base) = base_path {
             let base_str = base.to_string_lossy();
             if !base_str.is_empty() {
-                stream.cwd(&base_str).await.map_err(|e| {
-                    Error::Generic(format!("Failed to change to base directory: {}", e))
-                })?;
+                stream
+                    .cwd(&base_str)
+                    .await
+                    .map_err(|e| Self::ftp_error("Failed to change to base directory", e))?;
             }
         }
 
@@ -99,9 +102,25 @@ impl FtpStorage {
             password,
             base_path,
             stream: Arc::new(Mutex::new(stream)),
+            ids: PhantomData,
         })
     }
 
+    /// Address items by [`StoragePath`] instead of `String`.
+    pub fn with_path_ids(self) -> FtpStorage<StoragePath> {
+        FtpStorage {
+            host: self.host,
+            port: self.port,
+            username: self.username,
+            password: self.password,
+            base_path: self.base_path,
+            stream: self.stream,
+            ids: PhantomData,
+        }
+    }
+}
+
+impl<Id: PathId> FtpStorage<Id> {
     /// Get the full path by combining base_path with the given path
     fn full_path(&self, path: &str) -> String {
         if let Some(base) = &self.base_path {
@@ -111,11 +130,48 @@ impl FtpStorage {
         }
     }
 
+    /// Full path of the item `id`, see [`StoragePath`].
+    fn item_path(&self, id: &str) -> Result<String> {
+        Ok(self.full_path(&crate::path::file_key(id)?))
+    }
+
+    /// Full path of the folder `id`, where `""` and `"/"` name the root.
+    fn folder_path(&self, id: &str) -> Result<String> {
+        match id {
+            "" | "/" => Ok(self
+                .base_path
+                .as_ref()
+                .map(|base| base.to_string_lossy().to_string())
+                .unwrap_or_else(|| ".".to_string())),
+            _ => self.item_path(id),
+        }
+    }
+
     /// Check if error is "not found"
     fn is_not_found_error(error_msg: &str) -> bool {
         error_msg.contains("550") || error_msg.to_lowercase().contains("not found")
     }
 
+    /// Error for a failed FTP command, classified by the server's reply
+    /// code. `550` is left to the callers, as servers use it both for
+    /// missing files and for denied access.
+    fn ftp_error(context: &str, error: FtpError) -> Error {
+        let code = match &error {
+            FtpError::ConnectionError(_) => return Error::Connection(Box::new(error)),
+            FtpError::UnexpectedResponse(response) => response.status.code(),
+            _ => 0,
+        };
+        let message = format!("{context}: {error}");
+        match code {
+            421 | 425 | 426 | 450 | 451 => Error::Unavailable(message),
+            452 | 552 => Error::QuotaExceeded(message),
+            502 | 504 => Error::Unsupported(message),
+            530 | 532 => Error::PermissionDenied(message),
+            553 => Error::InvalidId(message),
+            _ => Error::Generic(message),
+        }
+    }
+
     /// Ensure parent directories exist
     async fn ensure_parent_dir(&self, path: &str) -> Result<()> {
         let path_obj = std::path::Path::new(path);
@@ -140,11 +196,11 @@ impl FtpStorage {
     }
 }
 
-impl Storage for FtpStorage {
-    type Id = String;
+impl<Id: PathId> Storage for FtpStorage<Id> {
+    type Id = Id;
 
     async fn exists(&self, id: &Self::Id) -> Result<bool> {
-        let path = self.full_path(id);
+        let path = self.item_path(id.as_ref())?;
         let mut stream = self.stream.lock().await;
 
         match stream.size(&path).await {
@@ -154,14 +210,14 @@ impl Storage for FtpStorage {
                 if Self::is_not_found_error(&error_msg) {
                     Ok(false)
                 } else {
-                    Err(Error::Generic(format!("Failed to check file: {}", e)))
+                    Err(Self::ftp_error("Failed to check file", e))
                 }
             }
         }
     }
 
     async fn folder_exists(&self, id: &Self::Id) -> Result<bool> {
-        let path = self.full_path(id);
+        let path = self.folder_path(id.as_ref())?;
         let mut stream = self.stream.lock().await;
 
         // Try to change to the directory
@@ -172,40 +228,63 @@ impl Storage for FtpStorage {
                 if Self::is_not_found_error(&error_msg) {
                     Ok(false)
                 } else {
-                    Err(Error::Generic(format!("Failed to check folder: {}", e)))
+                    Err(Self::ftp_error("Failed to check folder", e))
                 }
             }
         }
     }
 
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let path = self.item_path(id.as_ref())?;
+        let mut stream = self.stream.lock().await;
+
+        let size = match stream.size(&path).await {
+            Ok(size) => size as u64,
+            Err(e) => {
+                let error_msg = e.to_string();
+                if Self::is_not_found_error(&error_msg) {
+                    return Err(Error::NotFound(id.to_string()));
+                }
+                return Err(Self::ftp_error("Failed to stat file", e));
+            }
+        };
+
+        // MDTM is an extension some servers don't implement; treat it as optional.
+        let last_modified = stream.mdtm(&path).await.ok().and_then(|t| {
+            let secs = t.and_utc().timestamp();
+            u64::try_from(secs)
+                .ok()
+                .map(|s| std::time::UNIX_EPOCH + std::time::Duration::from_secs(s))
+        });
+
+        Ok(ObjectMeta {
+            size,
+            last_modified,
+            ..Default::default()
+        })
+    }
+
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
         &self,
         id: Self::Id,
-        mut input: R,
+        input: R,
         _len: Option<u64>,
     ) -> Result<()> {
-        let path = self.full_path(&id);
+        let path = self.item_path(id.as_ref())?;
 
         // Ensure parent directory exists
         self.ensure_parent_dir(&path).await?;
 
-        // Read all data into memory
-        let mut buffer = Vec::new();
-        input
-            .read_to_end(&mut buffer)
-            .await
-            .map_err(|e| Error::Io(e))?;
-
         let mut stream = self.stream.lock().await;
 
         // Upload the file - suppaftp expects futures::io::AsyncRead
-        use futures::io::AllowStdIo;
-        let mut cursor = AllowStdIo::new(std::io::Cursor::new(buffer));
+        use tokio_util::compat::TokioAsyncReadCompatExt;
+        let mut input = input.compat();
 
         stream
-            .put_file(&path, &mut cursor)
+            .put_file(&path, &mut input)
             .await
-            .map_err(|e| Error::Generic(format!("Failed to upload file: {}", e)))?;
+            .map_err(|e| Self::ftp_error("Failed to upload file", e))?;
 
         Ok(())
     }
@@ -215,7 +294,7 @@ impl Storage for FtpStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
-        let path = self.full_path(id);
+        let path = self.item_path(id.as_ref())?;
         let mut stream = self.stream.lock().await;
 
         // Retrieve the file into a buffer using retr method
@@ -235,9 +314,9 @@ impl Storage for FtpStorage {
             .map_err(|e| {
                 let error_msg = e.to_string();
                 if Self::is_not_found_error(&error_msg) {
-                    Error::NotFound(id.clone())
+                    Error::NotFound(id.to_string())
                 } else {
-                    Error::Generic(format!("Failed to download file: {}", e))
+                    Self::ftp_error("Failed to download file", e)
                 }
             })?;
 
@@ -249,8 +328,38 @@ impl Storage for FtpStorage {
         Ok(len)
     }
 
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let src_path = self.item_path(src.as_ref())?;
+        let dst_path = self.item_path(dst.as_ref())?;
+
+        // Ensure parent directory exists
+        self.ensure_parent_dir(&dst_path).await?;
+
+        let mut stream = self.stream.lock().await;
+
+        if let Err(e) = stream.size(&src_path).await {
+            let error_msg = e.to_string();
+            if Self::is_not_found_error(&error_msg) {
+                return Err(Error::NotFound(src.to_string()));
+            }
+            return Err(Self::ftp_error("Failed to check file", e));
+        }
+
+        // Many servers refuse RNTO onto an existing file, so clear it first.
+        let _ = stream.rm(&dst_path).await;
+
+        stream.rename(&src_path, &dst_path).await.map_err(|e| {
+            let error_msg = e.to_string();
+            if Self::is_not_found_error(&error_msg) {
+                Error::NotFound(src.to_string())
+            } else {
+                Self::ftp_error("Failed to rename file", e)
+            }
+        })
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
-        let path = self.full_path(id);
+        let path = self.item_path(id.as_ref())?;
         let mut stream = self.stream.lock().await;
 
         match stream.rm(&path).await {
@@ -261,13 +370,63 @@ impl Storage for FtpStorage {
                     // File doesn't exist - idempotent delete
                     Ok(())
                 } else {
-                    Err(Error::Generic(format!("Failed to delete file: {}", e)))
+                    Err(Self::ftp_error("Failed to delete file", e))
+                }
+            }
+        }
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        let root =
+            self.item_path(crate::delete_folder_prefix(folder.as_ref())?.trim_end_matches('/'))?;
+        let mut stream = self.stream.lock().await;
+
+        // Delete files while walking down, then the emptied directories
+        // deepest first.
+        let mut pending = vec![root];
+        let mut dirs = Vec::new();
+        while let Some(dir) = pending.pop() {
+            let lines = match stream.list(Some(&dir)).await {
+                Ok(lines) => lines,
+                // Folder doesn't exist - idempotent delete
+                Err(e) if Self::is_not_found_error(&e.to_string()) => continue,
+                Err(e) => return Err(Self::ftp_error("Failed to list directory", e)),
+            };
+            for line in lines {
+                let Ok(file) = line.parse::<suppaftp::list::File>() else {
+                    continue;
+                };
+                let name = file.name();
+                if name == "." || name == ".." {
+                    continue;
+                }
+                let path = format!("{}/{}", dir.trim_end_matches('/'), name);
+                if file.is_directory() {
+                    pending.push(path);
+                } else {
+                    stream
+                        .rm(&path)
+                        .await
+                        .map_err(|e| Self::ftp_error("Failed to delete file", e))?;
+                }
+            }
+            dirs.push(dir);
+        }
+
+        for dir in dirs.into_iter().rev() {
+            match stream.rmdir(&dir).await {
+                Ok(()) => {}
+                Err(e) if Self::is_not_found_error(&e.to_string()) => {}
+                Err(e) => {
+                    return Err(Self::ftp_error("Failed to remove directory", e));
                 }
             }
         }
+        Ok(())
     }
 
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
+        let prefix = prefix.map(AsRef::as_ref);
         let dir_path = if let Some(p) = prefix {
             self.full_path(p)
         } else {
@@ -285,7 +444,7 @@ impl Storage for FtpStorage {
                     // Directory doesn't exist, return empty list
                     return Ok(Box::pin(stream::iter(Vec::new().into_iter().map(Ok))));
                 } else {
-                    return Err(Error::Generic(format!("Failed to list directory: {}", e)));
+                    return Err(Self::ftp_error("Failed to list directory", e));
                 }
             }
         };
@@ -316,6 +475,56 @@ impl Storage for FtpStorage {
             }
         }
 
-        Ok(Box::pin(stream::iter(results.into_iter().map(Ok))))
+        Ok(Box::pin(stream::iter(
+            results.into_iter().map(|id| crate::path::file_id(&id)),
+        )))
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        let folder = folder.map(|f| f.as_ref().trim_matches('/')).unwrap_or("");
+        let dir_path = if folder.is_empty() {
+            self.base_path
+                .as_ref()
+                .map(|b| b.to_string_lossy().to_string())
+                .unwrap_or_else(|| ".".to_string())
+        } else {
+            self.full_path(folder)
+        };
+
+        let mut stream = self.stream.lock().await;
+
+        let lines = match stream.list(Some(&dir_path)).await {
+            Ok(lines) => lines,
+            Err(e) if Self::is_not_found_error(&e.to_string()) => {
+                return Ok(DirListing::default());
+            }
+            Err(e) => return Err(Self::ftp_error("Failed to list directory", e)),
+        };
+
+        let mut listing = DirListing::default();
+        for line in lines {
+            // LIST output is server-specific; suppaftp understands the
+            // common POSIX and DOS formats and we skip anything else.
+            let Ok(file) = line.parse::<suppaftp::list::File>() else {
+                continue;
+            };
+            let name = file.name();
+            if name == "." || name == ".." {
+                continue;
+            }
+            let id = if folder.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}/{}", folder, name)
+            };
+
+            if file.is_directory() {
+                listing.prefixes.push(crate::path::file_id(&id)?);
+            } else if file.is_file() {
+                listing.files.push(crate::path::file_id(&id)?);
+            }
+        }
+
+        Ok(listing.normalize())
     }
 }
@@ -1,8 +1,16 @@
-use crate::{Error, Result, Storage};
+use crate::multi::{DriveItem, DriveTree};
+use crate::{Error, GetReader, ObjectMeta, PutOptions, Result, Storage};
 use futures::stream::{BoxStream, StreamExt};
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::header::{
+    AUTHORIZATION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, HeaderMap, HeaderValue, LOCATION,
+    RANGE,
+};
 use reqwest::{Client, StatusCode, Url};
 use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// Google Drive storage adapter using native file IDs.
@@ -46,7 +54,8 @@ impl GoogleDriveStorage {
         })
     }
 
-    /// Override the base URL (useful for tests/mocks).
+    /// Override the base URL (useful for tests/mocks). Uploads go to the
+    /// same host, under `/upload` followed by the base URL's path.
     pub fn with_base_url(mut self, base_url: Url) -> Self {
         self.base_url = base_url;
         self
@@ -70,7 +79,9 @@ impl GoogleDriveStorage {
 
     fn file_url(&self, file_id: &str) -> Result<Url> {
         if file_id.is_empty() {
-            return Err(Error::Generic("gdrive file id cannot be empty".to_string()));
+            return Err(Error::InvalidId(
+                "gdrive file id cannot be empty".to_string(),
+            ));
         }
         self.base_url
             .join(&format!("files/{file_id}"))
@@ -83,20 +94,250 @@ impl GoogleDriveStorage {
         Ok(url)
     }
 
-    fn upload_url(&self, file_id: &str) -> Result<Url> {
-        // Use the upload endpoint for updating file content
-        let base = "https://www.googleapis.com/upload/drive/v3/";
-        let url_str = format!("{}files/{}?uploadType=media", base, file_id);
-        Url::parse(&url_str).map_err(|e| Error::Generic(format!("failed to build upload url: {e}")))
+    /// Upload endpoint for the content of `file_id`, or for a new file.
+    ///
+    /// Drive serves uploads under `/upload` followed by the API path.
+    fn upload_url(&self, file_id: Option<&str>, upload_type: &str) -> Result<Url> {
+        let mut base = self.base_url.clone();
+        base.set_path(&format!("/upload{}", self.base_url.path()));
+        let path = match file_id {
+            Some(file_id) => format!("files/{file_id}?uploadType={upload_type}"),
+            None => format!("files?uploadType={upload_type}"),
+        };
+        base.join(&path)
+            .map_err(|e| Error::Generic(format!("failed to build upload url: {e}")))
+    }
+
+    /// Chunk size for resumable uploads, and the most an upload holds in
+    /// memory at once. Must be a multiple of 256 KiB.
+    const UPLOAD_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+    /// Open a resumable upload session with `session`, then upload
+    /// `first_chunk` followed by the rest of `input` one chunk at a time.
+    ///
+    /// Returns the response to the final chunk, which describes the file.
+    async fn resumable_upload<R: AsyncRead + Send + Unpin>(
+        &self,
+        session: reqwest::RequestBuilder,
+        mut input: R,
+        first_chunk: Vec<u8>,
+        len: Option<u64>,
+    ) -> Result<reqwest::Response> {
+        let mut request = session.header("X-Upload-Content-Type", "application/octet-stream");
+        if let Some(len) = len {
+            request = request.header("X-Upload-Content-Length", len.to_string());
+        }
+        let resp = request.send().await.map_err(crate::http::request_error)?;
+        if !resp.status().is_success() {
+            return Err(Self::response_error(resp, "gdrive put failed").await);
+        }
+        let session_url = resp
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| Error::Generic("gdrive upload session has no location".to_string()))?
+            .to_string();
+
+        let mut offset = 0u64;
+        let mut data = first_chunk;
+        loop {
+            let n = data.len() as u64;
+            // A short chunk ends the upload; so does an empty one, which
+            // finalizes an upload whose length was an exact number of chunks.
+            let is_last = n < Self::UPLOAD_CHUNK_SIZE as u64 || len == Some(offset + n);
+            let content_range = match (n, is_last) {
+                (0, _) => format!("bytes */{offset}"),
+                (_, true) => format!("bytes {}-{}/{}", offset, offset + n - 1, offset + n),
+                (_, false) => format!("bytes {}-{}/*", offset, offset + n - 1),
+            };
+
+            // The session URL authorizes the upload on its own.
+            let resp = self
+                .client
+                .put(&session_url)
+                .header(CONTENT_RANGE, content_range)
+                .header(CONTENT_LENGTH, n.to_string())
+                .body(std::mem::take(&mut data))
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            // 308 "Resume Incomplete" acknowledges an intermediate chunk. It
+            // carries no Location header, so reqwest does not follow it.
+            let status = resp.status();
+            if status.is_success() {
+                return Ok(resp);
+            }
+            if status != StatusCode::PERMANENT_REDIRECT || is_last {
+                return Err(Self::response_error(resp, "gdrive put failed").await);
+            }
+
+            offset += n;
+            crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?;
+        }
+    }
+
+    /// Boundary separating the parts of a multipart upload of `data`.
+    ///
+    /// Each upload gets a fresh random boundary, and one that happens to
+    /// occur in `data` is drawn again, so no content can end a part early.
+    /// `RandomState` seeds are random enough for this without an RNG.
+    fn multipart_boundary(data: &[u8]) -> String {
+        use std::hash::BuildHasher;
+        loop {
+            let state = std::collections::hash_map::RandomState::new();
+            let boundary = format!(
+                "stowage-{:016x}{:016x}",
+                state.hash_one(0u8),
+                state.hash_one(1u8)
+            );
+            if !data
+                .windows(boundary.len())
+                .any(|window| window == boundary.as_bytes())
+            {
+                return boundary;
+            }
+        }
+    }
+
+    /// Body of a `multipart/related` upload: the JSON `metadata`, then the
+    /// file content, with the parts separated by `boundary`.
+    fn multipart_body(metadata: &serde_json::Value, data: &[u8], boundary: &str) -> Vec<u8> {
+        let mut body = format!(
+            "--{boundary}\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{metadata}\r\n\
+             --{boundary}\r\nContent-Type: application/octet-stream\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+        body
+    }
+
+    /// Upload `input` as a new file called `name` inside the folder
+    /// `parent_folder_id` and return the new file's ID.
+    ///
+    /// Metadata and content go up together: in a single multipart request
+    /// when the content fits in one upload chunk, through a resumable session
+    /// otherwise. Drive allows several files with the same name in a folder,
+    /// so this always creates a new file; use [`Storage::put`] with a file ID
+    /// to replace the content of an existing one.
+    pub async fn create<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        parent_folder_id: &str,
+        name: &str,
+        mut input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        let metadata = serde_json::json!({
+            "name": name,
+            "parents": [parent_folder_id],
+        });
+
+        let mut data = Vec::new();
+        let resp = if crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?
+            == Self::UPLOAD_CHUNK_SIZE
+        {
+            let url = self.upload_url(None, "resumable")?;
+            let headers = self.auth_headers().await?;
+            let session = self.client.post(url).headers(headers).json(&metadata);
+            self.resumable_upload(session, input, data, len).await?
+        } else {
+            let url = self.upload_url(None, "multipart")?;
+            let headers = self.auth_headers().await?;
+            let boundary = Self::multipart_boundary(&data);
+            let body = Self::multipart_body(&metadata, &data, &boundary);
+
+            let resp = self
+                .client
+                .post(url)
+                .headers(headers)
+                .query(&[("fields", "id")])
+                .header(
+                    CONTENT_TYPE,
+                    format!("multipart/related; boundary={boundary}"),
+                )
+                .header(CONTENT_LENGTH, body.len().to_string())
+                .body(body)
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                return Err(Self::response_error(resp, "gdrive create failed").await);
+            }
+            resp
+        };
+
+        let created: CreatedFile = resp.json().await.map_err(crate::http::request_error)?;
+        Ok(created.id)
+    }
+
+    /// Send a GET for the content of `file_id` and check the response status.
+    async fn download(&self, file_id: &str) -> Result<reqwest::Response> {
+        let url = self.download_url(file_id)?;
+        let headers = self.auth_headers().await?;
+
+        let resp = self
+            .client
+            .get(url)
+            .headers(headers)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            if status == StatusCode::NOT_FOUND {
+                return Err(Error::NotFound(file_id.to_string()));
+            }
+            return Err(Self::response_error(resp, "gdrive get_into failed").await);
+        }
+        Ok(resp)
+    }
+
+    /// Error for a failed response, read from its body.
+    async fn response_error(resp: reqwest::Response, context: &str) -> Error {
+        let status = resp.status();
+        let retry_after = crate::http::retry_after(resp.headers());
+        let text = resp.text().await.unwrap_or_default();
+        Self::map_http_error(status, retry_after, &text, context)
     }
 
-    fn map_http_error(status: StatusCode, body_snippet: &str, context: &str) -> Error {
-        match status {
-            StatusCode::NOT_FOUND => Error::NotFound(context.to_string()),
-            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
-                Error::PermissionDenied(format!("{context}: {status}"))
+    /// Classify a failed status. Drive reports throttling and full quotas as
+    /// `403` with a `reason`, so that is checked first.
+    fn map_http_error(
+        status: StatusCode,
+        retry_after: Option<Duration>,
+        body_snippet: &str,
+        context: &str,
+    ) -> Error {
+        let reason = serde_json::from_str::<serde_json::Value>(body_snippet)
+            .ok()
+            .and_then(|e| Some(e.pointer("/error/errors/0/reason")?.as_str()?.to_string()))
+            .unwrap_or_default();
+        match reason.as_str() {
+            "rateLimitExceeded" | "userRateLimitExceeded" | "sharingRateLimitExceeded" => {
+                Error::RateLimited {
+                    message: format!("{context}: {status} ({reason})"),
+                    retry_after,
+                }
             }
-            _ => Error::Generic(format!("{context}: {status} ({body_snippet})")),
+            "storageQuotaExceeded" | "teamDriveFileLimitExceeded" => {
+                Error::QuotaExceeded(format!("{context}: {status} ({reason})"))
+            }
+            _ => match status {
+                StatusCode::NOT_FOUND => Error::NotFound(context.to_string()),
+                StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+                    Error::PermissionDenied(format!("{context}: {status}"))
+                }
+                _ => crate::http::status_error(
+                    status,
+                    retry_after,
+                    context,
+                    format!("{context}: {status} ({body_snippet})"),
+                ),
+            },
         }
     }
 
@@ -135,12 +376,11 @@ impl GoogleDriveStorage {
             .query(&[("q", &query), ("fields", &"files(id)".to_string())])
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         let status = resp.status();
         if !status.is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            return Err(Self::map_http_error(status, &text, "gdrive search failed"));
+            return Err(Self::response_error(resp, "gdrive search failed").await);
         }
 
         let text = resp.text().await.unwrap_or_default();
@@ -160,6 +400,23 @@ impl GoogleDriveStorage {
     }
 }
 
+/// Subset of the Drive `File` resource returned by `stat`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileMetadata {
+    /// Drive reports sizes as decimal strings; absent for Google Docs files.
+    #[serde(default)]
+    size: Option<String>,
+    #[serde(default)]
+    modified_time: Option<String>,
+    #[serde(default)]
+    md5_checksum: Option<String>,
+    #[serde(default)]
+    mime_type: Option<String>,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+}
+
 impl Storage for GoogleDriveStorage {
     type Id = String;
 
@@ -175,15 +432,12 @@ impl Storage for GoogleDriveStorage {
             .query(&[("fields", "id")])
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match resp.status() {
             StatusCode::OK => Ok(true),
             StatusCode::NOT_FOUND => Ok(false),
-            status => {
-                let text = resp.text().await.unwrap_or_default();
-                Err(Self::map_http_error(status, &text, "gdrive exists failed"))
-            }
+            _ => Err(Self::response_error(resp, "gdrive exists failed").await),
         }
     }
 
@@ -199,7 +453,7 @@ impl Storage for GoogleDriveStorage {
             .query(&[("fields", "id,mimeType")])
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match resp.status() {
             StatusCode::OK => {
@@ -207,15 +461,47 @@ impl Storage for GoogleDriveStorage {
                 Ok(text.contains("application/vnd.google-apps.folder"))
             }
             StatusCode::NOT_FOUND => Ok(false),
-            status => {
-                let text = resp.text().await.unwrap_or_default();
-                Err(Self::map_http_error(
-                    status,
-                    &text,
-                    "gdrive folder_exists failed",
-                ))
+            _ => Err(Self::response_error(resp, "gdrive folder_exists failed").await),
+        }
+    }
+
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let url = self.file_url(id)?;
+        let headers = self.auth_headers().await?;
+
+        let resp = self
+            .client
+            .get(url)
+            .headers(headers)
+            .query(&[(
+                "fields",
+                "size,modifiedTime,md5Checksum,mimeType,properties",
+            )])
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            if status == StatusCode::NOT_FOUND {
+                return Err(Error::NotFound(id.clone()));
             }
+            return Err(Self::response_error(resp, "gdrive stat failed").await);
         }
+
+        let file: FileMetadata = resp.json().await.map_err(crate::http::request_error)?;
+
+        Ok(ObjectMeta {
+            size: file.size.and_then(|s| s.parse().ok()).unwrap_or(0),
+            last_modified: file
+                .modified_time
+                .as_deref()
+                .and_then(crate::time::parse_rfc3339),
+            etag: file.md5_checksum,
+            content_type: file.mime_type,
+            metadata: file.properties,
+            ..Default::default()
+        })
     }
 
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
@@ -224,40 +510,67 @@ impl Storage for GoogleDriveStorage {
         mut input: R,
         len: Option<u64>,
     ) -> Result<()> {
+        // Read one chunk; anything that fits goes up in a single media upload,
+        // larger content through a resumable session.
+        let mut data = Vec::new();
+        if crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?
+            == Self::UPLOAD_CHUNK_SIZE
+        {
+            let url = self.upload_url(Some(&id), "resumable")?;
+            let headers = self.auth_headers().await?;
+            let session = self
+                .client
+                .patch(url)
+                .headers(headers)
+                .header(CONTENT_LENGTH, "0");
+            return self
+                .resumable_upload(session, input, data, len)
+                .await
+                .map(drop);
+        }
+
         // Update existing file content by ID using the upload endpoint
-        let url = self.upload_url(&id)?;
+        let url = self.upload_url(Some(&id), "media")?;
         let headers = self.auth_headers().await?;
 
-        // Read data into memory
-        // Google Drive API requires knowing content length for uploads
-        let mut data = Vec::new();
-        tokio::io::copy(&mut input, &mut data)
-            .await
-            .map_err(|e| Error::Io(e))?;
-
-        let mut request = self
+        let resp = self
             .client
             .patch(url)
             .headers(headers)
             .header(CONTENT_TYPE, "application/octet-stream")
-            .body(data);
-
-        if let Some(len) = len {
-            request = request.header("Content-Length", len.to_string());
-        }
-
-        let resp = request
+            .header(CONTENT_LENGTH, data.len().to_string())
+            .body(data)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         if resp.status().is_success() {
             Ok(())
         } else {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            Err(Self::map_http_error(status, &text, "gdrive put failed"))
+            Err(Self::response_error(resp, "gdrive put failed").await)
+        }
+    }
+
+    /// The content type is stored as the file's `mimeType` and user metadata
+    /// is merged into its `properties`; cache control and content
+    /// disposition are ignored. Preconditions are not supported.
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        if options.has_preconditions() {
+            return Err(Error::Unsupported(
+                "conditional writes are not supported by this backend".to_string(),
+            ));
         }
+        self.put(id.clone(), input, len).await?;
+        if options.has_attributes() {
+            self.set_attributes(&id, &options).await?;
+        }
+        Ok(())
     }
 
     async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
@@ -265,6 +578,34 @@ impl Storage for GoogleDriveStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
+        let resp = self.download(id).await?;
+
+        let mut stream = resp.bytes_stream();
+        let mut total = 0;
+        while let Some(chunk) = stream.next().await {
+            let bytes = chunk.map_err(crate::http::request_error)?;
+            output.write_all(&bytes).await?;
+            total += bytes.len() as u64;
+        }
+        output.flush().await?;
+
+        Ok(total)
+    }
+
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        Ok(crate::io::response_reader(self.download(id).await?))
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        if range.is_empty() {
+            return Ok(0);
+        }
+
         let url = self.download_url(id)?;
         let headers = self.auth_headers().await?;
 
@@ -272,33 +613,19 @@ impl Storage for GoogleDriveStorage {
             .client
             .get(url)
             .headers(headers)
+            .header(RANGE, crate::io::range_header(&range))
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
-        let status = resp.status();
-        if !status.is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            if status == StatusCode::NOT_FOUND {
-                return Err(Error::NotFound(id.clone()));
+        match resp.status() {
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(0),
+            status if status.is_success() => {
+                crate::io::write_range_response(resp, range, output).await
             }
-            return Err(Self::map_http_error(
-                status,
-                &text,
-                "gdrive get_into failed",
-            ));
-        }
-
-        let mut stream = resp.bytes_stream();
-        let mut total = 0;
-        while let Some(chunk) = stream.next().await {
-            let bytes = chunk.map_err(|e| Error::Connection(Box::new(e)))?;
-            output.write_all(&bytes).await?;
-            total += bytes.len() as u64;
+            StatusCode::NOT_FOUND => Err(Error::NotFound(id.clone())),
+            _ => Err(Self::response_error(resp, "gdrive get_range failed").await),
         }
-        output.flush().await?;
-
-        Ok(total)
     }
 
     async fn delete(&self, id: &Self::Id) -> Result<()> {
@@ -311,21 +638,240 @@ impl Storage for GoogleDriveStorage {
             .headers(headers)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match resp.status() {
             StatusCode::NO_CONTENT | StatusCode::OK => Ok(()),
             StatusCode::NOT_FOUND => Ok(()), // idempotent
-            status => {
-                let text = resp.text().await.unwrap_or_default();
-                Err(Self::map_http_error(status, &text, "gdrive delete failed"))
-            }
+            _ => Err(Self::response_error(resp, "gdrive delete failed").await),
         }
     }
 
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        // Deleting a folder also deletes everything in it.
+        self.delete(folder).await
+    }
+
     async fn list(&self, _prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
         Err(Error::Generic(
             "GoogleDriveStorage::list is not implemented yet.".to_string(),
         ))
     }
 }
+
+/// Subset of the Drive `File` resource returned by folder searches.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChildFile {
+    id: String,
+    name: String,
+    mime_type: String,
+}
+
+impl From<ChildFile> for DriveItem {
+    fn from(file: ChildFile) -> Self {
+        Self {
+            is_folder: file.mime_type == GoogleDriveStorage::FOLDER_MIME_TYPE,
+            id: file.id,
+            name: file.name,
+        }
+    }
+}
+
+/// One page of a Drive `files.list` response.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileList {
+    files: Vec<ChildFile>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+/// Response of `files.create`.
+#[derive(Deserialize)]
+struct CreatedFile {
+    id: String,
+}
+
+impl GoogleDriveStorage {
+    const FOLDER_MIME_TYPE: &'static str = "application/vnd.google-apps.folder";
+
+    /// Quote `value` as a string literal for a Drive search query.
+    fn query_literal(value: &str) -> String {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+
+    /// Run a Drive search and return every matching file, following pages.
+    async fn search(&self, query: &str) -> Result<Vec<ChildFile>> {
+        let url = self
+            .base_url
+            .join("files")
+            .map_err(|e| Error::Generic(format!("failed to build search url: {e}")))?;
+
+        let mut files = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let headers = self.auth_headers().await?;
+            let mut request = self.client.get(url.clone()).headers(headers).query(&[
+                ("q", query),
+                ("fields", "nextPageToken,files(id,name,mimeType)"),
+                ("pageSize", "1000"),
+            ]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            let resp = request.send().await.map_err(crate::http::request_error)?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                return Err(Self::response_error(resp, "gdrive search failed").await);
+            }
+            let page: FileList = resp.json().await.map_err(crate::http::request_error)?;
+
+            files.extend(page.files);
+            page_token = page.next_page_token;
+            if page_token.is_none() {
+                return Ok(files);
+            }
+        }
+    }
+
+    /// Create an item from `metadata` without content and return its ID.
+    async fn create_metadata(&self, metadata: serde_json::Value) -> Result<String> {
+        let url = self
+            .base_url
+            .join("files")
+            .map_err(|e| Error::Generic(format!("failed to build create url: {e}")))?;
+        let headers = self.auth_headers().await?;
+
+        let resp = self
+            .client
+            .post(url)
+            .headers(headers)
+            .query(&[("fields", "id")])
+            .json(&metadata)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(Self::response_error(resp, "gdrive create failed").await);
+        }
+        let created: CreatedFile = resp.json().await.map_err(crate::http::request_error)?;
+        Ok(created.id)
+    }
+}
+
+impl DriveTree for GoogleDriveStorage {
+    fn root_folder_id(&self) -> String {
+        "root".to_string()
+    }
+
+    async fn list_children(&self, parent_id: &str) -> Result<Vec<DriveItem>> {
+        let query = format!(
+            "{} in parents and trashed = false",
+            Self::query_literal(parent_id)
+        );
+        let files = self.search(&query).await?;
+        Ok(files.into_iter().map(DriveItem::from).collect())
+    }
+
+    async fn find_child(&self, parent_id: &str, name: &str) -> Result<Option<DriveItem>> {
+        let query = format!(
+            "name = {} and {} in parents and trashed = false",
+            Self::query_literal(name),
+            Self::query_literal(parent_id)
+        );
+        let files = self.search(&query).await?;
+        Ok(files.into_iter().next().map(DriveItem::from))
+    }
+
+    async fn create_folder(&self, parent_id: &str, name: &str) -> Result<String> {
+        self.create_metadata(serde_json::json!({
+            "name": name,
+            "mimeType": Self::FOLDER_MIME_TYPE,
+            "parents": [parent_id],
+        }))
+        .await
+    }
+
+    async fn create_file<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        parent_id: &str,
+        name: &str,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        self.create(parent_id, name, input, len).await
+    }
+
+    async fn set_attributes(&self, id: &str, options: &PutOptions) -> Result<()> {
+        let mut metadata = serde_json::Map::new();
+        if let Some(content_type) = &options.content_type {
+            metadata.insert("mimeType".to_string(), content_type.clone().into());
+        }
+        if !options.user_metadata.is_empty() {
+            metadata.insert(
+                "properties".to_string(),
+                serde_json::json!(options.user_metadata),
+            );
+        }
+        if metadata.is_empty() {
+            return Ok(());
+        }
+
+        let url = self.file_url(id)?;
+        let headers = self.auth_headers().await?;
+        let resp = self
+            .client
+            .patch(url)
+            .headers(headers)
+            .query(&[("fields", "id")])
+            .json(&metadata)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            if status == StatusCode::NOT_FOUND {
+                return Err(Error::NotFound(id.to_string()));
+            }
+            return Err(Self::response_error(resp, "gdrive set_attributes failed").await);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multipart_body() {
+        let metadata = serde_json::json!({ "name": "a.txt", "parents": ["root"] });
+        let boundary = GoogleDriveStorage::multipart_boundary(b"hello");
+        let body = GoogleDriveStorage::multipart_body(&metadata, b"hello", &boundary);
+        let body = String::from_utf8(body).unwrap();
+
+        let parts: Vec<&str> = body.split(&format!("--{boundary}")).collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], "");
+        assert!(parts[1].starts_with("\r\nContent-Type: application/json"));
+        assert!(parts[1].ends_with(&format!("\r\n\r\n{metadata}\r\n")));
+        assert_eq!(
+            parts[2],
+            "\r\nContent-Type: application/octet-stream\r\n\r\nhello\r\n"
+        );
+        assert_eq!(parts[3], "--\r\n");
+    }
+
+    #[test]
+    fn test_multipart_boundary_is_unique() {
+        let first = GoogleDriveStorage::multipart_boundary(b"");
+        let second = GoogleDriveStorage::multipart_boundary(first.as_bytes());
+        assert_ne!(first, second);
+        assert!(!second.contains(&first));
+    }
+}
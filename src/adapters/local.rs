@@ -1,93 +1,553 @@
-use crate::{Error, Result, Storage};
+use crate::{
+    ContinuationToken, DirListing, Error, GetReader, ObjectMeta, Page, PathId, PutOptions, Result,
+    Storage, StoragePath,
+};
 use futures::stream::{self, BoxStream};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::Range;
 use std::path::{Component, Path, PathBuf};
-use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
 
 /// Local filesystem storage using relative paths under a root directory.
 ///
 /// Paths are validated to prevent directory traversal and absolute paths.
+///
+/// The filesystem has no place for content attributes, so those given to
+/// [`put_with`](Storage::put_with) are written to a sidecar file next to the
+/// item, `<name>.meta.stowage`, and reported by `stat`. Sidecars follow
+/// their item through `copy`, `rename` and `delete`, but not through changes
+/// made to the files outside the storage.
+///
+/// Sidecars and in-progress temp files (`<name>.<pid>-<n>.tmp.stowage`,
+/// with the writing process's ID and a counter) are left out of listings.
+/// So that no item is mistaken for one of them, a file or folder whose name
+/// ends in `.stowage` is stored with `.stowage` appended once more: the item
+/// `notes.stowage` is the file `notes.stowage.stowage`.
+///
+/// Paths with [escapes](StoragePath#escapes) are stored as they are. On
+/// Windows, where the backslash separates folders, they are rejected as
+/// [`Error::InvalidId`].
+///
+/// Writes go to a uniquely named temp file first and are moved into place
+/// once complete, so readers never see partial content. Writes through one
+/// storage and its clones are serialized per item, which makes conditional
+/// writes atomic among them; writers in other processes are not covered.
 #[derive(Clone, Debug)]
-pub struct LocalStorage {
+pub struct LocalStorage<Id = String> {
     root: PathBuf,
+    /// Per-item locks held while new content is moved into place.
+    locks: Arc<Mutex<HashMap<PathBuf, Arc<futures::lock::Mutex<()>>>>>,
+    ids: PhantomData<fn() -> Id>,
+}
+
+/// Holds the write lock of one item, and forgets the lock on drop unless
+/// another writer is waiting on it.
+struct WriteLock {
+    guard: Option<futures::lock::OwnedMutexGuard<()>>,
+    lock: Arc<futures::lock::Mutex<()>>,
+    locks: Arc<Mutex<HashMap<PathBuf, Arc<futures::lock::Mutex<()>>>>>,
+    path: PathBuf,
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        drop(self.guard.take());
+        let mut locks = self.locks.lock().expect("poisoned lock");
+        // One reference is the map's, the other is ours.
+        if Arc::strong_count(&self.lock) <= 2 {
+            locks.remove(&self.path);
+        }
+    }
 }
 
 impl LocalStorage {
     /// Create storage rooted at `root`.
     pub fn new(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+        Self {
+            root: root.into(),
+            locks: Arc::default(),
+            ids: PhantomData,
+        }
     }
 
+    /// Address items by [`StoragePath`] instead of `String`.
+    pub fn with_path_ids(self) -> LocalStorage<StoragePath> {
+        LocalStorage {
+            root: self.root,
+            locks: self.locks,
+            ids: PhantomData,
+        }
+    }
+}
+
+impl<Id: PathId> LocalStorage<Id> {
     /// Get the root directory.
     pub fn root(&self) -> &Path {
         &self.root
     }
 
-    fn validate_id(id: &str) -> Result<()> {
-        if id.is_empty() {
-            return Err(Error::Generic("id cannot be empty".into()));
-        }
-
-        let p = Path::new(id);
+    fn validate_id(id: &str) -> Result<StoragePath> {
+        let path = StoragePath::parse(id)?;
 
-        if p.is_absolute() {
+        // Windows drive prefixes like C:\
+        if Path::new(path.as_str())
+            .components()
+            .any(|c| matches!(c, Component::Prefix(_)))
+        {
             return Err(Error::PermissionDenied(format!(
-                "absolute paths are not allowed: {id}"
+                "path prefixes are not allowed: {id}"
             )));
         }
 
-        // Disallow traversal and tricky prefixes.
-        for c in p.components() {
-            match c {
-                Component::ParentDir => {
-                    return Err(Error::PermissionDenied(format!(
-                        "parent dir components ('..') are not allowed: {id}"
-                    )));
+        // Escapes, where the backslash separates folders
+        if Path::new(path.as_str()).components().count() != path.segments().count() {
+            return Err(Error::InvalidId(format!(
+                "file names on this platform cannot hold {id}"
+            )));
+        }
+
+        Ok(path)
+    }
+
+    /// Suffix of the storage's own files. Items whose names end in it get it
+    /// once more on disk.
+    const INTERNAL_SUFFIX: &str = ".stowage";
+
+    /// Suffix of the sidecar file holding an item's content attributes.
+    const SIDECAR_SUFFIX: &str = ".meta.stowage";
+
+    /// File name on disk of the path segment `segment`.
+    fn disk_name(segment: &str) -> Cow<'_, str> {
+        match segment.ends_with(Self::INTERNAL_SUFFIX) {
+            true => Cow::Owned(format!("{segment}{}", Self::INTERNAL_SUFFIX)),
+            false => Cow::Borrowed(segment),
+        }
+    }
+
+    /// Path segment of the file name `name` on disk, or `None` for the
+    /// storage's own files.
+    fn segment_name(name: &str) -> Option<&str> {
+        match name.strip_suffix(Self::INTERNAL_SUFFIX) {
+            None => Some(name),
+            Some(rest) if rest.ends_with(Self::INTERNAL_SUFFIX) => Some(rest),
+            Some(_) => None,
+        }
+    }
+
+    /// Whether the directory entry at `path` is one of the storage's own
+    /// files, to be left out of listings.
+    fn is_internal_path(path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| Self::segment_name(name).is_none())
+    }
+
+    /// Path of the sidecar holding the content attributes of the item at
+    /// `path`.
+    fn sidecar_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(Self::SIDECAR_SUFFIX);
+        path.with_file_name(name)
+    }
+
+    /// Content attributes stored for the item at `path`, if any.
+    async fn read_attributes(path: &Path) -> Result<ObjectMeta> {
+        match tokio::fs::read_to_string(Self::sidecar_path(path)).await {
+            Ok(text) => Ok(Self::decode_attributes(&text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ObjectMeta::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store the content attributes in `options` for the item at `path`, or
+    /// remove its sidecar if there are none.
+    async fn write_attributes(path: &Path, options: &PutOptions) -> Result<()> {
+        let sidecar = Self::sidecar_path(path);
+        if !options.has_attributes() {
+            return Self::remove_sidecar(path).await;
+        }
+        let text = Self::encode_attributes(&options.attributes());
+        let tmp_path = Self::write_temp(&sidecar, &mut text.as_bytes()).await?;
+        if let Err(e) = tokio::fs::rename(&tmp_path, &sidecar).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(Self::write_error(&sidecar, e));
+        }
+        Ok(())
+    }
+
+    /// Remove the sidecar of the item at `path`, if it has one.
+    async fn remove_sidecar(path: &Path) -> Result<()> {
+        match tokio::fs::remove_file(Self::sidecar_path(path)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Sidecar contents for `attributes`: one attribute per line, its name
+    /// and value separated by tabs, with tabs, line breaks and backslashes
+    /// escaped.
+    fn encode_attributes(attributes: &ObjectMeta) -> String {
+        let escape = |value: &str| {
+            value
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+        };
+        let mut text = String::new();
+        for (name, value) in [
+            ("content-type", &attributes.content_type),
+            ("cache-control", &attributes.cache_control),
+            ("content-disposition", &attributes.content_disposition),
+        ] {
+            if let Some(value) = value {
+                text.push_str(&format!("{name}\t{}\n", escape(value)));
+            }
+        }
+        let mut metadata: Vec<_> = attributes.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            text.push_str(&format!("metadata\t{}\t{}\n", escape(key), escape(value)));
+        }
+        text
+    }
+
+    /// Parse sidecar contents written by [`encode_attributes`](Self::encode_attributes).
+    /// Lines it does not recognize are skipped.
+    fn decode_attributes(text: &str) -> ObjectMeta {
+        let unescape = |value: &str| {
+            let mut out = String::with_capacity(value.len());
+            let mut chars = value.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    out.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('t') => out.push('\t'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some(other) => out.push(other),
+                    None => {}
                 }
-                Component::Prefix(_) => {
-                    // Windows drive prefixes like C:\
-                    return Err(Error::PermissionDenied(format!(
-                        "path prefixes are not allowed: {id}"
-                    )));
+            }
+            out
+        };
+        let mut attributes = ObjectMeta::default();
+        for line in text.lines() {
+            let fields: Vec<_> = line.split('\t').map(unescape).collect();
+            match fields.as_slice() {
+                [name, value] if name == "content-type" => {
+                    attributes.content_type = Some(value.clone())
                 }
-                Component::RootDir => {
-                    return Err(Error::PermissionDenied(format!(
-                        "root dir component is not allowed: {id}"
-                    )));
+                [name, value] if name == "cache-control" => {
+                    attributes.cache_control = Some(value.clone())
                 }
-                Component::CurDir | Component::Normal(_) => {}
+                [name, value] if name == "content-disposition" => {
+                    attributes.content_disposition = Some(value.clone())
+                }
+                [name, key, value] if name == "metadata" => {
+                    attributes.metadata.insert(key.clone(), value.clone());
+                }
+                _ => {}
             }
         }
+        attributes
+    }
 
-        Ok(())
+    /// Lock `path` against other writes through this storage and its clones.
+    async fn lock(&self, path: &Path) -> WriteLock {
+        let lock = {
+            let mut locks = self.locks.lock().expect("poisoned lock");
+            Arc::clone(locks.entry(path.to_path_buf()).or_default())
+        };
+        let guard = Arc::clone(&lock).lock_owned().await;
+        WriteLock {
+            guard: Some(guard),
+            lock,
+            locks: Arc::clone(&self.locks),
+            path: path.to_path_buf(),
+        }
     }
 
     fn path_for_id(&self, id: &str) -> Result<PathBuf> {
-        Self::validate_id(id)?;
-        Ok(self.root.join(id))
+        let path = Self::validate_id(id)?;
+        let mut full = self.root.clone();
+        for segment in path.segments() {
+            full.push(&*Self::disk_name(segment));
+        }
+        Ok(full)
     }
 
-    fn id_for_path(&self, p: &Path) -> Result<String> {
+    /// Open the file of item `id` for reading.
+    async fn open_file(&self, id: &str) -> Result<tokio::fs::File> {
+        let path = self.path_for_id(id.as_ref())?;
+        match tokio::fs::File::open(&path).await {
+            Ok(f) => Ok(f),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(Error::NotFound(id.to_string()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn id_for_path(&self, p: &Path) -> Result<Id> {
         let rel = p
             .strip_prefix(&self.root)
             .map_err(|e| Error::Generic(format!("failed to relativize path: {e}")))?;
 
-        let s = rel
-            .to_str()
-            .ok_or_else(|| Error::Generic("non-utf8 path under root".into()))?
-            .replace('\\', "/");
+        let mut segments = Vec::new();
+        for component in rel.components() {
+            let name = component
+                .as_os_str()
+                .to_str()
+                .ok_or_else(|| Error::Generic("non-utf8 path under root".into()))?;
+            segments.push(
+                Self::segment_name(name)
+                    .ok_or_else(|| Error::InvalidId(format!("not an item: {}", p.display())))?,
+            );
+        }
 
-        Self::validate_id(&s)?;
-        Ok(s)
+        crate::path::file_id(&segments.join("/"))
+    }
+
+    /// Classify an I/O error from writing `path`: a full disk, an invalid
+    /// file name or a folder in the way get their own variants, anything
+    /// else stays [`Error::Io`].
+    fn write_error(path: &Path, error: std::io::Error) -> Error {
+        use std::io::ErrorKind;
+        match error.kind() {
+            ErrorKind::StorageFull | ErrorKind::QuotaExceeded => {
+                Error::QuotaExceeded(format!("{}: {error}", path.display()))
+            }
+            ErrorKind::InvalidFilename => Error::InvalidId(path.display().to_string()),
+            ErrorKind::IsADirectory => Error::AlreadyExists(path.display().to_string()),
+            ErrorKind::NotADirectory => Error::Conflict(format!(
+                "a file is in the way of a folder on {}",
+                path.display()
+            )),
+            _ => Error::Io(error),
+        }
     }
 
     async fn ensure_parent_dir(path: &Path) -> Result<()> {
         if let Some(parent) = path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| Self::write_error(path, e))?;
+        }
+        Ok(())
+    }
+
+    /// A temp file name next to `path` that no other writer uses.
+    fn temp_path(path: &Path) -> PathBuf {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(
+            ".{}-{}.tmp.stowage",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        path.with_file_name(name)
+    }
+
+    /// Stream `input` into a new temp file next to `path` and return its path.
+    async fn write_temp<R: AsyncRead + Unpin>(path: &Path, input: &mut R) -> Result<PathBuf> {
+        let tmp_path = Self::temp_path(path);
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+            .await
+            .map_err(|e| Self::write_error(path, e))?;
+
+        // Stream copy.
+        let written = async {
+            tokio::io::copy(input, &mut file).await?;
+            file.flush().await
+        }
+        .await;
+        if let Err(e) = written {
+            drop(file);
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(Self::write_error(path, e));
+        }
+        Ok(tmp_path)
+    }
+
+    /// Move the finished temp file at `tmp_path` to `path`, unless `path`
+    /// already exists. Returns whether it was moved; the temp file is gone
+    /// either way.
+    async fn move_new(tmp_path: &Path, path: &Path) -> Result<bool> {
+        // A hard link fails atomically if the target exists.
+        let linked = tokio::fs::hard_link(tmp_path, path).await;
+        let moved = match linked {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            // Filesystems without hard links: check, then rename. Only
+            // writers outside this storage can slip in between.
+            Err(_) => match tokio::fs::metadata(path).await {
+                Ok(_) => Ok(false),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    match tokio::fs::rename(tmp_path, path).await {
+                        Ok(()) => return Ok(true),
+                        Err(e) => Err(Self::write_error(path, e)),
+                    }
+                }
+                Err(e) => Err(Self::write_error(path, e)),
+            },
+        };
+        let _ = tokio::fs::remove_file(tmp_path).await;
+        moved
+    }
+
+    /// Write `input` to `id` if the preconditions in `options` hold, then
+    /// store its content attributes, all under the item's lock.
+    async fn write<R: AsyncRead + Unpin>(
+        &self,
+        id: Id,
+        mut input: R,
+        options: &PutOptions,
+    ) -> Result<()> {
+        let path = self.path_for_id(id.as_ref())?;
+        Self::ensure_parent_dir(&path).await?;
+        let tmp_path = Self::write_temp(&path, &mut input).await?;
+        let _lock = self.lock(&path).await;
+
+        if !options.has_preconditions() {
+            // Best-effort replace.
+            if tokio::fs::metadata(&path).await.is_ok() {
+                let _ = tokio::fs::remove_file(&path).await;
+            }
+            tokio::fs::rename(&tmp_path, &path)
+                .await
+                .map_err(|e| Self::write_error(&path, e))?;
+        } else if options.if_match.is_none() && options.if_none_match.as_deref() == Some("*") {
+            // Create-only: exactly one of several concurrent writers succeeds.
+            if !Self::move_new(&tmp_path, &path).await? {
+                return Err(Error::PreconditionFailed(id.to_string()));
+            }
+        } else {
+            self.replace_matching(&id, &path, &tmp_path, options)
+                .await?;
+        }
+
+        Self::write_attributes(&path, options).await
+    }
+
+    /// Move `tmp_path` to `path` if the ETag preconditions in `options` hold
+    /// for item `id`. The caller holds the item's lock.
+    async fn replace_matching(
+        &self,
+        id: &Id,
+        path: &Path,
+        tmp_path: &Path,
+        options: &PutOptions,
+    ) -> Result<()> {
+        // ETag comparisons are checked under the item's lock, just before
+        // the new content is moved into place. The filesystem offers no
+        // compare-and-swap, so writers in other processes are not covered.
+        let current = match self.stat(id).await {
+            Ok(meta) => meta.etag,
+            Err(Error::NotFound(_)) => None,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(tmp_path).await;
+                return Err(e);
+            }
+        };
+        if !options.preconditions_hold(current.as_deref()) {
+            let _ = tokio::fs::remove_file(tmp_path).await;
+            return Err(Error::PreconditionFailed(id.to_string()));
+        }
+        if let Err(e) = tokio::fs::rename(tmp_path, path).await {
+            let _ = tokio::fs::remove_file(tmp_path).await;
+            return Err(Self::write_error(path, e));
         }
+
         Ok(())
     }
 
-    async fn list_recursive(&self, base: PathBuf) -> Result<Vec<String>> {
+    /// Walk `base` depth-first in sorted order and return up to `limit` file
+    /// ids that sort after `after`, plus whether any remain.
+    ///
+    /// Ids are ordered component by component, so a page only reads the
+    /// directories along its own path rather than the whole tree.
+    async fn list_page_sorted(
+        &self,
+        base: PathBuf,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<(Vec<Id>, bool)> {
+        let is_after = |id: &str| after.is_none_or(|a| id.split('/').gt(a.split('/')));
+
+        let md = match tokio::fs::metadata(&base).await {
+            Ok(md) => md,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((vec![], false)),
+            Err(e) => return Err(e.into()),
+        };
+        if md.is_file() {
+            let id = self.id_for_path(&base)?;
+            let ids = if is_after(id.as_ref()) {
+                vec![id]
+            } else {
+                vec![]
+            };
+            return Ok((ids, false));
+        }
+
+        let mut out = Vec::new();
+        let mut stack = vec![Self::sorted_entries(&base).await?.into_iter()];
+        while let Some(level) = stack.last_mut() {
+            let Some((path, is_dir)) = level.next() else {
+                stack.pop();
+                continue;
+            };
+            let id = self.id_for_path(&path)?;
+
+            if is_dir {
+                // Skip subtrees holding only ids at or before `after`.
+                let done = after.is_some_and(|a| {
+                    id.as_ref().split('/').lt(a.split('/')) && !a.starts_with(&format!("{id}/"))
+                });
+                if !done {
+                    stack.push(Self::sorted_entries(&path).await?.into_iter());
+                }
+            } else if is_after(id.as_ref()) {
+                if out.len() == limit {
+                    return Ok((out, true));
+                }
+                out.push(id);
+            }
+        }
+
+        Ok((out, false))
+    }
+
+    /// Files and directories directly inside `dir`, sorted by name.
+    async fn sorted_entries(dir: &Path) -> Result<Vec<(PathBuf, bool)>> {
+        let mut entries = Vec::new();
+        let mut rd = tokio::fs::read_dir(dir).await?;
+        while let Some(entry) = rd.next_entry().await? {
+            let ty = entry.file_type().await?;
+            if (ty.is_dir() || ty.is_file()) && !Self::is_internal_path(&entry.path()) {
+                entries.push((entry.path(), ty.is_dir()));
+            }
+        }
+        // In the order of the ids, not of the names on disk.
+        let name = |path: &Path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            Self::segment_name(&name).map(str::to_string)
+        };
+        entries.sort_by_cached_key(|(path, _)| name(path));
+        Ok(entries)
+    }
+
+    async fn list_recursive(&self, base: PathBuf) -> Result<Vec<Id>> {
         // If the base doesn't exist, return empty list.
         let md = match tokio::fs::metadata(&base).await {
             Ok(md) => md,
@@ -108,6 +568,9 @@ impl LocalStorage {
             while let Some(entry) = rd.next_entry().await? {
                 let path = entry.path();
                 let ty = entry.file_type().await?;
+                if Self::is_internal_path(&path) {
+                    continue;
+                }
                 if ty.is_dir() {
                     stack.push(path);
                 } else if ty.is_file() {
@@ -121,11 +584,11 @@ impl LocalStorage {
     }
 }
 
-impl Storage for LocalStorage {
-    type Id = String;
+impl<Id: PathId> Storage for LocalStorage<Id> {
+    type Id = Id;
 
     async fn exists(&self, id: &Self::Id) -> Result<bool> {
-        let path = self.path_for_id(id)?;
+        let path = self.path_for_id(id.as_ref())?;
         match tokio::fs::metadata(&path).await {
             Ok(md) => Ok(md.is_file()),
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
@@ -134,7 +597,7 @@ impl Storage for LocalStorage {
     }
 
     async fn folder_exists(&self, id: &Self::Id) -> Result<bool> {
-        let path = self.path_for_id(id)?;
+        let path = self.path_for_id(id.as_ref())?;
         match tokio::fs::metadata(&path).await {
             Ok(md) => Ok(md.is_dir()),
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
@@ -142,31 +605,51 @@ impl Storage for LocalStorage {
         }
     }
 
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let path = self.path_for_id(id.as_ref())?;
+        let md = match tokio::fs::metadata(&path).await {
+            Ok(md) if md.is_file() => md,
+            Ok(_) => return Err(Error::NotFound(id.to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::NotFound(id.to_string()));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let last_modified = md.modified().ok();
+        // No content hash is available without reading the file, so derive a
+        // weak validator from modification time and size instead.
+        let etag = last_modified
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| format!("{:x}-{:x}", d.as_nanos(), md.len()));
+
+        let attributes = Self::read_attributes(&path).await?;
+        Ok(ObjectMeta {
+            size: md.len(),
+            last_modified,
+            etag,
+            ..attributes
+        })
+    }
+
     async fn put<R: AsyncRead + Send + Unpin>(
         &self,
         id: Self::Id,
-        mut input: R,
+        input: R,
         _len: Option<u64>,
     ) -> Result<()> {
-        let path = self.path_for_id(&id)?;
-        Self::ensure_parent_dir(&path).await?;
-
         // Write to a temp file then rename into place for a more atomic update.
-        let tmp_path = path.with_extension("tmp.stowage");
-        let mut file = tokio::fs::File::create(&tmp_path).await?;
-
-        // Stream copy.
-        tokio::io::copy(&mut input, &mut file).await?;
-        file.flush().await?;
-        drop(file);
-
-        // Best-effort replace.
-        if tokio::fs::metadata(&path).await.is_ok() {
-            let _ = tokio::fs::remove_file(&path).await;
-        }
-        tokio::fs::rename(&tmp_path, &path).await?;
+        self.write(id, input, &PutOptions::new()).await
+    }
 
-        Ok(())
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        _len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        self.write(id, input, &options).await
     }
 
     async fn get_into<W: AsyncWrite + Send + Unpin>(
@@ -174,23 +657,113 @@ impl Storage for LocalStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
-        let path = self.path_for_id(id)?;
-        let mut file = match tokio::fs::File::open(&path).await {
-            Ok(f) => f,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return Err(Error::NotFound(id.clone()));
-            }
-            Err(e) => return Err(e.into()),
-        };
+        let mut file = self.open_file(id.as_ref()).await?;
 
         let n = tokio::io::copy(&mut file, &mut output).await?;
         output.flush().await?;
         Ok(n)
     }
 
+    async fn get_range<W: AsyncWrite + Send + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        mut output: W,
+    ) -> Result<u64> {
+        let mut file = self.open_file(id.as_ref()).await?;
+
+        if range.is_empty() {
+            return Ok(0);
+        }
+
+        file.seek(std::io::SeekFrom::Start(range.start)).await?;
+        let mut limited = file.take(range.end - range.start);
+
+        let n = tokio::io::copy(&mut limited, &mut output).await?;
+        output.flush().await?;
+        Ok(n)
+    }
+
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        Ok(GetReader::new(self.open_file(id.as_ref()).await?))
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let src_path = self.path_for_id(src.as_ref())?;
+        let dst_path = self.path_for_id(dst.as_ref())?;
+
+        if !tokio::fs::metadata(&src_path)
+            .await
+            .map(|md| md.is_file())
+            .unwrap_or(false)
+        {
+            return Err(Error::NotFound(src.to_string()));
+        }
+
+        Self::ensure_parent_dir(&dst_path).await?;
+
+        // Copy into a temp file then rename into place, as `put` does.
+        let tmp_path = Self::temp_path(&dst_path);
+        tokio::fs::copy(&src_path, &tmp_path)
+            .await
+            .map_err(|e| Self::write_error(&dst_path, e))?;
+        let _lock = self.lock(&dst_path).await;
+        tokio::fs::rename(&tmp_path, &dst_path)
+            .await
+            .map_err(|e| Self::write_error(&dst_path, e))?;
+
+        match tokio::fs::copy(Self::sidecar_path(&src_path), Self::sidecar_path(&dst_path)).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::remove_sidecar(&dst_path).await
+            }
+            Err(e) => Err(Self::write_error(&dst_path, e)),
+        }
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let src_path = self.path_for_id(src.as_ref())?;
+        let dst_path = self.path_for_id(dst.as_ref())?;
+
+        if !tokio::fs::metadata(&src_path)
+            .await
+            .map(|md| md.is_file())
+            .unwrap_or(false)
+        {
+            return Err(Error::NotFound(src.to_string()));
+        }
+
+        Self::ensure_parent_dir(&dst_path).await?;
+        let _lock = self.lock(&dst_path).await;
+        tokio::fs::rename(&src_path, &dst_path)
+            .await
+            .map_err(|e| Self::write_error(&dst_path, e))?;
+
+        let renamed =
+            tokio::fs::rename(Self::sidecar_path(&src_path), Self::sidecar_path(&dst_path)).await;
+        match renamed {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::remove_sidecar(&dst_path).await
+            }
+            Err(e) => Err(Self::write_error(&dst_path, e)),
+        }
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
-        let path = self.path_for_id(id)?;
-        match tokio::fs::remove_file(path).await {
+        let path = self.path_for_id(id.as_ref())?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Self::remove_sidecar(&path).await
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        crate::delete_folder_prefix(folder.as_ref())?;
+        let path = self.path_for_id(folder.as_ref())?;
+        match tokio::fs::remove_dir_all(path).await {
             Ok(()) => Ok(()),
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
             Err(e) => Err(e.into()),
@@ -199,7 +772,7 @@ impl Storage for LocalStorage {
 
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
         let base = match prefix {
-            Some(p) => self.path_for_id(p)?,
+            Some(p) => self.path_for_id(p.as_ref())?,
             None => self.root.clone(),
         };
 
@@ -208,4 +781,56 @@ impl Storage for LocalStorage {
         let ids = self.list_recursive(base).await?;
         Ok(Box::pin(stream::iter(ids.into_iter().map(Ok))))
     }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        let base = match prefix {
+            Some(p) => self.path_for_id(p.as_ref())?,
+            None => self.root.clone(),
+        };
+
+        // The token is the last id handed out.
+        let after = continuation.as_ref().map(ContinuationToken::as_str);
+        let (ids, more) = self.list_page_sorted(base, after, page_size.max(1)).await?;
+        let next = more.then(|| ContinuationToken::new(ids[ids.len() - 1].to_string()));
+
+        Ok(Page { ids, next })
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        let dir = match folder.map(|f| f.as_ref().trim_end_matches('/')) {
+            Some(f) if !f.is_empty() => self.path_for_id(f)?,
+            _ => self.root.clone(),
+        };
+
+        let mut rd = match tokio::fs::read_dir(&dir).await {
+            Ok(rd) => rd,
+            Err(e)
+                if e.kind() == std::io::ErrorKind::NotFound
+                    || e.kind() == std::io::ErrorKind::NotADirectory =>
+            {
+                return Ok(DirListing::default());
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut listing = DirListing::default();
+        while let Some(entry) = rd.next_entry().await? {
+            let ty = entry.file_type().await?;
+            if Self::is_internal_path(&entry.path()) {
+                continue;
+            }
+            if ty.is_dir() {
+                listing.prefixes.push(self.id_for_path(&entry.path())?);
+            } else if ty.is_file() {
+                listing.files.push(self.id_for_path(&entry.path())?);
+            }
+        }
+
+        Ok(listing.normalize())
+    }
 }
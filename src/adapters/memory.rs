@@ -1,16 +1,47 @@
-use crate::{Error, Result, Storage};
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Error, GetReader, ObjectMeta, Page, PutOptions,
+    Result, Storage,
+};
 use futures::stream::{self, BoxStream};
 use std::collections::HashMap;
 use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
 use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
 use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 /// In-memory storage using a `HashMap<String, Vec<u8>>`.
 ///
-/// Intended for tests and local development.
+/// Intended for tests and local development. Content attributes given to
+/// [`put_with`](Storage::put_with) are kept with each item and reported by
+/// `stat`.
 #[derive(Clone, Default)]
 pub struct MemoryStorage {
-    inner: Arc<RwLock<HashMap<String, Vec<u8>>>>,
+    inner: Arc<RwLock<HashMap<String, Entry>>>,
+}
+
+/// A stored object together with the metadata reported by `stat`.
+#[derive(Clone)]
+struct Entry {
+    data: Vec<u8>,
+    modified: SystemTime,
+    etag: String,
+    /// Content attributes from `put_with`.
+    attributes: ObjectMeta,
+}
+
+impl Entry {
+    fn new(data: Vec<u8>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        Self {
+            etag: format!("{:016x}", hasher.finish()),
+            modified: SystemTime::now(),
+            data,
+            attributes: ObjectMeta::default(),
+        }
+    }
 }
 
 impl MemoryStorage {
@@ -21,6 +52,10 @@ impl MemoryStorage {
 
     /// Create storage from an existing map.
     pub fn from_map(map: HashMap<String, Vec<u8>>) -> Self {
+        let map = map
+            .into_iter()
+            .map(|(id, data)| (id, Entry::new(data)))
+            .collect();
         Self {
             inner: Arc::new(RwLock::new(map)),
         }
@@ -45,11 +80,26 @@ impl MemoryStorage {
     pub fn get_bytes(&self, id: &str) -> Result<Vec<u8>> {
         let map = self.inner.read().expect("poisoned lock");
         map.get(id)
-            .cloned()
+            .map(|entry| entry.data.clone())
             .ok_or_else(|| Error::NotFound(id.to_string()))
     }
 }
 
+/// Read `input` to the end, reserving `len` bytes up front when known so the
+/// buffer is not repeatedly reallocated (and briefly doubled) while growing.
+async fn read_with_hint<I>(input: &mut I, len: Option<u64>) -> Result<Vec<u8>>
+where
+    I: tokio::io::AsyncRead + Unpin,
+{
+    let mut buf = Vec::new();
+    if let Some(len) = len.and_then(|len| usize::try_from(len).ok()) {
+        // The hint is untrusted, so don't abort on an absurd value.
+        let _ = buf.try_reserve_exact(len);
+    }
+    input.read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
 impl fmt::Debug for MemoryStorage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Avoid dumping potentially large in-memory contents.
@@ -78,15 +128,51 @@ impl Storage for MemoryStorage {
         Ok(map.keys().any(|key| key.starts_with(&prefix)))
     }
 
-    async fn put<I>(&self, id: Self::Id, mut input: I, _len: Option<u64>) -> Result<()>
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let map = self.inner.read().expect("poisoned lock");
+        let entry = map.get(id).ok_or_else(|| Error::NotFound(id.clone()))?;
+        Ok(ObjectMeta {
+            size: entry.data.len() as u64,
+            last_modified: Some(entry.modified),
+            etag: Some(entry.etag.clone()),
+            ..entry.attributes.clone()
+        })
+    }
+
+    async fn put<I>(&self, id: Self::Id, mut input: I, len: Option<u64>) -> Result<()>
+    where
+        I: tokio::io::AsyncRead + Send + Unpin,
+    {
+        let buf = read_with_hint(&mut input, len).await?;
+
+        let mut map = self.inner.write().expect("poisoned lock");
+        map.insert(id, Entry::new(buf));
+        Ok(())
+    }
+
+    async fn put_with<I>(
+        &self,
+        id: Self::Id,
+        mut input: I,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()>
     where
         I: tokio::io::AsyncRead + Send + Unpin,
     {
-        let mut buf = Vec::new();
-        input.read_to_end(&mut buf).await?;
+        let buf = read_with_hint(&mut input, len).await?;
 
+        // Check and insert under the same lock so concurrent writers can't interleave.
         let mut map = self.inner.write().expect("poisoned lock");
-        map.insert(id, buf);
+        let current = map.get(&id).map(|entry| entry.etag.as_str());
+        if !options.preconditions_hold(current) {
+            return Err(Error::PreconditionFailed(id));
+        }
+        let entry = Entry {
+            attributes: options.attributes(),
+            ..Entry::new(buf)
+        };
+        map.insert(id, entry);
         Ok(())
     }
 
@@ -97,7 +183,7 @@ impl Storage for MemoryStorage {
         let bytes = {
             let map = self.inner.read().expect("poisoned lock");
             map.get(id)
-                .cloned()
+                .map(|entry| entry.data.clone())
                 .ok_or_else(|| Error::NotFound(id.clone()))?
         };
 
@@ -106,12 +192,77 @@ impl Storage for MemoryStorage {
         Ok(bytes.len() as u64)
     }
 
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        let bytes = {
+            let map = self.inner.read().expect("poisoned lock");
+            map.get(id)
+                .map(|entry| entry.data.clone())
+                .ok_or_else(|| Error::NotFound(id.clone()))?
+        };
+        Ok(GetReader::new(std::io::Cursor::new(bytes)))
+    }
+
+    async fn get_range<O>(&self, id: &Self::Id, range: Range<u64>, mut output: O) -> Result<u64>
+    where
+        O: AsyncWrite + Send + Unpin,
+    {
+        let bytes = {
+            let map = self.inner.read().expect("poisoned lock");
+            let entry = map.get(id).ok_or_else(|| Error::NotFound(id.clone()))?;
+            let len = entry.data.len() as u64;
+            let start = range.start.min(len);
+            let end = range.end.min(len).max(start);
+            entry.data[start as usize..end as usize].to_vec()
+        };
+
+        output.write_all(&bytes).await?;
+        output.flush().await?;
+        Ok(bytes.len() as u64)
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let mut map = self.inner.write().expect("poisoned lock");
+        let entry = map
+            .get(src)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(src.clone()))?;
+        map.insert(dst, entry);
+        Ok(())
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let mut map = self.inner.write().expect("poisoned lock");
+        let entry = map
+            .remove(src)
+            .ok_or_else(|| Error::NotFound(src.clone()))?;
+        map.insert(dst, entry);
+        Ok(())
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
         let mut map = self.inner.write().expect("poisoned lock");
         map.remove(id);
         Ok(())
     }
 
+    async fn delete_many(&self, ids: Vec<Self::Id>) -> Result<BatchResult<Self::Id>> {
+        let mut map = self.inner.write().expect("poisoned lock");
+        for id in &ids {
+            map.remove(id);
+        }
+        Ok(BatchResult {
+            succeeded: ids,
+            failed: Vec::new(),
+        })
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        let prefix = crate::delete_folder_prefix(folder)?;
+        let mut map = self.inner.write().expect("poisoned lock");
+        map.retain(|key, _| !key.starts_with(&prefix));
+        Ok(())
+    }
+
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
         let prefix = prefix.cloned();
         let map = self.inner.read().expect("poisoned lock");
@@ -126,4 +277,64 @@ impl Storage for MemoryStorage {
 
         Ok(Box::pin(stream::iter(iter.map(Ok))))
     }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<crate::Entry<Self::Id>>>> {
+        let map = self.inner.read().expect("poisoned lock");
+
+        let mut entries: Vec<crate::Entry<String>> = map
+            .iter()
+            .filter(|(id, _)| prefix.is_none_or(|p| id.starts_with(p.as_str())))
+            .map(|(id, entry)| crate::Entry {
+                id: id.clone(),
+                size: entry.data.len() as u64,
+                modified: Some(entry.modified),
+                etag: Some(entry.etag.clone()),
+                is_dir: false,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(Box::pin(stream::iter(entries.into_iter().map(Ok))))
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        let page_size = page_size.max(1);
+        let map = self.inner.read().expect("poisoned lock");
+
+        // The token is the last id handed out, so pages stay consistent
+        // while items are added or removed.
+        let mut ids: Vec<&String> = map
+            .keys()
+            .filter(|id| prefix.is_none_or(|p| id.starts_with(p.as_str())))
+            .filter(|id| {
+                continuation
+                    .as_ref()
+                    .is_none_or(|t| id.as_str() > t.as_str())
+            })
+            .collect();
+        ids.sort();
+
+        let more = ids.len() > page_size;
+        let ids: Vec<String> = ids.into_iter().take(page_size).cloned().collect();
+        let next = more.then(|| ContinuationToken::new(ids[ids.len() - 1].clone()));
+
+        Ok(Page { ids, next })
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        let folder = crate::dir_prefix(folder.map(String::as_str));
+        let map = self.inner.read().expect("poisoned lock");
+        Ok(DirListing::from_keys(
+            &folder,
+            map.keys().map(String::as_str),
+        ))
+    }
 }
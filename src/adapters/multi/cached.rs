@@ -0,0 +1,720 @@
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, Error, GetReader, ObjectMeta, Page, Presign,
+    PresignMethod, PresignedRequest, PutOptions, Result, Storage,
+};
+use futures::stream::BoxStream;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::io;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tracing;
+
+/// Serves reads from a fast cache storage in front of a slower origin.
+///
+/// Downloads are answered from the cache when it holds the item and fetched
+/// from the origin into the cache when it does not. Concurrent misses for
+/// the same item share one fetch. Writes go to the origin and drop the
+/// item from the cache, so the next read fetches the new content.
+///
+/// The cache is bounded: once the cached items exceed the capacity, the
+/// least recently read ones are deleted from the cache storage, and items
+/// larger than the capacity are served from the origin without caching.
+/// Such items are remembered, so later reads go straight to the origin
+/// instead of streaming them into the cache again. With a TTL, items are
+/// deleted from the cache and fetched again once they are older than it,
+/// and oversized items are tried again. With a negative TTL, items found
+/// missing on the origin are reported missing by `exists` and downloads
+/// without asking the origin until it passes.
+///
+/// `stat` and listings always go to the origin, since the cache reports its
+/// own ETags and timestamps. The cache storage should be dedicated to this
+/// wrapper: items it held before are not counted or trusted.
+///
+/// ```
+/// # use stowage::{Storage, StorageExt};
+/// # use stowage::multi::CachedStorage;
+/// # use stowage::MemoryStorage;
+/// # use std::time::Duration;
+/// # async fn example() -> stowage::Result<()> {
+/// let storage = CachedStorage::new(MemoryStorage::new(), MemoryStorage::new())
+///     .with_capacity(64 * 1024 * 1024)
+///     .with_ttl(Duration::from_secs(300));
+/// storage.put_bytes("file.txt".to_string(), b"data").await?;
+/// // The first read fills the cache, later ones are served from it
+/// let data = storage.get_bytes(&"file.txt".to_string()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CachedStorage<O, C>
+where
+    O: Storage,
+    C: Storage<Id = O::Id>,
+{
+    origin: O,
+    cache: C,
+    capacity: u64,
+    ttl: Option<Duration>,
+    negative_ttl: Option<Duration>,
+    index: Mutex<CacheIndex<O::Id>>,
+    /// Per-item locks held while an item is fetched or invalidated.
+    fills: Mutex<HashMap<O::Id, Arc<futures::lock::Mutex<()>>>>,
+}
+
+impl<O, C> CachedStorage<O, C>
+where
+    O: Storage,
+    O::Id: Eq + Hash,
+    C: Storage<Id = O::Id>,
+{
+    /// Put `cache` in front of `origin`, with a capacity of 256 MiB, no TTL
+    /// and no negative caching.
+    pub fn new(origin: O, cache: C) -> Self {
+        Self {
+            origin,
+            cache,
+            capacity: 256 * 1024 * 1024,
+            ttl: None,
+            negative_ttl: None,
+            index: Mutex::new(CacheIndex::default()),
+            fills: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the most bytes kept in the cache (default: 256 MiB).
+    pub fn with_capacity(mut self, bytes: u64) -> Self {
+        self.capacity = bytes;
+        self
+    }
+
+    /// Fetch items again once they have been cached this long (default:
+    /// cached items stay valid until evicted or overwritten).
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Remember items missing on the origin for this long (default:
+    /// missing items are not remembered).
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = Some(ttl);
+        self
+    }
+
+    /// Get a reference to the origin storage.
+    pub fn origin(&self) -> &O {
+        &self.origin
+    }
+
+    /// Get a reference to the cache storage.
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// Get the most bytes kept in the cache.
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Get the number of bytes currently cached.
+    pub fn cached_bytes(&self) -> u64 {
+        self.index.lock().unwrap().bytes
+    }
+
+    /// Drop every item from the cache.
+    pub async fn clear(&self) {
+        let ids = self.index.lock().unwrap().clear();
+        self.discard(ids).await;
+    }
+
+    fn lookup(&self, id: &O::Id) -> Lookup {
+        self.index
+            .lock()
+            .unwrap()
+            .lookup(id, Instant::now(), self.ttl, self.negative_ttl)
+    }
+
+    /// Get the lock serializing fetches and invalidations of `id`.
+    fn fill_lock(&self, id: &O::Id) -> Arc<futures::lock::Mutex<()>> {
+        let mut fills = self.fills.lock().unwrap();
+        Arc::clone(fills.entry(id.clone()).or_default())
+    }
+
+    /// Forget the lock for `id` unless another task is waiting on it.
+    fn release_fill_lock(&self, id: &O::Id, lock: Arc<futures::lock::Mutex<()>>) {
+        let mut fills = self.fills.lock().unwrap();
+        // One reference is the map's, the other is ours.
+        if Arc::strong_count(&lock) <= 2 {
+            fills.remove(id);
+        }
+    }
+
+    /// Delete evicted or invalidated items from the cache storage.
+    async fn discard(&self, ids: Vec<O::Id>) {
+        for id in ids {
+            match self.cache.delete(&id).await {
+                Ok(()) | Err(Error::NotFound(_)) => {}
+                Err(e) => tracing::warn!(?id, error = ?e, "Failed to delete item from cache"),
+            }
+        }
+    }
+
+    /// Make sure `id` is in the cache, fetching it from the origin if needed.
+    ///
+    /// Returns `false` if the item is too large to cache.
+    async fn fill(&self, id: &O::Id) -> Result<bool> {
+        match self.lookup(id) {
+            Lookup::Hit => return Ok(true),
+            Lookup::Oversize => return Ok(false),
+            Lookup::Missing => return Err(Error::NotFound(format!("{id:?}"))),
+            Lookup::Expired | Lookup::Unknown => {}
+        }
+
+        let lock = self.fill_lock(id);
+        let result = {
+            let _guard = lock.lock().await;
+            self.fill_locked(id).await
+        };
+        self.release_fill_lock(id, lock);
+        result
+    }
+
+    async fn fill_locked(&self, id: &O::Id) -> Result<bool> {
+        // Another task may have fetched the item while we waited.
+        match self.lookup(id) {
+            Lookup::Hit => return Ok(true),
+            Lookup::Oversize => return Ok(false),
+            Lookup::Missing => return Err(Error::NotFound(format!("{id:?}"))),
+            Lookup::Expired => {
+                // Deleted under the lock, so a fresh copy is not lost.
+                self.index.lock().unwrap().remove(id);
+                self.discard(vec![id.clone()]).await;
+            }
+            Lookup::Unknown => {}
+        }
+
+        tracing::debug!(?id, "Cache miss, fetching from origin");
+        let reader = match self.origin.get_reader(id).await {
+            Ok(reader) => reader,
+            Err(e) => {
+                if matches!(e, Error::NotFound(_)) {
+                    self.remember_missing(id);
+                }
+                return Err(e);
+            }
+        };
+
+        let mut limited = LimitedReader {
+            inner: reader,
+            read: 0,
+            limit: self.capacity,
+        };
+        if let Err(e) = self.cache.put(id.clone(), &mut limited, None).await {
+            if limited.read > self.capacity {
+                tracing::debug!(?id, "Item exceeds cache capacity, not caching");
+                self.discard(vec![id.clone()]).await;
+                self.index
+                    .lock()
+                    .unwrap()
+                    .insert_oversize(id.clone(), Instant::now());
+                return Ok(false);
+            }
+            // Recover the origin's error if reading from it failed.
+            return Err(match e {
+                Error::Io(e) => Error::from_io(e),
+                e => e,
+            });
+        }
+
+        let evicted = self.index.lock().unwrap().insert(
+            id.clone(),
+            limited.read,
+            Instant::now(),
+            self.capacity,
+        );
+        if !evicted.is_empty() {
+            tracing::debug!(count = evicted.len(), "Evicting items from cache");
+        }
+        self.discard(evicted).await;
+        Ok(true)
+    }
+
+    fn remember_missing(&self, id: &O::Id) {
+        if self.negative_ttl.is_some() {
+            self.index
+                .lock()
+                .unwrap()
+                .insert_missing(id.clone(), Instant::now());
+        }
+    }
+
+    /// Drop `ids` from the cache after they changed on the origin.
+    ///
+    /// Holds each item's fill lock, so a fetch of the old content that was
+    /// already running cannot put it back afterwards.
+    async fn invalidate(&self, ids: &[O::Id]) {
+        for id in ids {
+            let lock = self.fill_lock(id);
+            {
+                let _guard = lock.lock().await;
+                let cached = self.index.lock().unwrap().remove(id);
+                if cached {
+                    self.discard(vec![id.clone()]).await;
+                }
+            }
+            self.release_fill_lock(id, lock);
+        }
+    }
+
+    /// Drop every item from the cache after a change on the origin that
+    /// cannot be traced to individual items.
+    async fn invalidate_all(&self) {
+        self.clear().await;
+    }
+
+    /// Forget `id` after the cache storage lost it, e.g. to an eviction
+    /// that raced with the read.
+    fn forget(&self, id: &O::Id) {
+        self.index.lock().unwrap().remove(id);
+    }
+}
+
+impl<O, C> Storage for CachedStorage<O, C>
+where
+    O: Storage,
+    O::Id: Eq + Hash,
+    C: Storage<Id = O::Id>,
+{
+    type Id = O::Id;
+
+    async fn exists(&self, id: &Self::Id) -> Result<bool> {
+        match self.lookup(id) {
+            Lookup::Hit | Lookup::Oversize => return Ok(true),
+            Lookup::Missing => return Ok(false),
+            Lookup::Expired | Lookup::Unknown => {}
+        }
+        let exists = self.origin.exists(id).await?;
+        if !exists {
+            self.remember_missing(id);
+        }
+        Ok(exists)
+    }
+
+    async fn folder_exists(&self, id: &Self::Id) -> Result<bool> {
+        self.origin.folder_exists(id).await
+    }
+
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        self.origin.stat(id).await
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        let result = self.origin.put(id.clone(), input, len).await;
+        self.invalidate(&[id]).await;
+        result
+    }
+
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        let result = self.origin.put_with(id.clone(), input, len, options).await;
+        self.invalidate(&[id]).await;
+        result
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        output: W,
+    ) -> Result<u64> {
+        if !self.fill(id).await? {
+            return self.origin.get_into(id, output).await;
+        }
+        let mut output = output;
+        match self.cache.get_into(id, &mut output).await {
+            Err(Error::NotFound(_)) => {
+                self.forget(id);
+                self.origin.get_into(id, output).await
+            }
+            result => result,
+        }
+    }
+
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        if !self.fill(id).await? {
+            return self.origin.get_reader(id).await;
+        }
+        match self.cache.get_reader(id).await {
+            Err(Error::NotFound(_)) => {
+                self.forget(id);
+                self.origin.get_reader(id).await
+            }
+            result => result,
+        }
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        if !self.fill(id).await? {
+            return self.origin.get_range(id, range, output).await;
+        }
+        let mut output = output;
+        match self.cache.get_range(id, range.clone(), &mut output).await {
+            Err(Error::NotFound(_)) => {
+                self.forget(id);
+                self.origin.get_range(id, range, output).await
+            }
+            result => result,
+        }
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let result = self.origin.copy(src, dst.clone()).await;
+        self.invalidate(&[dst]).await;
+        result
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let result = self.origin.rename(src, dst.clone()).await;
+        self.invalidate(&[src.clone(), dst]).await;
+        result
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<()> {
+        let result = self.origin.delete(id).await;
+        self.invalidate(std::slice::from_ref(id)).await;
+        result
+    }
+
+    async fn delete_many(&self, ids: Vec<Self::Id>) -> Result<BatchResult<Self::Id>> {
+        let result = self.origin.delete_many(ids.clone()).await;
+        self.invalidate(&ids).await;
+        result
+    }
+
+    async fn delete_prefix(&self, prefix: &Self::Id) -> Result<BatchResult<Self::Id>> {
+        // Which cached items the prefix covers is up to the origin.
+        let result = self.origin.delete_prefix(prefix).await;
+        self.invalidate_all().await;
+        result
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        let result = self.origin.delete_folder(folder).await;
+        self.invalidate_all().await;
+        result
+    }
+
+    async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
+        self.origin.list(prefix).await
+    }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        self.origin.list_with_meta(prefix).await
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        self.origin.list_page(prefix, page_size, continuation).await
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        self.origin.list_dir(folder).await
+    }
+}
+
+/// URLs are signed by the origin, so clients bypass the cache.
+impl<O, C> Presign for CachedStorage<O, C>
+where
+    O: Presign,
+    O::Id: Eq + Hash,
+    C: Storage<Id = O::Id>,
+{
+    async fn presign(
+        &self,
+        id: &Self::Id,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest> {
+        self.origin.presign(id, method, expires_in).await
+    }
+}
+
+/// What the index knows about an item.
+enum Lookup {
+    /// Cached and fresh.
+    Hit,
+    /// Cached but older than the TTL; the copy should be deleted.
+    Expired,
+    /// Found too large to cache.
+    Oversize,
+    /// Recently found missing on the origin.
+    Missing,
+    /// Ask the origin.
+    Unknown,
+}
+
+struct CachedItem {
+    size: u64,
+    filled_at: Instant,
+    /// Position in [`CacheIndex::recency`].
+    tick: u64,
+}
+
+/// Bookkeeping for the items in the cache storage.
+#[derive(Debug)]
+struct CacheIndex<Id> {
+    items: HashMap<Id, CachedItem>,
+    /// Cached items by last use, least recent first.
+    recency: BTreeMap<u64, Id>,
+    /// Items found missing on the origin, with when they were.
+    missing: HashMap<Id, Instant>,
+    /// Items found too large to cache, with when they were.
+    oversize: HashMap<Id, Instant>,
+    bytes: u64,
+    next_tick: u64,
+}
+
+impl Debug for CachedItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedItem")
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Id> Default for CacheIndex<Id> {
+    fn default() -> Self {
+        Self {
+            items: HashMap::new(),
+            recency: BTreeMap::new(),
+            missing: HashMap::new(),
+            oversize: HashMap::new(),
+            bytes: 0,
+            next_tick: 0,
+        }
+    }
+}
+
+impl<Id: Clone + Eq + Hash> CacheIndex<Id> {
+    /// Look `id` up, marking it as recently used on a hit.
+    ///
+    /// Expired items are reported, not dropped, so the caller can delete
+    /// their copy; other expired entries are dropped.
+    fn lookup(
+        &mut self,
+        id: &Id,
+        now: Instant,
+        ttl: Option<Duration>,
+        negative_ttl: Option<Duration>,
+    ) -> Lookup {
+        let expired = |since: Instant| ttl.is_some_and(|ttl| now.duration_since(since) >= ttl);
+        if let Some(item) = self.items.get_mut(id) {
+            if expired(item.filled_at) {
+                return Lookup::Expired;
+            }
+            self.recency.remove(&item.tick);
+            item.tick = self.next_tick;
+            self.recency.insert(self.next_tick, id.clone());
+            self.next_tick += 1;
+            return Lookup::Hit;
+        }
+        if let Some(&since) = self.missing.get(id) {
+            if negative_ttl.is_some_and(|ttl| now.duration_since(since) < ttl) {
+                return Lookup::Missing;
+            }
+            self.missing.remove(id);
+        }
+        if let Some(&since) = self.oversize.get(id) {
+            if !expired(since) {
+                return Lookup::Oversize;
+            }
+            self.oversize.remove(id);
+        }
+        Lookup::Unknown
+    }
+
+    /// Record `id` as cached and return the items to evict to stay within
+    /// `capacity`, least recently used first.
+    fn insert(&mut self, id: Id, size: u64, now: Instant, capacity: u64) -> Vec<Id> {
+        self.remove(&id);
+        self.items.insert(
+            id.clone(),
+            CachedItem {
+                size,
+                filled_at: now,
+                tick: self.next_tick,
+            },
+        );
+        self.recency.insert(self.next_tick, id);
+        self.next_tick += 1;
+        self.bytes += size;
+
+        let mut evicted = Vec::new();
+        while self.bytes > capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            if let Some(item) = self.items.remove(&oldest) {
+                self.bytes -= item.size;
+            }
+            evicted.push(oldest);
+        }
+        evicted
+    }
+
+    fn insert_missing(&mut self, id: Id, now: Instant) {
+        self.missing.insert(id, now);
+    }
+
+    /// Record `id` as too large to cache.
+    fn insert_oversize(&mut self, id: Id, now: Instant) {
+        self.remove(&id);
+        self.oversize.insert(id, now);
+    }
+
+    /// Forget `id`, returning whether it was cached.
+    fn remove(&mut self, id: &Id) -> bool {
+        self.missing.remove(id);
+        self.oversize.remove(id);
+        match self.items.remove(id) {
+            Some(item) => {
+                self.recency.remove(&item.tick);
+                self.bytes -= item.size;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget everything, returning the items that were cached.
+    fn clear(&mut self) -> Vec<Id> {
+        self.missing.clear();
+        self.oversize.clear();
+        self.bytes = 0;
+        self.recency.clear();
+        self.items.drain().map(|(id, _)| id).collect()
+    }
+}
+
+/// Counts the bytes read through it and fails once they pass `limit`, so
+/// filling the cache with an item that does not fit stops early.
+struct LimitedReader<R> {
+    inner: R,
+    read: u64,
+    limit: u64,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for LimitedReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        // Data already read is handed on; the read after it fails, since
+        // an error must not come with data.
+        if self.read > self.limit {
+            return Poll::Ready(Err(io::Error::other("item exceeds cache capacity")));
+        }
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        self.read += (buf.filled().len() - before) as u64;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_evicts_least_recently_used() {
+        let mut index = CacheIndex::default();
+        let now = Instant::now();
+
+        assert!(index.insert("a", 40, now, 100).is_empty());
+        assert!(index.insert("b", 40, now, 100).is_empty());
+        // Reading "a" makes "b" the least recently used
+        assert!(matches!(index.lookup(&"a", now, None, None), Lookup::Hit));
+        assert_eq!(index.insert("c", 40, now, 100), vec!["b"]);
+        assert_eq!(index.bytes, 80);
+
+        assert!(matches!(
+            index.lookup(&"b", now, None, None),
+            Lookup::Unknown
+        ));
+        assert!(index.remove(&"a"));
+        assert_eq!(index.bytes, 40);
+    }
+
+    #[test]
+    fn test_index_expiry() {
+        let mut index = CacheIndex::default();
+        let now = Instant::now();
+        let later = now + Duration::from_secs(10);
+        let ttl = Some(Duration::from_secs(5));
+
+        index.insert("a", 1, now, 100);
+        assert!(matches!(index.lookup(&"a", now, ttl, None), Lookup::Hit));
+        // Expired items stay counted until their copy is deleted
+        assert!(matches!(
+            index.lookup(&"a", later, ttl, None),
+            Lookup::Expired
+        ));
+        assert_eq!(index.bytes, 1);
+        assert!(index.remove(&"a"));
+        assert_eq!(index.bytes, 0);
+
+        index.insert_missing("b", now);
+        assert!(matches!(
+            index.lookup(&"b", now, None, ttl),
+            Lookup::Missing
+        ));
+        assert!(matches!(
+            index.lookup(&"b", later, None, ttl),
+            Lookup::Unknown
+        ));
+
+        index.insert_oversize("c", now);
+        assert!(matches!(
+            index.lookup(&"c", now, ttl, None),
+            Lookup::Oversize
+        ));
+        assert!(matches!(
+            index.lookup(&"c", later, ttl, None),
+            Lookup::Unknown
+        ));
+        index.insert_oversize("c", now);
+        assert!(!index.remove(&"c"));
+        assert!(matches!(
+            index.lookup(&"c", now, ttl, None),
+            Lookup::Unknown
+        ));
+    }
+}
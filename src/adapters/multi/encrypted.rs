@@ -0,0 +1,1048 @@
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, Error, GetReader, ObjectMeta, Page,
+    PutOptions, Result, Storage,
+};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::stream::{NewStream, StreamBE32, StreamPrimitive};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use futures::stream::{BoxStream, StreamExt};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Marks an item written by [`EncryptedStorage`], including the format version.
+const MAGIC: &[u8; 8] = b"STOWENC1";
+
+/// Longest key ID in bytes; the header reserves this much room for it.
+const MAX_KEY_ID_LEN: usize = 64;
+
+// Header layout: magic, key ID length, key ID padded to `MAX_KEY_ID_LEN`,
+// nonce and wrapped data key, then the nonce prefix of the STREAM
+// construction. Its fixed size lets `stat` report plaintext sizes.
+const KEY_ID_END: usize = MAGIC.len() + 1 + MAX_KEY_ID_LEN;
+const WRAPPED_KEY_START: usize = KEY_ID_END + 24;
+const STREAM_NONCE_START: usize = WRAPPED_KEY_START + 32 + TAG_LEN;
+const HEADER_LEN: usize = STREAM_NONCE_START + 19;
+
+/// Plaintext bytes per encrypted chunk.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Authentication tag appended to every chunk.
+const TAG_LEN: usize = 16;
+
+/// Size of a full chunk as stored.
+const SEALED_CHUNK_SIZE: usize = CHUNK_SIZE + TAG_LEN;
+
+/// Chunks fetched per request by [`EncryptedStorage::get_range`].
+const RANGE_BATCH_CHUNKS: u64 = 16;
+
+/// Per-item cipher: XChaCha20-Poly1305 in the STREAM construction with a
+/// 32-bit big-endian chunk counter and a last-chunk flag.
+type ChunkCipher = StreamBE32<XChaCha20Poly1305>;
+
+/// Key-encryption keys for [`EncryptedStorage`], by key ID.
+///
+/// The current key wraps the data keys of new items. Retired keys are kept
+/// so items written with them can still be read, until
+/// [`EncryptedStorage::rewrap`] has moved them to the current key.
+///
+/// The keyring wipes its keys from memory when it is dropped. Arrays passed
+/// to [`new`](Self::new) and [`with_key`](Self::with_key) are copies owned
+/// by the caller, who should wipe them too (e.g. with `zeroize`).
+///
+/// ```
+/// # use stowage::multi::Keyring;
+/// # fn example() -> stowage::Result<()> {
+/// let keyring = Keyring::new("2026-10", Keyring::generate_key())?
+///     .with_key("2025-01", Keyring::generate_key())?;
+/// assert_eq!(keyring.current_key_id(), "2026-10");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Keyring {
+    current: String,
+    keys: HashMap<String, XChaCha20Poly1305>,
+}
+
+impl Keyring {
+    /// Create a keyring whose current key is `key`, stored in item headers
+    /// as `key_id`.
+    ///
+    /// Key IDs are 1 to 64 bytes long and are not secret.
+    pub fn new(key_id: impl Into<String>, key: [u8; 32]) -> Result<Self> {
+        let key_id = check_key_id(key_id.into())?;
+        let keys = HashMap::from([(key_id.clone(), key_cipher(key))]);
+        Ok(Self {
+            current: key_id,
+            keys,
+        })
+    }
+
+    /// Add a retired key, used only to read items written with it.
+    pub fn with_key(mut self, key_id: impl Into<String>, key: [u8; 32]) -> Result<Self> {
+        let key_id = check_key_id(key_id.into())?;
+        if key_id == self.current {
+            return Err(Error::Generic(format!(
+                "key `{key_id}` is already the current key"
+            )));
+        }
+        self.keys.insert(key_id, key_cipher(key));
+        Ok(self)
+    }
+
+    /// ID of the key new items are encrypted with.
+    pub fn current_key_id(&self) -> &str {
+        &self.current
+    }
+
+    /// Whether the keyring holds a key with this ID.
+    pub fn contains(&self, key_id: &str) -> bool {
+        self.keys.contains_key(key_id)
+    }
+
+    /// Generate a random key.
+    pub fn generate_key() -> [u8; 32] {
+        XChaCha20Poly1305::generate_key(&mut OsRng).into()
+    }
+
+    /// Parse a key written as 64 hexadecimal digits.
+    pub fn key_from_hex(hex: &str) -> Result<[u8; 32]> {
+        let hex = hex.trim().as_bytes();
+        let invalid = || Error::Generic("key must be 64 hexadecimal digits".to_string());
+        if hex.len() != 64 {
+            return Err(invalid());
+        }
+        let mut key = [0u8; 32];
+        for (byte, pair) in key.iter_mut().zip(hex.chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(key)
+    }
+
+    fn get(&self, key_id: &str) -> Result<&XChaCha20Poly1305> {
+        self.keys.get(key_id).ok_or_else(|| {
+            Error::Generic(format!("encryption key `{key_id}` is not in the keyring"))
+        })
+    }
+}
+
+/// Every key is held by a cipher that wipes it when dropped.
+impl ZeroizeOnDrop for Keyring {}
+
+const _: () = {
+    const fn wipes_key_on_drop<T: ZeroizeOnDrop>() {}
+    wipes_key_on_drop::<XChaCha20Poly1305>();
+};
+
+/// Cipher for `key`, wiping the copy of the key passed in.
+fn key_cipher(mut key: [u8; 32]) -> XChaCha20Poly1305 {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&key));
+    key.zeroize();
+    cipher
+}
+
+/// Only the key IDs are shown.
+impl std::fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ids: Vec<_> = self.keys.keys().collect();
+        ids.sort();
+        f.debug_struct("Keyring")
+            .field("current", &self.current)
+            .field("keys", &ids)
+            .finish()
+    }
+}
+
+fn check_key_id(key_id: String) -> Result<String> {
+    if key_id.is_empty() || key_id.len() > MAX_KEY_ID_LEN {
+        return Err(Error::Generic(format!(
+            "key ID `{key_id}` must be 1 to {MAX_KEY_ID_LEN} bytes long"
+        )));
+    }
+    Ok(key_id)
+}
+
+/// Encrypts items client-side before they reach any storage backend.
+///
+/// Every item gets a fresh random data key. Its contents are encrypted with
+/// XChaCha20-Poly1305 in 64 KiB chunks using the STREAM construction, so
+/// uploads and downloads stay streaming, ranged reads only fetch the chunks
+/// they need, and reordered, altered or truncated chunks are detected. The
+/// data key is wrapped with the current key of the [`Keyring`] and stored in
+/// a header in front of the contents, together with that key's ID.
+///
+/// Rotating keys does not re-encrypt contents: make the new key current,
+/// keep the old one in the keyring, and call [`rewrap`](Self::rewrap) on
+/// each item to re-wrap its data key.
+///
+/// With [`with_encrypted_ids`](Self::with_encrypted_ids) identifiers are
+/// encrypted too, one path segment at a time, so folders still work and
+/// the backend only sees opaque names. Encryption is deterministic so items
+/// can be looked up by name; equal segments encrypt to equal names.
+///
+/// Sizes, modification times and content attributes from
+/// [`PutOptions`] are visible to the backend. Presigned URLs are not
+/// offered, since they would hand out ciphertext. Contents are not tied to
+/// their identifier, so someone with write access to the backend can
+/// swap or roll back whole items, but cannot read or alter their contents.
+///
+/// ```
+/// # use stowage::{Storage, StorageExt};
+/// # use stowage::multi::{EncryptedStorage, Keyring};
+/// # use stowage::MemoryStorage;
+/// # async fn example() -> stowage::Result<()> {
+/// let keyring = Keyring::new("2026-10", Keyring::generate_key())?;
+/// let storage = EncryptedStorage::new(MemoryStorage::new(), keyring);
+/// storage.put_bytes("secret.txt".to_string(), b"hello").await?;
+///
+/// assert_eq!(storage.get_string(&"secret.txt".to_string()).await?, "hello");
+/// let sealed = storage.inner().get_bytes(&"secret.txt".to_string())?;
+/// assert!(!sealed.windows(5).any(|w| w == b"hello"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct EncryptedStorage<S: Storage<Id = String>> {
+    inner: S,
+    keyring: Keyring,
+    ids: Option<IdCipher>,
+}
+
+impl<S: Storage<Id = String>> EncryptedStorage<S> {
+    /// Encrypt everything stored in `storage` with keys from `keyring`.
+    pub fn new(storage: S, keyring: Keyring) -> Self {
+        Self {
+            inner: storage,
+            keyring,
+            ids: None,
+        }
+    }
+
+    /// Also encrypt identifiers, with a key derived from `id_key`.
+    ///
+    /// The same `id_key` must be used for as long as the items exist; it is
+    /// not affected by rotating the keyring. Prefixes passed to listings
+    /// match whole path segments only, and encrypted segments are about
+    /// 1.6 times as long as the plaintext plus 64 characters, which limits
+    /// names to roughly 110 bytes per segment on backends with a 255 byte
+    /// limit.
+    pub fn with_encrypted_ids(mut self, id_key: [u8; 32]) -> Self {
+        let id_key = Zeroizing::new(id_key);
+        self.ids = Some(IdCipher::new(&id_key));
+        self
+    }
+
+    /// Whether identifiers are encrypted.
+    pub fn encrypts_ids(&self) -> bool {
+        self.ids.is_some()
+    }
+
+    /// The keys this storage encrypts and decrypts with.
+    pub fn keyring(&self) -> &Keyring {
+        &self.keyring
+    }
+
+    /// Get a reference to the inner storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap and return the inner storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// ID of the key an item's data key is wrapped with.
+    pub async fn key_id(&self, id: &str) -> Result<String> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN);
+        self.inner
+            .get_range(&self.encode_id(id), 0..HEADER_LEN as u64, &mut bytes)
+            .await?;
+        Ok(Header::parse(&bytes)?.key_id().to_string())
+    }
+
+    /// Re-wrap an item's data key with the current key of the keyring.
+    ///
+    /// Returns `false` if the item already uses the current key. Otherwise
+    /// the item is written back with a new header and its encrypted contents
+    /// unchanged, keeping its content attributes. Backends cannot replace
+    /// just the header, so this transfers the whole item, but never decrypts
+    /// it.
+    ///
+    /// Where the backend reports ETags and supports conditional writes, the
+    /// write only succeeds if the item is unchanged since it was read: if it
+    /// was overwritten in the meantime, this fails with
+    /// [`Error::PreconditionFailed`] rather than reverting it, and can simply
+    /// be called again. Other backends are written back unconditionally.
+    pub async fn rewrap(&self, id: &str) -> Result<bool> {
+        let key = self.encode_id(id);
+        match self.rewrap_key(id, &key, true).await {
+            Err(Error::Unsupported(_)) => self.rewrap_key(id, &key, false).await,
+            result => result,
+        }
+    }
+
+    /// Re-wrap the item stored as `key`, only replacing the version read if
+    /// `conditional`.
+    async fn rewrap_key(&self, id: &str, key: &str, conditional: bool) -> Result<bool> {
+        let key = key.to_string();
+        let mut meta = self.inner.stat(&key).await?;
+        let etag = meta.etag.take().filter(|_| conditional);
+        let mut reader = self.inner.get_reader(&key).await?;
+        let header = read_header(&mut reader).await?;
+        if header.key_id() == self.keyring.current_key_id() {
+            return Ok(false);
+        }
+        let header = header.rewrap(&self.keyring)?;
+        tracing::debug!(
+            id,
+            key_id = self.keyring.current_key_id(),
+            "Re-wrapping data key"
+        );
+
+        let input = io::Cursor::new(header.bytes).chain(reader);
+        let size = meta.size;
+        let mut options = attributes(meta);
+        if let Some(etag) = etag {
+            options = options.if_match(etag);
+        }
+        self.inner.put_with(key, input, Some(size), options).await?;
+        Ok(true)
+    }
+
+    /// Identifier in the inner storage.
+    fn encode_id(&self, id: &str) -> String {
+        match &self.ids {
+            Some(ids) => ids.encrypt(id),
+            None => id.to_string(),
+        }
+    }
+
+    /// Identifier as seen by callers of this storage.
+    fn decode_id(&self, id: String) -> Result<String> {
+        match &self.ids {
+            Some(ids) => ids.decrypt(&id),
+            None => Ok(id),
+        }
+    }
+
+    /// Batch results keep identifiers that cannot be decrypted as they are,
+    /// so a failure to decrypt one does not hide the outcome of the others.
+    fn decode_batch(&self, batch: BatchResult<String>) -> BatchResult<String> {
+        let decode = |id: String| self.decode_id(id.clone()).unwrap_or(id);
+        BatchResult {
+            succeeded: batch.succeeded.into_iter().map(decode).collect(),
+            failed: batch
+                .failed
+                .into_iter()
+                .map(|(id, e)| (decode(id), e))
+                .collect(),
+        }
+    }
+
+    fn decode_entry(&self, entry: Entry<String>) -> Result<Entry<String>> {
+        let size = if entry.is_dir {
+            entry.size
+        } else {
+            plaintext_size(entry.size)?
+        };
+        Ok(Entry {
+            id: self.decode_id(entry.id)?,
+            size,
+            ..entry
+        })
+    }
+}
+
+impl<S: Storage<Id = String>> Storage for EncryptedStorage<S> {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        self.inner.exists(&self.encode_id(id)).await
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        self.inner.folder_exists(&self.encode_id(id)).await
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        let meta = self.inner.stat(&self.encode_id(id)).await?;
+        Ok(ObjectMeta {
+            size: plaintext_size(meta.size)?,
+            ..meta
+        })
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        let (header, cipher) = Header::seal(&self.keyring);
+        let input = EncryptReader::new(input, header, cipher);
+        self.inner
+            .put(self.encode_id(&id), input, len.map(sealed_size))
+            .await
+    }
+
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        let (header, cipher) = Header::seal(&self.keyring);
+        let input = EncryptReader::new(input, header, cipher);
+        self.inner
+            .put_with(self.encode_id(&id), input, len.map(sealed_size), options)
+            .await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        mut output: W,
+    ) -> Result<u64> {
+        let mut reader = self.get_reader(id).await?;
+        let copied = tokio::io::copy(&mut reader, &mut output)
+            .await
+            .map_err(Error::from_io)?;
+        output.flush().await?;
+        Ok(copied)
+    }
+
+    /// Chunks are authenticated as they are read, so data from an item that
+    /// turns out to be truncated or altered may be returned before the
+    /// error.
+    async fn get_reader(&self, id: &String) -> Result<GetReader<'_>> {
+        let mut reader = self.inner.get_reader(&self.encode_id(id)).await?;
+        let cipher = read_header(&mut reader).await?.open(&self.keyring)?;
+        Ok(GetReader::new(DecryptReader::new(reader, cipher)))
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        range: Range<u64>,
+        mut output: W,
+    ) -> Result<u64> {
+        if range.is_empty() {
+            return Ok(0);
+        }
+        let key = self.encode_id(id);
+        let sealed_len = self.inner.stat(&key).await?.size;
+        let size = plaintext_size(sealed_len)?;
+        let end = range.end.min(size);
+        if range.start >= end {
+            return Ok(0);
+        }
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        self.inner
+            .get_range(&key, 0..HEADER_LEN as u64, &mut header)
+            .await?;
+        let cipher = Header::parse(&header)?.open(&self.keyring)?;
+
+        let chunk = CHUNK_SIZE as u64;
+        let last_chunk = chunk_count(size) - 1;
+        let mut index = range.start / chunk;
+        let mut written = 0;
+        let mut sealed = Vec::new();
+        while index <= (end - 1) / chunk {
+            let batch_end = (index + RANGE_BATCH_CHUNKS).min((end - 1) / chunk + 1);
+            let from = HEADER_LEN as u64 + index * SEALED_CHUNK_SIZE as u64;
+            let to = (HEADER_LEN as u64 + batch_end * SEALED_CHUNK_SIZE as u64).min(sealed_len);
+            sealed.clear();
+            self.inner.get_range(&key, from..to, &mut sealed).await?;
+            if sealed.len() as u64 != to - from {
+                return Err(Error::Generic(format!(
+                    "encrypted item `{id}` changed while reading"
+                )));
+            }
+
+            for (position, part) in (index..).zip(sealed.chunks(SEALED_CHUNK_SIZE)) {
+                let mut plain = part.to_vec();
+                open_chunk(&cipher, position, position == last_chunk, &mut plain)?;
+                let start = position * chunk;
+                let skip = range.start.saturating_sub(start) as usize;
+                let take = ((end - start) as usize).min(plain.len());
+                output.write_all(&plain[skip..take]).await?;
+                written += (take - skip) as u64;
+            }
+            index = batch_end;
+        }
+        output.flush().await?;
+        Ok(written)
+    }
+
+    /// Items are copied as they are stored; their contents are not tied to
+    /// their identifiers.
+    async fn copy(&self, src: &String, dst: String) -> Result<()> {
+        self.inner
+            .copy(&self.encode_id(src), self.encode_id(&dst))
+            .await
+    }
+
+    async fn rename(&self, src: &String, dst: String) -> Result<()> {
+        self.inner
+            .rename(&self.encode_id(src), self.encode_id(&dst))
+            .await
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        self.inner.delete(&self.encode_id(id)).await
+    }
+
+    async fn delete_many(&self, ids: Vec<String>) -> Result<BatchResult<String>> {
+        let ids = ids.iter().map(|id| self.encode_id(id)).collect();
+        let batch = self.inner.delete_many(ids).await?;
+        Ok(self.decode_batch(batch))
+    }
+
+    async fn delete_prefix(&self, prefix: &String) -> Result<BatchResult<String>> {
+        let batch = self.inner.delete_prefix(&self.encode_id(prefix)).await?;
+        Ok(self.decode_batch(batch))
+    }
+
+    async fn delete_folder(&self, folder: &String) -> Result<()> {
+        self.inner.delete_folder(&self.encode_id(folder)).await
+    }
+
+    async fn list(&self, prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        let prefix = prefix.map(|p| self.encode_id(p));
+        let stream = self.inner.list(prefix.as_ref()).await?;
+        Ok(stream
+            .map(|item| item.and_then(|id| self.decode_id(id)))
+            .boxed())
+    }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&String>,
+    ) -> Result<BoxStream<'_, Result<Entry<String>>>> {
+        let prefix = prefix.map(|p| self.encode_id(p));
+        let stream = self.inner.list_with_meta(prefix.as_ref()).await?;
+        Ok(stream
+            .map(|item| item.and_then(|entry| self.decode_entry(entry)))
+            .boxed())
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&String>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<String>> {
+        let prefix = prefix.map(|p| self.encode_id(p));
+        let page = self
+            .inner
+            .list_page(prefix.as_ref(), page_size, continuation)
+            .await?;
+        Ok(Page {
+            ids: page
+                .ids
+                .into_iter()
+                .map(|id| self.decode_id(id))
+                .collect::<Result<_>>()?,
+            next: page.next,
+        })
+    }
+
+    async fn list_dir(&self, folder: Option<&String>) -> Result<DirListing<String>> {
+        let folder = folder.map(|f| self.encode_id(f));
+        let listing = self.inner.list_dir(folder.as_ref()).await?;
+        Ok(DirListing {
+            files: listing
+                .files
+                .into_iter()
+                .map(|id| self.decode_id(id))
+                .collect::<Result<_>>()?,
+            prefixes: listing
+                .prefixes
+                .into_iter()
+                .map(|id| self.decode_id(id))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Content attributes of an item, to write it back unchanged.
+fn attributes(meta: ObjectMeta) -> PutOptions {
+    let mut options = PutOptions::new();
+    if let Some(content_type) = meta.content_type {
+        options = options.content_type(content_type);
+    }
+    if let Some(cache_control) = meta.cache_control {
+        options = options.cache_control(cache_control);
+    }
+    if let Some(content_disposition) = meta.content_disposition {
+        options = options.content_disposition(content_disposition);
+    }
+    for (key, value) in meta.metadata {
+        options = options.user_metadata(key, value);
+    }
+    options
+}
+
+/// Number of chunks an item of `size` plaintext bytes is stored in. Empty
+/// items still get one (empty) final chunk.
+fn chunk_count(size: u64) -> u64 {
+    size.div_ceil(CHUNK_SIZE as u64).max(1)
+}
+
+/// Stored size of an item of `size` plaintext bytes.
+fn sealed_size(size: u64) -> u64 {
+    HEADER_LEN as u64 + size + chunk_count(size) * TAG_LEN as u64
+}
+
+/// Plaintext size of an item stored in `sealed` bytes.
+fn plaintext_size(sealed: u64) -> Result<u64> {
+    let invalid = || Error::Generic("item is not encrypted or is truncated".to_string());
+    let body = sealed.checked_sub(HEADER_LEN as u64).ok_or_else(invalid)?;
+    let chunks = body.div_ceil(SEALED_CHUNK_SIZE as u64);
+    let last = body.saturating_sub((chunks.max(1) - 1) * SEALED_CHUNK_SIZE as u64);
+    if last < TAG_LEN as u64 {
+        return Err(invalid());
+    }
+    Ok(body - chunks * TAG_LEN as u64)
+}
+
+fn seal_chunk(cipher: &ChunkCipher, position: u64, last: bool, buf: &mut Vec<u8>) -> Result<()> {
+    let position = u32::try_from(position)
+        .map_err(|_| Error::Generic("item is too large to encrypt".to_string()))?;
+    cipher
+        .encrypt_in_place(position, last, b"", buf)
+        .map_err(|_| Error::Generic("failed to encrypt chunk".to_string()))
+}
+
+fn open_chunk(cipher: &ChunkCipher, position: u64, last: bool, buf: &mut Vec<u8>) -> Result<()> {
+    let position = u32::try_from(position)
+        .map_err(|_| Error::Generic("encrypted item is corrupt".to_string()))?;
+    cipher
+        .decrypt_in_place(position, last, b"", buf)
+        .map_err(|_| Error::Generic("encrypted item was altered or truncated".to_string()))
+}
+
+/// Header stored in front of an item's encrypted contents.
+struct Header {
+    bytes: [u8; HEADER_LEN],
+}
+
+impl Header {
+    /// Header for a new item, with a fresh data key.
+    fn seal(keyring: &Keyring) -> (Self, ChunkCipher) {
+        let mut data_key = Zeroizing::new([0u8; 32]);
+        OsRng.fill_bytes(data_key.as_mut());
+        let mut stream_nonce = [0u8; HEADER_LEN - STREAM_NONCE_START];
+        OsRng.fill_bytes(&mut stream_nonce);
+        let header = Self::wrap(keyring, &data_key, &stream_nonce);
+        let cipher = ChunkCipher::from_aead(
+            XChaCha20Poly1305::new(Key::from_slice(data_key.as_ref())),
+            (&stream_nonce).into(),
+        );
+        (header, cipher)
+    }
+
+    /// Wrap `data_key` with the current key of `keyring`.
+    fn wrap(keyring: &Keyring, data_key: &[u8; 32], stream_nonce: &[u8]) -> Self {
+        let key_id = keyring.current_key_id();
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[..MAGIC.len()].copy_from_slice(MAGIC);
+        bytes[MAGIC.len()] = key_id.len() as u8;
+        bytes[MAGIC.len() + 1..][..key_id.len()].copy_from_slice(key_id.as_bytes());
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: data_key.as_slice(),
+            aad: &bytes[..KEY_ID_END],
+        };
+        let wrapped = keyring
+            .get(key_id)
+            .expect("the current key is in the keyring")
+            .encrypt(&nonce, payload)
+            .expect("wrapping a data key cannot fail");
+        bytes[KEY_ID_END..WRAPPED_KEY_START].copy_from_slice(&nonce);
+        bytes[WRAPPED_KEY_START..STREAM_NONCE_START].copy_from_slice(&wrapped);
+        bytes[STREAM_NONCE_START..].copy_from_slice(stream_nonce);
+        Self { bytes }
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        let invalid = || Error::Generic("item is not encrypted or is truncated".to_string());
+        let bytes: [u8; HEADER_LEN] = bytes.try_into().map_err(|_| invalid())?;
+        let key_id_len = bytes[MAGIC.len()] as usize;
+        if &bytes[..MAGIC.len()] != MAGIC
+            || !(1..=MAX_KEY_ID_LEN).contains(&key_id_len)
+            || std::str::from_utf8(&bytes[MAGIC.len() + 1..][..key_id_len]).is_err()
+        {
+            return Err(invalid());
+        }
+        Ok(Self { bytes })
+    }
+
+    fn key_id(&self) -> &str {
+        let len = self.bytes[MAGIC.len()] as usize;
+        std::str::from_utf8(&self.bytes[MAGIC.len() + 1..][..len]).expect("checked in parse")
+    }
+
+    fn data_key(&self, keyring: &Keyring) -> Result<Zeroizing<[u8; 32]>> {
+        let payload = Payload {
+            msg: &self.bytes[WRAPPED_KEY_START..STREAM_NONCE_START],
+            aad: &self.bytes[..KEY_ID_END],
+        };
+        let data_key = keyring
+            .get(self.key_id())?
+            .decrypt(
+                XNonce::from_slice(&self.bytes[KEY_ID_END..WRAPPED_KEY_START]),
+                payload,
+            )
+            .map_err(|_| {
+                Error::Generic(format!(
+                    "cannot unwrap data key with key `{}`",
+                    self.key_id()
+                ))
+            })?;
+        let data_key = Zeroizing::new(data_key);
+        let mut key = Zeroizing::new([0u8; 32]);
+        key.copy_from_slice(&data_key);
+        Ok(key)
+    }
+
+    /// Cipher for the item's contents.
+    fn open(&self, keyring: &Keyring) -> Result<ChunkCipher> {
+        let data_key = self.data_key(keyring)?;
+        Ok(ChunkCipher::from_aead(
+            XChaCha20Poly1305::new(Key::from_slice(data_key.as_ref())),
+            self.bytes[STREAM_NONCE_START..].into(),
+        ))
+    }
+
+    /// The same data key, wrapped with the current key of `keyring`.
+    fn rewrap(&self, keyring: &Keyring) -> Result<Self> {
+        let data_key = self.data_key(keyring)?;
+        Ok(Self::wrap(
+            keyring,
+            &data_key,
+            &self.bytes[STREAM_NONCE_START..],
+        ))
+    }
+}
+
+async fn read_header<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Header> {
+    let mut bytes = [0u8; HEADER_LEN];
+    match reader.read_exact(&mut bytes).await {
+        Ok(_) => Header::parse(&bytes),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Header::parse(&[]),
+        Err(e) => Err(Error::from_io(e)),
+    }
+}
+
+/// Encrypts its input chunk by chunk, after emitting the header.
+struct EncryptReader<R> {
+    inner: R,
+    cipher: ChunkCipher,
+    /// Plaintext read ahead: one byte more than a chunk, so the final chunk
+    /// is known to be final before it is sealed.
+    plain: Box<[u8]>,
+    filled: usize,
+    position: u64,
+    sealed: Vec<u8>,
+    consumed: usize,
+    done: bool,
+}
+
+impl<R> EncryptReader<R> {
+    fn new(inner: R, header: Header, cipher: ChunkCipher) -> Self {
+        Self {
+            inner,
+            cipher,
+            plain: vec![0; CHUNK_SIZE + 1].into_boxed_slice(),
+            filled: 0,
+            position: 0,
+            sealed: header.bytes.to_vec(),
+            consumed: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EncryptReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.consumed < this.sealed.len() {
+                let n = buf.remaining().min(this.sealed.len() - this.consumed);
+                buf.put_slice(&this.sealed[this.consumed..][..n]);
+                this.consumed += n;
+                return Poll::Ready(Ok(()));
+            }
+            if this.done {
+                return Poll::Ready(Ok(()));
+            }
+
+            let last = ready!(fill(&mut this.inner, cx, &mut this.plain, &mut this.filled))?;
+            let len = if last { this.filled } else { CHUNK_SIZE };
+            this.sealed.clear();
+            this.sealed.extend_from_slice(&this.plain[..len]);
+            seal_chunk(&this.cipher, this.position, last, &mut this.sealed)
+                .map_err(io::Error::other)?;
+            this.plain.copy_within(len..this.filled, 0);
+            this.filled -= len;
+            this.position += 1;
+            this.consumed = 0;
+            this.done = last;
+        }
+    }
+}
+
+/// Decrypts and authenticates the chunks following the header.
+struct DecryptReader<R> {
+    inner: R,
+    cipher: ChunkCipher,
+    /// Sealed bytes read ahead: one byte more than a chunk, so the final
+    /// chunk is recognized and a truncated item fails to authenticate.
+    sealed: Box<[u8]>,
+    filled: usize,
+    position: u64,
+    plain: Vec<u8>,
+    consumed: usize,
+    done: bool,
+}
+
+impl<R> DecryptReader<R> {
+    fn new(inner: R, cipher: ChunkCipher) -> Self {
+        Self {
+            inner,
+            cipher,
+            sealed: vec![0; SEALED_CHUNK_SIZE + 1].into_boxed_slice(),
+            filled: 0,
+            position: 0,
+            plain: Vec::new(),
+            consumed: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for DecryptReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        loop {
+            if this.consumed < this.plain.len() {
+                let n = buf.remaining().min(this.plain.len() - this.consumed);
+                buf.put_slice(&this.plain[this.consumed..][..n]);
+                this.consumed += n;
+                return Poll::Ready(Ok(()));
+            }
+            if this.done {
+                return Poll::Ready(Ok(()));
+            }
+
+            let last = ready!(fill(
+                &mut this.inner,
+                cx,
+                &mut this.sealed,
+                &mut this.filled
+            ))?;
+            let len = if last { this.filled } else { SEALED_CHUNK_SIZE };
+            this.plain.clear();
+            this.plain.extend_from_slice(&this.sealed[..len]);
+            open_chunk(&this.cipher, this.position, last, &mut this.plain)
+                .map_err(io::Error::other)?;
+            this.sealed.copy_within(len..this.filled, 0);
+            this.filled -= len;
+            this.position += 1;
+            this.consumed = 0;
+            this.done = last;
+        }
+    }
+}
+
+/// Read from `reader` until `buf` is full or the input ends. Returns whether
+/// it ended.
+fn fill<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    cx: &mut Context<'_>,
+    buf: &mut [u8],
+    filled: &mut usize,
+) -> Poll<io::Result<bool>> {
+    while *filled < buf.len() {
+        let mut read = ReadBuf::new(&mut buf[*filled..]);
+        ready!(Pin::new(&mut *reader).poll_read(cx, &mut read))?;
+        match read.filled().len() {
+            0 => return Poll::Ready(Ok(true)),
+            n => *filled += n,
+        }
+    }
+    Poll::Ready(Ok(false))
+}
+
+/// Deterministic encryption of identifiers, one path segment at a time.
+///
+/// Each segment is encrypted with a nonce derived from it by HMAC-SHA256
+/// (a synthetic IV), and written in lowercase base32 so it also survives
+/// case-insensitive backends.
+#[derive(Clone)]
+struct IdCipher {
+    /// HMAC key deriving each segment's nonce, wiped on drop.
+    nonce_key: Zeroizing<[u8; 32]>,
+    cipher: XChaCha20Poly1305,
+}
+
+impl IdCipher {
+    fn new(id_key: &[u8; 32]) -> Self {
+        let derive = |label: &[u8]| {
+            let mut mac =
+                <Hmac<Sha256> as Mac>::new_from_slice(id_key).expect("HMAC takes any key size");
+            mac.update(label);
+            let mut key = Zeroizing::new([0u8; 32]);
+            key.copy_from_slice(&mac.finalize().into_bytes());
+            key
+        };
+        Self {
+            nonce_key: derive(b"stowage id nonce"),
+            cipher: key_cipher(*derive(b"stowage id key")),
+        }
+    }
+
+    fn encrypt(&self, id: &str) -> String {
+        let segments: Vec<_> = id
+            .split('/')
+            .map(|segment| {
+                if segment.is_empty() {
+                    return String::new();
+                }
+                let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(self.nonce_key.as_ref())
+                    .expect("HMAC takes any key size");
+                mac.update(segment.as_bytes());
+                let tag = mac.finalize().into_bytes();
+                let nonce = XNonce::from_slice(&tag[..24]);
+                let sealed = self
+                    .cipher
+                    .encrypt(nonce, segment.as_bytes())
+                    .expect("encrypting an identifier cannot fail");
+                base32_encode(&[nonce.as_slice(), &sealed].concat())
+            })
+            .collect();
+        segments.join("/")
+    }
+
+    fn decrypt(&self, id: &str) -> Result<String> {
+        let segments = id
+            .split('/')
+            .map(|segment| {
+                if segment.is_empty() {
+                    return Ok(String::new());
+                }
+                let invalid = || Error::InvalidId(format!("cannot decrypt identifier `{id}`"));
+                let raw = base32_decode(segment).ok_or_else(invalid)?;
+                if raw.len() < 24 + TAG_LEN {
+                    return Err(invalid());
+                }
+                let (nonce, sealed) = raw.split_at(24);
+                let plain = self
+                    .cipher
+                    .decrypt(XNonce::from_slice(nonce), sealed)
+                    .map_err(|_| invalid())?;
+                String::from_utf8(plain).map_err(|_| invalid())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(segments.join("/"))
+    }
+}
+
+impl std::fmt::Debug for IdCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdCipher").finish_non_exhaustive()
+    }
+}
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// RFC 4648 base32 in lowercase, without padding.
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &byte in data {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[(buffer >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = BASE32_ALPHABET.iter().position(|&a| a == c)?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base32_round_trip() {
+        assert_eq!(base32_encode(b"foobar"), "mzxw6ytboi");
+        for len in 0..12 {
+            let data: Vec<u8> = (0..len).map(|i: u8| i.wrapping_mul(37)).collect();
+            assert_eq!(base32_decode(&base32_encode(&data)).unwrap(), data);
+        }
+        assert!(base32_decode("MZXW6").is_none());
+    }
+
+    #[test]
+    fn test_sizes_round_trip() {
+        let chunk = CHUNK_SIZE as u64;
+        for size in [0, 1, chunk - 1, chunk, chunk + 1, 3 * chunk, 3 * chunk + 7] {
+            assert_eq!(plaintext_size(sealed_size(size)).unwrap(), size);
+        }
+        assert!(plaintext_size(HEADER_LEN as u64 + TAG_LEN as u64 - 1).is_err());
+        assert!(plaintext_size(sealed_size(chunk) + 3).is_err());
+    }
+
+    #[test]
+    fn test_id_cipher_keeps_segments() {
+        let ids = IdCipher::new(&[7; 32]);
+        let sealed = ids.encrypt("docs/2026/report.pdf");
+        assert_eq!(sealed.split('/').count(), 3);
+        assert_eq!(ids.encrypt("docs/2026/report.pdf"), sealed);
+        assert!(sealed.starts_with(&ids.encrypt("docs/")));
+        assert_eq!(ids.decrypt(&sealed).unwrap(), "docs/2026/report.pdf");
+        assert!(ids.decrypt("plain.txt").is_err());
+    }
+}
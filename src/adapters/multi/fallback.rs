@@ -1,6 +1,9 @@
-use crate::{Result, Storage};
+use crate::{
+    ContinuationToken, DirListing, Entry, GetReader, ObjectMeta, Page, PutOptions, Result, Storage,
+};
 use futures::stream::BoxStream;
 use std::fmt::Debug;
+use std::ops::Range;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tracing;
 
@@ -53,6 +56,41 @@ where
     pub fn is_write_through(&self) -> bool {
         self.write_through
     }
+
+    /// Write `input` with the content attributes in `options`, which must not
+    /// hold preconditions.
+    async fn put_streaming<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: P::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        if self.write_through {
+            // Write-through: stream the input to both backends at once
+            let (primary_writer, primary_reader) = crate::io::pipe();
+            let (secondary_writer, secondary_reader) = crate::io::pipe();
+
+            let (tee_result, primary_result, secondary_result) = tokio::join!(
+                crate::io::tee(input, vec![primary_writer, secondary_writer]),
+                self.primary
+                    .put_with(id.clone(), primary_reader, len, options.clone()),
+                self.secondary
+                    .put_with(id.clone(), secondary_reader, len, options),
+            );
+            tee_result?;
+
+            if let Err(e) = &secondary_result {
+                tracing::warn!(?id, error = ?e, "Secondary write failed (best-effort)");
+            }
+
+            // Return error if primary failed (secondary is best-effort in write-through)
+            primary_result
+        } else {
+            // Default: write only to primary
+            self.primary.put_with(id, input, len, options).await
+        }
+    }
 }
 
 impl<P, S> Storage for FallbackStorage<P, S>
@@ -94,44 +132,66 @@ where
         }
     }
 
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        // Try primary first; a miss or an error falls through to secondary
+        match self.primary.stat(id).await {
+            Ok(meta) => Ok(meta),
+            Err(e) => {
+                tracing::debug!(?id, error = ?e, "Primary stat failed, using fallback");
+                self.secondary.stat(id).await
+            }
+        }
+    }
+
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
         &self,
         id: Self::Id,
         input: R,
         len: Option<u64>,
     ) -> Result<()> {
-        if self.write_through {
-            // Write-through: write to both backends
-            // We need to buffer the input since we can't read from it twice
-            use tokio::io::AsyncReadExt;
-            let mut buffer = Vec::new();
-            let mut reader = input;
-            reader.read_to_end(&mut buffer).await?;
-
-            // Write to primary first
-            let primary_result = {
-                let cursor = std::io::Cursor::new(&buffer);
-                let mut async_cursor = tokio::io::BufReader::new(cursor);
-                self.primary.put(id.clone(), &mut async_cursor, len).await
-            };
-
-            // Write to secondary
-            let secondary_result = {
-                let cursor = std::io::Cursor::new(&buffer);
-                let mut async_cursor = tokio::io::BufReader::new(cursor);
-                self.secondary.put(id.clone(), &mut async_cursor, len).await
-            };
+        self.put_streaming(id, input, len, PutOptions::default())
+            .await
+    }
 
-            if let Err(e) = &secondary_result {
-                tracing::warn!(?id, error = ?e, "Secondary write failed (best-effort)");
-            }
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        if !options.has_preconditions() {
+            return self.put_streaming(id, input, len, options).await;
+        }
+        if !self.write_through {
+            return self.primary.put_with(id, input, len, options).await;
+        }
 
-            // Return error if primary failed (secondary is best-effort in write-through)
-            primary_result
-        } else {
-            // Default: write only to primary
-            self.primary.put(id, input, len).await
+        // Preconditions are evaluated against the primary only; the secondary
+        // is updated if the primary accepted the write. That needs the input
+        // twice, one after the other, so conditional writes are buffered.
+        use tokio::io::AsyncReadExt;
+        let mut buffer = Vec::new();
+        let mut reader = input;
+        reader.read_to_end(&mut buffer).await?;
+
+        let attributes = PutOptions {
+            if_match: None,
+            if_none_match: None,
+            ..options.clone()
+        };
+        self.primary
+            .put_with(id.clone(), &buffer[..], len, options)
+            .await?;
+
+        if let Err(e) = self
+            .secondary
+            .put_with(id.clone(), &buffer[..], len, attributes)
+            .await
+        {
+            tracing::warn!(?id, error = ?e, "Secondary write failed (best-effort)");
         }
+        Ok(())
     }
 
     async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
@@ -140,10 +200,56 @@ where
         output: W,
     ) -> Result<u64> {
         // Note: get_into only tries primary due to stream consumption.
-        // Use get_bytes() for fallback on reads.
+        // Use get_reader() or get_bytes() for fallback on reads.
         self.primary.get_into(id, output).await
     }
 
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        // Opening fails before anything is read, so unlike get_into this can
+        // fall through to secondary on a miss or an error.
+        match self.primary.get_reader(id).await {
+            Ok(reader) => Ok(reader),
+            Err(e) => {
+                tracing::debug!(?id, error = ?e, "Primary get_reader failed, using fallback");
+                self.secondary.get_reader(id).await
+            }
+        }
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        // Same as get_into: the output can only be consumed once.
+        self.primary.get_range(id, range, output).await
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let primary_result = self.primary.copy(src, dst.clone()).await;
+
+        if self.write_through
+            && let Err(e) = self.secondary.copy(src, dst).await
+        {
+            tracing::warn!(?src, error = ?e, "Secondary copy failed (best-effort)");
+        }
+
+        primary_result
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let primary_result = self.primary.rename(src, dst.clone()).await;
+
+        if self.write_through
+            && let Err(e) = self.secondary.rename(src, dst).await
+        {
+            tracing::warn!(?src, error = ?e, "Secondary rename failed (best-effort)");
+        }
+
+        primary_result
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
         // Delete from both backends (idempotent, so safe to try both)
         let primary_result = self.primary.delete(id).await;
@@ -161,11 +267,51 @@ where
         }
     }
 
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        // Same rules as delete: try both, succeed if either does
+        let primary_result = self.primary.delete_folder(folder).await;
+        let secondary_result = self.secondary.delete_folder(folder).await;
+
+        match (primary_result, secondary_result) {
+            (Ok(()), _) => Ok(()),
+            (_, Ok(())) => Ok(()),
+            (Err(e), Err(e2)) => {
+                tracing::error!(?folder, primary_error = ?e, secondary_error = ?e2, "Folder delete failed on both backends");
+                Err(e)
+            }
+        }
+    }
+
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
         // For list, we only query the primary
         // Merging lists from both backends would be complex and potentially confusing
         self.primary.list(prefix).await
     }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        // Like list, only the primary is consulted
+        self.primary.list_with_meta(prefix).await
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        // Like list, only the primary is consulted
+        self.primary
+            .list_page(prefix, page_size, continuation)
+            .await
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        // Like list, only the primary is consulted
+        self.primary.list_dir(folder).await
+    }
 }
 
 #[cfg(test)]
@@ -206,8 +352,20 @@ mod tests {
                 .is_err()
         );
 
-        // Note: get_into doesn't support fallback to secondary due to stream consumption.
-        // For fallback reads, use get_bytes() when the duplex stream issue is resolved.
+        // get_into doesn't fall back to secondary due to stream consumption;
+        // get_reader (and so get_bytes) does.
+        storage
+            .secondary()
+            .put_bytes("secondary-file".to_string(), b"secondary data")
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .get_bytes(&"secondary-file".to_string())
+                .await
+                .unwrap(),
+            b"secondary data"
+        );
     }
 
     #[cfg(feature = "memory")]
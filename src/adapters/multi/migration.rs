@@ -90,8 +90,10 @@ pub struct MigrateOptions<Id> {
     /// Must be at least 1; values of 0 are clamped to 1.  Default: `4`.
     pub concurrency: usize,
 
-    /// When `true`, each item is deleted from the source after it has been
-    /// successfully copied to the destination (move semantics).
+    /// When `true`, items successfully copied to the destination are deleted
+    /// from the source once all copies have finished (move semantics).  The
+    /// deletes go through [`Storage::delete_many`], so backends with a
+    /// native batch endpoint remove many items per request.
     ///
     /// A failed delete is logged as a warning but does **not** cause the item
     /// to appear in [`MigrationResult::errors`] — the copy has already
@@ -208,9 +210,6 @@ impl<Id: Debug> std::fmt::Display for MigrationResult<Id> {
 
 enum ItemOutcome<Id> {
     Transferred(Id),
-    TransferredAndDeleted(Id),
-    /// Copy succeeded but the subsequent source delete failed (logged, not fatal).
-    TransferredDeleteFailed(Id),
     Skipped(Id),
     Error(Id, crate::Error),
 }
@@ -225,9 +224,10 @@ enum ItemOutcome<Id> {
 /// 2. Items are transferred concurrently, up to `options.concurrency` at a time.
 /// 3. The operation is *best-effort*: per-item failures are collected in
 ///    [`MigrationResult::errors`] rather than aborting the whole run.
-/// 4. If `options.delete_source` is `true`, each successfully copied item is
-///    deleted from `source`.  A delete failure is logged as a warning but does
-///    **not** move the item to [`MigrationResult::errors`].
+/// 4. If `options.delete_source` is `true`, the successfully copied items are
+///    then deleted from `source` with a single [`Storage::delete_many`] call.
+///    A delete failure is logged as a warning but does **not** move the item
+///    to [`MigrationResult::errors`].
 ///
 /// # Errors
 ///
@@ -297,8 +297,6 @@ where
 
     let outcomes: Vec<ItemOutcome<S1::Id>> = futures::stream::iter(ids)
         .map(|id| {
-            let source = source;
-            let dest = dest;
             async move {
                 // ── Conflict check ────────────────────────────────────────
                 if conflict != ConflictStrategy::Overwrite {
@@ -334,25 +332,7 @@ where
                     return ItemOutcome::Error(id, e);
                 }
 
-                // ── Optional source deletion (move semantics) ─────────────
-                if delete_source {
-                    match source.delete(&id).await {
-                        Ok(()) => {
-                            tracing::debug!(?id, "Deleted source item after successful copy");
-                            ItemOutcome::TransferredAndDeleted(id)
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                ?id,
-                                error = ?e,
-                                "Copy succeeded but failed to delete source item"
-                            );
-                            ItemOutcome::TransferredDeleteFailed(id)
-                        }
-                    }
-                } else {
-                    ItemOutcome::Transferred(id)
-                }
+                ItemOutcome::Transferred(id)
             }
         })
         .buffer_unordered(concurrency)
@@ -366,13 +346,6 @@ where
             ItemOutcome::Transferred(id) => {
                 result.transferred.push(id);
             }
-            ItemOutcome::TransferredAndDeleted(id) => {
-                result.deleted.push(id.clone());
-                result.transferred.push(id);
-            }
-            ItemOutcome::TransferredDeleteFailed(id) => {
-                result.transferred.push(id);
-            }
             ItemOutcome::Skipped(id) => {
                 result.skipped.push(id);
             }
@@ -382,6 +355,29 @@ where
         }
     }
 
+    // ── Optional source deletion (move semantics) ─────────────────────────
+    if delete_source && !result.transferred.is_empty() {
+        match source.delete_many(result.transferred.clone()).await {
+            Ok(batch) => {
+                for (id, e) in &batch.failed {
+                    tracing::warn!(
+                        ?id,
+                        error = ?e,
+                        "Copy succeeded but failed to delete source item"
+                    );
+                }
+                tracing::debug!(
+                    deleted = batch.success_count(),
+                    "Deleted source items after successful copy"
+                );
+                result.deleted = batch.succeeded;
+            }
+            Err(e) => {
+                tracing::warn!(error = ?e, "Copies succeeded but failed to delete source items");
+            }
+        }
+    }
+
     tracing::info!(
         transferred = result.transferred_count(),
         skipped = result.skipped_count(),
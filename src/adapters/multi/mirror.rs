@@ -1,13 +1,23 @@
-use crate::{Error, MirrorFailureDetails, Result, Storage};
+use crate::{
+    ContinuationToken, DirListing, Entry, Error, GetReader, MirrorFailureDetails, ObjectMeta, Page,
+    PutOptions, Result, Storage,
+};
+use futures::StreamExt as _;
 use futures::stream::BoxStream;
 use std::fmt::Debug;
+use std::ops::Range;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tracing;
 
 /// Write operation strategy for mirrored backends.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
 pub enum WriteStrategy {
     /// All backends must succeed or operation fails.
     AllOrFail { rollback: bool },
@@ -21,6 +31,11 @@ pub enum WriteStrategy {
 
 /// Controls when a mirror operation returns to the caller.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum ReturnPolicy {
     /// Wait for all backends to complete before returning.
     /// Returns error if strategy threshold is not met.
@@ -59,13 +74,13 @@ impl WriteStrategy {
 
 /// Mirrors data across multiple backends for redundancy.
 ///
-/// Writes to all backends sequentially. Reads from primary (configurable).
+/// Streams writes to all backends concurrently. Reads from primary (configurable).
 /// Use [`WriteStrategy`] to control success criteria and [`ReturnPolicy`]
 /// to control when operations return to the caller.
 ///
 /// **Note:** For Optimistic return policy with background writes, `S` must be `'static`.
-/// Use `get_into()` directly for reads; `get_string()`/`get_bytes()` may have issues
-/// with duplex streams in some async contexts.
+/// `get_into()` reads from primary only; `get_reader()` (and so `get_bytes()`)
+/// falls back to the other backends when primary fails with anything but `NotFound`.
 #[derive(Debug)]
 pub struct MirrorStorage<S: Storage + 'static> {
     backends: Vec<Arc<S>>,
@@ -131,23 +146,22 @@ impl<S: Storage + 'static> MirrorStorage<S> {
     }
 
     /// Evaluate if the write results meet the strategy requirements.
-    /// Returns Ok(()) on success, or Error with detailed failure info.
-    fn evaluate_write_results(&self, results: &[Result<()>]) -> Result<MirrorFailureDetails> {
-        let successes: Vec<usize> = results
-            .iter()
-            .enumerate()
-            .filter_map(|(i, r)| if r.is_ok() { Some(i) } else { None })
-            .collect();
-
-        let failures: Vec<(usize, Box<Error>)> = results
-            .iter()
-            .enumerate()
-            .filter_map(|(i, r)| {
-                r.as_ref()
-                    .err()
-                    .map(|e| (i, Box::new(Error::Generic(e.to_string()))))
-            })
-            .collect();
+    /// Returns Ok(()) on success, or Error with detailed failure info
+    /// holding each backend's original error and timing.
+    fn evaluate_write_results(
+        &self,
+        results: Vec<(Result<()>, Duration)>,
+    ) -> Result<MirrorFailureDetails> {
+        let mut successes = Vec::new();
+        let mut failures = Vec::new();
+        let mut elapsed = Vec::with_capacity(results.len());
+        for (idx, (result, took)) in results.into_iter().enumerate() {
+            match result {
+                Ok(()) => successes.push(idx),
+                Err(e) => failures.push((idx, Box::new(e))),
+            }
+            elapsed.push((idx, took));
+        }
 
         let required = self.write_strategy.required_successes(self.backends.len());
 
@@ -155,6 +169,7 @@ impl<S: Storage + 'static> MirrorStorage<S> {
             successes,
             failures,
             rollback_errors: Vec::new(),
+            elapsed,
         };
 
         if details.success_count() >= required {
@@ -174,15 +189,242 @@ impl<S: Storage + 'static> MirrorStorage<S> {
         let mut rollback_errors = Vec::new();
 
         for &idx in successful_indices {
-            if let Some(backend) = self.backends.get(idx) {
-                if let Err(e) = backend.as_ref().delete(id).await {
-                    rollback_errors.push((idx, Box::new(e)));
-                }
+            if let Some(backend) = self.backends.get(idx)
+                && let Err(e) = backend.as_ref().delete(id).await
+            {
+                rollback_errors.push((idx, Box::new(e)));
             }
         }
 
         rollback_errors
     }
+
+    /// Log a failed write and roll back its successful backends if the
+    /// strategy asks for it.
+    async fn fail_write(&self, id: &S::Id, mut details: MirrorFailureDetails) -> Error {
+        tracing::error!(
+            ?id,
+            success_count = details.success_count(),
+            failure_count = details.failure_count(),
+            required = self.write_strategy.required_successes(self.backends.len()),
+            "Mirror write failed"
+        );
+        if self.write_strategy.should_rollback() && details.has_successes() {
+            tracing::info!(
+                ?id,
+                rollback_count = details.successes.len(),
+                "Starting rollback"
+            );
+            let rollback_errors = self.rollback_writes(id, &details.successes).await;
+            if !rollback_errors.is_empty() {
+                tracing::error!(
+                    ?id,
+                    rollback_error_count = rollback_errors.len(),
+                    "Rollback encountered errors"
+                );
+            } else {
+                tracing::info!(?id, "Rollback completed successfully");
+            }
+            details.rollback_errors = rollback_errors;
+        }
+        Error::MirrorFailure(details)
+    }
+
+    /// Run `op` against every backend and its index concurrently, applying
+    /// the backend timeout, and return each result with how long it took.
+    async fn apply_to_all<'a, F, Fut>(&'a self, op: F) -> Vec<(Result<()>, Duration)>
+    where
+        F: Fn(usize, &'a S) -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        let timeout = self.backend_timeout;
+        let futures = self.backends.iter().enumerate().map(|(idx, backend)| {
+            let fut = op(idx, backend.as_ref());
+            async move {
+                let started = Instant::now();
+                let result = match timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, fut)
+                        .await
+                        .unwrap_or_else(|_| Err(Error::Timeout("Backend timeout".to_string()))),
+                    None => fut.await,
+                };
+                (result, started.elapsed())
+            }
+        });
+        futures::future::join_all(futures).await
+    }
+
+    /// Evaluate the results of a server-side copy or rename against the
+    /// write strategy, undoing the successful ones with `undo` on failure.
+    async fn finish_relocation<'a, F, Fut>(
+        &'a self,
+        src: &S::Id,
+        results: Vec<(Result<()>, Duration)>,
+        undo: F,
+    ) -> Result<()>
+    where
+        F: Fn(&'a S) -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        match self.evaluate_write_results(results) {
+            Ok(_details) => Ok(()),
+            Err(Error::MirrorFailure(mut details)) => {
+                tracing::error!(
+                    ?src,
+                    success_count = details.success_count(),
+                    failure_count = details.failure_count(),
+                    "Mirror copy/rename failed"
+                );
+                if self.write_strategy.should_rollback() && details.has_successes() {
+                    for &idx in &details.successes {
+                        if let Err(e) = undo(self.backends[idx].as_ref()).await {
+                            details.rollback_errors.push((idx, Box::new(e)));
+                        }
+                    }
+                }
+                Err(Error::MirrorFailure(details))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Stream `input` to every backend at once, writing it with the content
+    /// attributes in `options`, which must not hold preconditions.
+    async fn put_streaming<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: S::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        let required_successes = self.write_strategy.required_successes(self.backends.len());
+        let backend_count = self.backends.len();
+
+        // Every backend uploads in its own task, reading from a bounded pipe
+        // that is fed from the input, so memory use does not grow with the
+        // size of the upload. Owning the tasks lets Optimistic and FastFail
+        // return while slower backends are still writing.
+        let mut writers = Vec::with_capacity(backend_count);
+        let mut aborts = Vec::with_capacity(backend_count);
+        let mut pending = futures::stream::FuturesUnordered::new();
+        for (idx, backend) in self.backends.iter().enumerate() {
+            let (writer, reader) = crate::io::pipe();
+            writers.push(writer);
+
+            let backend = Arc::clone(backend);
+            let task_id = id.clone();
+            let task_options = options.clone();
+            let timeout = self.backend_timeout;
+            let handle = tokio::spawn(async move {
+                let started = Instant::now();
+                let upload = backend
+                    .as_ref()
+                    .put_with(task_id.clone(), reader, len, task_options);
+                let result =
+                    match timeout {
+                        Some(timeout) => tokio::time::timeout(timeout, upload)
+                            .await
+                            .unwrap_or_else(|_| {
+                                tracing::warn!(
+                                    id = ?task_id,
+                                    backend_index = idx,
+                                    ?timeout,
+                                    "Backend write timed out"
+                                );
+                                Err(Error::Timeout("Backend timeout".to_string()))
+                            }),
+                        None => upload.await,
+                    };
+                (result, started.elapsed())
+            });
+            aborts.push(handle.abort_handle());
+            let spawned = Instant::now();
+            pending.push(async move {
+                let (result, took) = handle.await.unwrap_or_else(|e| {
+                    (
+                        Err(Error::Generic(format!("Backend write task failed: {e}"))),
+                        spawned.elapsed(),
+                    )
+                });
+                (idx, result, took)
+            });
+        }
+
+        if let Err(e) = crate::io::tee(input, writers).await {
+            // The pipes forward the error, so no backend commits a partial item.
+            tracing::warn!(?id, error = ?e, "Failed to read mirror input");
+            return Err(Error::Io(e));
+        }
+
+        let mut successes = Vec::new();
+        let mut failures = Vec::new();
+        let mut elapsed = Vec::with_capacity(backend_count);
+        while let Some((idx, result, took)) = pending.next().await {
+            match result {
+                Ok(()) => successes.push(idx),
+                Err(e) => {
+                    tracing::warn!(?id, backend_index = idx, error = ?e, ?took, "Backend write failed");
+                    failures.push((idx, Box::new(e)));
+                }
+            }
+            elapsed.push((idx, took));
+
+            let remaining = pending.len();
+            let threshold_met = successes.len() >= required_successes;
+            match self.return_policy {
+                ReturnPolicy::WaitAll => continue,
+                ReturnPolicy::Optimistic | ReturnPolicy::FastFail if threshold_met => {
+                    if remaining > 0 {
+                        tracing::info!(
+                            ?id,
+                            success_count = successes.len(),
+                            remaining_backends = remaining,
+                            "Threshold met, returning early with background writes"
+                        );
+                        let id = id.clone();
+                        tokio::spawn(async move {
+                            while let Some((idx, result, _)) = pending.next().await {
+                                if let Err(e) = result {
+                                    tracing::warn!(?id, backend_index = idx, error = ?e, "Background write failed");
+                                }
+                            }
+                        });
+                    }
+                    return Ok(());
+                }
+                ReturnPolicy::FastFail if successes.len() + remaining < required_successes => {
+                    tracing::warn!(
+                        ?id,
+                        success_count = successes.len(),
+                        required_successes,
+                        remaining_backends = remaining,
+                        "Success impossible, failing fast"
+                    );
+                    // Stop the writes still in flight; their results no longer matter.
+                    for abort in &aborts {
+                        abort.abort();
+                    }
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        successes.sort_unstable();
+        failures.sort_unstable_by_key(|(idx, _)| *idx);
+        elapsed.sort_unstable_by_key(|(idx, _)| *idx);
+        let details = MirrorFailureDetails {
+            successes,
+            failures,
+            rollback_errors: Vec::new(),
+            elapsed,
+        };
+        if details.success_count() >= required_successes {
+            Ok(())
+        } else {
+            Err(self.fail_write(&id, details).await)
+        }
+    }
 }
 
 impl<S: Storage + 'static> Storage for MirrorStorage<S> {
@@ -228,367 +470,179 @@ impl<S: Storage + 'static> Storage for MirrorStorage<S> {
         }
     }
 
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        // Check primary first; a NotFound there is authoritative, like `exists`
+        match self.primary().stat(id).await {
+            Err(Error::NotFound(msg)) => Err(Error::NotFound(msg)),
+            Err(e) => {
+                tracing::warn!(?id, error = ?e, "Primary stat failed, trying fallbacks");
+                for (idx, backend) in self.backends.iter().enumerate() {
+                    if idx == self.primary_index {
+                        continue;
+                    }
+                    if let Ok(meta) = backend.as_ref().stat(id).await {
+                        tracing::info!(?id, backend_index = idx, "Fallback succeeded");
+                        return Ok(meta);
+                    }
+                }
+                tracing::error!(?id, "All stat attempts failed");
+                Err(e)
+            }
+            ok => ok,
+        }
+    }
+
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
         &self,
         id: Self::Id,
         input: R,
         len: Option<u64>,
     ) -> Result<()> {
-        // Buffer the input since we need to write to multiple backends
-        use tokio::io::AsyncReadExt;
-        let mut buffer = Vec::new();
-        let mut reader = input;
-        reader.read_to_end(&mut buffer).await?;
+        self.put_streaming(id, input, len, PutOptions::default())
+            .await
+    }
 
-        let required_successes = self.write_strategy.required_successes(self.backends.len());
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        if !options.has_preconditions() {
+            return self.put_streaming(id, input, len, options).await;
+        }
+        let attributes = PutOptions {
+            if_match: None,
+            if_none_match: None,
+            ..options.clone()
+        };
 
-        match self.return_policy {
-            ReturnPolicy::WaitAll => {
-                // Write to all backends sequentially
-                let mut results = Vec::new();
-                for (idx, backend) in self.backends.iter().enumerate() {
-                    let cursor = std::io::Cursor::new(buffer.clone());
-                    let mut async_cursor = tokio::io::BufReader::new(cursor);
-                    let result = if let Some(timeout) = self.backend_timeout {
-                        tokio::time::timeout(
-                            timeout,
-                            backend.as_ref().put(id.clone(), &mut async_cursor, len),
-                        )
-                        .await
-                        .unwrap_or_else(|_| {
-                            tracing::warn!(
-                                ?id,
-                                backend_index = idx,
-                                ?timeout,
-                                "Backend write timed out"
-                            );
-                            Err(Error::Generic("Backend timeout".to_string()))
-                        })
-                    } else {
-                        backend
-                            .as_ref()
-                            .put(id.clone(), &mut async_cursor, len)
-                            .await
-                    };
-                    results.push(result);
-                }
+        // ETags differ between backends, so the preconditions are evaluated
+        // against the primary alone, which gets the input directly. Once the
+        // primary accepts the write the other backends are brought in line
+        // unconditionally by streaming the primary's copy to them, so the
+        // input is read only once and nothing is buffered. The return policy
+        // does not apply: the call always waits for every backend.
+        let primary = self.primary();
+        let started = Instant::now();
+        let conditional = primary.put_with(id.clone(), input, len, options);
+        match self.backend_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, conditional)
+                .await
+                .unwrap_or_else(|_| Err(Error::Timeout("Backend timeout".to_string())))?,
+            None => conditional.await?,
+        }
 
-                // Evaluate results
-                match self.evaluate_write_results(&results) {
-                    Ok(_details) => Ok(()),
-                    Err(Error::MirrorFailure(mut details)) => {
-                        tracing::error!(
-                            ?id,
-                            success_count = details.success_count(),
-                            failure_count = details.failure_count(),
-                            required = required_successes,
-                            "Mirror write failed"
-                        );
-                        // Rollback if strategy requires it
-                        if self.write_strategy.should_rollback() && details.has_successes() {
-                            tracing::info!(
-                                ?id,
-                                rollback_count = details.successes.len(),
-                                "Starting rollback"
-                            );
-                            let rollback_errors =
-                                self.rollback_writes(&id, &details.successes).await;
-                            if !rollback_errors.is_empty() {
-                                tracing::error!(
-                                    ?id,
-                                    rollback_error_count = rollback_errors.len(),
-                                    "Rollback encountered errors"
-                                );
-                            } else {
-                                tracing::info!(?id, "Rollback completed successfully");
-                            }
-                            details.rollback_errors = rollback_errors;
-                        }
-                        Err(Error::MirrorFailure(details))
+        let primary_elapsed = started.elapsed();
+
+        let mut writers = Vec::with_capacity(self.backends.len());
+        let readers: std::sync::Mutex<Vec<_>> = std::sync::Mutex::new(
+            (0..self.backends.len())
+                .map(|idx| {
+                    (idx != self.primary_index).then(|| {
+                        let (writer, reader) = crate::io::pipe();
+                        writers.push(writer);
+                        reader
+                    })
+                })
+                .collect(),
+        );
+        let replicate = async {
+            let copied = match primary.get_reader(&id).await {
+                Ok(reader) => crate::io::tee(reader, writers).await,
+                Err(e) => {
+                    let e = std::io::Error::other(e);
+                    for writer in writers {
+                        writer.fail(&e).await;
                     }
-                    Err(e) => Err(e),
+                    Err(e)
                 }
+            };
+            if let Err(e) = copied {
+                tracing::warn!(?id, error = ?e, "Failed to read back the primary's copy");
             }
-
-            ReturnPolicy::Optimistic => {
-                // Write to backends until we have enough successes, then spawn background task for the rest
-                let mut success_count = 0;
-                let mut successes = Vec::new();
-                let mut failures = Vec::new();
-
-                for (idx, backend) in self.backends.iter().enumerate() {
-                    let cursor = std::io::Cursor::new(buffer.clone());
-                    let mut async_cursor = tokio::io::BufReader::new(cursor);
-                    let result = if let Some(timeout) = self.backend_timeout {
-                        tokio::time::timeout(
-                            timeout,
-                            backend.as_ref().put(id.clone(), &mut async_cursor, len),
-                        )
-                        .await
-                        .unwrap_or_else(|_| {
-                            tracing::warn!(
-                                ?id,
-                                backend_index = idx,
-                                ?timeout,
-                                "Backend write timed out"
-                            );
-                            Err(Error::Generic("Backend timeout".to_string()))
-                        })
-                    } else {
-                        backend
-                            .as_ref()
-                            .put(id.clone(), &mut async_cursor, len)
-                            .await
-                    };
-
-                    match result {
-                        Ok(_) => {
-                            success_count += 1;
-                            successes.push(idx);
-                            // Return early once we have enough successes
-                            if success_count >= required_successes {
-                                tracing::info!(
-                                    ?id,
-                                    success_count,
-                                    completed_backends = idx + 1,
-                                    remaining_backends = self.backends.len() - (idx + 1),
-                                    "Threshold met, returning early with background writes"
-                                );
-                                // Spawn background task for remaining backends
-                                if idx + 1 < self.backends.len() {
-                                    let remaining_backends: Vec<Arc<S>> =
-                                        self.backends[(idx + 1)..].to_vec();
-                                    let buffer_clone = buffer.clone();
-                                    let id_clone = id.clone();
-                                    let timeout = self.backend_timeout;
-                                    let _remaining_count = remaining_backends.len();
-                                    // Spawn background task for remaining backends
-                                    // This is why S: 'static is required - the task must own the Arc
-                                    tokio::spawn(async move {
-                                        for (rel_idx, backend) in
-                                            remaining_backends.iter().enumerate()
-                                        {
-                                            let abs_idx = idx + 1 + rel_idx;
-                                            let cursor = std::io::Cursor::new(buffer_clone.clone());
-                                            let mut async_cursor =
-                                                tokio::io::BufReader::new(cursor);
-                                            let result = if let Some(timeout) = timeout {
-                                                tokio::time::timeout(
-                                                    timeout,
-                                                    backend.as_ref().put(
-                                                        id_clone.clone(),
-                                                        &mut async_cursor,
-                                                        len,
-                                                    ),
-                                                )
-                                                .await
-                                            } else {
-                                                Ok(backend
-                                                    .as_ref()
-                                                    .put(id_clone.clone(), &mut async_cursor, len)
-                                                    .await)
-                                            };
-
-                                            if let Ok(Err(e)) = &result {
-                                                tracing::warn!(?id_clone, backend_index = abs_idx, error = ?e, "Background write failed");
-                                            } else if result.is_err() {
-                                                tracing::warn!(
-                                                    ?id_clone,
-                                                    backend_index = abs_idx,
-                                                    "Background write timed out"
-                                                );
-                                            }
-                                        }
-                                    });
-                                }
-                                return Ok(());
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!(?id, backend_index = idx, error = ?e, "Backend write failed");
-                            failures.push((idx, Box::new(Error::Generic(e.to_string()))));
-                        }
-                    }
-                }
-
-                // If we get here, we didn't meet the threshold
-                tracing::error!(
-                    ?id,
-                    success_count,
-                    required_successes,
-                    "Mirror write failed"
-                );
-                let details = MirrorFailureDetails {
-                    successes,
-                    failures,
-                    rollback_errors: Vec::new(),
-                };
-
-                // Rollback if strategy requires it
-                if self.write_strategy.should_rollback() && !details.successes.is_empty() {
-                    tracing::info!(
-                        ?id,
-                        rollback_count = details.successes.len(),
-                        "Starting rollback"
-                    );
-                    let rollback_errors = self.rollback_writes(&id, &details.successes).await;
-                    if !rollback_errors.is_empty() {
-                        tracing::error!(
-                            ?id,
-                            rollback_error_count = rollback_errors.len(),
-                            "Rollback encountered errors"
-                        );
-                    }
-                    Err(Error::MirrorFailure(MirrorFailureDetails {
-                        rollback_errors,
-                        ..details
-                    }))
-                } else {
-                    Err(Error::MirrorFailure(details))
+        };
+        let uploads = self.apply_to_all(|idx, backend| {
+            let id = id.clone();
+            let reader = readers.lock().unwrap()[idx].take();
+            let attributes = attributes.clone();
+            async move {
+                match reader {
+                    Some(reader) => backend.put_with(id, reader, len, attributes).await,
+                    None => Ok(()),
                 }
             }
+        });
+        let ((), mut results) = futures::join!(replicate, uploads);
+        results[self.primary_index].1 = primary_elapsed;
+
+        match self.evaluate_write_results(results) {
+            Ok(_details) => Ok(()),
+            Err(Error::MirrorFailure(details)) => Err(self.fail_write(&id, details).await),
+            Err(e) => Err(e),
+        }
+    }
 
-            ReturnPolicy::FastFail => {
-                // Write to backends, but return early if we know we can't succeed
-                let mut success_count = 0;
-                let mut successes = Vec::new();
-                let mut failures = Vec::new();
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        output: W,
+    ) -> Result<u64> {
+        // Note: get_into only tries primary due to stream consumption.
+        // Use get_reader() or get_bytes() for fallback on reads.
+        self.primary().get_into(id, output).await
+    }
 
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        // Like `stat`: a NotFound on primary is authoritative, other errors
+        // try the remaining backends before anything has been read.
+        match self.primary().get_reader(id).await {
+            Err(Error::NotFound(msg)) => Err(Error::NotFound(msg)),
+            Err(e) => {
+                tracing::warn!(?id, error = ?e, "Primary get_reader failed, trying fallbacks");
                 for (idx, backend) in self.backends.iter().enumerate() {
-                    let cursor = std::io::Cursor::new(buffer.clone());
-                    let mut async_cursor = tokio::io::BufReader::new(cursor);
-                    let result = if let Some(timeout) = self.backend_timeout {
-                        tokio::time::timeout(
-                            timeout,
-                            backend.as_ref().put(id.clone(), &mut async_cursor, len),
-                        )
-                        .await
-                        .unwrap_or_else(|_| {
-                            tracing::warn!(
-                                ?id,
-                                backend_index = idx,
-                                ?timeout,
-                                "Backend write timed out"
-                            );
-                            Err(Error::Generic("Backend timeout".to_string()))
-                        })
-                    } else {
-                        backend
-                            .as_ref()
-                            .put(id.clone(), &mut async_cursor, len)
-                            .await
-                    };
-
-                    match result {
-                        Ok(_) => {
-                            success_count += 1;
-                            successes.push(idx);
-                            // Return early if we have enough successes
-                            if success_count >= required_successes {
-                                return Ok(());
-                            }
-                        }
-                        Err(e) => {
-                            failures.push((idx, Box::new(Error::Generic(e.to_string()))));
-                        }
-                    }
-
-                    // Calculate if success is still possible
-                    let remaining_backends = self.backends.len() - (idx + 1);
-                    let max_possible_successes = success_count + remaining_backends;
-
-                    // If we can't possibly meet the threshold, fail fast
-                    if max_possible_successes < required_successes {
-                        tracing::warn!(
-                            ?id,
-                            success_count,
-                            required_successes,
-                            remaining_backends,
-                            "Success impossible, failing fast"
-                        );
-                        let details = MirrorFailureDetails {
-                            successes,
-                            failures,
-                            rollback_errors: Vec::new(),
-                        };
-
-                        // Rollback if strategy requires it
-                        if self.write_strategy.should_rollback() && !details.successes.is_empty() {
-                            tracing::info!(
-                                ?id,
-                                rollback_count = details.successes.len(),
-                                "Starting rollback"
-                            );
-                            let rollback_errors =
-                                self.rollback_writes(&id, &details.successes).await;
-                            if !rollback_errors.is_empty() {
-                                tracing::error!(
-                                    ?id,
-                                    rollback_error_count = rollback_errors.len(),
-                                    "Rollback encountered errors"
-                                );
-                            }
-                            return Err(Error::MirrorFailure(MirrorFailureDetails {
-                                rollback_errors,
-                                ..details
-                            }));
-                        } else {
-                            return Err(Error::MirrorFailure(details));
-                        }
+                    if idx == self.primary_index {
+                        continue;
                     }
-                }
-
-                // If we get here, evaluate final results
-                if success_count >= required_successes {
-                    Ok(())
-                } else {
-                    tracing::error!(
-                        ?id,
-                        success_count,
-                        required_successes,
-                        "Mirror write failed"
-                    );
-                    let details = MirrorFailureDetails {
-                        successes,
-                        failures,
-                        rollback_errors: Vec::new(),
-                    };
-
-                    // Rollback if strategy requires it
-                    if self.write_strategy.should_rollback() && !details.successes.is_empty() {
-                        tracing::info!(
-                            ?id,
-                            rollback_count = details.successes.len(),
-                            "Starting rollback"
-                        );
-                        let rollback_errors = self.rollback_writes(&id, &details.successes).await;
-                        if !rollback_errors.is_empty() {
-                            tracing::error!(
-                                ?id,
-                                rollback_error_count = rollback_errors.len(),
-                                "Rollback encountered errors"
-                            );
-                        }
-                        Err(Error::MirrorFailure(MirrorFailureDetails {
-                            rollback_errors,
-                            ..details
-                        }))
-                    } else {
-                        Err(Error::MirrorFailure(details))
+                    if let Ok(reader) = backend.as_ref().get_reader(id).await {
+                        tracing::info!(?id, backend_index = idx, "Fallback succeeded");
+                        return Ok(reader);
                     }
                 }
+                tracing::error!(?id, "All get_reader attempts failed");
+                Err(e)
             }
+            ok => ok,
         }
     }
 
-    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
         &self,
         id: &Self::Id,
+        range: Range<u64>,
         output: W,
     ) -> Result<u64> {
-        // Note: get_into only tries primary due to stream consumption.
-        // Use get_bytes() for fallback on reads.
-        self.primary().get_into(id, output).await
+        // Same as get_into: the output can only be consumed once.
+        self.primary().get_range(id, range, output).await
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        // Copy on every backend in parallel; the return policy does not
+        // apply since no data is streamed through the mirror.
+        let results = self
+            .apply_to_all(|_, backend| backend.copy(src, dst.clone()))
+            .await;
+        self.finish_relocation(src, results, |backend| backend.delete(&dst))
+            .await
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let results = self
+            .apply_to_all(|_, backend| backend.rename(src, dst.clone()))
+            .await;
+        self.finish_relocation(src, results, |backend| backend.rename(&dst, src.clone()))
+            .await
     }
 
     async fn delete(&self, id: &Self::Id) -> Result<()> {
@@ -616,12 +670,64 @@ impl<S: Storage + 'static> Storage for MirrorStorage<S> {
         }
     }
 
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        // Same rules as delete: all backends in parallel, AtLeastOne
+        let futures = self
+            .backends
+            .iter()
+            .map(|backend| backend.as_ref().delete_folder(folder));
+        let results: Vec<Result<()>> = futures::future::join_all(futures).await;
+
+        let successes = results.iter().filter(|r| r.is_ok()).count();
+        let failures = results.len() - successes;
+
+        if successes > 0 {
+            if failures > 0 {
+                tracing::warn!(
+                    ?folder,
+                    successes,
+                    failures,
+                    "Folder delete succeeded partially"
+                );
+            }
+            Ok(())
+        } else {
+            tracing::error!(?folder, "Folder delete failed on all backends");
+            results.into_iter().find(|r| r.is_err()).unwrap()
+        }
+    }
+
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
         // List from primary only
         // Merging lists from multiple backends would require deduplication
         // and is complex to implement with streams
         self.primary().list(prefix).await
     }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        // List from primary only, as with list
+        self.primary().list_with_meta(prefix).await
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        // List from primary only, as with list
+        self.primary()
+            .list_page(prefix, page_size, continuation)
+            .await
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        // List from primary only, as with list
+        self.primary().list_dir(folder).await
+    }
 }
 
 /// Builder for [`MirrorStorage`].
@@ -914,6 +1020,29 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "memory")]
+    #[tokio::test]
+    async fn test_mirror_delete_folder() {
+        use crate::MemoryStorage;
+
+        let storage = MirrorStorage::new(vec![MemoryStorage::new(), MemoryStorage::new()]);
+        storage
+            .put_bytes("dir/a".to_string(), b"data")
+            .await
+            .unwrap();
+        storage
+            .put_bytes("dir/sub/b".to_string(), b"data")
+            .await
+            .unwrap();
+
+        storage.delete_folder(&"dir".to_string()).await.unwrap();
+
+        for index in 0..2 {
+            let backend = storage.backend(index).unwrap();
+            assert!(!backend.folder_exists(&"dir".to_string()).await.unwrap());
+        }
+    }
+
     #[cfg(feature = "memory")]
     #[tokio::test]
     async fn test_mirror_read_fallback() {
@@ -2,17 +2,34 @@
 //!
 //! Composite patterns implementing `Storage` by coordinating multiple backends:
 //!
+//! - [`CachedStorage`] - Serves reads from a cache in front of an origin
+//! - [`EncryptedStorage`] - Encrypts contents (and optionally identifiers) client-side
 //! - [`FallbackStorage`] - Falls back to secondary on primary failure
 //! - [`MirrorStorage`] - Replicates data across multiple backends
 //! - [`ReadOnlyStorage`] - Prevents all write operations
+//! - [`PrefixedStorage`] - Scopes a backend to a key prefix
+//! - [`PathResolvingStorage`] - Addresses an ID-based drive by path
+//! - [`RetryStorage`] - Retries transient failures with backoff
 //! - [`migration`] - Bulk-migrate items between any two storage backends
 
+mod cached;
+#[cfg(feature = "encryption")]
+mod encrypted;
 mod fallback;
 pub mod migration;
 mod mirror;
+mod path_resolving;
+mod prefixed;
 mod readonly;
+mod retry;
 
+pub use cached::CachedStorage;
+#[cfg(feature = "encryption")]
+pub use encrypted::{EncryptedStorage, Keyring};
 pub use fallback::FallbackStorage;
 pub use migration::{ConflictStrategy, MigrateOptions, MigrationResult, migrate};
 pub use mirror::{MirrorStorage, MirrorStorageBuilder, ReturnPolicy, WriteStrategy};
+pub use path_resolving::{DriveItem, DriveTree, PathResolvingStorage};
+pub use prefixed::PrefixedStorage;
 pub use readonly::ReadOnlyStorage;
+pub use retry::RetryStorage;
@@ -0,0 +1,587 @@
+use crate::{DirListing, Error, GetReader, ObjectMeta, PutOptions, Result, Storage, StoragePath};
+use futures::stream::{self, BoxStream};
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// An item inside a folder of a [`DriveTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriveItem {
+    /// Native item ID.
+    pub id: String,
+    /// Name of the item inside its folder.
+    pub name: String,
+    /// True for folders.
+    pub is_folder: bool,
+}
+
+/// An ID-based backend whose items form a folder tree, so paths can be
+/// resolved against it by [`PathResolvingStorage`].
+///
+/// Implemented by the Google Drive, OneDrive and Box adapters.
+pub trait DriveTree: Storage<Id = String> {
+    /// ID of the folder that paths are resolved from by default.
+    fn root_folder_id(&self) -> String;
+
+    /// The items directly inside the folder `parent_id`.
+    fn list_children(&self, parent_id: &str)
+    -> impl Future<Output = Result<Vec<DriveItem>>> + Send;
+
+    /// Find the item called `name` directly inside the folder `parent_id`.
+    ///
+    /// If several items share the name, one of them is returned. The default
+    /// implementation searches [`list_children`](Self::list_children).
+    fn find_child(
+        &self,
+        parent_id: &str,
+        name: &str,
+    ) -> impl Future<Output = Result<Option<DriveItem>>> + Send {
+        async move {
+            Ok(self
+                .list_children(parent_id)
+                .await?
+                .into_iter()
+                .find(|item| item.name == name))
+        }
+    }
+
+    /// Create a folder called `name` inside `parent_id` and return its ID.
+    fn create_folder(
+        &self,
+        parent_id: &str,
+        name: &str,
+    ) -> impl Future<Output = Result<String>> + Send;
+
+    /// Create a file called `name` inside `parent_id` with the contents of
+    /// `input` and return its ID.
+    fn create_file<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        parent_id: &str,
+        name: &str,
+        input: R,
+        len: Option<u64>,
+    ) -> impl Future<Output = Result<String>> + Send;
+
+    /// Store the content attributes in `options` on the file `id`.
+    ///
+    /// The default implementation ignores them, for drives without
+    /// per-item attributes.
+    fn set_attributes(
+        &self,
+        id: &str,
+        options: &PutOptions,
+    ) -> impl Future<Output = Result<()>> + Send {
+        let _ = (id, options);
+        async { Ok(()) }
+    }
+}
+
+/// Addresses an ID-based drive (Google Drive, OneDrive, Box) by path.
+///
+/// Paths such as `reports/2024/q1.csv` are validated as [`StoragePath`]s and
+/// resolved one folder at a time from the root folder, so the drive can be
+/// used with [`migrate`](crate::multi::migrate) or
+/// [`MirrorStorage`](crate::multi::MirrorStorage) alongside path-based
+/// backends. Writes create missing intermediate folders.
+///
+/// Resolved IDs are cached by path. Deletes through this wrapper invalidate
+/// the affected paths, and a `NotFound` from the drive drops the cached entry
+/// so the next call resolves the path afresh. Call
+/// [`invalidate`](Self::invalidate) or [`clear_cache`](Self::clear_cache)
+/// after changing the drive by other means.
+///
+/// Drives allow several items with the same name in a folder, so concurrent
+/// writes through this wrapper create each missing file or folder only once:
+/// the first creates it and the others wait, then use it.
+///
+/// ```rust,ignore
+/// use stowage::adapters::gdrive::{GoogleDriveStorage, TokenProvider};
+/// use stowage::multi::PathResolvingStorage;
+/// use stowage::StorageExt;
+///
+/// let drive = GoogleDriveStorage::new(reqwest::Client::new(), TokenProvider::Static(token))?;
+/// let storage = PathResolvingStorage::new(drive);
+/// storage.put_bytes("reports/2024/q1.csv".to_string(), b"data").await?;
+/// ```
+#[derive(Debug)]
+pub struct PathResolvingStorage<S: DriveTree> {
+    inner: S,
+    root_id: String,
+    cache: RwLock<HashMap<String, DriveItem>>,
+    /// Per-path locks held while a file or folder is created.
+    creating: Mutex<HashMap<String, Arc<futures::lock::Mutex<()>>>>,
+}
+
+impl<S: DriveTree> PathResolvingStorage<S> {
+    /// Resolve paths from the root folder of `storage`.
+    pub fn new(storage: S) -> Self {
+        let root_id = storage.root_folder_id();
+        Self::with_root(storage, root_id)
+    }
+
+    /// Resolve paths from the folder `root_id` of `storage`.
+    pub fn with_root(storage: S, root_id: impl Into<String>) -> Self {
+        Self {
+            inner: storage,
+            root_id: root_id.into(),
+            cache: RwLock::new(HashMap::new()),
+            creating: Mutex::default(),
+        }
+    }
+
+    /// Get a reference to the inner storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap and return the inner storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// ID of the folder paths are resolved from.
+    pub fn root_id(&self) -> &str {
+        &self.root_id
+    }
+
+    /// Resolve `path` to the ID of the file or folder it names.
+    ///
+    /// Returns `Ok(None)` if nothing exists at the path.
+    pub async fn resolve(&self, path: &str) -> Result<Option<String>> {
+        let path = StoragePath::parse(path)?;
+        Ok(self.lookup(&path).await?.map(|item| item.id))
+    }
+
+    /// Forget the cached IDs of `path` and everything below it.
+    pub fn invalidate(&self, path: &str) {
+        let path = path.trim_matches('/');
+        let below = format!("{path}/");
+        self.cache
+            .write()
+            .unwrap()
+            .retain(|key, _| key != path && !key.starts_with(&below));
+    }
+
+    /// Forget all cached IDs.
+    pub fn clear_cache(&self) {
+        self.cache.write().unwrap().clear();
+    }
+
+    fn cached(&self, path: &str) -> Option<DriveItem> {
+        self.cache.read().unwrap().get(path).cloned()
+    }
+
+    fn remember(&self, path: String, item: DriveItem) {
+        self.cache.write().unwrap().insert(path, item);
+    }
+
+    /// Get the lock serializing the creation of the item at `path`.
+    fn create_lock(&self, path: &StoragePath) -> Arc<futures::lock::Mutex<()>> {
+        let mut creating = self.creating.lock().unwrap();
+        Arc::clone(creating.entry(path.to_string()).or_default())
+    }
+
+    /// Forget the lock for `path` unless another task is waiting on it.
+    fn release_create_lock(&self, path: &StoragePath, lock: Arc<futures::lock::Mutex<()>>) {
+        let mut creating = self.creating.lock().unwrap();
+        // One reference is the map's, the other is ours.
+        if Arc::strong_count(&lock) <= 2 {
+            creating.remove(path.as_str());
+        }
+    }
+
+    /// Drop the cached entry for `path` if the backend no longer has it.
+    fn check<T>(&self, path: &StoragePath, result: Result<T>) -> Result<T> {
+        if let Err(Error::NotFound(_)) = &result {
+            self.invalidate(path.as_str());
+        }
+        result
+    }
+
+    /// Walk `path` from the root, one segment at a time.
+    async fn lookup(&self, path: &StoragePath) -> Result<Option<DriveItem>> {
+        if let Some(item) = self.cached(path.as_str()) {
+            return Ok(Some(item));
+        }
+
+        let mut parent_id = self.root_id.clone();
+        let mut current = String::new();
+        let mut item = None;
+        for segment in path.segments() {
+            if item
+                .as_ref()
+                .is_some_and(|item: &DriveItem| !item.is_folder)
+            {
+                return Ok(None);
+            }
+            if !current.is_empty() {
+                current.push('/');
+            }
+            current.push_str(segment);
+
+            let found = match self.cached(&current) {
+                Some(found) => found,
+                None => match self.inner.find_child(&parent_id, segment).await? {
+                    Some(found) => {
+                        self.remember(current.clone(), found.clone());
+                        found
+                    }
+                    None => return Ok(None),
+                },
+            };
+            parent_id = found.id.clone();
+            item = Some(found);
+        }
+        Ok(item)
+    }
+
+    /// Resolve `path` to a file ID, or fail with [`Error::NotFound`].
+    async fn file_id(&self, path: &StoragePath) -> Result<String> {
+        match self.lookup(path).await? {
+            Some(item) if !item.is_folder => Ok(item.id),
+            _ => Err(Error::NotFound(path.to_string())),
+        }
+    }
+
+    /// Write the file at `path`, creating it and any missing folders if it
+    /// does not exist, and return its ID.
+    async fn put_file<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        path: &StoragePath,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        if let Some(item) = self.lookup(path).await? {
+            return self.update_file(path, item, input, len).await;
+        }
+
+        let parent_id = self.ensure_folder(path.parent()).await?;
+        let lock = self.create_lock(path);
+        let result = {
+            let _guard = lock.lock().await;
+            self.create_file_locked(path, &parent_id, input, len).await
+        };
+        self.release_create_lock(path, lock);
+        result
+    }
+
+    /// Replace the content of the existing file `item` at `path`.
+    async fn update_file<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        path: &StoragePath,
+        item: DriveItem,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        if item.is_folder {
+            return Err(Error::AlreadyExists(format!("`{path}` is a folder")));
+        }
+        let result = self.inner.put(item.id.clone(), input, len).await;
+        self.check(path, result).map(|()| item.id)
+    }
+
+    async fn create_file_locked<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        path: &StoragePath,
+        parent_id: &str,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        // Another task may have created the file while we waited.
+        if let Some(item) = self.lookup(path).await? {
+            return self.update_file(path, item, input, len).await;
+        }
+
+        let name = path.file_name();
+        let file_id = self.inner.create_file(parent_id, name, input, len).await?;
+        self.remember(
+            path.to_string(),
+            DriveItem {
+                id: file_id.clone(),
+                name: name.to_string(),
+                is_folder: false,
+            },
+        );
+        Ok(file_id)
+    }
+
+    /// ID of the folder at `path`, creating it and any missing parents.
+    async fn ensure_folder(&self, path: Option<StoragePath>) -> Result<String> {
+        let Some(path) = path else {
+            return Ok(self.root_id.clone());
+        };
+        if let Some(item) = self.lookup(&path).await? {
+            return Self::folder_id(&path, item);
+        }
+
+        let parent_id = Box::pin(self.ensure_folder(path.parent())).await?;
+        let lock = self.create_lock(&path);
+        let result = {
+            let _guard = lock.lock().await;
+            self.create_folder_locked(&path, &parent_id).await
+        };
+        self.release_create_lock(&path, lock);
+        result
+    }
+
+    async fn create_folder_locked(&self, path: &StoragePath, parent_id: &str) -> Result<String> {
+        // Another task may have created the folder while we waited.
+        if let Some(item) = self.lookup(path).await? {
+            return Self::folder_id(path, item);
+        }
+
+        let name = path.file_name();
+        tracing::debug!(path = path.as_str(), "Creating drive folder");
+        let id = match self.inner.create_folder(parent_id, name).await {
+            Ok(id) => id,
+            // Created elsewhere since the lookup, e.g. by another process.
+            Err(e @ (Error::AlreadyExists(_) | Error::Conflict(_))) => {
+                self.invalidate(path.as_str());
+                return match self.lookup(path).await? {
+                    Some(item) => Self::folder_id(path, item),
+                    None => Err(e),
+                };
+            }
+            Err(e) => return Err(e),
+        };
+        self.remember(
+            path.to_string(),
+            DriveItem {
+                id: id.clone(),
+                name: name.to_string(),
+                is_folder: true,
+            },
+        );
+        Ok(id)
+    }
+
+    /// ID of `item` found at `path`, which must be a folder.
+    fn folder_id(path: &StoragePath, item: DriveItem) -> Result<String> {
+        match item.is_folder {
+            true => Ok(item.id),
+            false => Err(Error::Conflict(format!("`{path}` is a file, not a folder"))),
+        }
+    }
+
+    /// Every file below the folder `folder_id` whose path under `base`
+    /// starts with `prefix`.
+    async fn walk(
+        &self,
+        base: Option<String>,
+        folder_id: String,
+        prefix: &str,
+    ) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        let mut pending = vec![(base, folder_id)];
+        while let Some((base, folder_id)) = pending.pop() {
+            for child in self.inner.list_children(&folder_id).await? {
+                // Paths below a folder extend the folder's path, so skipping
+                // a folder that does not match skips nothing that would.
+                let Some(path) = Self::child_path(base.as_deref(), &child.name)
+                    .filter(|path| path.starts_with(prefix))
+                else {
+                    continue;
+                };
+                if child.is_folder {
+                    pending.push((Some(path.clone()), child.id.clone()));
+                } else {
+                    files.push(path.clone());
+                }
+                self.remember(path, child);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Path of the child `name` of `base`, or `None` if the name cannot be
+    /// part of a path (e.g. it contains `/`).
+    fn child_path(base: Option<&str>, name: &str) -> Option<String> {
+        if name.contains('/') {
+            return None;
+        }
+        let path = match base {
+            Some(base) => format!("{base}/{name}"),
+            None => name.to_string(),
+        };
+        StoragePath::parse(&path).ok().map(StoragePath::into_string)
+    }
+}
+
+impl<S: DriveTree> Storage for PathResolvingStorage<S> {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        let path = StoragePath::parse(id)?;
+        match self.lookup(&path).await? {
+            Some(item) if !item.is_folder => {
+                let exists = self.inner.exists(&item.id).await?;
+                if !exists {
+                    self.invalidate(path.as_str());
+                }
+                Ok(exists)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        if id.trim_matches('/').is_empty() {
+            return Ok(true);
+        }
+        let path = StoragePath::parse(id)?;
+        match self.lookup(&path).await? {
+            Some(item) if item.is_folder => {
+                let exists = self.inner.folder_exists(&item.id).await?;
+                if !exists {
+                    self.invalidate(path.as_str());
+                }
+                Ok(exists)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        let path = StoragePath::parse(id)?;
+        let file_id = self.file_id(&path).await?;
+        let result = self.inner.stat(&file_id).await;
+        self.check(&path, result)
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        let path = StoragePath::parse(&id)?;
+        self.put_file(&path, input, len).await.map(drop)
+    }
+
+    /// Preconditions are not supported; content attributes are stored with
+    /// [`DriveTree::set_attributes`] once the content is written.
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        if options.has_preconditions() {
+            return Err(Error::Unsupported(
+                "conditional writes are not supported by this backend".to_string(),
+            ));
+        }
+        let path = StoragePath::parse(&id)?;
+        let file_id = self.put_file(&path, input, len).await?;
+        if options.has_attributes() {
+            let result = self.inner.set_attributes(&file_id, &options).await;
+            return self.check(&path, result);
+        }
+        Ok(())
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        output: W,
+    ) -> Result<u64> {
+        let path = StoragePath::parse(id)?;
+        let file_id = self.file_id(&path).await?;
+        let result = self.inner.get_into(&file_id, output).await;
+        self.check(&path, result)
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        let path = StoragePath::parse(id)?;
+        let file_id = self.file_id(&path).await?;
+        let result = self.inner.get_range(&file_id, range, output).await;
+        self.check(&path, result)
+    }
+
+    async fn get_reader(&self, id: &String) -> Result<GetReader<'_>> {
+        let path = StoragePath::parse(id)?;
+        let file_id = self.file_id(&path).await?;
+        let result = self.inner.get_reader(&file_id).await;
+        self.check(&path, result)
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        let path = StoragePath::parse(id)?;
+        match self.lookup(&path).await? {
+            Some(item) if !item.is_folder => {
+                let result = self.inner.delete(&item.id).await;
+                self.invalidate(path.as_str());
+                result
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn delete_folder(&self, folder: &String) -> Result<()> {
+        let folder = crate::delete_folder_prefix(folder)?;
+        let path = StoragePath::parse(&folder)?;
+        match self.lookup(&path).await? {
+            Some(item) if item.is_folder => {
+                let result = self.inner.delete_folder(&item.id).await;
+                self.invalidate(path.as_str());
+                result
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Matches `prefix` as a string, like the other adapters: only the
+    /// folder up to its last `/` is walked, and its files are filtered.
+    async fn list(&self, prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        let prefix = prefix.map(|p| p.trim_start_matches('/')).unwrap_or("");
+        let folder = prefix.rsplit_once('/').map_or("", |(folder, _)| folder);
+        let files = if folder.is_empty() {
+            self.walk(None, self.root_id.clone(), prefix).await?
+        } else {
+            let path = StoragePath::parse(folder)?;
+            match self.lookup(&path).await? {
+                Some(item) if item.is_folder => {
+                    self.walk(Some(path.into_string()), item.id, prefix).await?
+                }
+                _ => Vec::new(),
+            }
+        };
+        Ok(Box::pin(stream::iter(files.into_iter().map(Ok))))
+    }
+
+    async fn list_dir(&self, folder: Option<&String>) -> Result<DirListing<String>> {
+        let folder = folder.map(|f| f.trim_matches('/')).unwrap_or("");
+        let (base, folder_id) = if folder.is_empty() {
+            (None, self.root_id.clone())
+        } else {
+            let path = StoragePath::parse(folder)?;
+            match self.lookup(&path).await? {
+                Some(item) if item.is_folder => (Some(path.into_string()), item.id),
+                _ => return Ok(DirListing::default()),
+            }
+        };
+
+        let mut listing = DirListing::default();
+        for child in self.inner.list_children(&folder_id).await? {
+            let Some(path) = Self::child_path(base.as_deref(), &child.name) else {
+                continue;
+            };
+            if child.is_folder {
+                listing.prefixes.push(path.clone());
+            } else {
+                listing.files.push(path.clone());
+            }
+            self.remember(path, child);
+        }
+        Ok(listing.normalize())
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, GetReader, ObjectMeta, Page, Presign,
+    PresignMethod, PresignedRequest, PutOptions, Result, Storage, delete_folder_prefix, dir_prefix,
+};
+use futures::stream::{BoxStream, StreamExt};
+use std::ops::Range;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Scopes a path-based storage backend to a key prefix.
+///
+/// Every identifier is resolved relative to the prefix, and listings return
+/// identifiers with the prefix removed, so several logical stores can share
+/// one bucket or directory.
+///
+/// ```
+/// # use stowage::{Storage, StorageExt};
+/// # use stowage::multi::PrefixedStorage;
+/// # use stowage::MemoryStorage;
+/// # async fn example() -> stowage::Result<()> {
+/// let storage = PrefixedStorage::new(MemoryStorage::new(), "tenants/acme");
+/// storage.put_bytes("report.csv".to_string(), b"data").await?;
+///
+/// let key = "tenants/acme/report.csv".to_string();
+/// assert!(storage.inner().exists(&key).await?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PrefixedStorage<S: Storage<Id = String>> {
+    inner: S,
+    prefix: String,
+}
+
+impl<S: Storage<Id = String>> PrefixedStorage<S> {
+    /// Wrap `storage` so all identifiers live under `prefix`.
+    ///
+    /// Leading and trailing slashes are ignored; an empty prefix leaves
+    /// identifiers unchanged.
+    pub fn new(storage: S, prefix: impl AsRef<str>) -> Self {
+        Self {
+            inner: storage,
+            prefix: dir_prefix(Some(prefix.as_ref())),
+        }
+    }
+
+    /// The prefix, with a trailing `/` unless it is empty.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Get a reference to the inner storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap and return the inner storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Identifier in the inner storage.
+    fn full(&self, id: &str) -> String {
+        format!("{}{id}", self.prefix)
+    }
+
+    /// Identifier as seen by callers of this storage.
+    fn strip(&self, id: String) -> String {
+        match id.strip_prefix(&self.prefix) {
+            Some(rest) => rest.to_string(),
+            None => id,
+        }
+    }
+
+    fn strip_batch(&self, batch: BatchResult<String>) -> BatchResult<String> {
+        BatchResult {
+            succeeded: batch
+                .succeeded
+                .into_iter()
+                .map(|id| self.strip(id))
+                .collect(),
+            failed: batch
+                .failed
+                .into_iter()
+                .map(|(id, e)| (self.strip(id), e))
+                .collect(),
+        }
+    }
+}
+
+impl<S: Storage<Id = String>> Storage for PrefixedStorage<S> {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        self.inner.exists(&self.full(id)).await
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        self.inner.folder_exists(&self.full(id)).await
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        self.inner.stat(&self.full(id)).await
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        self.inner.put(self.full(&id), input, len).await
+    }
+
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        self.inner
+            .put_with(self.full(&id), input, len, options)
+            .await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        output: W,
+    ) -> Result<u64> {
+        self.inner.get_into(&self.full(id), output).await
+    }
+
+    async fn get_reader(&self, id: &String) -> Result<GetReader<'_>> {
+        self.inner.get_reader(&self.full(id)).await
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        self.inner.get_range(&self.full(id), range, output).await
+    }
+
+    async fn copy(&self, src: &String, dst: String) -> Result<()> {
+        self.inner.copy(&self.full(src), self.full(&dst)).await
+    }
+
+    async fn rename(&self, src: &String, dst: String) -> Result<()> {
+        self.inner.rename(&self.full(src), self.full(&dst)).await
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        self.inner.delete(&self.full(id)).await
+    }
+
+    async fn delete_many(&self, ids: Vec<String>) -> Result<BatchResult<String>> {
+        let ids = ids.iter().map(|id| self.full(id)).collect();
+        let batch = self.inner.delete_many(ids).await?;
+        Ok(self.strip_batch(batch))
+    }
+
+    async fn delete_prefix(&self, prefix: &String) -> Result<BatchResult<String>> {
+        let batch = self.inner.delete_prefix(&self.full(prefix)).await?;
+        Ok(self.strip_batch(batch))
+    }
+
+    async fn delete_folder(&self, folder: &String) -> Result<()> {
+        // The inner root is below our prefix, so check against our own root
+        delete_folder_prefix(folder)?;
+        self.inner.delete_folder(&self.full(folder)).await
+    }
+
+    async fn list(&self, prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        let full = self.full(prefix.map_or("", String::as_str));
+        let stream = self.inner.list(Some(&full)).await?;
+        Ok(stream.map(|item| item.map(|id| self.strip(id))).boxed())
+    }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&String>,
+    ) -> Result<BoxStream<'_, Result<Entry<String>>>> {
+        let full = self.full(prefix.map_or("", String::as_str));
+        let stream = self.inner.list_with_meta(Some(&full)).await?;
+        Ok(stream
+            .map(|item| {
+                item.map(|entry| Entry {
+                    id: self.strip(entry.id),
+                    ..entry
+                })
+            })
+            .boxed())
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&String>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<String>> {
+        let full = self.full(prefix.map_or("", String::as_str));
+        let page = self
+            .inner
+            .list_page(Some(&full), page_size, continuation)
+            .await?;
+        Ok(Page {
+            ids: page.ids.into_iter().map(|id| self.strip(id)).collect(),
+            next: page.next,
+        })
+    }
+
+    async fn list_dir(&self, folder: Option<&String>) -> Result<DirListing<String>> {
+        let full = self.full(&dir_prefix(folder.map(String::as_str)));
+        let listing = self.inner.list_dir(Some(&full)).await?;
+        Ok(DirListing {
+            files: listing.files.into_iter().map(|id| self.strip(id)).collect(),
+            prefixes: listing
+                .prefixes
+                .into_iter()
+                .map(|id| self.strip(id))
+                .collect(),
+        })
+    }
+}
+
+impl<S: Presign<Id = String>> Presign for PrefixedStorage<S> {
+    async fn presign(
+        &self,
+        id: &String,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest> {
+        self.inner.presign(&self.full(id), method, expires_in).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StorageExt;
+
+    #[cfg(feature = "memory")]
+    #[tokio::test]
+    async fn test_prefixed_keys() {
+        use crate::MemoryStorage;
+
+        let storage = PrefixedStorage::new(MemoryStorage::new(), "/data/");
+        assert_eq!(storage.prefix(), "data/");
+
+        storage
+            .put_bytes("a/b.txt".to_string(), b"data")
+            .await
+            .unwrap();
+        assert!(
+            storage
+                .inner()
+                .exists(&"data/a/b.txt".to_string())
+                .await
+                .unwrap()
+        );
+
+        let listing = storage.list_dir(None).await.unwrap();
+        assert_eq!(listing.prefixes, vec!["a".to_string()]);
+        let listing = storage.list_dir(Some(&"a".to_string())).await.unwrap();
+        assert_eq!(listing.files, vec!["a/b.txt".to_string()]);
+    }
+
+    #[cfg(feature = "memory")]
+    #[tokio::test]
+    async fn test_prefixed_hides_other_keys() {
+        use crate::MemoryStorage;
+        use futures::stream::TryStreamExt;
+
+        let inner = MemoryStorage::new();
+        inner
+            .put_bytes("other/secret.txt".to_string(), b"data")
+            .await
+            .unwrap();
+
+        let storage = PrefixedStorage::new(inner, "data");
+        storage
+            .put_bytes("file.txt".to_string(), b"data")
+            .await
+            .unwrap();
+
+        let ids: Vec<String> = storage
+            .list(None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["file.txt".to_string()]);
+        assert!(!storage.exists(&"secret.txt".to_string()).await.unwrap());
+
+        // Deleting our root must not reach the inner storage's other keys
+        assert!(storage.delete_folder(&String::new()).await.is_err());
+        assert!(
+            storage
+                .inner()
+                .exists(&"other/secret.txt".to_string())
+                .await
+                .unwrap()
+        );
+    }
+}
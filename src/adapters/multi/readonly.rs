@@ -1,6 +1,11 @@
-use crate::{Error, Result, Storage};
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, Error, GetReader, ObjectMeta, Page, Presign,
+    PresignMethod, PresignedRequest, PutOptions, Result, Storage,
+};
 use futures::stream::BoxStream;
 use std::fmt::Debug;
+use std::ops::Range;
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tracing;
 
@@ -52,6 +57,10 @@ impl<S: Storage> Storage for ReadOnlyStorage<S> {
         self.inner.folder_exists(id).await
     }
 
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        self.inner.stat(id).await
+    }
+
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
         &self,
         id: Self::Id,
@@ -64,6 +73,19 @@ impl<S: Storage> Storage for ReadOnlyStorage<S> {
         ))
     }
 
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        _input: R,
+        _len: Option<u64>,
+        _options: PutOptions,
+    ) -> Result<()> {
+        tracing::warn!(?id, "Write operation blocked (read-only storage)");
+        Err(Error::PermissionDenied(
+            "write operations not allowed on read-only storage".to_string(),
+        ))
+    }
+
     async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
         &self,
         id: &Self::Id,
@@ -72,6 +94,33 @@ impl<S: Storage> Storage for ReadOnlyStorage<S> {
         self.inner.get_into(id, output).await
     }
 
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        self.inner.get_reader(id).await
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        self.inner.get_range(id, range, output).await
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        tracing::warn!(?src, ?dst, "Copy operation blocked (read-only storage)");
+        Err(Error::PermissionDenied(
+            "write operations not allowed on read-only storage".to_string(),
+        ))
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        tracing::warn!(?src, ?dst, "Rename operation blocked (read-only storage)");
+        Err(Error::PermissionDenied(
+            "write operations not allowed on read-only storage".to_string(),
+        ))
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
         tracing::warn!(?id, "Delete operation blocked (read-only storage)");
         Err(Error::PermissionDenied(
@@ -79,9 +128,71 @@ impl<S: Storage> Storage for ReadOnlyStorage<S> {
         ))
     }
 
+    async fn delete_many(&self, ids: Vec<Self::Id>) -> Result<BatchResult<Self::Id>> {
+        tracing::warn!(
+            count = ids.len(),
+            "Delete operation blocked (read-only storage)"
+        );
+        Err(Error::PermissionDenied(
+            "delete operations not allowed on read-only storage".to_string(),
+        ))
+    }
+
+    async fn delete_prefix(&self, prefix: &Self::Id) -> Result<BatchResult<Self::Id>> {
+        tracing::warn!(?prefix, "Delete operation blocked (read-only storage)");
+        Err(Error::PermissionDenied(
+            "delete operations not allowed on read-only storage".to_string(),
+        ))
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        tracing::warn!(?folder, "Delete operation blocked (read-only storage)");
+        Err(Error::PermissionDenied(
+            "delete operations not allowed on read-only storage".to_string(),
+        ))
+    }
+
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
         self.inner.list(prefix).await
     }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        self.inner.list_with_meta(prefix).await
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        self.inner.list_page(prefix, page_size, continuation).await
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        self.inner.list_dir(folder).await
+    }
+}
+
+/// Only download URLs are handed out.
+impl<S: Presign> Presign for ReadOnlyStorage<S> {
+    async fn presign(
+        &self,
+        id: &Self::Id,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest> {
+        if method != PresignMethod::Get {
+            tracing::warn!(?id, "Write operation blocked (read-only storage)");
+            return Err(Error::PermissionDenied(
+                "write operations not allowed on read-only storage".to_string(),
+            ));
+        }
+        self.inner.presign(id, method, expires_in).await
+    }
 }
 
 #[cfg(test)]
@@ -140,6 +251,44 @@ mod tests {
         assert!(matches!(result.unwrap_err(), Error::PermissionDenied(_)));
     }
 
+    #[cfg(feature = "memory")]
+    #[tokio::test]
+    async fn test_readonly_delete_many_rejected() {
+        use crate::MemoryStorage;
+
+        let inner = MemoryStorage::new();
+        inner
+            .put_bytes("test.txt".to_string(), b"data")
+            .await
+            .unwrap();
+
+        let storage = ReadOnlyStorage::new(inner);
+
+        let result = storage.delete_many(vec!["test.txt".to_string()]).await;
+        assert!(matches!(result, Err(Error::PermissionDenied(_))));
+        assert!(storage.exists(&"test.txt".to_string()).await.unwrap());
+    }
+
+    #[cfg(feature = "memory")]
+    #[tokio::test]
+    async fn test_readonly_delete_folder_rejected() {
+        use crate::MemoryStorage;
+
+        let inner = MemoryStorage::new();
+        inner
+            .put_bytes("dir/test.txt".to_string(), b"data")
+            .await
+            .unwrap();
+
+        let storage = ReadOnlyStorage::new(inner);
+
+        let result = storage.delete_folder(&"dir".to_string()).await;
+        assert!(matches!(result, Err(Error::PermissionDenied(_))));
+        let result = storage.delete_prefix(&"dir/".to_string()).await;
+        assert!(matches!(result, Err(Error::PermissionDenied(_))));
+        assert!(storage.exists(&"dir/test.txt".to_string()).await.unwrap());
+    }
+
     #[cfg(feature = "memory")]
     #[tokio::test]
     async fn test_readonly_exists() {
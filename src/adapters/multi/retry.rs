@@ -0,0 +1,426 @@
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, Error, GetReader, ObjectMeta, Page, Presign,
+    PresignMethod, PresignedRequest, PutOptions, Result, Storage,
+};
+use futures::stream::BoxStream;
+use std::fmt::Debug;
+use std::future::Future;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite};
+use tracing;
+
+/// Retries failed operations on the wrapped storage with exponential backoff.
+///
+/// Only errors for which [`Error::is_retryable`] holds are retried; the
+/// delay before the next attempt is the one the backend asked for with
+/// `Retry-After` ([`Error::retry_after`]) or else the current backoff, which
+/// starts at the initial backoff and doubles up to the maximum. With jitter
+/// enabled (the default) each backoff is shortened by a random amount of up
+/// to half, so clients failing together do not retry together.
+///
+/// Operations that can safely run again are retried: lookups, listings,
+/// downloads, copies and deletes. Downloads are not retried once data has
+/// reached the output, and uploads only when their input fits in the replay
+/// buffer and they have no preconditions. `rename` is never retried, since a
+/// repeat would fail once the first attempt went through.
+///
+/// ```
+/// # use stowage::{Storage, StorageExt};
+/// # use stowage::multi::RetryStorage;
+/// # use stowage::MemoryStorage;
+/// # use std::time::Duration;
+/// # async fn example() -> stowage::Result<()> {
+/// let storage = RetryStorage::new(MemoryStorage::new())
+///     .with_max_attempts(5)
+///     .with_backoff(Duration::from_millis(200), Duration::from_secs(5))
+///     .with_deadline(Duration::from_secs(30));
+/// storage.put_bytes("file.txt".to_string(), b"data").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RetryStorage<S: Storage> {
+    inner: S,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    deadline: Option<Duration>,
+    replay_limit: usize,
+}
+
+impl<S: Storage> RetryStorage<S> {
+    /// Wrap `storage`, making up to 3 attempts per operation with a backoff
+    /// from 100 ms up to 10 s, jitter, no deadline and an 8 MiB replay buffer.
+    pub fn new(storage: S) -> Self {
+        Self {
+            inner: storage,
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            deadline: None,
+            replay_limit: 8 * 1024 * 1024,
+        }
+    }
+
+    /// Set the number of attempts per operation, including the first
+    /// (default: 3). Values below 1 are treated as 1.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set the backoff before the first retry and the most it grows to
+    /// (default: 100 ms and 10 s).
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Enable/disable random jitter on the backoff (default: enabled).
+    pub fn with_jitter(mut self, enabled: bool) -> Self {
+        self.jitter = enabled;
+        self
+    }
+
+    /// Limit the total time an operation may take across all attempts and
+    /// delays (default: none).
+    ///
+    /// An attempt still running when the deadline passes is abandoned with
+    /// [`Error::Timeout`], and no retry is started whose delay would end
+    /// past the deadline.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set how many bytes of upload input are buffered so a failed upload
+    /// can be sent again (default: 8 MiB). Larger uploads get one attempt.
+    pub fn with_replay_limit(mut self, bytes: usize) -> Self {
+        self.replay_limit = bytes;
+        self
+    }
+
+    /// Get the number of attempts per operation.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Get the backoff before the first retry.
+    pub fn initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+
+    /// Get the most the backoff grows to.
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+
+    /// Get the total deadline per operation.
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+
+    /// Get a reference to the inner storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwrap and return the inner storage.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Start tracking the attempts of one operation.
+    fn schedule(&self) -> Schedule {
+        Schedule {
+            started: Instant::now(),
+            attempts: 0,
+            backoff: self.initial_backoff,
+        }
+    }
+
+    /// Run one attempt, abandoning it if the deadline passes first.
+    async fn attempt<T>(
+        &self,
+        schedule: &Schedule,
+        operation: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let Some(deadline) = self.deadline else {
+            return operation.await;
+        };
+        let remaining = deadline.saturating_sub(schedule.started.elapsed());
+        tokio::time::timeout(remaining, operation)
+            .await
+            .unwrap_or_else(|_| {
+                Err(Error::Timeout(format!(
+                    "retry deadline of {deadline:?} exceeded"
+                )))
+            })
+    }
+
+    /// Record a failed attempt and wait before the next one, or return the
+    /// error if it should not be retried.
+    async fn back_off(&self, schedule: &mut Schedule, operation: &str, error: Error) -> Result<()> {
+        schedule.attempts += 1;
+        if !error.is_retryable() || schedule.attempts >= self.max_attempts {
+            return Err(error);
+        }
+
+        let backoff = schedule.backoff;
+        schedule.backoff = (backoff * 2).min(self.max_backoff);
+        let delay = error.retry_after().unwrap_or_else(|| {
+            if self.jitter {
+                backoff.mul_f64(1.0 - random_fraction() / 2.0)
+            } else {
+                backoff
+            }
+        });
+
+        if let Some(deadline) = self.deadline
+            && schedule.started.elapsed() + delay >= deadline
+        {
+            tracing::warn!(operation, ?delay, ?deadline, error = ?error, "Retry would pass the deadline, giving up");
+            return Err(error);
+        }
+        tracing::warn!(
+            operation,
+            attempt = schedule.attempts,
+            ?delay,
+            error = ?error,
+            "Retrying failed storage operation"
+        );
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+
+    /// Run `operation` until it succeeds or its error should be returned.
+    async fn retry<T, F, Fut>(&self, name: &str, operation: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut schedule = self.schedule();
+        loop {
+            match self.attempt(&schedule, operation()).await {
+                Err(e) => self.back_off(&mut schedule, name, e).await?,
+                ok => return ok,
+            }
+        }
+    }
+
+    /// Upload `input`, buffering it first so failed attempts can be repeated
+    /// when it fits in the replay buffer.
+    async fn put_replayable<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: S::Id,
+        mut input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        let limit = self.replay_limit as u64;
+        if options.has_preconditions() || len.is_some_and(|len| len > limit) {
+            // A repeated conditional write could fail on the first attempt's
+            // own success, and large inputs are not worth holding in memory.
+            return self.inner.put_with(id, input, len, options).await;
+        }
+
+        let mut buffer = Vec::new();
+        (&mut input)
+            .take(limit + 1)
+            .read_to_end(&mut buffer)
+            .await?;
+        if buffer.len() as u64 > limit {
+            let input = std::io::Cursor::new(buffer).chain(input);
+            return self.inner.put_with(id, input, len, options).await;
+        }
+
+        self.retry("put", || {
+            self.inner
+                .put_with(id.clone(), &buffer[..], len, options.clone())
+        })
+        .await
+    }
+}
+
+/// Progress of one operation across its attempts.
+struct Schedule {
+    started: Instant,
+    /// Attempts that have failed so far.
+    attempts: u32,
+    /// Backoff before the next retry, before jitter.
+    backoff: Duration,
+}
+
+/// A random number in `[0, 1)` for jitter.
+///
+/// Every `RandomState` is seeded differently, which is random enough to
+/// spread out retries without pulling in an RNG.
+fn random_fraction() -> f64 {
+    use std::hash::BuildHasher;
+    let bits = std::collections::hash_map::RandomState::new().hash_one(Instant::now());
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl<S: Storage> Storage for RetryStorage<S> {
+    type Id = S::Id;
+
+    async fn exists(&self, id: &Self::Id) -> Result<bool> {
+        self.retry("exists", || self.inner.exists(id)).await
+    }
+
+    async fn folder_exists(&self, id: &Self::Id) -> Result<bool> {
+        self.retry("folder_exists", || self.inner.folder_exists(id))
+            .await
+    }
+
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        self.retry("stat", || self.inner.stat(id)).await
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        self.put_replayable(id, input, len, PutOptions::default())
+            .await
+    }
+
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        self.put_replayable(id, input, len, options).await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        output: W,
+    ) -> Result<u64> {
+        let mut output = output;
+        let mut schedule = self.schedule();
+        loop {
+            // A range covering everything, so the writer only counts.
+            let mut counted = crate::io::RangeWriter::new(&mut output, 0..u64::MAX);
+            let result = self
+                .attempt(&schedule, self.inner.get_into(id, &mut counted))
+                .await;
+            match result {
+                // Once data has reached the output a retry would repeat it.
+                Err(e) if counted.written() == 0 => {
+                    self.back_off(&mut schedule, "get_into", e).await?
+                }
+                other => return other,
+            }
+        }
+    }
+
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        // Only opening the download is retried; errors while reading are not.
+        self.retry("get_reader", || self.inner.get_reader(id)).await
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        let mut output = output;
+        let mut schedule = self.schedule();
+        loop {
+            let mut counted = crate::io::RangeWriter::new(&mut output, 0..u64::MAX);
+            let result = self
+                .attempt(
+                    &schedule,
+                    self.inner.get_range(id, range.clone(), &mut counted),
+                )
+                .await;
+            match result {
+                Err(e) if counted.written() == 0 => {
+                    self.back_off(&mut schedule, "get_range", e).await?
+                }
+                other => return other,
+            }
+        }
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        self.retry("copy", || self.inner.copy(src, dst.clone()))
+            .await
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        self.inner.rename(src, dst).await
+    }
+
+    async fn delete(&self, id: &Self::Id) -> Result<()> {
+        self.retry("delete", || self.inner.delete(id)).await
+    }
+
+    async fn delete_many(&self, ids: Vec<Self::Id>) -> Result<BatchResult<Self::Id>> {
+        self.retry("delete_many", || self.inner.delete_many(ids.clone()))
+            .await
+    }
+
+    async fn delete_prefix(&self, prefix: &Self::Id) -> Result<BatchResult<Self::Id>> {
+        self.retry("delete_prefix", || self.inner.delete_prefix(prefix))
+            .await
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        self.retry("delete_folder", || self.inner.delete_folder(folder))
+            .await
+    }
+
+    async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
+        // As with get_reader, errors from the stream itself are not retried.
+        self.retry("list", || self.inner.list(prefix)).await
+    }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        self.retry("list_with_meta", || self.inner.list_with_meta(prefix))
+            .await
+    }
+
+    async fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> Result<Page<Self::Id>> {
+        self.retry("list_page", || {
+            self.inner
+                .list_page(prefix, page_size, continuation.clone())
+        })
+        .await
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        self.retry("list_dir", || self.inner.list_dir(folder)).await
+    }
+}
+
+/// Signing is local, so it is passed through without retries.
+impl<S: Presign> Presign for RetryStorage<S> {
+    async fn presign(
+        &self,
+        id: &Self::Id,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest> {
+        self.inner.presign(id, method, expires_in).await
+    }
+}
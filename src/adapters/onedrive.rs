@@ -1,9 +1,15 @@
-use crate::{Error, Result, Storage};
+use crate::multi::{DriveItem, DriveTree};
+use crate::{BatchResult, Error, GetReader, ObjectMeta, Result, Storage};
 use futures::stream::{BoxStream, StreamExt};
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HeaderMap, HeaderValue};
+use reqwest::header::{
+    AUTHORIZATION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, HeaderMap, HeaderValue, RANGE,
+};
 use reqwest::{Client, StatusCode, Url};
 use secrecy::{ExposeSecret, SecretString};
-use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
 
 /// OneDrive storage adapter using native item IDs.
 ///
@@ -46,6 +52,12 @@ impl OneDriveStorage {
         })
     }
 
+    /// Override the base URL (useful for national clouds and tests/mocks).
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
     async fn access_token(&self) -> Result<String> {
         match &self.token_provider {
             TokenProvider::Static(tok) => Ok(tok.expose_secret().to_string()),
@@ -64,7 +76,7 @@ impl OneDriveStorage {
 
     fn item_url(&self, item_id: &str) -> Result<Url> {
         if item_id.is_empty() {
-            return Err(Error::Generic(
+            return Err(Error::InvalidId(
                 "onedrive item id cannot be empty".to_string(),
             ));
         }
@@ -79,16 +91,352 @@ impl OneDriveStorage {
             .map_err(|e| Error::Generic(format!("failed to build content url: {e}")))
     }
 
-    fn map_http_error(status: StatusCode, body_snippet: &str, context: &str) -> Error {
-        match status {
-            StatusCode::NOT_FOUND => Error::NotFound(context.to_string()),
-            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
-                Error::PermissionDenied(format!("{context}: {status}"))
+    /// Chunk size for upload sessions, and the most an upload holds in
+    /// memory at once. Must be a multiple of 320 KiB.
+    const UPLOAD_CHUNK_SIZE: usize = 10 * 1024 * 1024;
+
+    /// URL of `action` on the item called `name` inside the folder
+    /// `parent_id`, which need not exist yet.
+    fn child_url(&self, parent_id: &str, name: &str, action: &str) -> Result<Url> {
+        if parent_id.is_empty() || name.is_empty() {
+            return Err(Error::Generic(
+                "onedrive parent id and name cannot be empty".to_string(),
+            ));
+        }
+        let mut url = self
+            .base_url
+            .join("me/drive/items/")
+            .map_err(|e| Error::Generic(format!("failed to build item url: {e}")))?;
+        url.path_segments_mut()
+            .map_err(|_| Error::Generic("onedrive base url cannot be a base".to_string()))?
+            .pop_if_empty()
+            .push(&format!("{parent_id}:"))
+            .push(&format!("{name}:"))
+            .push(action);
+        Ok(url)
+    }
+
+    /// Upload `first_chunk` followed by the rest of `input` (`len` bytes in
+    /// total) through an upload session, one chunk at a time.
+    ///
+    /// `session_url` is the item's `createUploadSession` URL and `conflict`
+    /// its conflict behavior. Returns the response to the final chunk, which
+    /// describes the item.
+    async fn session_upload<R: AsyncRead + Send + Unpin>(
+        &self,
+        session_url: Url,
+        conflict: &str,
+        mut input: R,
+        first_chunk: Vec<u8>,
+        len: u64,
+    ) -> Result<reqwest::Response> {
+        let headers = self.auth_headers().await?;
+
+        let resp = self
+            .client
+            .post(session_url)
+            .headers(headers)
+            .json(&serde_json::json!({
+                "item": { "@microsoft.graph.conflictBehavior": conflict }
+            }))
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+        if !resp.status().is_success() {
+            return Err(Self::response_error(resp, "onedrive put failed").await);
+        }
+        let session: UploadSession = resp
+            .json()
+            .await
+            .map_err(|e| Error::Generic(format!("onedrive upload session decode failed: {e}")))?;
+
+        let mut offset = 0u64;
+        let mut data = first_chunk;
+        while !data.is_empty() {
+            let n = data.len() as u64;
+            // The upload URL is pre-authenticated; sending the bearer token
+            // to it is not allowed.
+            let resp = self
+                .client
+                .put(&session.upload_url)
+                .header(
+                    CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", offset, offset + n - 1, len),
+                )
+                .header(CONTENT_LENGTH, n.to_string())
+                .body(std::mem::take(&mut data))
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            // 202 acknowledges an intermediate chunk; 200/201 completes the item.
+            let status = resp.status();
+            if !status.is_success() {
+                return Err(Self::response_error(resp, "onedrive put failed").await);
+            }
+            if status != StatusCode::ACCEPTED {
+                return Ok(resp);
+            }
+
+            offset += n;
+            crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?;
+        }
+
+        Err(Error::Generic(format!(
+            "onedrive upload ended after {offset} of {len} bytes"
+        )))
+    }
+
+    /// [`session_upload`](Self::session_upload) for input whose first
+    /// chunk filled the chunk buffer.
+    async fn large_upload<R: AsyncRead + Send + Unpin>(
+        &self,
+        session_url: Url,
+        conflict: &str,
+        mut input: R,
+        mut data: Vec<u8>,
+        len: Option<u64>,
+    ) -> Result<reqwest::Response> {
+        match len {
+            Some(len) => {
+                self.session_upload(session_url, conflict, input, data, len)
+                    .await
+            }
+            None => {
+                // Upload sessions need the total size up front, so without
+                // a length hint the input is spooled to a temporary file
+                // first, one chunk at a time.
+                tracing::debug!(%session_url, "Spooling OneDrive upload of unknown length");
+                let mut spool = SpoolFile::create().await?;
+                let mut len = 0;
+                while !data.is_empty() {
+                    spool.file.write_all(&data).await?;
+                    len += data.len() as u64;
+                    crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?;
+                }
+                spool.file.flush().await?;
+                spool.file.rewind().await?;
+
+                crate::io::fill_chunk(&mut spool.file, &mut data, Self::UPLOAD_CHUNK_SIZE).await?;
+                self.session_upload(session_url, conflict, &mut spool.file, data, len)
+                    .await
+            }
+        }
+    }
+
+    /// Upload `input` as a new file called `name` inside the folder
+    /// `parent_folder_id` and return the new item's ID.
+    ///
+    /// The file is addressed by name, so it is created in the same request
+    /// as its content: a single upload when the content fits in one chunk,
+    /// an upload session otherwise. Fails if the folder already contains an
+    /// item called `name`; use [`Storage::put`] with an item ID to replace
+    /// the content of an existing file.
+    pub async fn create<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        parent_folder_id: &str,
+        name: &str,
+        mut input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        let mut data = Vec::new();
+        let resp = if crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?
+            == Self::UPLOAD_CHUNK_SIZE
+        {
+            let session_url = self.child_url(parent_folder_id, name, "createUploadSession")?;
+            self.large_upload(session_url, "fail", input, data, len)
+                .await?
+        } else {
+            // PUT /me/drive/items/{parent-id}:/{name}:/content
+            let mut url = self.child_url(parent_folder_id, name, "content")?;
+            url.query_pairs_mut()
+                .append_pair("@microsoft.graph.conflictBehavior", "fail");
+            let headers = self.auth_headers().await?;
+
+            let resp = self
+                .client
+                .put(url)
+                .headers(headers)
+                .header(CONTENT_TYPE, "application/octet-stream")
+                .header(CONTENT_LENGTH, data.len().to_string())
+                .body(data)
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                return Err(Self::response_error(resp, "onedrive create failed").await);
+            }
+            resp
+        };
+
+        let item: ChildItem = resp.json().await.map_err(crate::http::request_error)?;
+        Ok(item.id)
+    }
+
+    /// Send a GET for the content of `item_id` and check the response status.
+    async fn download(&self, item_id: &str) -> Result<reqwest::Response> {
+        let url = self.content_url(item_id)?;
+        let headers = self.auth_headers().await?;
+
+        let resp = self
+            .client
+            .get(url)
+            .headers(headers)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            if status == StatusCode::NOT_FOUND {
+                return Err(Error::NotFound(item_id.to_string()));
+            }
+            return Err(Self::response_error(resp, "onedrive get_into failed").await);
+        }
+        Ok(resp)
+    }
+
+    /// Error for a failed response, read from its body.
+    async fn response_error(resp: reqwest::Response, context: &str) -> Error {
+        let status = resp.status();
+        let retry_after = crate::http::retry_after(resp.headers());
+        let text = resp.text().await.unwrap_or_default();
+        Self::map_http_error(status, retry_after, &text, context)
+    }
+
+    /// Classify a failed status, using the `code` of the Graph error body
+    /// where it is more specific than the status alone.
+    fn map_http_error(
+        status: StatusCode,
+        retry_after: Option<Duration>,
+        body_snippet: &str,
+        context: &str,
+    ) -> Error {
+        let code = serde_json::from_str::<serde_json::Value>(body_snippet)
+            .ok()
+            .and_then(|e| Some(e.pointer("/error/code")?.as_str()?.to_string()))
+            .unwrap_or_default();
+        match code.as_str() {
+            "nameAlreadyExists" => Error::AlreadyExists(context.to_string()),
+            "quotaLimitReached" => Error::QuotaExceeded(format!("{context}: {status} ({code})")),
+            "activityLimitReached" => Error::RateLimited {
+                message: format!("{context}: {status} ({code})"),
+                retry_after,
+            },
+            "notSupported" => Error::Unsupported(format!("{context}: {status} ({code})")),
+            _ => match status {
+                StatusCode::NOT_FOUND => Error::NotFound(context.to_string()),
+                StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+                    Error::PermissionDenied(format!("{context}: {status}"))
+                }
+                _ => crate::http::status_error(
+                    status,
+                    retry_after,
+                    context,
+                    format!("{context}: {status} ({body_snippet})"),
+                ),
+            },
+        }
+    }
+
+    /// Most requests a single Graph `$batch` call may contain.
+    const DELETE_BATCH_SIZE: usize = 20;
+
+    /// Delete `ids` with one JSON `$batch` request, recording the outcome for
+    /// each item.
+    async fn delete_batch(&self, ids: &[String], result: &mut BatchResult<String>) {
+        let mut submitted = Vec::with_capacity(ids.len());
+        for id in ids {
+            if id.is_empty() {
+                result.failed.push((
+                    id.clone(),
+                    Error::InvalidId("onedrive item id cannot be empty".to_string()),
+                ));
+            } else {
+                submitted.push(id.clone());
+            }
+        }
+        if submitted.is_empty() {
+            return;
+        }
+
+        let requests: Vec<BatchRequest> = submitted
+            .iter()
+            .enumerate()
+            .map(|(i, id)| BatchRequest {
+                id: i.to_string(),
+                method: "DELETE",
+                url: format!("/me/drive/items/{id}"),
+            })
+            .collect();
+        let response = match self.send_batch(&requests).await {
+            Ok(response) => response,
+            Err(e) => return result.fail_all(submitted, &e),
+        };
+
+        let mut responses: std::collections::HashMap<String, BatchResponse> = response
+            .responses
+            .into_iter()
+            .map(|r| (r.id.clone(), r))
+            .collect();
+        for (i, id) in submitted.into_iter().enumerate() {
+            let Some(response) = responses.remove(&i.to_string()) else {
+                result.failed.push((
+                    id,
+                    Error::Generic("missing from onedrive batch response".to_string()),
+                ));
+                continue;
+            };
+            match StatusCode::from_u16(response.status) {
+                // 404 is fine: delete is idempotent
+                Ok(StatusCode::NO_CONTENT | StatusCode::OK | StatusCode::NOT_FOUND) => {
+                    result.succeeded.push(id)
+                }
+                Ok(status) => {
+                    let body = response.body.map(|b| b.to_string()).unwrap_or_default();
+                    let error = Self::map_http_error(status, None, &body, "onedrive delete failed");
+                    result.failed.push((id, error));
+                }
+                Err(_) => result.failed.push((
+                    id,
+                    Error::Generic(format!(
+                        "onedrive delete failed: status {}",
+                        response.status
+                    )),
+                )),
             }
-            _ => Error::Generic(format!("{context}: {status} ({body_snippet})")),
         }
     }
 
+    /// Send one JSON `$batch` request.
+    async fn send_batch(&self, requests: &[BatchRequest]) -> Result<BatchResponses> {
+        let url = self
+            .base_url
+            .join("$batch")
+            .map_err(|e| Error::Generic(format!("failed to build batch url: {e}")))?;
+        let headers = self.auth_headers().await?;
+
+        let resp = self
+            .client
+            .post(url)
+            .headers(headers)
+            .json(&serde_json::json!({ "requests": requests }))
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(Self::response_error(resp, "onedrive batch failed").await);
+        }
+
+        resp.json()
+            .await
+            .map_err(|e| Error::Generic(format!("failed to parse batch response: {e}")))
+    }
+
     /// Get a folder's ID by its path.
     ///
     /// The path should be relative to the root (e.g., "Documents/Work").
@@ -111,7 +459,7 @@ impl OneDriveStorage {
             .query(&[("select", "id")])
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match resp.status() {
             StatusCode::OK => {
@@ -128,18 +476,61 @@ impl OneDriveStorage {
                 ))
             }
             StatusCode::NOT_FOUND => Err(Error::NotFound(path.to_string())),
-            status => {
-                let text = resp.text().await.unwrap_or_default();
-                Err(Self::map_http_error(
-                    status,
-                    &text,
-                    "get folder id by path failed",
-                ))
-            }
+            _ => Err(Self::response_error(resp, "get folder id by path failed").await),
         }
     }
 }
 
+/// Subset of the Graph `driveItem` resource returned by `stat`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DriveItemMetadata {
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    last_modified_date_time: Option<String>,
+    #[serde(default)]
+    e_tag: Option<String>,
+    #[serde(default)]
+    file: Option<FileFacet>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileFacet {
+    #[serde(default)]
+    mime_type: Option<String>,
+}
+
+/// Response of `createUploadSession`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadSession {
+    upload_url: String,
+}
+
+/// One request inside a JSON `$batch` call.
+#[derive(Serialize)]
+struct BatchRequest {
+    id: String,
+    method: &'static str,
+    url: String,
+}
+
+/// Response of a JSON `$batch` call.
+#[derive(Deserialize)]
+struct BatchResponses {
+    responses: Vec<BatchResponse>,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    id: String,
+    status: u16,
+    #[serde(default)]
+    body: Option<serde_json::Value>,
+}
+
 impl Storage for OneDriveStorage {
     type Id = String;
 
@@ -154,19 +545,12 @@ impl Storage for OneDriveStorage {
             .query(&[("select", "id")]) // Fetch minimal metadata
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match resp.status() {
             StatusCode::OK => Ok(true),
             StatusCode::NOT_FOUND => Ok(false),
-            status => {
-                let text = resp.text().await.unwrap_or_default();
-                Err(Self::map_http_error(
-                    status,
-                    &text,
-                    "onedrive exists failed",
-                ))
-            }
+            _ => Err(Self::response_error(resp, "onedrive exists failed").await),
         }
     }
 
@@ -182,7 +566,7 @@ impl Storage for OneDriveStorage {
             .query(&[("select", "id,folder")])
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match resp.status() {
             StatusCode::OK => {
@@ -190,15 +574,43 @@ impl Storage for OneDriveStorage {
                 Ok(text.contains("\"folder\""))
             }
             StatusCode::NOT_FOUND => Ok(false),
-            status => {
-                let text = resp.text().await.unwrap_or_default();
-                Err(Self::map_http_error(
-                    status,
-                    &text,
-                    "onedrive folder_exists failed",
-                ))
+            _ => Err(Self::response_error(resp, "onedrive folder_exists failed").await),
+        }
+    }
+
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let url = self.item_url(id)?;
+        let headers = self.auth_headers().await?;
+
+        let resp = self
+            .client
+            .get(url)
+            .headers(headers)
+            .query(&[("select", "size,lastModifiedDateTime,eTag,file")])
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            if status == StatusCode::NOT_FOUND {
+                return Err(Error::NotFound(id.clone()));
             }
+            return Err(Self::response_error(resp, "onedrive stat failed").await);
         }
+
+        let item: DriveItemMetadata = resp.json().await.map_err(crate::http::request_error)?;
+
+        Ok(ObjectMeta {
+            size: item.size,
+            last_modified: item
+                .last_modified_date_time
+                .as_deref()
+                .and_then(crate::time::parse_rfc3339),
+            etag: item.e_tag,
+            content_type: item.file.and_then(|f| f.mime_type),
+            ..Default::default()
+        })
     }
 
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
@@ -207,40 +619,42 @@ impl Storage for OneDriveStorage {
         mut input: R,
         len: Option<u64>,
     ) -> Result<()> {
+        // Read one chunk; anything that fits goes up in a single request,
+        // larger content through an upload session.
+        let mut data = Vec::new();
+        if crate::io::fill_chunk(&mut input, &mut data, Self::UPLOAD_CHUNK_SIZE).await?
+            == Self::UPLOAD_CHUNK_SIZE
+        {
+            let session_url = self
+                .base_url
+                .join(&format!("me/drive/items/{id}/createUploadSession"))
+                .map_err(|e| Error::Generic(format!("failed to build upload session url: {e}")))?;
+            return self
+                .large_upload(session_url, "replace", input, data, len)
+                .await
+                .map(drop);
+        }
+
         // Update existing file content by item ID
         // PUT /me/drive/items/{item-id}/content
         let url = self.content_url(&id)?;
         let headers = self.auth_headers().await?;
 
-        // Read data into memory
-        // OneDrive API works well with buffered uploads for small-medium files
-        let mut data = Vec::new();
-        tokio::io::copy(&mut input, &mut data)
-            .await
-            .map_err(|e| Error::Io(e))?;
-
-        let mut request = self
+        let resp = self
             .client
             .put(url)
             .headers(headers)
             .header(CONTENT_TYPE, "application/octet-stream")
-            .body(data);
-
-        if let Some(len) = len {
-            request = request.header("Content-Length", len.to_string());
-        }
-
-        let resp = request
+            .header(CONTENT_LENGTH, data.len().to_string())
+            .body(data)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         if resp.status().is_success() {
             Ok(())
         } else {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            Err(Self::map_http_error(status, &text, "onedrive put failed"))
+            Err(Self::response_error(resp, "onedrive put failed").await)
         }
     }
 
@@ -249,6 +663,34 @@ impl Storage for OneDriveStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
+        let resp = self.download(id).await?;
+
+        let mut stream = resp.bytes_stream();
+        let mut total = 0;
+        while let Some(chunk) = stream.next().await {
+            let bytes = chunk.map_err(crate::http::request_error)?;
+            output.write_all(&bytes).await?;
+            total += bytes.len() as u64;
+        }
+        output.flush().await?;
+
+        Ok(total)
+    }
+
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        Ok(crate::io::response_reader(self.download(id).await?))
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        if range.is_empty() {
+            return Ok(0);
+        }
+
         let url = self.content_url(id)?;
         let headers = self.auth_headers().await?;
 
@@ -256,33 +698,19 @@ impl Storage for OneDriveStorage {
             .client
             .get(url)
             .headers(headers)
+            .header(RANGE, crate::io::range_header(&range))
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
-        let status = resp.status();
-        if !status.is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            if status == StatusCode::NOT_FOUND {
-                return Err(Error::NotFound(id.clone()));
+        match resp.status() {
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(0),
+            status if status.is_success() => {
+                crate::io::write_range_response(resp, range, output).await
             }
-            return Err(Self::map_http_error(
-                status,
-                &text,
-                "onedrive get_into failed",
-            ));
+            StatusCode::NOT_FOUND => Err(Error::NotFound(id.clone())),
+            _ => Err(Self::response_error(resp, "onedrive get_range failed").await),
         }
-
-        let mut stream = resp.bytes_stream();
-        let mut total = 0;
-        while let Some(chunk) = stream.next().await {
-            let bytes = chunk.map_err(|e| Error::Connection(Box::new(e)))?;
-            output.write_all(&bytes).await?;
-            total += bytes.len() as u64;
-        }
-        output.flush().await?;
-
-        Ok(total)
     }
 
     async fn delete(&self, id: &Self::Id) -> Result<()> {
@@ -295,20 +723,26 @@ impl Storage for OneDriveStorage {
             .headers(headers)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         match resp.status() {
             StatusCode::NO_CONTENT | StatusCode::OK => Ok(()),
             StatusCode::NOT_FOUND => Ok(()), // idempotent
-            status => {
-                let text = resp.text().await.unwrap_or_default();
-                Err(Self::map_http_error(
-                    status,
-                    &text,
-                    "onedrive delete failed",
-                ))
-            }
+            _ => Err(Self::response_error(resp, "onedrive delete failed").await),
+        }
+    }
+
+    async fn delete_many(&self, ids: Vec<Self::Id>) -> Result<BatchResult<Self::Id>> {
+        let mut result = BatchResult::new();
+        for batch in ids.chunks(Self::DELETE_BATCH_SIZE) {
+            self.delete_batch(batch, &mut result).await;
         }
+        Ok(result)
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        // Deleting a folder also deletes everything in it.
+        self.delete(folder).await
     }
 
     async fn list(&self, _prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
@@ -317,3 +751,169 @@ impl Storage for OneDriveStorage {
         ))
     }
 }
+
+/// Subset of the Graph `driveItem` resource returned by folder listings.
+#[derive(Deserialize)]
+struct ChildItem {
+    id: String,
+    name: String,
+    #[serde(default)]
+    folder: Option<serde_json::Value>,
+}
+
+impl From<ChildItem> for DriveItem {
+    fn from(item: ChildItem) -> Self {
+        Self {
+            id: item.id,
+            name: item.name,
+            is_folder: item.folder.is_some(),
+        }
+    }
+}
+
+/// One page of a `children` listing.
+#[derive(Deserialize)]
+struct ChildPage {
+    value: Vec<ChildItem>,
+    #[serde(rename = "@odata.nextLink", default)]
+    next_link: Option<String>,
+}
+
+impl OneDriveStorage {
+    /// Create the item described by `body` inside `parent_id` and return its ID.
+    async fn create_child(&self, parent_id: &str, body: serde_json::Value) -> Result<String> {
+        let url = self
+            .base_url
+            .join(&format!("me/drive/items/{parent_id}/children"))
+            .map_err(|e| Error::Generic(format!("failed to build children url: {e}")))?;
+        let headers = self.auth_headers().await?;
+
+        let resp = self
+            .client
+            .post(url)
+            .headers(headers)
+            .json(&body)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(Self::response_error(resp, "onedrive create failed").await);
+        }
+        let item: ChildItem = resp.json().await.map_err(crate::http::request_error)?;
+        Ok(item.id)
+    }
+}
+
+impl DriveTree for OneDriveStorage {
+    fn root_folder_id(&self) -> String {
+        "root".to_string()
+    }
+
+    async fn list_children(&self, parent_id: &str) -> Result<Vec<DriveItem>> {
+        let mut url = self
+            .base_url
+            .join(&format!("me/drive/items/{parent_id}/children"))
+            .map_err(|e| Error::Generic(format!("failed to build children url: {e}")))?;
+        url.query_pairs_mut()
+            .append_pair("$select", "id,name,folder")
+            .append_pair("$top", "1000");
+
+        let mut items = Vec::new();
+        loop {
+            let headers = self.auth_headers().await?;
+            let resp = self
+                .client
+                .get(url)
+                .headers(headers)
+                .send()
+                .await
+                .map_err(crate::http::request_error)?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                return Err(Self::response_error(resp, "onedrive list failed").await);
+            }
+            let page: ChildPage = resp.json().await.map_err(crate::http::request_error)?;
+
+            items.extend(page.value.into_iter().map(DriveItem::from));
+            match page.next_link {
+                Some(next) => {
+                    url = Url::parse(&next)
+                        .map_err(|e| Error::Generic(format!("invalid next link: {e}")))?;
+                }
+                None => return Ok(items),
+            }
+        }
+    }
+
+    async fn create_folder(&self, parent_id: &str, name: &str) -> Result<String> {
+        self.create_child(
+            parent_id,
+            serde_json::json!({
+                "name": name,
+                "folder": {},
+                "@microsoft.graph.conflictBehavior": "fail",
+            }),
+        )
+        .await
+    }
+
+    async fn create_file<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        parent_id: &str,
+        name: &str,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        self.create(parent_id, name, input, len).await
+    }
+}
+
+/// Temporary file holding an upload of unknown length, removed on drop.
+struct SpoolFile {
+    path: std::path::PathBuf,
+    file: tokio::fs::File,
+}
+
+impl SpoolFile {
+    async fn create() -> Result<Self> {
+        static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let n = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path =
+            std::env::temp_dir().join(format!("stowage-onedrive-{}-{n}.spool", std::process::id()));
+        let file = tokio::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await?;
+        Ok(Self { path, file })
+    }
+}
+
+impl Drop for SpoolFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_child_url_encodes_name() {
+        let storage =
+            OneDriveStorage::new(Client::new(), TokenProvider::Static("token".into())).unwrap();
+        let url = storage
+            .child_url("root", "q1 #2/draft.csv", "content")
+            .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://graph.microsoft.com/v1.0/me/drive/items/root:/q1%20%232%2Fdraft.csv:/content"
+        );
+        assert!(storage.child_url("root", "", "content").is_err());
+    }
+}
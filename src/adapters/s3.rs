@@ -1,13 +1,24 @@
-use crate::{Error, Result, Storage};
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, Error, GetReader, ObjectMeta, Page, PathId,
+    Presign, PresignMethod, PresignedRequest, PutOptions, Result, Storage, StoragePath,
+};
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectIdentifier};
 use aws_sdk_s3::{Client, primitives::ByteStream};
-use futures::stream::BoxStream;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use aws_smithy_types::error::display::DisplayErrorContext;
+use futures::stream::{self, BoxStream};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// AWS S3 storage adapter using object keys as identifiers.
 #[derive(Clone, Debug)]
-pub struct S3Storage {
+pub struct S3Storage<Id = String> {
     client: Client,
     bucket: String,
+    ids: PhantomData<fn() -> Id>,
 }
 
 impl S3Storage {
@@ -15,30 +26,536 @@ impl S3Storage {
         Self {
             client,
             bucket: bucket.into(),
+            ids: PhantomData,
         }
     }
 
+    /// Address items by [`StoragePath`] instead of `String`.
+    pub fn with_path_ids(self) -> S3Storage<StoragePath> {
+        S3Storage {
+            client: self.client,
+            bucket: self.bucket,
+            ids: PhantomData,
+        }
+    }
+
+    /// Build a client from the default AWS config chain, with optional
+    /// overrides for the region, a custom endpoint (e.g. MinIO) and static
+    /// credentials.
+    pub(crate) async fn load_client(
+        region: Option<&str>,
+        endpoint: Option<&str>,
+        force_path_style: bool,
+        credentials: Option<aws_sdk_s3::config::Credentials>,
+    ) -> Client {
+        use aws_config::{BehaviorVersion, Region};
+
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(region) = region {
+            loader = loader.region(Region::new(region.to_string()));
+        }
+        if let Some(credentials) = credentials {
+            loader = loader.credentials_provider(credentials);
+        }
+        let shared = loader.load().await;
+
+        let mut config = aws_sdk_s3::config::Builder::from(&shared);
+        if let Some(endpoint) = endpoint {
+            config = config.endpoint_url(endpoint);
+        }
+        if force_path_style {
+            config = config.force_path_style(true);
+        }
+        Client::from_conf(config.build())
+    }
+}
+
+impl<Id: PathId> S3Storage<Id> {
     pub fn bucket(&self) -> &str {
         &self.bucket
     }
 
-    fn validate_key(key: &str) -> Result<()> {
-        if key.is_empty() {
-            return Err(Error::Generic("s3 key cannot be empty".to_string()));
+    /// Key of the object for item `id`, see [`StoragePath`].
+    fn object_key(id: &Id) -> Result<String> {
+        crate::path::object_key(id.as_ref())
+    }
+
+    /// Send a `GetObject` request for the whole of `key`.
+    async fn get_object(
+        &self,
+        key: &Id,
+    ) -> Result<aws_sdk_s3::operation::get_object::GetObjectOutput> {
+        let key = Self::object_key(key)?;
+
+        let resp = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .send()
+            .await;
+
+        match resp {
+            Ok(out) => Ok(out),
+            Err(e) => {
+                let msg = e.to_string();
+                if msg.contains("NotFound")
+                    || msg.contains("NoSuchKey")
+                    || msg.contains("404")
+                    || msg.contains("StatusCode(404)")
+                {
+                    return Err(Error::NotFound(key));
+                }
+                Err(Self::map_sdk_err(e))
+            }
         }
-        Ok(())
     }
 
     fn map_sdk_err<E>(e: E) -> Error
     where
         E: std::error::Error + Send + Sync + 'static,
     {
+        let meta_str = format!("{:?}", e);
+        let code = meta_str
+            .split_once("code: Some(\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(code, _)| code);
+        let message = || DisplayErrorContext(&e).to_string();
+        if let Some(error) = code.and_then(|code| Self::classify_code(code, message)) {
+            return error;
+        }
+        // Timeouts from the SDK itself or from the connector.
+        if meta_str.starts_with("TimeoutError") || meta_str.contains("kind: Timeout") {
+            return Error::Timeout(message());
+        }
         Error::Connection(Box::new(e))
     }
+
+    /// Error for an S3 error `code` that has a more specific variant than
+    /// [`Error::Connection`]. Callers that know the key check for missing
+    /// keys and failed preconditions first, to report the key itself.
+    fn classify_code(code: &str, message: impl FnOnce() -> String) -> Option<Error> {
+        let error = match code {
+            "NoSuchKey" | "NotFound" | "NoSuchBucket" | "NoSuchUpload" => {
+                Error::NotFound(message())
+            }
+            "PreconditionFailed" | "ConditionalRequestConflict" => {
+                Error::PreconditionFailed(message())
+            }
+            "SlowDown"
+            | "Throttling"
+            | "ThrottlingException"
+            | "RequestLimitExceeded"
+            | "TooManyRequestsException" => Error::RateLimited {
+                message: message(),
+                retry_after: None,
+            },
+            "RequestTimeout" => Error::Timeout(message()),
+            "InternalError" | "ServiceUnavailable" => Error::Unavailable(message()),
+            "AccessDenied"
+            | "AllAccessDisabled"
+            | "InvalidAccessKeyId"
+            | "SignatureDoesNotMatch"
+            | "ExpiredToken" => Error::PermissionDenied(message()),
+            "KeyTooLongError" | "InvalidObjectName" => Error::InvalidId(message()),
+            "NotImplemented" => Error::Unsupported(message()),
+            _ => return None,
+        };
+        Some(error)
+    }
+
+    /// User metadata for a request; S3 sends each entry as an
+    /// `x-amz-meta-*` header.
+    fn user_metadata(metadata: HashMap<String, String>) -> Option<HashMap<String, String>> {
+        (!metadata.is_empty()).then_some(metadata)
+    }
+
+    /// Returns true if an SDK error reports a missing key.
+    fn is_not_found_err<E>(e: &E) -> bool
+    where
+        E: std::fmt::Display + std::fmt::Debug,
+    {
+        let msg = e.to_string();
+        let meta_str = format!("{:?}", e);
+        msg.contains("NotFound")
+            || msg.contains("NoSuchKey")
+            || msg.contains("404")
+            || meta_str.contains("NotFound")
+            || meta_str.contains("NoSuchKey")
+    }
+
+    /// Returns true if an SDK error reports a failed conditional write.
+    ///
+    /// S3 answers `412 PreconditionFailed`, or `409 ConditionalRequestConflict`
+    /// when a concurrent conditional write to the same key wins the race.
+    fn is_precondition_failed_err<E>(e: &E) -> bool
+    where
+        E: std::fmt::Debug,
+    {
+        let meta_str = format!("{:?}", e);
+        meta_str.contains("PreconditionFailed") || meta_str.contains("ConditionalRequestConflict")
+    }
+
+    /// Largest object `CopyObject` accepts; bigger objects need a multipart copy.
+    const MAX_SINGLE_COPY: u64 = 5 * 1024 * 1024 * 1024;
+
+    /// Part size used for multipart copies.
+    const COPY_PART_SIZE: u64 = 512 * 1024 * 1024;
+
+    /// Default part size for multipart uploads, and the most an upload holds
+    /// in memory at once.
+    const UPLOAD_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+    /// Most parts a multipart upload may have.
+    const MAX_UPLOAD_PARTS: u64 = 10_000;
+
+    /// Part size for an upload of `len` bytes.
+    ///
+    /// Known lengths get parts large enough to stay within the part limit;
+    /// unknown lengths use the default, which caps them at about 78 GiB.
+    fn upload_part_size(len: Option<u64>) -> usize {
+        let needed = len.map_or(0, |len| len.div_ceil(Self::MAX_UPLOAD_PARTS));
+        needed.max(Self::UPLOAD_PART_SIZE) as usize
+    }
+
+    /// List every object whose id starts with `prefix` with ListObjectsV2,
+    /// following continuation tokens.
+    async fn list_entries(
+        client: &Client,
+        bucket: &str,
+        prefix: Option<&str>,
+    ) -> Result<Vec<Entry<Id>>> {
+        let mut entries = Vec::new();
+        let mut continuation: Option<String> = None;
+
+        loop {
+            let resp = client
+                .list_objects_v2()
+                .bucket(bucket)
+                .set_prefix(prefix.map(crate::path::object_prefix))
+                .set_continuation_token(continuation.take())
+                .send()
+                .await
+                .map_err(Self::map_sdk_err)?;
+
+            for object in resp.contents() {
+                let Some(key) = object.key() else {
+                    continue;
+                };
+                let id: Id = crate::path::object_id(key)?;
+                if !prefix.is_none_or(|prefix| id.as_ref().starts_with(prefix)) {
+                    continue;
+                }
+                entries.push(Entry {
+                    id,
+                    size: object.size().unwrap_or(0).max(0) as u64,
+                    modified: object
+                        .last_modified()
+                        .and_then(|t| std::time::SystemTime::try_from(*t).ok()),
+                    etag: object.e_tag().map(str::to_string),
+                    // Zero-byte "folder marker" objects created by consoles and tools
+                    is_dir: key.ends_with('/'),
+                });
+            }
+
+            match resp.next_continuation_token() {
+                Some(token) if resp.is_truncated().unwrap_or(false) => {
+                    continuation = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Most keys a single DeleteObjects request may name.
+    const DELETE_BATCH_SIZE: usize = 1000;
+
+    /// Delete up to [`DELETE_BATCH_SIZE`](Self::DELETE_BATCH_SIZE) keys with
+    /// one DeleteObjects request, recording the outcome for each key.
+    async fn delete_objects(
+        client: &Client,
+        bucket: &str,
+        keys: Vec<String>,
+        result: &mut BatchResult<String>,
+    ) {
+        let objects = keys
+            .iter()
+            .map(|key| ObjectIdentifier::builder().key(key).build())
+            .collect::<std::result::Result<Vec<_>, _>>();
+        let delete = objects.and_then(|objects| {
+            Delete::builder()
+                .set_objects(Some(objects))
+                .quiet(true)
+                .build()
+        });
+        let delete = match delete {
+            Ok(delete) => delete,
+            Err(e) => return result.fail_all(keys, &Self::map_sdk_err(e)),
+        };
+
+        let resp = match client
+            .delete_objects()
+            .bucket(bucket)
+            .delete(delete)
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(e) => return result.fail_all(keys, &Self::map_sdk_err(e)),
+        };
+
+        // Quiet mode only reports the keys that could not be deleted.
+        let mut errors: std::collections::HashMap<&str, Error> = resp
+            .errors()
+            .iter()
+            .filter(|e| e.code() != Some("NoSuchKey"))
+            .filter_map(|e| {
+                let key = e.key()?;
+                let message = format!(
+                    "{}: {}",
+                    e.code().unwrap_or("Unknown"),
+                    e.message().unwrap_or("delete failed")
+                );
+                let error = e
+                    .code()
+                    .and_then(|code| Self::classify_code(code, || message.clone()))
+                    .unwrap_or(Error::Generic(message));
+                Some((key, error))
+            })
+            .collect();
+
+        for key in keys {
+            match errors.remove(key.as_str()) {
+                Some(error) => result.failed.push((key, error)),
+                None => result.succeeded.push(key),
+            }
+        }
+    }
+
+    /// Upload `first_part` followed by the rest of `input` as a multipart
+    /// upload, holding a single part in memory at a time.
+    async fn multipart_upload<I>(
+        client: &Client,
+        bucket: &str,
+        key: &str,
+        mut input: I,
+        first_part: Vec<u8>,
+        part_size: usize,
+        options: PutOptions,
+    ) -> Result<()>
+    where
+        I: AsyncRead + Send + Unpin,
+    {
+        let upload = client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(key)
+            .set_content_type(options.content_type)
+            .set_cache_control(options.cache_control)
+            .set_content_disposition(options.content_disposition)
+            .set_metadata(Self::user_metadata(options.user_metadata))
+            .send()
+            .await
+            .map_err(Self::map_sdk_err)?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| Error::Generic("s3 multipart upload returned no upload id".into()))?
+            .to_string();
+
+        let upload_parts = async {
+            let mut parts = Vec::new();
+            let mut part_number = 1i32;
+            let mut buf = first_part;
+            loop {
+                let resp = client
+                    .upload_part()
+                    .bucket(bucket)
+                    .key(key)
+                    .upload_id(&upload_id)
+                    .part_number(part_number)
+                    .body(ByteStream::from(std::mem::take(&mut buf)))
+                    .send()
+                    .await
+                    .map_err(Self::map_sdk_err)?;
+                parts.push(
+                    CompletedPart::builder()
+                        .set_e_tag(resp.e_tag().map(str::to_string))
+                        .part_number(part_number)
+                        .build(),
+                );
+                part_number += 1;
+
+                if crate::io::fill_chunk(&mut input, &mut buf, part_size).await? == 0 {
+                    break;
+                }
+            }
+
+            // Preconditions are checked when the upload is committed.
+            client
+                .complete_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .set_if_match(options.if_match)
+                .set_if_none_match(options.if_none_match)
+                .send()
+                .await
+                .map_err(|e| {
+                    if Self::is_precondition_failed_err(&e) {
+                        Error::PreconditionFailed(key.to_string())
+                    } else {
+                        Self::map_sdk_err(e)
+                    }
+                })?;
+
+            Result::<()>::Ok(())
+        };
+
+        if let Err(e) = upload_parts.await {
+            // Don't leave orphaned parts behind.
+            let _ = client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+                .await;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Server-side copy of `src` to `dst` within `bucket`.
+    async fn copy_object(client: &Client, bucket: &str, src: &str, dst: &str) -> Result<()> {
+        let copy_source = format!("{}/{}", bucket, urlencoding::encode(src));
+
+        let head = client
+            .head_object()
+            .bucket(bucket)
+            .key(src)
+            .send()
+            .await
+            .map_err(|e| {
+                if Self::is_not_found_err(&e) {
+                    Error::NotFound(src.to_string())
+                } else {
+                    Self::map_sdk_err(e)
+                }
+            })?;
+        let size = head.content_length().unwrap_or(0).max(0) as u64;
+
+        if size <= Self::MAX_SINGLE_COPY {
+            client
+                .copy_object()
+                .bucket(bucket)
+                .copy_source(copy_source)
+                .key(dst)
+                .send()
+                .await
+                .map_err(|e| {
+                    if Self::is_not_found_err(&e) {
+                        Error::NotFound(src.to_string())
+                    } else {
+                        Self::map_sdk_err(e)
+                    }
+                })?;
+            return Ok(());
+        }
+
+        // Unlike CopyObject, a multipart copy does not carry the attributes
+        // over by itself.
+        let upload = client
+            .create_multipart_upload()
+            .bucket(bucket)
+            .key(dst)
+            .set_content_type(head.content_type)
+            .set_cache_control(head.cache_control)
+            .set_content_disposition(head.content_disposition)
+            .set_metadata(head.metadata)
+            .send()
+            .await
+            .map_err(Self::map_sdk_err)?;
+        let upload_id = upload
+            .upload_id()
+            .ok_or_else(|| Error::Generic("s3 multipart upload returned no upload id".into()))?
+            .to_string();
+
+        let copy_parts = async {
+            let mut parts = Vec::new();
+            let mut offset = 0u64;
+            let mut part_number = 1i32;
+            while offset < size {
+                let end = (offset + Self::COPY_PART_SIZE).min(size);
+                let resp = client
+                    .upload_part_copy()
+                    .bucket(bucket)
+                    .key(dst)
+                    .upload_id(&upload_id)
+                    .part_number(part_number)
+                    .copy_source(&copy_source)
+                    .copy_source_range(crate::io::range_header(&(offset..end)))
+                    .send()
+                    .await
+                    .map_err(Self::map_sdk_err)?;
+
+                let e_tag = resp
+                    .copy_part_result()
+                    .and_then(|r| r.e_tag())
+                    .map(str::to_string);
+                parts.push(
+                    CompletedPart::builder()
+                        .set_e_tag(e_tag)
+                        .part_number(part_number)
+                        .build(),
+                );
+
+                offset = end;
+                part_number += 1;
+            }
+
+            client
+                .complete_multipart_upload()
+                .bucket(bucket)
+                .key(dst)
+                .upload_id(&upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .send()
+                .await
+                .map_err(Self::map_sdk_err)?;
+
+            Result::<()>::Ok(())
+        };
+
+        if let Err(e) = copy_parts.await {
+            // Don't leave orphaned parts behind.
+            let _ = client
+                .abort_multipart_upload()
+                .bucket(bucket)
+                .key(dst)
+                .upload_id(&upload_id)
+                .send()
+                .await;
+            return Err(e);
+        }
+
+        Ok(())
+    }
 }
 
-impl Storage for S3Storage {
-    type Id = String;
+impl<Id: PathId> Storage for S3Storage<Id> {
+    type Id = Id;
 
     fn exists(&self, id: &Self::Id) -> impl std::future::Future<Output = Result<bool>> + Send {
         let client = self.client.clone();
@@ -46,7 +563,7 @@ impl Storage for S3Storage {
         let key = id.clone();
 
         async move {
-            Self::validate_key(&key)?;
+            let key = Self::object_key(&key)?;
 
             let resp = client.head_object().bucket(bucket).key(key).send().await;
 
@@ -79,13 +596,17 @@ impl Storage for S3Storage {
     ) -> impl std::future::Future<Output = Result<bool>> + Send {
         let client = self.client.clone();
         let bucket = self.bucket.clone();
-        let mut prefix = id.clone();
+        let id = id.clone();
 
         async move {
-            Self::validate_key(&prefix)?;
+            // The bucket itself is the root folder.
+            if id.as_ref().is_empty() || id.as_ref() == "/" {
+                return Ok(true);
+            }
 
             // In S3, folders don't exist as objects - they're just prefixes
             // Check if any objects exist with this prefix
+            let mut prefix = Self::object_key(&id)?;
             if !prefix.ends_with('/') {
                 prefix.push('/');
             }
@@ -103,41 +624,120 @@ impl Storage for S3Storage {
         }
     }
 
+    fn stat(&self, id: &Self::Id) -> impl std::future::Future<Output = Result<ObjectMeta>> + Send {
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        let key = id.clone();
+
+        async move {
+            let key = Self::object_key(&key)?;
+
+            let resp = client.head_object().bucket(bucket).key(&key).send().await;
+
+            let out = match resp {
+                Ok(out) => out,
+                Err(e) if Self::is_not_found_err(&e) => return Err(Error::NotFound(key)),
+                Err(e) => return Err(Self::map_sdk_err(e)),
+            };
+
+            Ok(ObjectMeta {
+                size: out.content_length().unwrap_or(0).max(0) as u64,
+                last_modified: out
+                    .last_modified()
+                    .and_then(|t| std::time::SystemTime::try_from(*t).ok()),
+                etag: out.e_tag().map(str::to_string),
+                content_type: out.content_type().map(str::to_string),
+                cache_control: out.cache_control().map(str::to_string),
+                content_disposition: out.content_disposition().map(str::to_string),
+                metadata: out.metadata().cloned().unwrap_or_default(),
+            })
+        }
+    }
+
     fn put<I: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: I,
+        len: Option<u64>,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        self.put_with(id, input, len, PutOptions::default())
+    }
+
+    fn put_with<I: AsyncRead + Send + Sync + Unpin>(
         &self,
         id: Self::Id,
         mut input: I,
-        _len: Option<u64>,
+        len: Option<u64>,
+        options: PutOptions,
     ) -> impl std::future::Future<Output = Result<()>> + Send {
         let client = self.client.clone();
         let bucket = self.bucket.clone();
         let key = id;
 
         async move {
-            Self::validate_key(&key)?;
+            let key = Self::object_key(&key)?;
 
-            // Buffer the input (tokio AsyncRead) into memory.
+            // Read one part; anything that fits goes up in a single request.
+            let part_size = Self::upload_part_size(len);
             let mut buf = Vec::new();
-            input.read_to_end(&mut buf).await?;
+            if crate::io::fill_chunk(&mut input, &mut buf, part_size).await? < part_size {
+                client
+                    .put_object()
+                    .bucket(bucket)
+                    .key(&key)
+                    .body(ByteStream::from(buf))
+                    .set_content_type(options.content_type)
+                    .set_cache_control(options.cache_control)
+                    .set_content_disposition(options.content_disposition)
+                    .set_metadata(Self::user_metadata(options.user_metadata))
+                    .set_if_match(options.if_match)
+                    .set_if_none_match(options.if_none_match)
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        if Self::is_precondition_failed_err(&e) {
+                            Error::PreconditionFailed(key.clone())
+                        } else {
+                            Self::map_sdk_err(e)
+                        }
+                    })?;
+                return Ok(());
+            }
 
-            let body = ByteStream::from(buf);
+            Self::multipart_upload(&client, &bucket, &key, input, buf, part_size, options).await
+        }
+    }
 
-            client
-                .put_object()
-                .bucket(bucket)
-                .key(key)
-                .body(body)
-                .send()
-                .await
-                .map_err(Self::map_sdk_err)?;
+    async fn get_into<O: AsyncWrite + Send + Unpin>(
+        &self,
+        id: &Self::Id,
+        mut output: O,
+    ) -> Result<u64> {
+        let out = self.get_object(id).await?;
 
-            Ok(())
+        // Stream the S3 body into the provided Tokio writer.
+        let mut stream = out.body;
+
+        let mut written: u64 = 0;
+        while let Some(chunk) = stream.next().await {
+            let bytes = chunk.map_err(Self::map_sdk_err)?;
+            output.write_all(&bytes).await?;
+            written = written.saturating_add(bytes.len() as u64);
         }
+
+        output.flush().await?;
+        Ok(written)
     }
 
-    fn get_into<O: AsyncWrite + Send + Unpin>(
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        let out = self.get_object(id).await?;
+        Ok(GetReader::new(out.body.into_async_read()))
+    }
+
+    fn get_range<O: AsyncWrite + Send + Unpin>(
         &self,
         id: &Self::Id,
+        range: Range<u64>,
         mut output: O,
     ) -> impl std::future::Future<Output = Result<u64>> + Send {
         let client = self.client.clone();
@@ -145,14 +745,29 @@ impl Storage for S3Storage {
         let key = id.clone();
 
         async move {
-            Self::validate_key(&key)?;
+            let key = Self::object_key(&key)?;
 
-            let resp = client.get_object().bucket(bucket).key(&key).send().await;
+            if range.is_empty() {
+                return Ok(0);
+            }
+
+            let resp = client
+                .get_object()
+                .bucket(bucket)
+                .key(&key)
+                .range(crate::io::range_header(&range))
+                .send()
+                .await;
 
             let out = match resp {
                 Ok(out) => out,
                 Err(e) => {
                     let msg = e.to_string();
+                    let meta_str = format!("{:?}", e);
+                    // Requested range starts at or beyond the end of the object.
+                    if msg.contains("InvalidRange") || meta_str.contains("InvalidRange") {
+                        return Ok(0);
+                    }
                     if msg.contains("NotFound")
                         || msg.contains("NoSuchKey")
                         || msg.contains("404")
@@ -164,7 +779,6 @@ impl Storage for S3Storage {
                 }
             };
 
-            // Stream the S3 body into the provided Tokio writer.
             let mut stream = out.body;
 
             let mut written: u64 = 0;
@@ -179,13 +793,57 @@ impl Storage for S3Storage {
         }
     }
 
+    fn copy(
+        &self,
+        src: &Self::Id,
+        dst: Self::Id,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        let src = src.clone();
+
+        async move {
+            let src = Self::object_key(&src)?;
+            let dst = Self::object_key(&dst)?;
+
+            Self::copy_object(&client, &bucket, &src, &dst).await
+        }
+    }
+
+    fn rename(
+        &self,
+        src: &Self::Id,
+        dst: Self::Id,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        let src = src.clone();
+
+        async move {
+            let src = Self::object_key(&src)?;
+            let dst = Self::object_key(&dst)?;
+
+            // S3 has no rename; copy server-side then remove the source.
+            Self::copy_object(&client, &bucket, &src, &dst).await?;
+            client
+                .delete_object()
+                .bucket(bucket)
+                .key(src)
+                .send()
+                .await
+                .map_err(Self::map_sdk_err)?;
+
+            Ok(())
+        }
+    }
+
     fn delete(&self, id: &Self::Id) -> impl std::future::Future<Output = Result<()>> + Send {
         let client = self.client.clone();
         let bucket = self.bucket.clone();
         let key = id.clone();
 
         async move {
-            Self::validate_key(&key)?;
+            let key = Self::object_key(&key)?;
 
             let resp = client.delete_object().bucket(bucket).key(key).send().await;
 
@@ -208,20 +866,242 @@ impl Storage for S3Storage {
         }
     }
 
+    fn delete_many(
+        &self,
+        ids: Vec<Self::Id>,
+    ) -> impl std::future::Future<Output = Result<BatchResult<Self::Id>>> + Send {
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+
+        async move {
+            let mut result = BatchResult::new();
+            let mut ids_by_key: HashMap<String, Vec<Id>> = HashMap::new();
+            for id in ids {
+                match Self::object_key(&id) {
+                    Ok(key) => ids_by_key.entry(key).or_default().push(id),
+                    Err(e) => result.failed.push((id, e)),
+                }
+            }
+
+            // Report the outcome under the caller's ids, not the keys.
+            let keys: Vec<String> = ids_by_key.keys().cloned().collect();
+            let mut deleted = BatchResult::new();
+            for batch in keys.chunks(Self::DELETE_BATCH_SIZE) {
+                Self::delete_objects(&client, &bucket, batch.to_vec(), &mut deleted).await;
+            }
+            for key in deleted.succeeded {
+                result
+                    .succeeded
+                    .extend(ids_by_key.remove(&key).unwrap_or_default());
+            }
+            for (key, error) in deleted.failed {
+                result.fail_all(ids_by_key.remove(&key).unwrap_or_default(), &error);
+            }
+
+            Ok(result)
+        }
+    }
+
+    fn delete_folder(
+        &self,
+        folder: &Self::Id,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        let prefix = crate::delete_folder_prefix(folder.as_ref());
+
+        async move {
+            // Folders are only key prefixes, so removing the keys removes the folder.
+            let entries = Self::list_entries(&self.client, &self.bucket, Some(&prefix?)).await?;
+            let ids = entries.into_iter().map(|entry| entry.id).collect();
+            self.delete_many(ids).await?.into_result()
+        }
+    }
+
     fn list(
         &self,
         prefix: Option<&Self::Id>,
     ) -> impl std::future::Future<Output = Result<BoxStream<'_, Result<Self::Id>>>> + Send {
-        let _ = prefix;
+        let prefix = prefix.cloned();
+
+        async move {
+            let entries = Self::list_entries(
+                &self.client,
+                &self.bucket,
+                prefix.as_ref().map(AsRef::as_ref),
+            )
+            .await?;
+            let ids = entries.into_iter().map(|entry| Ok(entry.id));
+            Ok(Box::pin(stream::iter(ids)) as BoxStream<'_, _>)
+        }
+    }
+
+    fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> impl std::future::Future<Output = Result<BoxStream<'_, Result<Entry<Self::Id>>>>> + Send
+    {
+        let prefix = prefix.cloned();
+
+        async move {
+            let entries = Self::list_entries(
+                &self.client,
+                &self.bucket,
+                prefix.as_ref().map(AsRef::as_ref),
+            )
+            .await?;
+            Ok(Box::pin(stream::iter(entries.into_iter().map(Ok))) as BoxStream<'_, _>)
+        }
+    }
+
+    fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> impl std::future::Future<Output = Result<Page<Self::Id>>> + Send {
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        let prefix = prefix.cloned();
+        // S3 caps a page at 1000 keys.
+        let max_keys = page_size.clamp(1, 1000) as i32;
+
+        async move {
+            let resp = client
+                .list_objects_v2()
+                .bucket(bucket)
+                .set_prefix(
+                    prefix
+                        .as_ref()
+                        .map(|p| crate::path::object_prefix(p.as_ref())),
+                )
+                .max_keys(max_keys)
+                .set_continuation_token(continuation.map(|t| t.as_str().to_string()))
+                .send()
+                .await
+                .map_err(Self::map_sdk_err)?;
+
+            // Keys escaped past the prefix make a page shorter, not longer.
+            let ids = resp
+                .contents()
+                .iter()
+                .filter_map(|object| crate::path::object_id::<Id>(object.key()?).ok())
+                .filter(|id| {
+                    prefix
+                        .as_ref()
+                        .is_none_or(|prefix| id.as_ref().starts_with(prefix.as_ref()))
+                })
+                .collect();
+            let next = resp
+                .next_continuation_token()
+                .filter(|_| resp.is_truncated().unwrap_or(false))
+                .map(ContinuationToken::new);
+
+            Ok(Page { ids, next })
+        }
+    }
+
+    fn list_dir(
+        &self,
+        folder: Option<&Self::Id>,
+    ) -> impl std::future::Future<Output = Result<DirListing<Self::Id>>> + Send {
+        let client = self.client.clone();
+        let bucket = self.bucket.clone();
+        let prefix = crate::dir_prefix(folder.map(AsRef::as_ref));
+
         async move {
-            Err(Error::Generic(
-                "S3Storage::list not implemented yet for get_into-based trait evolution"
-                    .to_string(),
-            ))
+            let key_prefix = crate::path::object_prefix(&prefix);
+            let mut listing = DirListing::<Id>::default();
+            let mut continuation: Option<String> = None;
+
+            loop {
+                let resp = client
+                    .list_objects_v2()
+                    .bucket(&bucket)
+                    .prefix(&key_prefix)
+                    .delimiter("/")
+                    .set_continuation_token(continuation.take())
+                    .send()
+                    .await
+                    .map_err(Self::map_sdk_err)?;
+
+                for object in resp.contents() {
+                    // Skip the zero-byte "folder marker" some tools create.
+                    if let Some(key) = object.key().filter(|k| *k != key_prefix) {
+                        listing.files.push(crate::path::object_id(key)?);
+                    }
+                }
+                for common in resp.common_prefixes() {
+                    let folder = common.prefix().map(|p| p.strip_suffix('/').unwrap_or(p));
+                    if let Some(Ok(id)) = folder.map(crate::path::object_id) {
+                        listing.prefixes.push(id);
+                    }
+                }
+
+                match resp.next_continuation_token() {
+                    Some(token) if resp.is_truncated().unwrap_or(false) => {
+                        continuation = Some(token.to_string());
+                    }
+                    _ => break,
+                }
+            }
+
+            // Keys with empty segments escape into names outside the folder,
+            // see `StoragePath`.
+            listing.files.retain(|id| id.as_ref().starts_with(&prefix));
+            listing
+                .prefixes
+                .retain(|id| id.as_ref().starts_with(&prefix));
+            Ok(listing.normalize())
         }
     }
 }
 
+/// Presigned URLs are signed with SigV4 using the client's credentials, and
+/// may be valid for at most 7 days. URLs signed with temporary credentials
+/// stop working when those credentials expire.
+impl<Id: PathId> Presign for S3Storage<Id> {
+    async fn presign(
+        &self,
+        id: &Self::Id,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest> {
+        let key = Self::object_key(id)?;
+
+        let config = PresigningConfig::expires_in(expires_in)
+            .map_err(|e| Error::Generic(format!("invalid presign expiry: {e}")))?;
+        let expires_at = config.start_time() + config.expires();
+
+        let request = match method {
+            PresignMethod::Get => self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(&key)
+                .presigned(config)
+                .await
+                .map_err(Self::map_sdk_err)?,
+            PresignMethod::Put => self
+                .client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(&key)
+                .presigned(config)
+                .await
+                .map_err(Self::map_sdk_err)?,
+        };
+
+        Ok(PresignedRequest {
+            method,
+            url: request.uri().to_string(),
+            headers: request
+                .headers()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            expires_at,
+        })
+    }
+}
+
 // Needed for `.next()` on the S3 byte stream
 #[allow(unused_imports)]
 use futures::StreamExt;
@@ -1,9 +1,11 @@
-use crate::{Error, Result, Storage};
+use crate::{DirListing, Entry, Error, ObjectMeta, PathId, Result, Storage, StoragePath};
 use futures::stream::{self, BoxStream};
 use secrecy::{ExposeSecret, SecretString};
-use ssh2::{Session, Sftp};
-use std::io::{Read, Write};
+use ssh2::{ErrorCode, RenameFlags, Session, Sftp};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
 use std::net::TcpStream;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
@@ -11,7 +13,7 @@ use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 /// SFTP storage adapter using SSH2 for secure file transfers.
 ///
 /// Supports password-based authentication.
-pub struct SftpStorage {
+pub struct SftpStorage<Id = String> {
     host: String,
     port: u16,
     username: String,
@@ -20,9 +22,10 @@ pub struct SftpStorage {
     // SSH2 Session is not thread-safe, so we wrap in Arc<Mutex>
     // In production, consider connection pooling
     session: Arc<Mutex<Session>>,
+    ids: PhantomData<fn() -> Id>,
 }
 
-impl std::fmt::Debug for SftpStorage {
+impl<Id> std::fmt::Debug for SftpStorage<Id> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SftpStorage")
             .field("host", &self.host)
@@ -97,9 +100,25 @@ impl SftpStorage {
             password,
             base_path: base_path.map(|p| p.into()),
             session: Arc::new(Mutex::new(session)),
+            ids: PhantomData,
         })
     }
 
+    /// Address items by [`StoragePath`] instead of `String`.
+    pub fn with_path_ids(self) -> SftpStorage<StoragePath> {
+        SftpStorage {
+            host: self.host,
+            port: self.port,
+            username: self.username,
+            password: self.password,
+            base_path: self.base_path,
+            session: self.session,
+            ids: PhantomData,
+        }
+    }
+}
+
+impl<Id: PathId> SftpStorage<Id> {
     /// Get the full path by combining base_path with the given path
     fn full_path(&self, path: &str) -> PathBuf {
         if let Some(base) = &self.base_path {
@@ -109,6 +128,54 @@ impl SftpStorage {
         }
     }
 
+    /// Full path of the item `id`, see [`StoragePath`].
+    fn item_path(&self, id: &str) -> Result<PathBuf> {
+        Ok(self.full_path(&crate::path::file_key(id)?))
+    }
+
+    /// Full path of the folder `id`, where `""` and `"/"` name the root.
+    fn folder_path(&self, id: &str) -> Result<PathBuf> {
+        match id {
+            "" | "/" => Ok(self.base_path.clone().unwrap_or_else(|| PathBuf::from("."))),
+            _ => self.item_path(id),
+        }
+    }
+
+    /// Error for a failed SSH or SFTP call, classified by its status code
+    /// (`LIBSSH2_FX_*` or `LIBSSH2_ERROR_*`). Callers that know the item
+    /// check for missing files themselves.
+    fn sftp_error(context: &str, error: ssh2::Error) -> Error {
+        let message = format!("{context}: {error}");
+        match error.code() {
+            ErrorCode::SFTP(code) => match code {
+                // NO_SUCH_FILE, NO_SUCH_PATH
+                2 | 10 => Error::NotFound(message),
+                // PERMISSION_DENIED, WRITE_PROTECT
+                3 | 12 => Error::PermissionDenied(message),
+                // FILE_ALREADY_EXISTS
+                11 => Error::AlreadyExists(message),
+                // NO_SPACE_ON_FILESYSTEM, QUOTA_EXCEEDED
+                14 | 15 => Error::QuotaExceeded(message),
+                // OP_UNSUPPORTED
+                8 => Error::Unsupported(message),
+                // INVALID_FILENAME
+                20 => Error::InvalidId(message),
+                // LOCK_CONFLICT, NOT_A_DIRECTORY
+                17 | 19 => Error::Conflict(message),
+                // NO_CONNECTION, CONNECTION_LOST
+                6 | 7 => Error::Connection(Box::new(error)),
+                _ => Error::Generic(message),
+            },
+            ErrorCode::Session(code) => match code {
+                // ERROR_TIMEOUT, ERROR_SOCKET_TIMEOUT
+                -9 | -30 => Error::Timeout(message),
+                // ERROR_SOCKET_SEND, ERROR_SOCKET_DISCONNECT, ERROR_SOCKET_RECV
+                -7 | -13 | -43 => Error::Connection(Box::new(error)),
+                _ => Error::Generic(message),
+            },
+        }
+    }
+
     /// Execute an SFTP operation in a blocking task
     fn with_sftp<F, R>(&self, f: F) -> impl std::future::Future<Output = Result<R>> + Send
     where
@@ -123,7 +190,7 @@ impl SftpStorage {
                     .map_err(|e| Error::Generic(format!("Mutex lock failed: {}", e)))?;
                 let sftp = session
                     .sftp()
-                    .map_err(|e| Error::Generic(format!("SFTP channel failed: {}", e)))?;
+                    .map_err(|e| Self::sftp_error("SFTP channel failed", e))?;
                 f(&sftp)
             })
             .await
@@ -140,7 +207,7 @@ impl SftpStorage {
                 .map_err(|e| Error::Generic(format!("Mutex lock failed: {}", e)))?;
             let sftp = session
                 .sftp()
-                .map_err(|e| Error::Generic(format!("SFTP channel failed: {}", e)))?;
+                .map_err(|e| Self::sftp_error("SFTP channel failed", e))?;
 
             // Try to create parent directories recursively
             let parent_str = parent.to_string_lossy();
@@ -160,13 +227,100 @@ impl SftpStorage {
         }
         Ok(())
     }
+
+    /// Read the files directly inside `prefix` (or the base path) with their metadata.
+    async fn list_files(&self, prefix: Option<&str>) -> Result<Vec<Entry<String>>> {
+        let dir_path = if let Some(p) = prefix {
+            self.full_path(p)
+        } else if let Some(base) = &self.base_path {
+            base.clone()
+        } else {
+            PathBuf::from(".")
+        };
+
+        let prefix_str = prefix.map(|s| s.to_string());
+        let base_path = self.base_path.clone();
+
+        self.with_sftp(move |sftp| {
+            let mut results = Vec::new();
+
+            // Try to read directory
+            match sftp.readdir(&dir_path) {
+                Ok(entries) => {
+                    for (path, stat) in entries {
+                        // Only include regular files
+                        if stat.is_file() {
+                            // Convert path back to relative string
+                            let path_str = if let Some(base) = &base_path {
+                                path.strip_prefix(base)
+                                    .unwrap_or(&path)
+                                    .to_string_lossy()
+                                    .to_string()
+                            } else {
+                                path.to_string_lossy().to_string()
+                            };
+
+                            let entry = Entry {
+                                id: path_str,
+                                size: stat.size.unwrap_or(0),
+                                modified: stat.mtime.map(|t| {
+                                    std::time::UNIX_EPOCH + std::time::Duration::from_secs(t)
+                                }),
+                                etag: None,
+                                is_dir: false,
+                            };
+
+                            // Apply prefix filter if specified
+                            if let Some(ref prefix) = prefix_str {
+                                if entry.id.starts_with(prefix) {
+                                    results.push(entry);
+                                }
+                            } else {
+                                results.push(entry);
+                            }
+                        }
+                    }
+                    Ok(results)
+                }
+                Err(e) => {
+                    let error_msg = e.to_string();
+                    if error_msg.contains("no such file")
+                        || error_msg.contains("LIBSSH2_FX_NO_SUCH_FILE")
+                    {
+                        // Directory doesn't exist, return empty list
+                        Ok(Vec::new())
+                    } else {
+                        Err(Self::sftp_error("SFTP readdir failed", e))
+                    }
+                }
+            }
+        })
+        .await
+    }
+
+    /// Remove `dir` and everything below it, depth first.
+    fn remove_tree(sftp: &Sftp, dir: &Path) -> Result<()> {
+        let entries = sftp
+            .readdir(dir)
+            .map_err(|e| Self::sftp_error("SFTP readdir failed", e))?;
+        for (path, stat) in entries {
+            if stat.is_dir() {
+                Self::remove_tree(sftp, &path)?;
+            } else {
+                sftp.unlink(&path)
+                    .map_err(|e| Self::sftp_error("SFTP delete failed", e))?;
+            }
+        }
+        sftp.rmdir(dir)
+            .map_err(|e| Self::sftp_error("SFTP rmdir failed", e))
+    }
 }
 
-impl Storage for SftpStorage {
-    type Id = String;
+impl<Id: PathId> Storage for SftpStorage<Id> {
+    type Id = Id;
 
     async fn exists(&self, id: &Self::Id) -> Result<bool> {
-        let path = self.full_path(id);
+        let path = self.item_path(id.as_ref())?;
         self.with_sftp(move |sftp| {
             match sftp.stat(&path) {
                 Ok(_) => Ok(true),
@@ -178,7 +332,7 @@ impl Storage for SftpStorage {
                     {
                         Ok(false)
                     } else {
-                        Err(Error::Generic(format!("SFTP stat failed: {}", e)))
+                        Err(Self::sftp_error("SFTP stat failed", e))
                     }
                 }
             }
@@ -187,7 +341,7 @@ impl Storage for SftpStorage {
     }
 
     async fn folder_exists(&self, id: &Self::Id) -> Result<bool> {
-        let path = self.full_path(id);
+        let path = self.folder_path(id.as_ref())?;
 
         self.with_sftp(move |sftp| match sftp.stat(&path) {
             Ok(stat) => Ok(stat.is_dir()),
@@ -198,7 +352,34 @@ impl Storage for SftpStorage {
                 {
                     Ok(false)
                 } else {
-                    Err(Error::Generic(format!("SFTP stat failed: {}", e)))
+                    Err(Self::sftp_error("SFTP stat failed", e))
+                }
+            }
+        })
+        .await
+    }
+
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let path = self.item_path(id.as_ref())?;
+        let id_clone = id.to_string();
+
+        self.with_sftp(move |sftp| match sftp.stat(&path) {
+            Ok(stat) if stat.is_file() => Ok(ObjectMeta {
+                size: stat.size.unwrap_or(0),
+                last_modified: stat
+                    .mtime
+                    .map(|t| std::time::UNIX_EPOCH + std::time::Duration::from_secs(t)),
+                ..Default::default()
+            }),
+            Ok(_) => Err(Error::NotFound(id_clone)),
+            Err(e) => {
+                let error_msg = e.to_string();
+                if error_msg.contains("no such file")
+                    || error_msg.contains("LIBSSH2_FX_NO_SUCH_FILE")
+                {
+                    Err(Error::NotFound(id_clone))
+                } else {
+                    Err(Self::sftp_error("SFTP stat failed", e))
                 }
             }
         })
@@ -208,32 +389,38 @@ impl Storage for SftpStorage {
     async fn put<R: AsyncRead + Send + Sync + Unpin>(
         &self,
         id: Self::Id,
-        mut input: R,
+        input: R,
         _len: Option<u64>,
     ) -> Result<()> {
-        let path = self.full_path(&id);
+        let path = self.item_path(id.as_ref())?;
 
         // Ensure parent directory exists
         self.ensure_parent_dir(&path)?;
 
-        // Read all data into memory (for now - could be improved with streaming)
-        let mut buffer = Vec::new();
-        tokio::io::copy(&mut input, &mut buffer)
-            .await
-            .map_err(|e| Error::Io(e))?;
+        // The blocking SFTP task reads the input through a bounded pipe.
+        let (writer, reader) = crate::io::pipe();
+        let mut reader = tokio_util::io::SyncIoBridge::new(reader);
 
-        self.with_sftp(move |sftp| {
+        let upload = self.with_sftp(move |sftp| {
             let mut remote_file = sftp
                 .create(&path)
-                .map_err(|e| Error::Generic(format!("SFTP create failed: {}", e)))?;
-
-            remote_file.write_all(&buffer).map_err(|e| Error::Io(e))?;
-
-            remote_file.flush().map_err(|e| Error::Io(e))?;
+                .map_err(|e| Self::sftp_error("SFTP create failed", e))?;
+
+            let copied =
+                std::io::copy(&mut reader, &mut remote_file).and_then(|_| remote_file.flush());
+            if let Err(e) = copied {
+                // Don't leave a truncated file behind.
+                drop(remote_file);
+                let _ = sftp.unlink(&path);
+                return Err(Error::Io(e));
+            }
 
             Ok(())
-        })
-        .await
+        });
+
+        let (pumped, uploaded) = tokio::join!(crate::io::tee(input, vec![writer]), upload);
+        pumped?;
+        uploaded
     }
 
     async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
@@ -241,8 +428,8 @@ impl Storage for SftpStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
-        let path = self.full_path(id);
-        let id_clone = id.clone();
+        let path = self.item_path(id.as_ref())?;
+        let id_clone = id.to_string();
 
         let buffer = self
             .with_sftp(move |sftp| {
@@ -253,7 +440,7 @@ impl Storage for SftpStorage {
                     {
                         Error::NotFound(id_clone.clone())
                     } else {
-                        Error::Generic(format!("SFTP open failed: {}", e))
+                        Self::sftp_error("SFTP open failed", e)
                     }
                 })?;
 
@@ -273,8 +460,88 @@ impl Storage for SftpStorage {
         Ok(len)
     }
 
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        mut output: W,
+    ) -> Result<u64> {
+        if range.is_empty() {
+            return Ok(0);
+        }
+
+        let path = self.item_path(id.as_ref())?;
+        let id_clone = id.to_string();
+
+        let buffer = self
+            .with_sftp(move |sftp| {
+                let mut remote_file = sftp.open(&path).map_err(|e| {
+                    let error_msg = e.to_string();
+                    if error_msg.contains("no such file")
+                        || error_msg.contains("LIBSSH2_FX_NO_SUCH_FILE")
+                    {
+                        Error::NotFound(id_clone.clone())
+                    } else {
+                        Self::sftp_error("SFTP open failed", e)
+                    }
+                })?;
+
+                remote_file.seek(SeekFrom::Start(range.start))?;
+
+                let mut buffer = Vec::new();
+                remote_file
+                    .take(range.end - range.start)
+                    .read_to_end(&mut buffer)?;
+
+                Ok(buffer)
+            })
+            .await?;
+
+        let len = buffer.len() as u64;
+        output.write_all(&buffer).await.map_err(|e| Error::Io(e))?;
+        output.flush().await.map_err(|e| Error::Io(e))?;
+
+        Ok(len)
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        let src_path = self.item_path(src.as_ref())?;
+        let dst_path = self.item_path(dst.as_ref())?;
+        let src_clone = src.to_string();
+
+        // Ensure parent directory exists
+        self.ensure_parent_dir(&dst_path)?;
+
+        self.with_sftp(move |sftp| {
+            let flags = Some(RenameFlags::OVERWRITE | RenameFlags::ATOMIC | RenameFlags::NATIVE);
+            match sftp.rename(&src_path, &dst_path, flags) {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    let error_msg = e.to_string();
+                    if error_msg.contains("no such file")
+                        || error_msg.contains("LIBSSH2_FX_NO_SUCH_FILE")
+                    {
+                        return Err(Error::NotFound(src_clone));
+                    }
+
+                    // SFTPv3 servers ignore the overwrite flag and refuse to
+                    // replace an existing file, so remove it and retry once.
+                    if sftp.stat(&dst_path).is_ok() {
+                        sftp.unlink(&dst_path)
+                            .map_err(|e| Self::sftp_error("SFTP rename failed", e))?;
+                        sftp.rename(&src_path, &dst_path, flags)
+                            .map_err(|e| Self::sftp_error("SFTP rename failed", e))
+                    } else {
+                        Err(Self::sftp_error("SFTP rename failed", e))
+                    }
+                }
+            }
+        })
+        .await
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
-        let path = self.full_path(id);
+        let path = self.item_path(id.as_ref())?;
 
         self.with_sftp(move |sftp| {
             match sftp.unlink(&path) {
@@ -287,7 +554,34 @@ impl Storage for SftpStorage {
                         // File doesn't exist - idempotent delete
                         Ok(())
                     } else {
-                        Err(Error::Generic(format!("SFTP delete failed: {}", e)))
+                        Err(Self::sftp_error("SFTP delete failed", e))
+                    }
+                }
+            }
+        })
+        .await
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        let path =
+            self.item_path(crate::delete_folder_prefix(folder.as_ref())?.trim_end_matches('/'))?;
+
+        self.with_sftp(move |sftp| {
+            match sftp.stat(&path) {
+                Ok(stat) if stat.is_dir() => Self::remove_tree(sftp, &path),
+                Ok(_) => Err(Error::Generic(format!(
+                    "not a folder: {}",
+                    path.to_string_lossy()
+                ))),
+                Err(e) => {
+                    let error_msg = e.to_string();
+                    if error_msg.contains("no such file")
+                        || error_msg.contains("LIBSSH2_FX_NO_SUCH_FILE")
+                    {
+                        // Folder doesn't exist - idempotent delete
+                        Ok(())
+                    } else {
+                        Err(Self::sftp_error("SFTP stat failed", e))
                     }
                 }
             }
@@ -296,64 +590,77 @@ impl Storage for SftpStorage {
     }
 
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
-        let dir_path = if let Some(p) = prefix {
-            self.full_path(p)
+        let entries = self.list_files(prefix.map(AsRef::as_ref)).await?;
+        Ok(Box::pin(stream::iter(
+            entries
+                .into_iter()
+                .map(|entry| crate::path::file_id(&entry.id)),
+        )))
+    }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        let entries = self.list_files(prefix.map(AsRef::as_ref)).await?;
+        Ok(Box::pin(stream::iter(entries.into_iter().map(|entry| {
+            crate::path::file_id(&entry.id).map(|id| Entry {
+                id,
+                size: entry.size,
+                modified: entry.modified,
+                etag: entry.etag,
+                is_dir: entry.is_dir,
+            })
+        }))))
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        let folder = folder.map(|f| f.as_ref().trim_matches('/')).unwrap_or("");
+        let dir_path = if !folder.is_empty() {
+            self.full_path(folder)
         } else if let Some(base) = &self.base_path {
             base.clone()
         } else {
             PathBuf::from(".")
         };
+        let folder = folder.to_string();
 
-        let prefix_str = prefix.map(|s| s.to_string());
-        let base_path = self.base_path.clone();
-
-        let entries = self
-            .with_sftp(move |sftp| {
-                let mut results = Vec::new();
-
-                // Try to read directory
-                match sftp.readdir(&dir_path) {
-                    Ok(entries) => {
-                        for (path, stat) in entries {
-                            // Only include regular files
-                            if stat.is_file() {
-                                // Convert path back to relative string
-                                let path_str = if let Some(base) = &base_path {
-                                    path.strip_prefix(base)
-                                        .unwrap_or(&path)
-                                        .to_string_lossy()
-                                        .to_string()
-                                } else {
-                                    path.to_string_lossy().to_string()
-                                };
-
-                                // Apply prefix filter if specified
-                                if let Some(ref prefix) = prefix_str {
-                                    if path_str.starts_with(prefix) {
-                                        results.push(path_str);
-                                    }
-                                } else {
-                                    results.push(path_str);
-                                }
-                            }
-                        }
-                        Ok(results)
-                    }
-                    Err(e) => {
-                        let error_msg = e.to_string();
-                        if error_msg.contains("no such file")
-                            || error_msg.contains("LIBSSH2_FX_NO_SUCH_FILE")
-                        {
-                            // Directory doesn't exist, return empty list
-                            Ok(Vec::new())
-                        } else {
-                            Err(Error::Generic(format!("SFTP readdir failed: {}", e)))
-                        }
+        self.with_sftp(move |sftp| {
+            let entries = match sftp.readdir(&dir_path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    let error_msg = e.to_string();
+                    if error_msg.contains("no such file")
+                        || error_msg.contains("LIBSSH2_FX_NO_SUCH_FILE")
+                    {
+                        return Ok(DirListing::default());
                     }
+                    return Err(Self::sftp_error("SFTP readdir failed", e));
                 }
-            })
-            .await?;
+            };
+
+            let mut listing = DirListing::default();
+            for (path, stat) in entries {
+                let Some(name) = path.file_name().map(|n| n.to_string_lossy()) else {
+                    continue;
+                };
+                if name == "." || name == ".." {
+                    continue;
+                }
+                let id = if folder.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}/{}", folder, name)
+                };
 
-        Ok(Box::pin(stream::iter(entries.into_iter().map(Ok))))
+                if stat.is_dir() {
+                    listing.prefixes.push(crate::path::file_id(&id)?);
+                } else if stat.is_file() {
+                    listing.files.push(crate::path::file_id(&id)?);
+                }
+            }
+            Ok(listing.normalize())
+        })
+        .await
     }
 }
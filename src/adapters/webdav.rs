@@ -1,8 +1,13 @@
-use crate::{Error, Result, Storage};
+use crate::{
+    DirListing, Entry, Error, GetReader, ObjectMeta, PathId, Result, Storage, StoragePath,
+};
 use futures::stream::{self, BoxStream, StreamExt};
-use reqwest::header::CONTENT_TYPE;
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, RANGE};
 use reqwest::{Client, StatusCode};
 use secrecy::{ExposeSecret, SecretString};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 
 /// WebDAV storage adapter using HTTP Basic Authentication.
@@ -10,11 +15,12 @@ use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 /// Supported by Nextcloud, ownCloud, and other WebDAV-compatible services.
 /// Paths should not start with "/" (e.g., `"folder/file.txt"`).
 #[derive(Clone, Debug)]
-pub struct WebDAVStorage {
+pub struct WebDAVStorage<Id = String> {
     client: Client,
     base_url: String,
     username: String,
     password: SecretString,
+    ids: PhantomData<fn() -> Id>,
 }
 
 impl WebDAVStorage {
@@ -36,28 +42,81 @@ impl WebDAVStorage {
             base_url,
             username: username.into(),
             password: SecretString::from(password.into()),
+            ids: PhantomData,
         }
     }
 
+    /// Address items by [`StoragePath`] instead of `String`.
+    pub fn with_path_ids(self) -> WebDAVStorage<StoragePath> {
+        WebDAVStorage {
+            client: self.client,
+            base_url: self.base_url,
+            username: self.username,
+            password: self.password,
+            ids: PhantomData,
+        }
+    }
+}
+
+impl<Id: PathId> WebDAVStorage<Id> {
+    /// URL of `path`, percent-encoding each segment.
     fn resource_url(&self, path: &str) -> String {
-        let clean_path = path.trim_start_matches('/');
-        format!("{}/{}", self.base_url, clean_path)
+        let segments: Vec<_> = path
+            .trim_start_matches('/')
+            .split('/')
+            .map(urlencoding::encode)
+            .collect();
+        format!("{}/{}", self.base_url, segments.join("/"))
+    }
+
+    /// URL of the item `id`, see [`StoragePath`].
+    fn item_url(&self, id: &str) -> Result<String> {
+        Ok(self.resource_url(&crate::path::file_key(id)?))
+    }
+
+    /// Send a GET for item `id` and check the response status.
+    async fn download(&self, id: &str) -> Result<reqwest::Response> {
+        let url = self.item_url(id)?;
+
+        let response = self
+            .client
+            .get(&url)
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(self.response_error(&response, id));
+        }
+        Ok(response)
     }
 
-    fn map_error(&self, status: StatusCode, path: &str) -> Error {
+    /// Error for a failed response about `path`.
+    fn response_error(&self, response: &reqwest::Response, path: &str) -> Error {
+        let retry_after = crate::http::retry_after(response.headers());
+        self.map_error(response.status(), retry_after, path)
+    }
+
+    fn map_error(&self, status: StatusCode, retry_after: Option<Duration>, path: &str) -> Error {
         match status {
-            StatusCode::NOT_FOUND => Error::NotFound(path.to_string()),
             StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
                 Error::PermissionDenied("WebDAV authentication failed".to_string())
             }
             StatusCode::CONFLICT => {
-                Error::Generic(format!("WebDAV conflict: parent directory may not exist"))
+                Error::Conflict("WebDAV conflict: parent directory may not exist".to_string())
             }
-            StatusCode::LOCKED => Error::PermissionDenied("Resource is locked".to_string()),
+            StatusCode::LOCKED => Error::Conflict("Resource is locked".to_string()),
             StatusCode::INSUFFICIENT_STORAGE => {
-                Error::Generic("Insufficient storage space".to_string())
+                Error::QuotaExceeded("Insufficient storage space".to_string())
             }
-            _ => Error::Generic(format!("WebDAV error: {}", status)),
+            _ => crate::http::status_error(
+                status,
+                retry_after,
+                path,
+                format!("WebDAV error: {}", status),
+            ),
         }
     }
 
@@ -83,7 +142,7 @@ impl WebDAVStorage {
             .basic_auth(&self.username, Some(self.password.expose_secret()))
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         // 201 Created or 405 Method Not Allowed (already exists) are both OK
         match response.status() {
@@ -91,13 +150,39 @@ impl WebDAVStorage {
             _ => Ok(()), // Ignore errors, the PUT will fail if directory creation was necessary
         }
     }
+
+    /// Issue a server-side `COPY` or `MOVE` from `src` to `dst`, overwriting `dst`.
+    async fn transfer(&self, method: &'static [u8], src: &str, dst: &str) -> Result<()> {
+        let src_url = self.item_url(src)?;
+        let dst = crate::path::file_key(dst)?;
+        let dst_url = self.resource_url(&dst);
+        self.ensure_parent_dir(&dst).await?;
+
+        let response = self
+            .client
+            .request(reqwest::Method::from_bytes(method).unwrap(), src_url)
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
+            .header("Destination", dst_url)
+            .header("Overwrite", "T")
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        // 201 Created (new resource) or 204 No Content (overwritten)
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(self.response_error(&response, src))
+        }
+    }
 }
 
-impl Storage for WebDAVStorage {
-    type Id = String;
+impl<Id: PathId> Storage for WebDAVStorage<Id> {
+    type Id = Id;
 
     async fn exists(&self, id: &Self::Id) -> Result<bool> {
-        let url = self.resource_url(id);
+        let url = self.item_url(id.as_ref())?;
 
         let response = self
             .client
@@ -105,13 +190,17 @@ impl Storage for WebDAVStorage {
             .basic_auth(&self.username, Some(self.password.expose_secret()))
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         Ok(response.status().is_success())
     }
 
     async fn folder_exists(&self, id: &Self::Id) -> Result<bool> {
-        let url = self.resource_url(id);
+        // The root is the base URL itself.
+        let url = match id.as_ref() {
+            "" | "/" => self.resource_url(""),
+            _ => self.item_url(id.as_ref())?,
+        };
 
         // Use PROPFIND to check if it's a collection (directory)
         let response = self
@@ -121,7 +210,7 @@ impl Storage for WebDAVStorage {
             .header("Depth", "0")
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         if response.status() == StatusCode::NOT_FOUND {
             return Ok(false);
@@ -132,43 +221,78 @@ impl Storage for WebDAVStorage {
         }
 
         // Check if the response indicates it's a collection
-        let body = response
-            .text()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+        let body = response.text().await.map_err(crate::http::request_error)?;
 
         Ok(body.contains("<d:collection/>") || body.contains("collection"))
     }
 
-    async fn put<R: AsyncRead + Send + Sync + Unpin>(
-        &self,
-        id: Self::Id,
-        mut input: R,
-        _len: Option<u64>,
-    ) -> Result<()> {
-        // Ensure parent directory exists
-        self.ensure_parent_dir(&id).await?;
-
-        let url = self.resource_url(&id);
-
-        // Read data into memory
-        let mut data = Vec::new();
-        tokio::io::copy(&mut input, &mut data).await?;
+    async fn stat(&self, id: &Self::Id) -> Result<ObjectMeta> {
+        let url = self.item_url(id.as_ref())?;
 
         let response = self
             .client
-            .put(&url)
+            .head(&url)
             .basic_auth(&self.username, Some(self.password.expose_secret()))
-            .header(CONTENT_TYPE, "application/octet-stream")
-            .body(data)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(self.response_error(&response, id.as_ref()));
+        }
+
+        let headers = response.headers();
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+
+        Ok(ObjectMeta {
+            size: header("content-length")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            last_modified: header("last-modified")
+                .as_deref()
+                .and_then(crate::time::parse_http_date),
+            etag: header("etag"),
+            content_type: header("content-type"),
+            ..Default::default()
+        })
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        let path = crate::path::file_key(id.as_ref())?;
+        let url = self.resource_url(&path);
+
+        // Ensure parent directory exists
+        self.ensure_parent_dir(&path).await?;
+
+        let response = crate::io::send_streaming(input, |body| {
+            let mut request = self
+                .client
+                .put(&url)
+                .basic_auth(&self.username, Some(self.password.expose_secret()))
+                .header(CONTENT_TYPE, "application/octet-stream");
+            // Some servers reject chunked uploads, so send the length when known.
+            if let Some(len) = len {
+                request = request.header(CONTENT_LENGTH, len);
+            }
+            Ok(request.body(body))
+        })
+        .await?;
 
         if response.status().is_success() {
             Ok(())
         } else {
-            Err(self.map_error(response.status(), &id))
+            Err(self.response_error(&response, id.as_ref()))
         }
     }
 
@@ -177,26 +301,13 @@ impl Storage for WebDAVStorage {
         id: &Self::Id,
         mut output: W,
     ) -> Result<u64> {
-        let url = self.resource_url(id);
-
-        let response = self
-            .client
-            .get(&url)
-            .basic_auth(&self.username, Some(self.password.expose_secret()))
-            .send()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            return Err(self.map_error(status, id));
-        }
+        let response = self.download(id.as_ref()).await?;
 
         let mut stream = response.bytes_stream();
         let mut total_bytes = 0u64;
 
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| Error::Connection(Box::new(e)))?;
+            let chunk = chunk.map_err(crate::http::request_error)?;
             output.write_all(&chunk).await?;
             total_bytes += chunk.len() as u64;
         }
@@ -205,8 +316,52 @@ impl Storage for WebDAVStorage {
         Ok(total_bytes)
     }
 
+    async fn get_reader(&self, id: &Self::Id) -> Result<GetReader<'_>> {
+        Ok(crate::io::response_reader(
+            self.download(id.as_ref()).await?,
+        ))
+    }
+
+    async fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> Result<u64> {
+        if range.is_empty() {
+            return Ok(0);
+        }
+
+        let url = self.item_url(id.as_ref())?;
+
+        let response = self
+            .client
+            .get(&url)
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
+            .header(RANGE, crate::io::range_header(&range))
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        match response.status() {
+            StatusCode::RANGE_NOT_SATISFIABLE => Ok(0),
+            status if status.is_success() => {
+                crate::io::write_range_response(response, range, output).await
+            }
+            _ => Err(self.response_error(&response, id.as_ref())),
+        }
+    }
+
+    async fn copy(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        self.transfer(b"COPY", src.as_ref(), dst.as_ref()).await
+    }
+
+    async fn rename(&self, src: &Self::Id, dst: Self::Id) -> Result<()> {
+        self.transfer(b"MOVE", src.as_ref(), dst.as_ref()).await
+    }
+
     async fn delete(&self, id: &Self::Id) -> Result<()> {
-        let url = self.resource_url(id);
+        let url = self.item_url(id.as_ref())?;
 
         let response = self
             .client
@@ -214,18 +369,122 @@ impl Storage for WebDAVStorage {
             .basic_auth(&self.username, Some(self.password.expose_secret()))
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         // Success or 404 are both OK (idempotent delete)
         if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
             Ok(())
         } else {
-            Err(self.map_error(response.status(), id))
+            Err(self.response_error(&response, id.as_ref()))
+        }
+    }
+
+    async fn delete_folder(&self, folder: &Self::Id) -> Result<()> {
+        // DELETE on a collection removes its members too (RFC 4918 §9.6.1).
+        let prefix = crate::delete_folder_prefix(folder.as_ref())?;
+        let url = self.resource_url(&prefix);
+
+        let response = self
+            .client
+            .delete(&url)
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            Err(self.response_error(&response, folder.as_ref()))
         }
     }
 
     async fn list(&self, prefix: Option<&Self::Id>) -> Result<BoxStream<'_, Result<Self::Id>>> {
-        let path = prefix.map(|p| p.as_str()).unwrap_or("");
+        let path = prefix.map(AsRef::as_ref).unwrap_or("");
+        let entries = self.propfind_tree(path).await?;
+
+        Ok(Box::pin(stream::iter(
+            entries
+                .into_iter()
+                .map(|entry| crate::path::file_id(&entry.id)),
+        )))
+    }
+
+    async fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> Result<BoxStream<'_, Result<Entry<Self::Id>>>> {
+        let path = prefix.map(AsRef::as_ref).unwrap_or("");
+        let entries = self.propfind_tree(path).await?;
+
+        Ok(Box::pin(stream::iter(entries.into_iter().map(|entry| {
+            crate::path::file_id(&entry.id).map(|id| Entry {
+                id,
+                size: entry.size,
+                modified: entry.modified,
+                etag: entry.etag,
+                is_dir: entry.is_dir,
+            })
+        }))))
+    }
+
+    async fn list_dir(&self, folder: Option<&Self::Id>) -> Result<DirListing<Self::Id>> {
+        let path = folder.map(|p| p.as_ref().trim_matches('/')).unwrap_or("");
+        let dir_url = if path.is_empty() {
+            self.resource_url("")
+        } else {
+            self.resource_url(&format!("{}/", path))
+        };
+
+        let propfind_body = r#"<?xml version="1.0" encoding="utf-8" ?>
+<D:propfind xmlns:D="DAV:">
+  <D:prop>
+    <D:resourcetype/>
+  </D:prop>
+</D:propfind>"#;
+
+        let response = self
+            .client
+            .request(reqwest::Method::from_bytes(b"PROPFIND").unwrap(), &dir_url)
+            .basic_auth(&self.username, Some(self.password.expose_secret()))
+            .header("Depth", "1")
+            .header(CONTENT_TYPE, "application/xml")
+            .body(propfind_body)
+            .send()
+            .await
+            .map_err(crate::http::request_error)?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(DirListing::default());
+        }
+        if !status.is_success() {
+            return Err(self.response_error(&response, path));
+        }
+
+        let body = response.text().await.map_err(crate::http::request_error)?;
+
+        let mut listing = DirListing::default();
+        for entry in self.parse_propfind_entries(&body) {
+            let id = entry.id.trim_end_matches('/');
+            // Depth 1 also reports the folder itself.
+            if id.is_empty() || id == path {
+                continue;
+            }
+            if entry.is_dir {
+                listing.prefixes.push(crate::path::file_id(id)?);
+            } else {
+                listing.files.push(crate::path::file_id(id)?);
+            }
+        }
+
+        Ok(listing.normalize())
+    }
+}
+
+impl<Id: PathId> WebDAVStorage<Id> {
+    /// List every file below `path` with a `Depth: infinity` PROPFIND.
+    async fn propfind_tree(&self, path: &str) -> Result<Vec<Entry<String>>> {
         let url = self.resource_url(path);
 
         // PROPFIND request with depth infinity to list all files
@@ -234,6 +493,8 @@ impl Storage for WebDAVStorage {
   <D:prop>
     <D:resourcetype/>
     <D:getcontentlength/>
+    <D:getlastmodified/>
+    <D:getetag/>
   </D:prop>
 </D:propfind>"#;
 
@@ -246,30 +507,41 @@ impl Storage for WebDAVStorage {
             .body(propfind_body)
             .send()
             .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+            .map_err(crate::http::request_error)?;
 
         let status = response.status();
         if !status.is_success() {
-            return Err(self.map_error(status, path));
+            return Err(self.response_error(&response, path));
         }
 
-        let body = response
-            .text()
-            .await
-            .map_err(|e| Error::Connection(Box::new(e)))?;
+        let body = response.text().await.map_err(crate::http::request_error)?;
 
         // Parse XML response
-        let file_paths = self.parse_propfind_response(&body, path)?;
-
-        Ok(Box::pin(stream::iter(file_paths.into_iter().map(Ok))))
+        self.parse_propfind_response(&body, path)
     }
-}
 
-impl WebDAVStorage {
-    /// Parse WebDAV PROPFIND XML response to extract file paths.
+    /// Parse WebDAV PROPFIND XML response to extract file entries.
     /// This is a simple parser - for production, consider using a proper XML library.
-    fn parse_propfind_response(&self, xml: &str, prefix: &str) -> Result<Vec<String>> {
-        let mut file_paths = Vec::new();
+    fn parse_propfind_response(&self, xml: &str, prefix: &str) -> Result<Vec<Entry<String>>> {
+        let mut files: Vec<Entry<String>> = self
+            .parse_propfind_entries(xml)
+            .into_iter()
+            .filter(|entry| {
+                // Only files, filtered by prefix if specified
+                !entry.is_dir
+                    && !entry.id.is_empty()
+                    && (prefix.is_empty() || entry.id.starts_with(prefix))
+            })
+            .collect();
+
+        files.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(files)
+    }
+
+    /// Extract each `<D:response>` as an entry whose id is the path relative
+    /// to the base URL, marking collections (directories) with `is_dir`.
+    fn parse_propfind_entries(&self, xml: &str) -> Vec<Entry<String>> {
+        let mut entries = Vec::new();
         let base_url_decoded = urlencoding::decode(&self.base_url)
             .unwrap_or_else(|_| std::borrow::Cow::Borrowed(&self.base_url));
 
@@ -277,11 +549,7 @@ impl WebDAVStorage {
         let responses: Vec<&str> = xml.split("<D:response>").skip(1).collect();
 
         for response_block in responses {
-            // Check if it's a file (not a collection/directory)
             let is_collection = response_block.contains("<D:collection/>");
-            if is_collection {
-                continue;
-            }
 
             // Extract href
             if let Some(href_start) = response_block.find("<D:href>") {
@@ -303,17 +571,41 @@ impl WebDAVStorage {
                             decoded.trim_start_matches('/')
                         };
 
-                    if !relative_path.is_empty() {
-                        // Filter by prefix if specified
-                        if prefix.is_empty() || relative_path.starts_with(prefix) {
-                            file_paths.push(relative_path.to_string());
-                        }
-                    }
+                    entries.push(Entry {
+                        id: relative_path.to_string(),
+                        size: dav_prop(response_block, "getcontentlength")
+                            .and_then(|v| v.parse().ok())
+                            .unwrap_or(0),
+                        modified: dav_prop(response_block, "getlastmodified")
+                            .and_then(crate::time::parse_http_date),
+                        etag: dav_prop(response_block, "getetag")
+                            .filter(|v| !v.is_empty())
+                            .map(|v| v.replace("&quot;", "\"").replace("&amp;", "&")),
+                        is_dir: is_collection,
+                    });
                 }
             }
         }
 
-        file_paths.sort();
-        Ok(file_paths)
+        entries
+    }
+}
+
+/// Text of the first `<prefix:name>` property in a PROPFIND response block,
+/// whatever namespace prefix the server chose.
+fn dav_prop<'a>(block: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!(":{name}>");
+    let mut from = 0;
+    while let Some(pos) = block[from..].find(&needle) {
+        let at = from + pos;
+        let tag_start = block[..at].rfind('<')?;
+        // Skip closing tags
+        if !block[tag_start..at].starts_with("</") {
+            let value_start = at + needle.len();
+            let value_end = value_start + block[value_start..].find("</")?;
+            return Some(block[value_start..value_end].trim());
+        }
+        from = at + needle.len();
     }
+    None
 }
@@ -0,0 +1,783 @@
+//! Declarative storage configuration.
+//!
+//! [`StorageConfig`] describes a backend, or a whole topology of multi
+//! adapters, as plain data. It deserializes from any serde format (TOML,
+//! JSON, YAML, ...), so replication layouts can change without recompiling.
+//!
+//! Secrets are never written inline: they are read from an environment
+//! variable or a file through [`SecretSource`] when the storage is built.
+//!
+//! # Example
+//!
+//! ```toml
+//! type = "mirror"
+//! write_strategy = { type = "quorum", rollback = true }
+//! return_policy = "fast_fail"
+//! backend_timeout_ms = 5000
+//!
+//! [[backends]]
+//! type = "s3"
+//! bucket = "assets-primary"
+//! region = "eu-west-1"
+//!
+//! [[backends]]
+//! type = "azure"
+//! account = "acme"
+//! container = "assets"
+//! sas_token = { env = "AZURE_SAS_TOKEN" }
+//! ```
+//!
+//! ```rust
+//! # #[cfg(feature = "memory")]
+//! # async fn example(config: stowage::StorageConfig) -> Result<(), Box<dyn std::error::Error>> {
+//! use stowage::StorageExt;
+//!
+//! // `config` deserialized from e.g. the TOML above
+//! let storage = config.build().await?;
+//! storage.put_bytes("file.txt".to_string(), b"hello").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use secrecy::SecretString;
+use serde::Deserialize;
+
+use crate::multi::{
+    CachedStorage, FallbackStorage, MirrorStorage, PrefixedStorage, ReadOnlyStorage, RetryStorage,
+};
+use crate::multi::{ReturnPolicy, WriteStrategy};
+use crate::{DynStorage, Error, Result, StorageExt, StorageRegistry};
+
+/// A storage backend built from a [`StorageConfig`].
+type BuiltStorage = Box<dyn DynStorage<Id = String>>;
+
+/// Where to read a secret from.
+///
+/// ```toml
+/// password = { env = "FTP_PASSWORD" }
+/// password = { file = "/run/secrets/ftp_password" }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretSource {
+    /// Read the secret from an environment variable.
+    Env(String),
+    /// Read the secret from a file. Trailing newlines are stripped.
+    File(PathBuf),
+}
+
+impl SecretSource {
+    /// Read the secret.
+    pub fn resolve(&self) -> Result<SecretString> {
+        match self {
+            SecretSource::Env(var) => std::env::var(var).map(SecretString::from).map_err(|e| {
+                Error::InvalidId(format!("cannot read secret from environment `{var}`: {e}"))
+            }),
+            SecretSource::File(path) => {
+                let secret = std::fs::read_to_string(path).map_err(|e| {
+                    Error::InvalidId(format!(
+                        "cannot read secret from file `{}`: {e}",
+                        path.display()
+                    ))
+                })?;
+                Ok(SecretString::from(secret.trim_end_matches(['\r', '\n'])))
+            }
+        }
+    }
+}
+
+/// Declarative description of a storage backend or topology.
+///
+/// The `type` field selects the variant. Adapter variants are only available
+/// when their cargo feature is enabled; multi adapters nest further configs.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StorageConfig {
+    /// Open the backend from a URL with [`StorageRegistry::new`].
+    Url {
+        /// Storage URL, e.g. `s3://bucket/prefix`.
+        url: String,
+    },
+
+    /// In-memory storage.
+    #[cfg(feature = "memory")]
+    Memory,
+
+    /// Local filesystem storage.
+    #[cfg(feature = "local")]
+    Local(LocalConfig),
+
+    /// Amazon S3 (or S3-compatible) storage.
+    #[cfg(feature = "s3")]
+    S3(S3Config),
+
+    /// Azure Blob Storage.
+    #[cfg(feature = "azure")]
+    Azure(AzureConfig),
+
+    /// Google Drive.
+    #[cfg(feature = "gdrive")]
+    Gdrive(GoogleDriveConfig),
+
+    /// OneDrive.
+    #[cfg(feature = "onedrive")]
+    Onedrive(OneDriveConfig),
+
+    /// Dropbox.
+    #[cfg(feature = "dropbox")]
+    Dropbox(DropboxConfig),
+
+    /// Box.com.
+    #[cfg(feature = "box_storage")]
+    Box(BoxConfig),
+
+    /// WebDAV.
+    #[cfg(feature = "webdav")]
+    Webdav(WebDAVConfig),
+
+    /// SFTP.
+    #[cfg(feature = "sftp")]
+    Sftp(SftpConfig),
+
+    /// FTP.
+    #[cfg(feature = "ftp")]
+    Ftp(FtpConfig),
+
+    /// [`MirrorStorage`] over several backends.
+    Mirror(MirrorConfig),
+
+    /// [`FallbackStorage`] over a primary and a secondary backend.
+    Fallback(FallbackConfig),
+
+    /// [`ReadOnlyStorage`] around a backend.
+    ReadOnly(ReadOnlyConfig),
+
+    /// [`PrefixedStorage`] around a backend.
+    Prefixed(PrefixedConfig),
+
+    /// [`RetryStorage`] around a backend.
+    Retry(RetryConfig),
+
+    /// [`CachedStorage`] in front of a backend.
+    Cached(CachedConfig),
+
+    /// [`EncryptedStorage`](crate::multi::EncryptedStorage) around a backend.
+    #[cfg(feature = "encryption")]
+    Encrypted(EncryptedConfig),
+}
+
+impl StorageConfig {
+    /// Build the storage described by this config, resolving secrets and
+    /// connecting to the backends as needed.
+    pub fn build(&self) -> BoxFuture<'_, Result<Box<dyn DynStorage<Id = String>>>> {
+        Box::pin(async move {
+            match self {
+                StorageConfig::Url { url } => StorageRegistry::new().open(url).await,
+                #[cfg(feature = "memory")]
+                StorageConfig::Memory => Ok(crate::MemoryStorage::new().boxed()),
+                #[cfg(feature = "local")]
+                StorageConfig::Local(config) => config.build(),
+                #[cfg(feature = "s3")]
+                StorageConfig::S3(config) => config.build().await,
+                #[cfg(feature = "azure")]
+                StorageConfig::Azure(config) => config.build(),
+                #[cfg(feature = "gdrive")]
+                StorageConfig::Gdrive(config) => config.build(),
+                #[cfg(feature = "onedrive")]
+                StorageConfig::Onedrive(config) => config.build(),
+                #[cfg(feature = "dropbox")]
+                StorageConfig::Dropbox(config) => config.build(),
+                #[cfg(feature = "box_storage")]
+                StorageConfig::Box(config) => config.build(),
+                #[cfg(feature = "webdav")]
+                StorageConfig::Webdav(config) => config.build(),
+                #[cfg(feature = "sftp")]
+                StorageConfig::Sftp(config) => config.build().await,
+                #[cfg(feature = "ftp")]
+                StorageConfig::Ftp(config) => config.build().await,
+                StorageConfig::Mirror(config) => config.build().await,
+                StorageConfig::Fallback(config) => config.build().await,
+                StorageConfig::ReadOnly(config) => {
+                    Ok(ReadOnlyStorage::new(config.inner.build().await?).boxed())
+                }
+                StorageConfig::Prefixed(config) => {
+                    let inner = config.inner.build().await?;
+                    Ok(PrefixedStorage::new(inner, &config.prefix).boxed())
+                }
+                StorageConfig::Retry(config) => config.build().await,
+                StorageConfig::Cached(config) => config.build().await,
+                #[cfg(feature = "encryption")]
+                StorageConfig::Encrypted(config) => config.build().await,
+            }
+        })
+    }
+}
+
+/// Config for [`LocalStorage`](crate::adapters::local::LocalStorage).
+#[cfg(feature = "local")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalConfig {
+    /// Root directory.
+    pub root: PathBuf,
+}
+
+#[cfg(feature = "local")]
+impl LocalConfig {
+    fn build(&self) -> Result<BuiltStorage> {
+        Ok(crate::adapters::local::LocalStorage::new(&self.root).boxed())
+    }
+}
+
+/// Config for [`S3Storage`](crate::adapters::s3::S3Storage).
+///
+/// Without `access_key_id`, credentials come from the usual AWS environment
+/// and profile chain.
+#[cfg(feature = "s3")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct S3Config {
+    /// Bucket name.
+    pub bucket: String,
+    /// AWS region.
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Custom endpoint, e.g. for MinIO.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Use path-style addressing.
+    #[serde(default)]
+    pub path_style: bool,
+    /// Static access key ID.
+    #[serde(default)]
+    pub access_key_id: Option<SecretSource>,
+    /// Static secret access key, required with `access_key_id`.
+    #[serde(default)]
+    pub secret_access_key: Option<SecretSource>,
+    /// Session token for temporary credentials.
+    #[serde(default)]
+    pub session_token: Option<SecretSource>,
+}
+
+#[cfg(feature = "s3")]
+impl S3Config {
+    async fn build(&self) -> Result<BuiltStorage> {
+        use crate::adapters::s3::S3Storage;
+        use secrecy::ExposeSecret;
+
+        let credentials = match (&self.access_key_id, &self.secret_access_key) {
+            (Some(key_id), Some(secret_key)) => {
+                let session_token = self
+                    .session_token
+                    .as_ref()
+                    .map(|token| token.resolve())
+                    .transpose()?;
+                Some(aws_sdk_s3::config::Credentials::new(
+                    key_id.resolve()?.expose_secret(),
+                    secret_key.resolve()?.expose_secret(),
+                    session_token.map(|token| token.expose_secret().to_string()),
+                    None,
+                    "stowage-config",
+                ))
+            }
+            (None, None) => None,
+            _ => {
+                return Err(Error::InvalidId(
+                    "s3 config needs both access_key_id and secret_access_key".to_string(),
+                ));
+            }
+        };
+
+        let client = S3Storage::load_client(
+            self.region.as_deref(),
+            self.endpoint.as_deref(),
+            self.path_style,
+            credentials,
+        )
+        .await;
+        Ok(S3Storage::new(client, &self.bucket).boxed())
+    }
+}
+
+/// Config for [`AzureStorage`](crate::adapters::azure::AzureStorage).
+#[cfg(feature = "azure")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AzureConfig {
+    /// Storage account name.
+    pub account: String,
+    /// Container name.
+    pub container: String,
+    /// SAS token, without the leading `?`.
+    pub sas_token: SecretSource,
+    /// Custom endpoint, e.g. for Azurite.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+#[cfg(feature = "azure")]
+impl AzureConfig {
+    fn build(&self) -> Result<BuiltStorage> {
+        use crate::adapters::azure::AzureStorage;
+        use secrecy::ExposeSecret;
+
+        let sas_token = self.sas_token.resolve()?;
+        let sas_token = sas_token.expose_secret();
+        let storage = match &self.endpoint {
+            Some(endpoint) => {
+                AzureStorage::with_endpoint(&self.account, &self.container, sas_token, endpoint)
+            }
+            None => AzureStorage::new(&self.account, &self.container, sas_token),
+        };
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`GoogleDriveStorage`](crate::adapters::gdrive::GoogleDriveStorage).
+#[cfg(feature = "gdrive")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GoogleDriveConfig {
+    /// OAuth2 access token.
+    pub access_token: SecretSource,
+    /// Override the API base URL.
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+#[cfg(feature = "gdrive")]
+impl GoogleDriveConfig {
+    fn build(&self) -> Result<BuiltStorage> {
+        use crate::adapters::gdrive::{GoogleDriveStorage, TokenProvider};
+
+        let token = TokenProvider::Static(self.access_token.resolve()?);
+        let mut storage = GoogleDriveStorage::new(reqwest::Client::new(), token)?;
+        if let Some(base_url) = &self.base_url {
+            let base_url = reqwest::Url::parse(base_url)
+                .map_err(|e| Error::InvalidId(format!("invalid base url: {e}")))?;
+            storage = storage.with_base_url(base_url);
+        }
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`OneDriveStorage`](crate::adapters::onedrive::OneDriveStorage).
+#[cfg(feature = "onedrive")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OneDriveConfig {
+    /// OAuth2 access token.
+    pub access_token: SecretSource,
+}
+
+#[cfg(feature = "onedrive")]
+impl OneDriveConfig {
+    fn build(&self) -> Result<BuiltStorage> {
+        use crate::adapters::onedrive::{OneDriveStorage, TokenProvider};
+
+        let token = TokenProvider::Static(self.access_token.resolve()?);
+        Ok(OneDriveStorage::new(reqwest::Client::new(), token)?.boxed())
+    }
+}
+
+/// Config for [`DropboxStorage`](crate::adapters::dropbox::DropboxStorage).
+#[cfg(feature = "dropbox")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DropboxConfig {
+    /// OAuth2 access token.
+    pub access_token: SecretSource,
+}
+
+#[cfg(feature = "dropbox")]
+impl DropboxConfig {
+    fn build(&self) -> Result<BuiltStorage> {
+        use secrecy::ExposeSecret;
+
+        let token = self.access_token.resolve()?;
+        Ok(crate::adapters::dropbox::DropboxStorage::new(token.expose_secret()).boxed())
+    }
+}
+
+/// Config for [`BoxStorage`](crate::adapters::box_storage::BoxStorage).
+#[cfg(feature = "box_storage")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoxConfig {
+    /// OAuth2 access token.
+    pub access_token: SecretSource,
+    /// Parent folder ID for new uploads (default `"0"`, the root).
+    #[serde(default)]
+    pub folder_id: Option<String>,
+}
+
+#[cfg(feature = "box_storage")]
+impl BoxConfig {
+    fn build(&self) -> Result<BuiltStorage> {
+        use crate::adapters::box_storage::BoxStorage;
+        use secrecy::ExposeSecret;
+
+        let token = self.access_token.resolve()?;
+        let storage = match &self.folder_id {
+            Some(folder_id) => BoxStorage::with_folder(token.expose_secret(), folder_id),
+            None => BoxStorage::new(token.expose_secret()),
+        };
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`WebDAVStorage`](crate::adapters::webdav::WebDAVStorage).
+#[cfg(feature = "webdav")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebDAVConfig {
+    /// WebDAV endpoint URL.
+    pub url: String,
+    /// User name.
+    pub username: String,
+    /// Password.
+    pub password: SecretSource,
+}
+
+#[cfg(feature = "webdav")]
+impl WebDAVConfig {
+    fn build(&self) -> Result<BuiltStorage> {
+        use secrecy::ExposeSecret;
+
+        let password = self.password.resolve()?;
+        let storage = crate::adapters::webdav::WebDAVStorage::new(
+            &self.url,
+            &self.username,
+            password.expose_secret(),
+        );
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`SftpStorage`](crate::adapters::sftp::SftpStorage).
+#[cfg(feature = "sftp")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SftpConfig {
+    /// Server address, `host:port`.
+    pub address: String,
+    /// User name.
+    pub username: String,
+    /// Password.
+    pub password: SecretSource,
+    /// Base path to prefix all file operations with.
+    #[serde(default)]
+    pub base_path: Option<PathBuf>,
+}
+
+#[cfg(feature = "sftp")]
+impl SftpConfig {
+    async fn build(&self) -> Result<BuiltStorage> {
+        use secrecy::ExposeSecret;
+
+        let password = self.password.resolve()?;
+        let storage = crate::adapters::sftp::SftpStorage::new(
+            &self.address,
+            &self.username,
+            password.expose_secret(),
+            self.base_path.clone(),
+        )
+        .await?;
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`FtpStorage`](crate::adapters::ftp::FtpStorage).
+#[cfg(feature = "ftp")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FtpConfig {
+    /// Server address, `host:port`.
+    pub address: String,
+    /// User name.
+    pub username: String,
+    /// Password.
+    pub password: SecretSource,
+    /// Base path to prefix all file operations with.
+    #[serde(default)]
+    pub base_path: Option<PathBuf>,
+}
+
+#[cfg(feature = "ftp")]
+impl FtpConfig {
+    async fn build(&self) -> Result<BuiltStorage> {
+        use secrecy::ExposeSecret;
+
+        let password = self.password.resolve()?;
+        let storage = crate::adapters::ftp::FtpStorage::new(
+            &self.address,
+            &self.username,
+            password.expose_secret(),
+            self.base_path.clone(),
+        )
+        .await?;
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`MirrorStorage`].
+///
+/// Unset fields keep the [`MirrorStorage::builder`] defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MirrorConfig {
+    /// Mirrored backends, in order.
+    pub backends: Vec<StorageConfig>,
+    /// Write success criteria.
+    #[serde(default)]
+    pub write_strategy: Option<WriteStrategy>,
+    /// When operations return to the caller.
+    #[serde(default)]
+    pub return_policy: Option<ReturnPolicy>,
+    /// Per-backend timeout in milliseconds.
+    #[serde(default)]
+    pub backend_timeout_ms: Option<u64>,
+    /// Index of the backend used for reads.
+    #[serde(default)]
+    pub primary_index: Option<usize>,
+}
+
+impl MirrorConfig {
+    async fn build(&self) -> Result<BuiltStorage> {
+        if self.backends.is_empty() {
+            return Err(Error::InvalidId(
+                "mirror config needs at least one backend".to_string(),
+            ));
+        }
+        if let Some(index) = self.primary_index
+            && index >= self.backends.len()
+        {
+            return Err(Error::InvalidId(format!(
+                "mirror primary_index {index} is out of range for {} backends",
+                self.backends.len()
+            )));
+        }
+
+        let mut builder = MirrorStorage::builder();
+        for backend in &self.backends {
+            builder = builder.add_backend(backend.build().await?);
+        }
+        if let Some(strategy) = self.write_strategy {
+            builder = builder.write_strategy(strategy);
+        }
+        if let Some(policy) = self.return_policy {
+            builder = builder.return_policy(policy);
+        }
+        if let Some(timeout) = self.backend_timeout_ms {
+            builder = builder.backend_timeout(Duration::from_millis(timeout));
+        }
+        if let Some(index) = self.primary_index {
+            builder = builder.primary_index(index);
+        }
+        Ok(builder.build().boxed())
+    }
+}
+
+/// Config for [`FallbackStorage`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FallbackConfig {
+    /// Backend tried first.
+    pub primary: Box<StorageConfig>,
+    /// Backend used when the primary fails.
+    pub secondary: Box<StorageConfig>,
+    /// Also write to the secondary.
+    #[serde(default)]
+    pub write_through: bool,
+}
+
+impl FallbackConfig {
+    async fn build(&self) -> Result<BuiltStorage> {
+        let primary = self.primary.build().await?;
+        let secondary = self.secondary.build().await?;
+        let storage =
+            FallbackStorage::new(primary, secondary).with_write_through(self.write_through);
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`ReadOnlyStorage`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReadOnlyConfig {
+    /// Wrapped backend.
+    pub inner: Box<StorageConfig>,
+}
+
+/// Config for [`PrefixedStorage`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrefixedConfig {
+    /// Key prefix.
+    pub prefix: String,
+    /// Wrapped backend.
+    pub inner: Box<StorageConfig>,
+}
+
+/// Config for [`RetryStorage`].
+///
+/// Unset fields keep the [`RetryStorage::new`] defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Wrapped backend.
+    pub inner: Box<StorageConfig>,
+    /// Attempts per operation, including the first.
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+    /// Backoff before the first retry in milliseconds.
+    #[serde(default)]
+    pub initial_backoff_ms: Option<u64>,
+    /// Most the backoff grows to in milliseconds.
+    #[serde(default)]
+    pub max_backoff_ms: Option<u64>,
+    /// Randomize the backoff.
+    #[serde(default)]
+    pub jitter: Option<bool>,
+    /// Total time per operation in milliseconds.
+    #[serde(default)]
+    pub deadline_ms: Option<u64>,
+    /// Bytes of upload input buffered for retries.
+    #[serde(default)]
+    pub replay_limit: Option<usize>,
+}
+
+impl RetryConfig {
+    async fn build(&self) -> Result<BuiltStorage> {
+        let mut storage = RetryStorage::new(self.inner.build().await?);
+        if let Some(attempts) = self.max_attempts {
+            storage = storage.with_max_attempts(attempts);
+        }
+        if self.initial_backoff_ms.is_some() || self.max_backoff_ms.is_some() {
+            let initial = self
+                .initial_backoff_ms
+                .map_or(storage.initial_backoff(), Duration::from_millis);
+            let max = self
+                .max_backoff_ms
+                .map_or(storage.max_backoff(), Duration::from_millis);
+            storage = storage.with_backoff(initial, max);
+        }
+        if let Some(jitter) = self.jitter {
+            storage = storage.with_jitter(jitter);
+        }
+        if let Some(deadline) = self.deadline_ms {
+            storage = storage.with_deadline(Duration::from_millis(deadline));
+        }
+        if let Some(bytes) = self.replay_limit {
+            storage = storage.with_replay_limit(bytes);
+        }
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`CachedStorage`].
+///
+/// Unset fields keep the [`CachedStorage::new`] defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CachedConfig {
+    /// Backend the items come from.
+    pub origin: Box<StorageConfig>,
+    /// Backend the items are cached in.
+    pub cache: Box<StorageConfig>,
+    /// Most bytes kept in the cache.
+    #[serde(default)]
+    pub capacity_bytes: Option<u64>,
+    /// How long cached items stay valid in milliseconds.
+    #[serde(default)]
+    pub ttl_ms: Option<u64>,
+    /// How long items missing on the origin are remembered in milliseconds.
+    #[serde(default)]
+    pub negative_ttl_ms: Option<u64>,
+}
+
+impl CachedConfig {
+    async fn build(&self) -> Result<BuiltStorage> {
+        let origin = self.origin.build().await?;
+        let cache = self.cache.build().await?;
+        let mut storage = CachedStorage::new(origin, cache);
+        if let Some(bytes) = self.capacity_bytes {
+            storage = storage.with_capacity(bytes);
+        }
+        if let Some(ttl) = self.ttl_ms {
+            storage = storage.with_ttl(Duration::from_millis(ttl));
+        }
+        if let Some(ttl) = self.negative_ttl_ms {
+            storage = storage.with_negative_ttl(Duration::from_millis(ttl));
+        }
+        Ok(storage.boxed())
+    }
+}
+
+/// Config for [`EncryptedStorage`](crate::multi::EncryptedStorage).
+///
+/// Keys are 64 hexadecimal digits, read through [`SecretSource`]. The first
+/// entry of `keys` encrypts new items; the others are retired keys kept for
+/// reading.
+///
+/// ```toml
+/// type = "encrypted"
+/// id_key = { env = "STORAGE_ID_KEY" }
+/// keys = [
+///     { id = "2026-10", key = { env = "STORAGE_KEY_2026_10" } },
+///     { id = "2025-01", key = { file = "/run/secrets/storage_key_2025_01" } },
+/// ]
+/// inner = { type = "memory" }
+/// ```
+#[cfg(feature = "encryption")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedConfig {
+    /// Wrapped backend.
+    pub inner: Box<StorageConfig>,
+    /// Key-encryption keys, current key first.
+    pub keys: Vec<EncryptionKeyConfig>,
+    /// Key for encrypting identifiers; identifiers stay readable if unset.
+    #[serde(default)]
+    pub id_key: Option<SecretSource>,
+}
+
+/// One key of an [`EncryptedConfig`].
+#[cfg(feature = "encryption")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptionKeyConfig {
+    /// Key ID stored with each item.
+    pub id: String,
+    /// The key, as 64 hexadecimal digits.
+    pub key: SecretSource,
+}
+
+#[cfg(feature = "encryption")]
+impl EncryptedConfig {
+    async fn build(&self) -> Result<BuiltStorage> {
+        use crate::multi::{EncryptedStorage, Keyring};
+        use secrecy::ExposeSecret;
+
+        let read_key =
+            |source: &SecretSource| Keyring::key_from_hex(source.resolve()?.expose_secret());
+        let (current, retired) = self
+            .keys
+            .split_first()
+            .ok_or_else(|| Error::InvalidId("encrypted storage needs a key".to_string()))?;
+        let mut keyring = Keyring::new(&current.id, read_key(&current.key)?)?;
+        for key in retired {
+            keyring = keyring.with_key(&key.id, read_key(&key.key)?)?;
+        }
+
+        let mut storage = EncryptedStorage::new(self.inner.build().await?, keyring);
+        if let Some(id_key) = &self.id_key {
+            storage = storage.with_encrypted_ids(read_key(id_key)?);
+        }
+        Ok(storage.boxed())
+    }
+}
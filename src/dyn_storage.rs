@@ -0,0 +1,432 @@
+//! Object-safe counterpart of [`Storage`].
+//!
+//! [`Storage`] has generic methods and returns `impl Future`, so it cannot be
+//! used as a trait object. [`DynStorage`] mirrors it with boxed readers,
+//! writers and futures. Every [`Storage`] implements [`DynStorage`], and
+//! `Box<dyn DynStorage>` / `Arc<dyn DynStorage>` implement [`Storage`] again,
+//! so different backends can share one collection or one multi adapter.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "memory")]
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! use stowage::multi::MirrorStorage;
+//! use stowage::{DynStorage, MemoryStorage, StorageExt};
+//!
+//! // Any mix of backends with the same `Id` type
+//! let backends: Vec<Box<dyn DynStorage<Id = String>>> = vec![
+//!     MemoryStorage::new().boxed(),
+//!     MemoryStorage::new().boxed(),
+//! ];
+//!
+//! let mirror = MirrorStorage::new(backends);
+//! mirror.put_bytes("file.txt".to_string(), b"hello").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    BatchResult, ContinuationToken, DirListing, Entry, GetReader, ObjectMeta, Page, PutOptions,
+    Result, Storage,
+};
+
+/// A boxed upload source, as taken by [`DynStorage::dyn_put`].
+pub type DynReader<'a> = Box<dyn AsyncRead + Send + Sync + Unpin + 'a>;
+
+/// A boxed download sink, as taken by [`DynStorage::dyn_get_into`].
+pub type DynWriter<'a> = Box<dyn AsyncWrite + Send + Sync + Unpin + 'a>;
+
+/// Future of a listing stream that borrows the storage for `'a`.
+type ListFuture<'p, 'a, T> = BoxFuture<'p, Result<BoxStream<'a, Result<T>>>>;
+
+/// Object-safe version of [`Storage`].
+///
+/// There is no need to implement this directly: it is implemented for every
+/// [`Storage`]. Use it as `Box<dyn DynStorage<Id = ...>>` (see
+/// [`StorageExt::boxed`](crate::StorageExt::boxed)) or
+/// `Arc<dyn DynStorage<Id = ...>>`, both of which implement [`Storage`], and
+/// call the usual [`Storage`] methods on those.
+///
+/// The methods carry a `dyn_` prefix so they never clash with [`Storage`]
+/// methods when both traits are in scope. Each one behaves exactly like the
+/// [`Storage`] method of the same name.
+pub trait DynStorage: Send + Sync + Debug {
+    /// The identifier type for this storage backend.
+    type Id: Clone + Debug + Send + Sync + 'static;
+
+    /// See [`Storage::exists`].
+    fn dyn_exists<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<bool>>;
+
+    /// See [`Storage::folder_exists`].
+    fn dyn_folder_exists<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<bool>>;
+
+    /// See [`Storage::stat`].
+    fn dyn_stat<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<ObjectMeta>>;
+
+    /// See [`Storage::put`].
+    fn dyn_put<'a>(
+        &'a self,
+        id: Self::Id,
+        input: DynReader<'a>,
+        len: Option<u64>,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// See [`Storage::put_with`].
+    fn dyn_put_with<'a>(
+        &'a self,
+        id: Self::Id,
+        input: DynReader<'a>,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// See [`Storage::get_into`].
+    fn dyn_get_into<'a>(
+        &'a self,
+        id: &'a Self::Id,
+        output: DynWriter<'a>,
+    ) -> BoxFuture<'a, Result<u64>>;
+
+    /// See [`Storage::get_range`].
+    fn dyn_get_range<'a>(
+        &'a self,
+        id: &'a Self::Id,
+        range: Range<u64>,
+        output: DynWriter<'a>,
+    ) -> BoxFuture<'a, Result<u64>>;
+
+    /// See [`Storage::get_reader`].
+    fn dyn_get_reader<'a, 'i>(&'a self, id: &'i Self::Id) -> BoxFuture<'i, Result<GetReader<'a>>>
+    where
+        'a: 'i;
+
+    /// See [`Storage::copy`].
+    fn dyn_copy<'a>(&'a self, src: &'a Self::Id, dst: Self::Id) -> BoxFuture<'a, Result<()>>;
+
+    /// See [`Storage::rename`].
+    fn dyn_rename<'a>(&'a self, src: &'a Self::Id, dst: Self::Id) -> BoxFuture<'a, Result<()>>;
+
+    /// See [`Storage::delete`].
+    fn dyn_delete<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<()>>;
+
+    /// See [`Storage::delete_many`].
+    fn dyn_delete_many(&self, ids: Vec<Self::Id>) -> BoxFuture<'_, Result<BatchResult<Self::Id>>>;
+
+    /// See [`Storage::delete_prefix`].
+    fn dyn_delete_prefix<'a>(
+        &'a self,
+        prefix: &'a Self::Id,
+    ) -> BoxFuture<'a, Result<BatchResult<Self::Id>>>;
+
+    /// See [`Storage::delete_folder`].
+    fn dyn_delete_folder<'a>(&'a self, folder: &'a Self::Id) -> BoxFuture<'a, Result<()>>;
+
+    /// See [`Storage::list`].
+    fn dyn_list<'a, 'p>(&'a self, prefix: Option<&'p Self::Id>) -> ListFuture<'p, 'a, Self::Id>
+    where
+        'a: 'p;
+
+    /// See [`Storage::list_with_meta`].
+    fn dyn_list_with_meta<'a, 'p>(
+        &'a self,
+        prefix: Option<&'p Self::Id>,
+    ) -> ListFuture<'p, 'a, Entry<Self::Id>>
+    where
+        'a: 'p;
+
+    /// See [`Storage::list_page`].
+    fn dyn_list_page<'a>(
+        &'a self,
+        prefix: Option<&'a Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> BoxFuture<'a, Result<Page<Self::Id>>>;
+
+    /// See [`Storage::list_dir`].
+    fn dyn_list_dir<'a>(
+        &'a self,
+        folder: Option<&'a Self::Id>,
+    ) -> BoxFuture<'a, Result<DirListing<Self::Id>>>;
+}
+
+impl<S: Storage> DynStorage for S {
+    type Id = S::Id;
+
+    fn dyn_exists<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(self.exists(id))
+    }
+
+    fn dyn_folder_exists<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<bool>> {
+        Box::pin(self.folder_exists(id))
+    }
+
+    fn dyn_stat<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<ObjectMeta>> {
+        Box::pin(self.stat(id))
+    }
+
+    fn dyn_put<'a>(
+        &'a self,
+        id: Self::Id,
+        input: DynReader<'a>,
+        len: Option<u64>,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.put(id, input, len))
+    }
+
+    fn dyn_put_with<'a>(
+        &'a self,
+        id: Self::Id,
+        input: DynReader<'a>,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.put_with(id, input, len, options))
+    }
+
+    fn dyn_get_into<'a>(
+        &'a self,
+        id: &'a Self::Id,
+        output: DynWriter<'a>,
+    ) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(self.get_into(id, output))
+    }
+
+    fn dyn_get_range<'a>(
+        &'a self,
+        id: &'a Self::Id,
+        range: Range<u64>,
+        output: DynWriter<'a>,
+    ) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(self.get_range(id, range, output))
+    }
+
+    fn dyn_get_reader<'a, 'i>(&'a self, id: &'i Self::Id) -> BoxFuture<'i, Result<GetReader<'a>>>
+    where
+        'a: 'i,
+    {
+        Box::pin(self.get_reader(id))
+    }
+
+    fn dyn_copy<'a>(&'a self, src: &'a Self::Id, dst: Self::Id) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.copy(src, dst))
+    }
+
+    fn dyn_rename<'a>(&'a self, src: &'a Self::Id, dst: Self::Id) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.rename(src, dst))
+    }
+
+    fn dyn_delete<'a>(&'a self, id: &'a Self::Id) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.delete(id))
+    }
+
+    fn dyn_delete_many(&self, ids: Vec<Self::Id>) -> BoxFuture<'_, Result<BatchResult<Self::Id>>> {
+        Box::pin(self.delete_many(ids))
+    }
+
+    fn dyn_delete_prefix<'a>(
+        &'a self,
+        prefix: &'a Self::Id,
+    ) -> BoxFuture<'a, Result<BatchResult<Self::Id>>> {
+        Box::pin(self.delete_prefix(prefix))
+    }
+
+    fn dyn_delete_folder<'a>(&'a self, folder: &'a Self::Id) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.delete_folder(folder))
+    }
+
+    fn dyn_list<'a, 'p>(&'a self, prefix: Option<&'p Self::Id>) -> ListFuture<'p, 'a, Self::Id>
+    where
+        'a: 'p,
+    {
+        Box::pin(self.list(prefix))
+    }
+
+    fn dyn_list_with_meta<'a, 'p>(
+        &'a self,
+        prefix: Option<&'p Self::Id>,
+    ) -> ListFuture<'p, 'a, Entry<Self::Id>>
+    where
+        'a: 'p,
+    {
+        Box::pin(self.list_with_meta(prefix))
+    }
+
+    fn dyn_list_page<'a>(
+        &'a self,
+        prefix: Option<&'a Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> BoxFuture<'a, Result<Page<Self::Id>>> {
+        Box::pin(self.list_page(prefix, page_size, continuation))
+    }
+
+    fn dyn_list_dir<'a>(
+        &'a self,
+        folder: Option<&'a Self::Id>,
+    ) -> BoxFuture<'a, Result<DirListing<Self::Id>>> {
+        Box::pin(self.list_dir(folder))
+    }
+}
+
+/// Implement [`Storage`] for a pointer to `dyn DynStorage` by forwarding to
+/// the `dyn_` methods.
+macro_rules! impl_storage_for_dyn {
+    ($ptr:ident) => {
+        impl<I> Storage for $ptr<dyn DynStorage<Id = I>>
+        where
+            I: Clone + Debug + Send + Sync + 'static,
+        {
+            type Id = I;
+
+            fn exists(&self, id: &I) -> impl std::future::Future<Output = Result<bool>> + Send {
+                async move { (**self).dyn_exists(id).await }
+            }
+
+            fn folder_exists(
+                &self,
+                id: &I,
+            ) -> impl std::future::Future<Output = Result<bool>> + Send {
+                async move { (**self).dyn_folder_exists(id).await }
+            }
+
+            fn stat(&self, id: &I) -> impl std::future::Future<Output = Result<ObjectMeta>> + Send {
+                async move { (**self).dyn_stat(id).await }
+            }
+
+            fn put<R: AsyncRead + Send + Sync + Unpin>(
+                &self,
+                id: I,
+                input: R,
+                len: Option<u64>,
+            ) -> impl std::future::Future<Output = Result<()>> + Send {
+                async move { (**self).dyn_put(id, Box::new(input), len).await }
+            }
+
+            fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+                &self,
+                id: I,
+                input: R,
+                len: Option<u64>,
+                options: PutOptions,
+            ) -> impl std::future::Future<Output = Result<()>> + Send {
+                async move {
+                    (**self)
+                        .dyn_put_with(id, Box::new(input), len, options)
+                        .await
+                }
+            }
+
+            fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+                &self,
+                id: &I,
+                output: W,
+            ) -> impl std::future::Future<Output = Result<u64>> + Send {
+                async move { (**self).dyn_get_into(id, Box::new(output)).await }
+            }
+
+            fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+                &self,
+                id: &I,
+                range: Range<u64>,
+                output: W,
+            ) -> impl std::future::Future<Output = Result<u64>> + Send {
+                async move { (**self).dyn_get_range(id, range, Box::new(output)).await }
+            }
+
+            fn get_reader(
+                &self,
+                id: &I,
+            ) -> impl std::future::Future<Output = Result<GetReader<'_>>> + Send {
+                async move { (**self).dyn_get_reader(id).await }
+            }
+
+            fn copy(
+                &self,
+                src: &I,
+                dst: I,
+            ) -> impl std::future::Future<Output = Result<()>> + Send {
+                async move { (**self).dyn_copy(src, dst).await }
+            }
+
+            fn rename(
+                &self,
+                src: &I,
+                dst: I,
+            ) -> impl std::future::Future<Output = Result<()>> + Send {
+                async move { (**self).dyn_rename(src, dst).await }
+            }
+
+            fn delete(&self, id: &I) -> impl std::future::Future<Output = Result<()>> + Send {
+                async move { (**self).dyn_delete(id).await }
+            }
+
+            fn delete_many(
+                &self,
+                ids: Vec<I>,
+            ) -> impl std::future::Future<Output = Result<BatchResult<I>>> + Send {
+                async move { (**self).dyn_delete_many(ids).await }
+            }
+
+            fn delete_prefix(
+                &self,
+                prefix: &I,
+            ) -> impl std::future::Future<Output = Result<BatchResult<I>>> + Send {
+                async move { (**self).dyn_delete_prefix(prefix).await }
+            }
+
+            fn delete_folder(
+                &self,
+                folder: &I,
+            ) -> impl std::future::Future<Output = Result<()>> + Send {
+                async move { (**self).dyn_delete_folder(folder).await }
+            }
+
+            fn list(
+                &self,
+                prefix: Option<&I>,
+            ) -> impl std::future::Future<Output = Result<BoxStream<'_, Result<I>>>> + Send {
+                async move { (**self).dyn_list(prefix).await }
+            }
+
+            fn list_with_meta(
+                &self,
+                prefix: Option<&I>,
+            ) -> impl std::future::Future<Output = Result<BoxStream<'_, Result<Entry<I>>>>> + Send
+            {
+                async move { (**self).dyn_list_with_meta(prefix).await }
+            }
+
+            fn list_page(
+                &self,
+                prefix: Option<&I>,
+                page_size: usize,
+                continuation: Option<ContinuationToken>,
+            ) -> impl std::future::Future<Output = Result<Page<I>>> + Send {
+                async move {
+                    (**self)
+                        .dyn_list_page(prefix, page_size, continuation)
+                        .await
+                }
+            }
+
+            fn list_dir(
+                &self,
+                folder: Option<&I>,
+            ) -> impl std::future::Future<Output = Result<DirListing<I>>> + Send {
+                async move { (**self).dyn_list_dir(folder).await }
+            }
+        }
+    };
+}
+
+impl_storage_for_dyn!(Box);
+impl_storage_for_dyn!(Arc);
@@ -0,0 +1,163 @@
+//! Classification of failed HTTP responses, shared by the REST adapters.
+//!
+//! Adapters check their backend's own error codes first (e.g. Azure's
+//! `x-ms-error-code` or Dropbox's error summaries) and fall back to
+//! [`status_error`] for everything else, so the same status maps to the same
+//! [`Error`] variant on every backend.
+
+use std::time::Duration;
+
+use reqwest::StatusCode;
+
+use crate::Error;
+
+/// Delay requested by a `Retry-After` header, given either as a number of
+/// seconds or as an HTTP date.
+#[cfg(any(
+    feature = "azure",
+    feature = "webdav",
+    feature = "gdrive",
+    feature = "onedrive",
+    feature = "box_storage"
+))]
+pub(crate) fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = crate::time::parse_http_date(value)?;
+    Some(
+        at.duration_since(std::time::SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Error for a response that failed with `status`.
+///
+/// `id` names the item in [`Error::NotFound`] and
+/// [`Error::PreconditionFailed`]; the other variants carry `message`.
+pub(crate) fn status_error(
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    id: &str,
+    message: String,
+) -> Error {
+    match status {
+        StatusCode::NOT_FOUND | StatusCode::GONE => Error::NotFound(id.to_string()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::PermissionDenied(message),
+        StatusCode::PRECONDITION_FAILED => Error::PreconditionFailed(id.to_string()),
+        StatusCode::CONFLICT | StatusCode::LOCKED => Error::Conflict(message),
+        StatusCode::TOO_MANY_REQUESTS => Error::RateLimited {
+            message,
+            retry_after,
+        },
+        // Throttling backends answer 503 with a Retry-After.
+        StatusCode::SERVICE_UNAVAILABLE if retry_after.is_some() => Error::RateLimited {
+            message,
+            retry_after,
+        },
+        StatusCode::INTERNAL_SERVER_ERROR
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE => Error::Unavailable(message),
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Error::Timeout(message),
+        StatusCode::INSUFFICIENT_STORAGE => Error::QuotaExceeded(message),
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => Error::Unsupported(message),
+        StatusCode::URI_TOO_LONG => Error::InvalidId(id.to_string()),
+        _ => Error::Generic(message),
+    }
+}
+
+/// Error for a request that could not be sent or whose response could not
+/// be read: [`Error::Timeout`] if it timed out, otherwise
+/// [`Error::Connection`].
+pub(crate) fn request_error(error: reqwest::Error) -> Error {
+    if error.is_timeout() {
+        Error::Timeout(error.to_string())
+    } else {
+        Error::Connection(Box::new(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    #[cfg(any(
+        feature = "azure",
+        feature = "webdav",
+        feature = "gdrive",
+        feature = "onedrive",
+        feature = "box_storage"
+    ))]
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        // Dates in the past mean "now"
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_status_error() {
+        let error = |status| status_error(status, None, "a.txt", "failed".to_string());
+
+        assert!(matches!(error(StatusCode::NOT_FOUND), Error::NotFound(id) if id == "a.txt"));
+        assert!(matches!(
+            error(StatusCode::FORBIDDEN),
+            Error::PermissionDenied(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::PRECONDITION_FAILED),
+            Error::PreconditionFailed(_)
+        ));
+        assert!(matches!(error(StatusCode::CONFLICT), Error::Conflict(_)));
+        assert!(matches!(
+            error(StatusCode::INSUFFICIENT_STORAGE),
+            Error::QuotaExceeded(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::GATEWAY_TIMEOUT),
+            Error::Timeout(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::NOT_IMPLEMENTED),
+            Error::Unsupported(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::URI_TOO_LONG),
+            Error::InvalidId(_)
+        ));
+        assert!(matches!(error(StatusCode::BAD_REQUEST), Error::Generic(_)));
+        assert!(matches!(
+            error(StatusCode::SERVICE_UNAVAILABLE),
+            Error::Unavailable(_)
+        ));
+
+        let throttled = status_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            Some(Duration::from_secs(3)),
+            "a.txt",
+            "busy".to_string(),
+        );
+        assert!(throttled.is_retryable());
+        assert_eq!(throttled.retry_after(), Some(Duration::from_secs(3)));
+        assert!(error(StatusCode::TOO_MANY_REQUESTS).is_retryable());
+        assert!(!error(StatusCode::FORBIDDEN).is_retryable());
+    }
+}
@@ -0,0 +1,505 @@
+//! Internal I/O adapters shared by the trait defaults and the adapters.
+
+use std::io;
+use std::ops::Range;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use futures::{SinkExt as _, StreamExt as _};
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, ReadBuf};
+
+/// Size of the chunks [`tee`] reads from its input.
+pub(crate) const PIPE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Number of chunks a [`pipe`] buffers before the writer has to wait.
+const PIPE_CAPACITY: usize = 4;
+
+/// An [`AsyncWrite`] that forwards only the bytes falling inside `range` of
+/// the overall stream and silently discards everything else.
+///
+/// Used to emulate ranged reads on top of a full download.
+pub(crate) struct RangeWriter<W> {
+    inner: W,
+    range: Range<u64>,
+    /// Offset of the next incoming byte within the overall stream.
+    pos: u64,
+    /// Bytes forwarded to `inner` so far.
+    written: u64,
+}
+
+impl<W> RangeWriter<W> {
+    pub(crate) fn new(inner: W, range: Range<u64>) -> Self {
+        Self {
+            inner,
+            range,
+            pos: 0,
+            written: 0,
+        }
+    }
+
+    /// Number of bytes forwarded to the inner writer.
+    pub(crate) fn written(&self) -> u64 {
+        self.written
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for RangeWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let buf_end = this.pos + buf.len() as u64;
+
+        let keep_start = this.range.start.max(this.pos);
+        let keep_end = this.range.end.min(buf_end);
+
+        if keep_start >= keep_end {
+            // Nothing in this chunk is wanted.
+            this.pos = buf_end;
+            return Poll::Ready(Ok(buf.len()));
+        }
+
+        let skip = (keep_start - this.pos) as usize;
+        let take = (keep_end - keep_start) as usize;
+
+        let n = match Pin::new(&mut this.inner).poll_write(cx, &buf[skip..skip + take]) {
+            Poll::Ready(Ok(n)) => n,
+            other => return other,
+        };
+
+        this.written += n as u64;
+        // Only report the skipped prefix plus what the inner writer accepted,
+        // so the caller re-sends anything that was not written.
+        let consumed = if n == take { buf.len() } else { skip + n };
+        this.pos += consumed as u64;
+        Poll::Ready(Ok(consumed))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+enum PipeMessage {
+    Data(Vec<u8>),
+    End,
+    Failed(io::Error),
+}
+
+/// Create a bounded in-memory pipe for streaming an upload into a consumer
+/// that needs an owned (`'static`) reader, such as a spawned task or a
+/// streaming request body.
+///
+/// Unlike [`tokio::io::duplex`], the reader only reports EOF after
+/// [`PipeWriter::finish`]. If the writer fails or is dropped early the
+/// reader returns an error, so a truncated input is never committed as if it
+/// were complete.
+pub(crate) fn pipe() -> (PipeWriter, PipeReader) {
+    let (tx, rx) = mpsc::channel(PIPE_CAPACITY);
+    (
+        PipeWriter { tx },
+        PipeReader {
+            rx,
+            chunk: Vec::new(),
+            pos: 0,
+            done: false,
+        },
+    )
+}
+
+/// Sending half of a [`pipe`].
+pub(crate) struct PipeWriter {
+    tx: mpsc::Sender<PipeMessage>,
+}
+
+impl PipeWriter {
+    /// Send a chunk, waiting while the pipe is full.
+    ///
+    /// Fails with [`io::ErrorKind::BrokenPipe`] once the reader is gone.
+    pub(crate) async fn send(&mut self, chunk: Vec<u8>) -> io::Result<()> {
+        self.tx
+            .send(PipeMessage::Data(chunk))
+            .await
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+
+    /// Signal a clean end of input.
+    pub(crate) async fn finish(mut self) {
+        let _ = self.tx.send(PipeMessage::End).await;
+    }
+
+    /// Abort the stream; the reader fails with an error of the same kind.
+    pub(crate) async fn fail(mut self, error: &io::Error) {
+        let error = io::Error::new(error.kind(), error.to_string());
+        let _ = self.tx.send(PipeMessage::Failed(error)).await;
+    }
+}
+
+/// Receiving half of a [`pipe`].
+pub(crate) struct PipeReader {
+    rx: mpsc::Receiver<PipeMessage>,
+    chunk: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl PipeReader {
+    /// Convert the reader into a stream of chunks, e.g. for a streaming
+    /// HTTP request body.
+    #[cfg(any(feature = "box_storage", feature = "webdav"))]
+    pub(crate) fn into_stream(
+        self,
+    ) -> impl futures::Stream<Item = io::Result<Vec<u8>>> + Send + 'static {
+        futures::stream::unfold(self, |mut reader| async move {
+            if reader.done {
+                return None;
+            }
+            let item = match reader.rx.next().await {
+                Some(PipeMessage::Data(chunk)) => Ok(chunk),
+                Some(PipeMessage::End) => return None,
+                Some(PipeMessage::Failed(e)) => {
+                    reader.done = true;
+                    Err(e)
+                }
+                None => {
+                    reader.done = true;
+                    Err(truncated())
+                }
+            };
+            Some((item, reader))
+        })
+    }
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "upload input ended before it was complete",
+    )
+}
+
+impl AsyncRead for PipeReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        while this.pos == this.chunk.len() {
+            if this.done {
+                return Poll::Ready(Ok(()));
+            }
+            match this.rx.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(PipeMessage::Data(chunk))) => {
+                    this.chunk = chunk;
+                    this.pos = 0;
+                }
+                Poll::Ready(Some(PipeMessage::End)) => this.done = true,
+                Poll::Ready(Some(PipeMessage::Failed(e))) => {
+                    this.done = true;
+                    return Poll::Ready(Err(e));
+                }
+                Poll::Ready(None) => {
+                    this.done = true;
+                    return Poll::Ready(Err(truncated()));
+                }
+            }
+        }
+
+        let n = buf.remaining().min(this.chunk.len() - this.pos);
+        buf.put_slice(&this.chunk[this.pos..this.pos + n]);
+        this.pos += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Copy `input` into every writer in `outputs`.
+///
+/// Outputs whose reader has gone away are dropped and the rest keep going;
+/// reading stops early once no output is left. A read error is forwarded to
+/// every remaining output and returned.
+pub(crate) async fn tee<R>(mut input: R, mut outputs: Vec<PipeWriter>) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    loop {
+        if outputs.is_empty() {
+            return Ok(());
+        }
+
+        let mut chunk = Vec::with_capacity(PIPE_CHUNK_SIZE);
+        match (&mut input)
+            .take(PIPE_CHUNK_SIZE as u64)
+            .read_to_end(&mut chunk)
+            .await
+        {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                for output in outputs {
+                    output.fail(&e).await;
+                }
+                return Err(e);
+            }
+        }
+
+        let mut live = Vec::with_capacity(outputs.len());
+        let last = outputs.len() - 1;
+        for (i, mut output) in outputs.into_iter().enumerate() {
+            let chunk = if i == last {
+                std::mem::take(&mut chunk)
+            } else {
+                chunk.clone()
+            };
+            if output.send(chunk).await.is_ok() {
+                live.push(output);
+            }
+        }
+        outputs = live;
+    }
+
+    for output in outputs {
+        output.finish().await;
+    }
+    Ok(())
+}
+
+/// Send the request built by `build`, streaming `input` as its body.
+///
+/// `build` receives the streaming body and returns the request to send.
+#[cfg(any(feature = "box_storage", feature = "webdav"))]
+pub(crate) async fn send_streaming<R, F>(input: R, build: F) -> crate::Result<reqwest::Response>
+where
+    R: AsyncRead + Unpin,
+    F: FnOnce(reqwest::Body) -> crate::Result<reqwest::RequestBuilder>,
+{
+    let (writer, reader) = pipe();
+    let request = build(reqwest::Body::wrap_stream(reader.into_stream()))?;
+
+    // If the server answers before taking the whole body, the pipe breaks
+    // and `tee` stops reading; the response then reports what went wrong.
+    let (pumped, sent) = tokio::join!(tee(input, vec![writer]), request.send());
+    pumped?;
+    sent.map_err(|e| crate::Error::Connection(Box::new(e)))
+}
+
+/// Reader over the body of `response`.
+///
+/// Body errors are wrapped so [`Error::from_io`](crate::Error::from_io)
+/// recovers them as [`Error::Connection`](crate::Error::Connection).
+#[cfg(any(
+    feature = "azure",
+    feature = "webdav",
+    feature = "gdrive",
+    feature = "onedrive",
+    feature = "dropbox",
+    feature = "box_storage"
+))]
+pub(crate) fn response_reader<'a>(response: reqwest::Response) -> crate::GetReader<'a> {
+    let body = response
+        .bytes_stream()
+        .map(|chunk| chunk.map_err(|e| io::Error::other(crate::Error::Connection(Box::new(e)))));
+    crate::GetReader::new(tokio_util::io::StreamReader::new(body))
+}
+
+/// Read from `input` until `buf` holds `size` bytes or the input ends.
+///
+/// `buf` is cleared first. Returns the number of bytes read; anything less
+/// than `size` means the input is exhausted.
+#[cfg(any(
+    feature = "s3",
+    feature = "azure",
+    feature = "gdrive",
+    feature = "onedrive",
+    feature = "dropbox"
+))]
+pub(crate) async fn fill_chunk<R>(
+    input: &mut R,
+    buf: &mut Vec<u8>,
+    size: usize,
+) -> io::Result<usize>
+where
+    R: AsyncRead + Unpin,
+{
+    buf.clear();
+    buf.reserve_exact(size);
+    input.take(size as u64).read_to_end(buf).await
+}
+
+/// Format an HTTP `Range` header value for a half-open byte range.
+///
+/// The range must be non-empty.
+#[cfg(any(
+    feature = "azure",
+    feature = "webdav",
+    feature = "gdrive",
+    feature = "onedrive",
+    feature = "s3"
+))]
+pub(crate) fn range_header(range: &Range<u64>) -> String {
+    format!("bytes={}-{}", range.start, range.end - 1)
+}
+
+/// Stream the body of a successful ranged `GET` into `output`.
+///
+/// Servers that ignore the `Range` header reply `200 OK` with the whole
+/// body; in that case the unwanted bytes are discarded locally.
+#[cfg(any(
+    feature = "azure",
+    feature = "webdav",
+    feature = "gdrive",
+    feature = "onedrive"
+))]
+pub(crate) async fn write_range_response<W>(
+    response: reqwest::Response,
+    range: Range<u64>,
+    output: W,
+) -> crate::Result<u64>
+where
+    W: AsyncWrite + Send + Unpin,
+{
+    use futures::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    // A 206 body is exactly the requested range; a 200 body is the whole
+    // item and has to be trimmed.
+    let partial = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let keep = if partial { 0..u64::MAX } else { range.clone() };
+    let mut output = RangeWriter::new(output, keep);
+    let mut received = 0u64;
+
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| crate::Error::Connection(Box::new(e)))?;
+        output.write_all(&chunk).await?;
+        received += chunk.len() as u64;
+        // No need to download the rest of a full body once the range is covered.
+        if !partial && received >= range.end {
+            break;
+        }
+    }
+
+    output.flush().await?;
+    Ok(output.written())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    #[tokio::test]
+    async fn test_range_writer_across_chunks() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut out = Vec::new();
+        {
+            let mut writer = RangeWriter::new(&mut out, 15..42);
+            for chunk in data.chunks(7) {
+                writer.write_all(chunk).await.unwrap();
+            }
+            assert_eq!(writer.written(), 27);
+        }
+        assert_eq!(out, &data[15..42]);
+    }
+
+    #[tokio::test]
+    async fn test_range_writer_past_end() {
+        let data: Vec<u8> = (0..10).collect();
+        let mut out = Vec::new();
+        let mut writer = RangeWriter::new(&mut out, 20..30);
+        writer.write_all(&data).await.unwrap();
+        assert_eq!(writer.written(), 0);
+    }
+
+    /// Reader that yields `data` and then fails.
+    struct FailingReader<'a> {
+        data: &'a [u8],
+    }
+
+    impl AsyncRead for FailingReader<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if self.data.is_empty() {
+                return Poll::Ready(Err(io::Error::other("source failed")));
+            }
+            let n = self.data.len().min(buf.remaining());
+            buf.put_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tee_copies_to_every_reader() {
+        let data: Vec<u8> = (0..=255).cycle().take(3 * PIPE_CHUNK_SIZE + 17).collect();
+        let (w1, mut r1) = pipe();
+        let (w2, mut r2) = pipe();
+
+        let (pumped, a, b) = tokio::join!(
+            tee(&data[..], vec![w1, w2]),
+            async {
+                let mut out = Vec::new();
+                r1.read_to_end(&mut out).await.map(|_| out)
+            },
+            async {
+                let mut out = Vec::new();
+                r2.read_to_end(&mut out).await.map(|_| out)
+            },
+        );
+        pumped.unwrap();
+        assert_eq!(a.unwrap(), data);
+        assert_eq!(b.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_tee_survives_dropped_reader() {
+        let data = vec![7u8; 2 * PIPE_CHUNK_SIZE];
+        let (w1, r1) = pipe();
+        let (w2, mut r2) = pipe();
+        drop(r1);
+
+        let (pumped, b) = tokio::join!(tee(&data[..], vec![w1, w2]), async {
+            let mut out = Vec::new();
+            r2.read_to_end(&mut out).await.map(|_| out)
+        });
+        pumped.unwrap();
+        assert_eq!(b.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_pipe_reports_failed_input() {
+        let (writer, mut reader) = pipe();
+
+        let (pumped, read) = tokio::join!(
+            tee(FailingReader { data: b"partial" }, vec![writer]),
+            async {
+                let mut out = Vec::new();
+                reader.read_to_end(&mut out).await
+            },
+        );
+        assert!(pumped.is_err());
+        assert!(read.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pipe_dropped_writer_is_not_eof() {
+        let (mut writer, mut reader) = pipe();
+        writer.send(b"partial".to_vec()).await.unwrap();
+        drop(writer);
+
+        let mut out = Vec::new();
+        let err = reader.read_to_end(&mut out).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
 
-use futures::stream::BoxStream;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
 
 #[cfg(feature = "memory")]
@@ -10,13 +13,56 @@ pub use adapters::multi::migration::{ConflictStrategy, MigrateOptions, Migration
 
 pub use adapters::multi;
 
+pub use dyn_storage::{DynReader, DynStorage, DynWriter};
+
+pub use path::{PathId, StoragePath};
+
+pub use presign::{Presign, PresignMethod, PresignedRequest};
+
+pub use registry::{StorageRegistry, StorageUrl};
+
+pub use streaming::{GetReader, PutWriter};
+
+#[cfg(feature = "serde")]
+pub use config::{SecretSource, StorageConfig};
+
+#[cfg(feature = "serde")]
+pub mod config;
+mod dyn_storage;
+#[cfg(any(
+    feature = "azure",
+    feature = "webdav",
+    feature = "gdrive",
+    feature = "onedrive",
+    feature = "dropbox",
+    feature = "box_storage"
+))]
+mod http;
+mod io;
+mod path;
+mod presign;
+mod registry;
+mod streaming;
+
+#[cfg(any(
+    feature = "azure",
+    feature = "webdav",
+    feature = "gdrive",
+    feature = "onedrive",
+    feature = "dropbox",
+    feature = "box_storage"
+))]
+mod time;
+
 /// A specialized Result type for Storage operations.
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Details about a mirror operation failure.
 ///
-/// Contains backend indices and full error objects for successes/failures,
-/// plus any rollback errors if rollback was attempted.
+/// Contains backend indices and the original errors for successes/failures,
+/// how long each backend took, plus any rollback errors if rollback was
+/// attempted. The first failure is exposed as the error's
+/// [`source`](std::error::Error::source).
 ///
 /// ```
 /// # use stowage::{Error, MirrorFailureDetails, StorageExt};
@@ -32,7 +78,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 ///     Err(Error::MirrorFailure(details)) => {
 ///         println!("{} of {} failed", details.failure_count(), details.total_backends());
 ///         for (idx, error) in &details.failures {
-///             eprintln!("Backend {}: {:?}", idx, error);
+///             eprintln!("Backend {}: {:?} after {:?}", idx, error, details.elapsed_for(*idx));
 ///         }
 ///     }
 ///     _ => {}
@@ -48,6 +94,9 @@ pub struct MirrorFailureDetails {
     pub failures: Vec<(usize, Box<Error>)>,
     /// Errors that occurred during rollback (if any)
     pub rollback_errors: Vec<(usize, Box<Error>)>,
+    /// Indices of backends and how long they took to succeed or fail.
+    /// Backends that had not finished when the operation gave up are missing.
+    pub elapsed: Vec<(usize, Duration)>,
 }
 
 impl MirrorFailureDetails {
@@ -90,6 +139,22 @@ impl MirrorFailureDetails {
     pub fn successful_indices(&self) -> &[usize] {
         &self.successes
     }
+
+    /// Get the error the backend at `index` failed with
+    pub fn error_for(&self, index: usize) -> Option<&Error> {
+        self.failures
+            .iter()
+            .find(|(idx, _)| *idx == index)
+            .map(|(_, e)| e.as_ref())
+    }
+
+    /// Get how long the backend at `index` took to succeed or fail
+    pub fn elapsed_for(&self, index: usize) -> Option<Duration> {
+        self.elapsed
+            .iter()
+            .find(|(idx, _)| *idx == index)
+            .map(|(_, elapsed)| *elapsed)
+    }
 }
 
 impl std::fmt::Display for MirrorFailureDetails {
@@ -107,6 +172,14 @@ impl std::fmt::Display for MirrorFailureDetails {
     }
 }
 
+impl std::error::Error for MirrorFailureDetails {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.failures
+            .first()
+            .map(|(_, e)| e.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
 /// A unified Error type for storage operations.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -116,6 +189,50 @@ pub enum Error {
     #[error("Permission denied: {0}")]
     PermissionDenied(String),
 
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    /// The item (or a folder in its place) already exists and the backend
+    /// will not replace it.
+    #[error("Already exists: {0}")]
+    AlreadyExists(String),
+
+    /// The request conflicts with the item's current state, e.g. a lock held
+    /// by another client or a missing parent folder.
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// The backend is throttling requests. `retry_after` is the delay it
+    /// asked for, where it gave one.
+    #[error("Rate limited: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+
+    /// The account or volume is out of space.
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    /// The backend or the connection to it timed out.
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    /// The backend is temporarily unable to handle the request, e.g. an
+    /// HTTP `500`, `502` or `503`.
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    /// The backend does not support the operation.
+    #[error("Unsupported operation: {0}")]
+    Unsupported(String),
+
+    /// The id is not a valid name for the backend, e.g. too long or with
+    /// forbidden characters, or another argument or configuration value was
+    /// rejected, such as the root folder passed to `delete_folder`.
+    #[error("Invalid id: {0}")]
+    InvalidId(String),
+
     #[error("Storage backend connection error")]
     Connection(#[source] Box<dyn std::error::Error + Send + Sync>),
 
@@ -125,10 +242,492 @@ pub enum Error {
     #[error("Generic storage error: {0}")]
     Generic(String),
 
-    #[error("{0}")]
+    #[error(transparent)]
     MirrorFailure(MirrorFailureDetails),
 }
 
+impl Error {
+    /// Whether the same request may succeed if retried later.
+    ///
+    /// True for rate limits, timeouts, temporarily unavailable backends,
+    /// connection failures and I/O errors such as a reset connection, and
+    /// for mirror failures where every failed backend's error is retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::RateLimited { .. }
+            | Error::Timeout(_)
+            | Error::Unavailable(_)
+            | Error::Connection(_) => true,
+            Error::Io(e) => matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::NotConnected
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            ),
+            Error::MirrorFailure(details) => {
+                details.has_failures() && details.failures.iter().all(|(_, e)| e.is_retryable())
+            }
+            _ => false,
+        }
+    }
+
+    /// The delay the backend asked for before retrying, from a
+    /// [`RateLimited`](Error::RateLimited) error.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Convert an I/O error back into the storage error it wraps, as
+    /// returned when reading from a [`GetReader`] whose download failed, or
+    /// wrap it as [`Error::Io`].
+    pub fn from_io(error: std::io::Error) -> Self {
+        if error.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            let inner = error.into_inner().expect("checked above");
+            return *inner.downcast::<Error>().expect("checked above");
+        }
+        Error::Io(error)
+    }
+
+    /// Copy of this error for reporting one failure more than once, e.g.
+    /// against every item of a batch request that failed as a whole.
+    ///
+    /// The variant and message are kept; source errors are flattened into
+    /// their message.
+    pub(crate) fn replicate(&self) -> Self {
+        match self {
+            Error::NotFound(s) => Error::NotFound(s.clone()),
+            Error::PermissionDenied(s) => Error::PermissionDenied(s.clone()),
+            Error::PreconditionFailed(s) => Error::PreconditionFailed(s.clone()),
+            Error::AlreadyExists(s) => Error::AlreadyExists(s.clone()),
+            Error::Conflict(s) => Error::Conflict(s.clone()),
+            Error::RateLimited {
+                message,
+                retry_after,
+            } => Error::RateLimited {
+                message: message.clone(),
+                retry_after: *retry_after,
+            },
+            Error::QuotaExceeded(s) => Error::QuotaExceeded(s.clone()),
+            Error::Timeout(s) => Error::Timeout(s.clone()),
+            Error::Unavailable(s) => Error::Unavailable(s.clone()),
+            Error::Unsupported(s) => Error::Unsupported(s.clone()),
+            Error::InvalidId(s) => Error::InvalidId(s.clone()),
+            Error::Connection(e) => {
+                Error::Connection(Box::new(std::io::Error::other(e.to_string())))
+            }
+            Error::Io(e) => Error::Io(std::io::Error::new(e.kind(), e.to_string())),
+            Error::Generic(s) => Error::Generic(s.clone()),
+            Error::MirrorFailure(details) => Error::Generic(details.to_string()),
+        }
+    }
+}
+
+/// Metadata describing a stored item, as returned by [`Storage::stat`].
+///
+/// Fields a backend does not report are left as `None` (or empty).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObjectMeta {
+    /// Size of the item in bytes.
+    pub size: u64,
+    /// Last modification time.
+    pub last_modified: Option<SystemTime>,
+    /// Entity tag or content hash, in the backend's native format.
+    pub etag: Option<String>,
+    /// MIME type of the content.
+    pub content_type: Option<String>,
+    /// `Cache-Control` value served with the content.
+    pub cache_control: Option<String>,
+    /// `Content-Disposition` value served with the content.
+    pub content_disposition: Option<String>,
+    /// Custom user metadata attached to the item.
+    pub metadata: HashMap<String, String>,
+}
+
+/// A listed item together with its basic metadata, as returned by
+/// [`Storage::list_with_meta`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry<Id> {
+    /// Identifier of the item.
+    pub id: Id,
+    /// Size in bytes (zero for folders).
+    pub size: u64,
+    /// Last modification time, if the backend reports one.
+    pub modified: Option<SystemTime>,
+    /// Entity tag or content hash, in the backend's native format.
+    pub etag: Option<String>,
+    /// True for folder entries, such as S3 "folder marker" keys ending in `/`.
+    pub is_dir: bool,
+}
+
+impl<Id> Entry<Id> {
+    /// Build a file entry from the metadata returned by [`Storage::stat`].
+    pub fn from_meta(id: Id, meta: ObjectMeta) -> Self {
+        Self {
+            id,
+            size: meta.size,
+            modified: meta.last_modified,
+            etag: meta.etag,
+            is_dir: false,
+        }
+    }
+}
+
+/// Opaque position in a paginated listing, as returned in [`Page::next`].
+///
+/// Tokens are plain strings, so they can be stored (e.g. in a checkpoint
+/// file) and passed back to [`Storage::list_page`] after a restart. A token
+/// is only meaningful to the backend and prefix that produced it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContinuationToken(String);
+
+impl ContinuationToken {
+    /// Wrap a token previously obtained from [`as_str`](Self::as_str).
+    pub fn new(token: impl Into<String>) -> Self {
+        Self(token.into())
+    }
+
+    /// The token as a string, for persisting.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for ContinuationToken {
+    fn from(token: String) -> Self {
+        Self(token)
+    }
+}
+
+impl std::fmt::Display for ContinuationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// One page of a listing, as returned by [`Storage::list_page`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<Id> {
+    /// Identifiers on this page.
+    pub ids: Vec<Id>,
+    /// Token for the following page, or `None` if this is the last one.
+    pub next: Option<ContinuationToken>,
+}
+
+/// One level of a hierarchical listing, as returned by [`Storage::list_dir`].
+///
+/// Both lists are sorted. Folder identifiers carry no trailing `/`, so they
+/// can be passed straight back to [`Storage::list_dir`] or
+/// [`Storage::folder_exists`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirListing<Id> {
+    /// Items directly inside the folder.
+    pub files: Vec<Id>,
+    /// Folders directly inside the folder (S3 "common prefixes").
+    pub prefixes: Vec<Id>,
+}
+
+impl<Id> Default for DirListing<Id> {
+    fn default() -> Self {
+        Self {
+            files: Vec::new(),
+            prefixes: Vec::new(),
+        }
+    }
+}
+
+impl<Id: Ord> DirListing<Id> {
+    /// Sort both lists and drop duplicates.
+    pub(crate) fn normalize(mut self) -> Self {
+        self.files.sort();
+        self.files.dedup();
+        self.prefixes.sort();
+        self.prefixes.dedup();
+        self
+    }
+}
+
+impl DirListing<String> {
+    /// Group flat keys into one level below `folder` (as returned by
+    /// [`dir_prefix`]); keys outside it are ignored.
+    #[cfg(feature = "memory")]
+    pub(crate) fn from_keys<'a>(folder: &str, keys: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut listing = Self::default();
+        for key in keys {
+            let Some(rest) = key.strip_prefix(folder) else {
+                continue;
+            };
+            match rest.split_once('/') {
+                Some((dir, _)) if !dir.is_empty() => {
+                    listing.prefixes.push(format!("{folder}{dir}"));
+                }
+                Some(_) => {}
+                None if !rest.is_empty() => listing.files.push(key.to_string()),
+                None => {}
+            }
+        }
+        listing.normalize()
+    }
+}
+
+/// Per-item outcome of a batch operation such as [`Storage::delete_many`].
+///
+/// Batches are best-effort: a failing item does not stop the others.
+/// Items appear in no particular order.
+#[derive(Debug)]
+pub struct BatchResult<Id> {
+    /// Items the operation succeeded for.
+    pub succeeded: Vec<Id>,
+    /// Items the operation failed for, with the reason.
+    pub failed: Vec<(Id, Error)>,
+}
+
+impl<Id> BatchResult<Id> {
+    /// Create an empty result.
+    pub fn new() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    /// Returns `true` when no item failed.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Number of items the operation succeeded for.
+    pub fn success_count(&self) -> usize {
+        self.succeeded.len()
+    }
+
+    /// Number of items the operation failed for.
+    pub fn failure_count(&self) -> usize {
+        self.failed.len()
+    }
+
+    /// `Ok(())` if every item succeeded, otherwise the error of the first
+    /// failed item.
+    pub fn into_result(self) -> Result<()> {
+        match self.failed.into_iter().next() {
+            None => Ok(()),
+            Some((_, e)) => Err(e),
+        }
+    }
+
+    /// Record `error` against every item in `ids`, e.g. when a whole batch
+    /// request failed.
+    #[cfg(any(
+        feature = "s3",
+        feature = "azure",
+        feature = "onedrive",
+        feature = "dropbox"
+    ))]
+    pub(crate) fn fail_all(&mut self, ids: impl IntoIterator<Item = Id>, error: &Error) {
+        self.failed
+            .extend(ids.into_iter().map(|id| (id, error.replicate())));
+    }
+}
+
+impl<Id> Default for BatchResult<Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Id> FromIterator<(Id, Result<()>)> for BatchResult<Id> {
+    fn from_iter<I: IntoIterator<Item = (Id, Result<()>)>>(iter: I) -> Self {
+        let mut result = Self::new();
+        for (id, outcome) in iter {
+            match outcome {
+                Ok(()) => result.succeeded.push(id),
+                Err(e) => result.failed.push((id, e)),
+            }
+        }
+        result
+    }
+}
+
+impl<Id> std::fmt::Display for BatchResult<Id> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Batch: {} succeeded, {} failed",
+            self.success_count(),
+            self.failure_count()
+        )
+    }
+}
+
+/// Key prefix of a folder passed to [`Storage::delete_folder`] on path-based
+/// backends, as returned by [`dir_prefix`].
+///
+/// The root is refused so a bad argument cannot wipe the whole storage; use
+/// [`Storage::delete_prefix`] for that.
+pub(crate) fn delete_folder_prefix(folder: &str) -> Result<String> {
+    match dir_prefix(Some(folder)) {
+        prefix if prefix.is_empty() => Err(Error::InvalidId(
+            "refusing to delete the root folder".to_string(),
+        )),
+        prefix => Ok(prefix),
+    }
+}
+
+/// Number of single deletes the default [`Storage::delete_many`] runs at once.
+const DELETE_MANY_CONCURRENCY: usize = 8;
+
+/// Key prefix for the contents of `folder` in path-based backends: empty for
+/// the root, otherwise the folder path with exactly one trailing `/`.
+pub(crate) fn dir_prefix(folder: Option<&str>) -> String {
+    match folder.map(|f| f.trim_matches('/')) {
+        None | Some("") => String::new(),
+        Some(f) => format!("{f}/"),
+    }
+}
+
+/// Options for [`Storage::put_with`].
+///
+/// Preconditions compare against the ETag reported by [`Storage::stat`] and
+/// let concurrent writers do optimistic concurrency control: read an item,
+/// then write it back with `if_match` set to the ETag that was read. A write
+/// whose precondition does not hold fails with [`Error::PreconditionFailed`].
+///
+/// Content attributes (content type, cache control, content disposition and
+/// user metadata) are stored with the item and reported by [`Storage::stat`].
+/// S3 and Azure serve them as response headers, so browsers render downloads
+/// with the right type. Backends that cannot store an attribute ignore it;
+/// see each adapter for what it keeps.
+///
+/// ```rust
+/// use stowage::PutOptions;
+///
+/// // Only create the item if nothing exists at the target yet.
+/// let create_only = PutOptions::new().if_none_match("*");
+///
+/// // Only replace the item if nobody changed it since it was read.
+/// let replace = PutOptions::new().if_match("\"9b2cf535f27731c974343645a3985328\"");
+///
+/// // Serve an image inline, cached for a day.
+/// let image = PutOptions::new()
+///     .content_type("image/png")
+///     .cache_control("public, max-age=86400")
+///     .user_metadata("uploaded-by", "alice");
+/// # let _ = (create_only, replace, image);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PutOptions {
+    /// Only write if the item exists and its ETag equals this value.
+    /// `"*"` matches any existing item.
+    pub if_match: Option<String>,
+    /// Only write if the item's ETag differs from this value.
+    /// `"*"` only writes if the item does not exist.
+    pub if_none_match: Option<String>,
+    /// MIME type of the content.
+    pub content_type: Option<String>,
+    /// `Cache-Control` value to serve the content with.
+    pub cache_control: Option<String>,
+    /// `Content-Disposition` value to serve the content with, e.g.
+    /// `attachment; filename="report.csv"`.
+    pub content_disposition: Option<String>,
+    /// Custom key-value metadata. Keys should be lowercase ASCII letters,
+    /// digits and `-`, which every backend accepts.
+    pub user_metadata: HashMap<String, String>,
+}
+
+impl PutOptions {
+    /// Create options with no preconditions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require the current ETag to equal `etag`.
+    pub fn if_match(mut self, etag: impl Into<String>) -> Self {
+        self.if_match = Some(etag.into());
+        self
+    }
+
+    /// Require the current ETag to differ from `etag`; pass `"*"` to require
+    /// that the item does not exist.
+    pub fn if_none_match(mut self, etag: impl Into<String>) -> Self {
+        self.if_none_match = Some(etag.into());
+        self
+    }
+
+    /// Set the MIME type of the content.
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Set the `Cache-Control` value to serve the content with.
+    pub fn cache_control(mut self, cache_control: impl Into<String>) -> Self {
+        self.cache_control = Some(cache_control.into());
+        self
+    }
+
+    /// Set the `Content-Disposition` value to serve the content with.
+    pub fn content_disposition(mut self, content_disposition: impl Into<String>) -> Self {
+        self.content_disposition = Some(content_disposition.into());
+        self
+    }
+
+    /// Add a custom metadata entry.
+    pub fn user_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.user_metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Returns true if any precondition is set.
+    pub fn has_preconditions(&self) -> bool {
+        self.if_match.is_some() || self.if_none_match.is_some()
+    }
+
+    /// Returns true if any content attribute is set.
+    pub fn has_attributes(&self) -> bool {
+        self.content_type.is_some()
+            || self.cache_control.is_some()
+            || self.content_disposition.is_some()
+            || !self.user_metadata.is_empty()
+    }
+
+    /// The content attributes as [`ObjectMeta`] fields, for backends that
+    /// keep them alongside the item and merge them into `stat` results.
+    #[cfg(any(feature = "memory", feature = "local"))]
+    pub(crate) fn attributes(&self) -> ObjectMeta {
+        ObjectMeta {
+            content_type: self.content_type.clone(),
+            cache_control: self.cache_control.clone(),
+            content_disposition: self.content_disposition.clone(),
+            metadata: self.user_metadata.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Evaluate the preconditions against the ETag of the item currently
+    /// stored, or `None` if there is no such item.
+    pub fn preconditions_hold(&self, current_etag: Option<&str>) -> bool {
+        let if_match = match (self.if_match.as_deref(), current_etag) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some("*"), Some(_)) => true,
+            (Some(expected), Some(current)) => expected == current,
+        };
+        let if_none_match = match (self.if_none_match.as_deref(), current_etag) {
+            (None, _) | (Some(_), None) => true,
+            (Some("*"), Some(_)) => false,
+            (Some(unexpected), Some(current)) => unexpected != current,
+        };
+        if_match && if_none_match
+    }
+}
+
 /// Adapter modules, gated behind Cargo features.
 pub mod adapters {
     #[cfg(feature = "azure")]
@@ -202,6 +801,23 @@ pub trait Storage: Send + Sync + Debug {
         id: &Self::Id,
     ) -> impl std::future::Future<Output = Result<bool>> + Send;
 
+    /// Fetch an item's metadata without downloading its contents.
+    ///
+    /// Returns [`Error::NotFound`] if the item does not exist.
+    ///
+    /// The default implementation streams the item through
+    /// [`get_into`](Self::get_into) into a sink and only reports its size.
+    /// Adapters override it with a native metadata request.
+    fn stat(&self, id: &Self::Id) -> impl std::future::Future<Output = Result<ObjectMeta>> + Send {
+        async move {
+            let size = self.get_into(id, tokio::io::sink()).await?;
+            Ok(ObjectMeta {
+                size,
+                ..ObjectMeta::default()
+            })
+        }
+    }
+
     /// Store data. `len` is optional and may be used by some backends.
     fn put<R: AsyncRead + Send + Sync + Unpin>(
         &self,
@@ -210,6 +826,32 @@ pub trait Storage: Send + Sync + Debug {
         len: Option<u64>,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
 
+    /// Store data with the content attributes in `options`, subject to its
+    /// preconditions.
+    ///
+    /// Returns [`Error::PreconditionFailed`] if a precondition does not hold,
+    /// in which case nothing is written. Adapters that support conditional
+    /// writes evaluate the preconditions atomically with the write where the
+    /// backend allows it. The default implementation ignores content
+    /// attributes, only accepts options without preconditions and otherwise
+    /// returns [`Error::Unsupported`].
+    fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        async move {
+            if options.has_preconditions() {
+                return Err(Error::Unsupported(
+                    "conditional writes are not supported by this backend".to_string(),
+                ));
+            }
+            self.put(id, input, len).await
+        }
+    }
+
     /// Retrieve data and write to `output`. Returns bytes written.
     fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
         &self,
@@ -217,14 +859,281 @@ pub trait Storage: Send + Sync + Debug {
         output: W,
     ) -> impl std::future::Future<Output = Result<u64>> + Send;
 
+    /// Retrieve the bytes in `range` and write them to `output`. Returns bytes written.
+    ///
+    /// A range extending past the end of the item is truncated, so fewer bytes
+    /// than requested (possibly zero) may be written. An empty range writes
+    /// nothing and returns `Ok(0)` without contacting the backend.
+    ///
+    /// The default implementation streams the whole item through
+    /// [`get_into`](Self::get_into) and discards bytes outside the range.
+    /// Adapters with native ranged reads override it.
+    fn get_range<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        range: Range<u64>,
+        output: W,
+    ) -> impl std::future::Future<Output = Result<u64>> + Send {
+        async move {
+            if range.is_empty() {
+                return Ok(0);
+            }
+            let mut writer = io::RangeWriter::new(output, range);
+            self.get_into(id, &mut writer).await?;
+            Ok(writer.written())
+        }
+    }
+
+    /// Open an item for reading, to pull its data lazily (e.g. into a
+    /// decompressor or an HTTP response).
+    ///
+    /// Errors opening the item, such as [`Error::NotFound`], are returned
+    /// here; errors after that surface from the reads and can be recovered
+    /// with [`Error::from_io`].
+    ///
+    /// The default implementation runs [`get_into`](Self::get_into) as part
+    /// of the reads, through an in-memory pipe. Adapters that can hand out a
+    /// reader directly (a file, an HTTP response body) override it.
+    fn get_reader(
+        &self,
+        id: &Self::Id,
+    ) -> impl std::future::Future<Output = Result<GetReader<'_>>> + Send {
+        let id = id.clone();
+        async move {
+            let (writer, reader) = tokio::io::duplex(streaming::PIPE_BUFFER_SIZE);
+            GetReader::download(
+                reader,
+                Box::pin(async move { self.get_into(&id, writer).await }),
+            )
+            .await
+        }
+    }
+
+    /// Copy an item to `dst` within this storage, replacing any item already at `dst`.
+    ///
+    /// Returns [`Error::NotFound`] if `src` does not exist. Adapters use their
+    /// server-side copy where one exists; the default implementation streams
+    /// the bytes through the client with [`get_into`](Self::get_into) and
+    /// [`put`](Self::put).
+    ///
+    /// For ID-based backends `dst` follows the same rules as for `put`.
+    fn copy(
+        &self,
+        src: &Self::Id,
+        dst: Self::Id,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        async move {
+            let (mut client, mut server) = tokio::io::duplex(64 * 1024);
+
+            let download_fut = async {
+                let result = self.get_into(src, &mut server).await;
+                drop(server); // Close the write half so the upload sees EOF
+                result
+            };
+
+            let upload_fut = self.put(dst, &mut client, None);
+
+            tokio::try_join!(download_fut, upload_fut)?;
+            Ok(())
+        }
+    }
+
+    /// Rename (move) an item to `dst` within this storage, replacing any item
+    /// already at `dst`.
+    ///
+    /// Returns [`Error::NotFound`] if `src` does not exist. Adapters use their
+    /// native rename or move where one exists; the default implementation is
+    /// [`copy`](Self::copy) followed by [`delete`](Self::delete) of `src`.
+    fn rename(
+        &self,
+        src: &Self::Id,
+        dst: Self::Id,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        async move {
+            self.copy(src, dst).await?;
+            self.delete(src).await
+        }
+    }
+
     /// Delete an item. Idempotent (returns `Ok(())` if already deleted).
     fn delete(&self, id: &Self::Id) -> impl std::future::Future<Output = Result<()>> + Send;
 
+    /// Delete several items, reporting the outcome for each one.
+    ///
+    /// Like [`delete`](Self::delete) this is idempotent: items that do not
+    /// exist count as deleted. Per-item failures are reported in the
+    /// [`BatchResult`] rather than as an `Err`, which is reserved for
+    /// failures that prevent the batch from being attempted at all.
+    ///
+    /// Adapters with a native batch endpoint (S3, Azure, Dropbox, OneDrive)
+    /// delete many items per request. The default implementation runs a
+    /// bounded number of [`delete`](Self::delete) calls concurrently.
+    fn delete_many(
+        &self,
+        ids: Vec<Self::Id>,
+    ) -> impl std::future::Future<Output = Result<BatchResult<Self::Id>>> + Send {
+        async move {
+            let outcomes: Vec<_> = futures::stream::iter(ids)
+                .map(|id| async move {
+                    let outcome = self.delete(&id).await;
+                    (id, outcome)
+                })
+                .buffer_unordered(DELETE_MANY_CONCURRENCY)
+                .collect()
+                .await;
+            Ok(outcomes.into_iter().collect())
+        }
+    }
+
+    /// Delete every item that [`list`](Self::list) returns for `prefix`,
+    /// reporting the outcome for each one.
+    ///
+    /// The default implementation lists the items and removes them with
+    /// [`delete_many`](Self::delete_many), so object stores delete them in
+    /// batches. Folders are left behind on backends that have them; use
+    /// [`delete_folder`](Self::delete_folder) to remove those too.
+    fn delete_prefix(
+        &self,
+        prefix: &Self::Id,
+    ) -> impl std::future::Future<Output = Result<BatchResult<Self::Id>>> + Send {
+        async move {
+            let ids: Vec<Self::Id> = self.list(Some(prefix)).await?.try_collect().await?;
+            self.delete_many(ids).await
+        }
+    }
+
+    /// Delete a folder together with everything in it. Idempotent (returns
+    /// `Ok(())` if the folder does not exist).
+    ///
+    /// **Path-based backends** take the folder path, with or without a
+    /// trailing `/`, and refuse to delete the root with
+    /// [`Error::InvalidId`]. **ID-based backends** take the folder's ID.
+    ///
+    /// Google Drive, OneDrive, Dropbox, Box and WebDAV delete the folder
+    /// natively and Local uses `remove_dir_all`. SFTP and FTP walk the tree,
+    /// and object stores (S3, Azure, Memory) delete every item under the
+    /// folder with [`delete_prefix`](Self::delete_prefix). If anything is
+    /// left behind, the first error is returned.
+    ///
+    /// The default implementation returns [`Error::Unsupported`].
+    fn delete_folder(
+        &self,
+        folder: &Self::Id,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        let _ = folder;
+        async move {
+            Err(Error::Unsupported(
+                "folder deletion is not supported by this backend".to_string(),
+            ))
+        }
+    }
+
     /// List identifiers matching an optional prefix.
     fn list(
         &self,
         prefix: Option<&Self::Id>,
     ) -> impl std::future::Future<Output = Result<BoxStream<'_, Result<Self::Id>>>> + Send;
+
+    /// List the same items as [`list`](Self::list), each with its size,
+    /// modification time and ETag.
+    ///
+    /// Adapters whose listing responses already carry this metadata return
+    /// it directly. The default implementation calls [`stat`](Self::stat)
+    /// for every listed item, i.e. one extra request per item on remote
+    /// backends.
+    fn list_with_meta(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> impl std::future::Future<Output = Result<BoxStream<'_, Result<Entry<Self::Id>>>>> + Send
+    {
+        async move {
+            let ids = self.list(prefix).await?;
+            let entries = ids.filter_map(move |id| async move {
+                let id = match id {
+                    Ok(id) => id,
+                    Err(e) => return Some(Err(e)),
+                };
+                match self.stat(&id).await {
+                    Ok(meta) => Some(Ok(Entry::from_meta(id, meta))),
+                    // Deleted since it was listed
+                    Err(Error::NotFound(_)) => None,
+                    Err(e) => Some(Err(e)),
+                }
+            });
+            Ok(entries.boxed())
+        }
+    }
+
+    /// List one page of at most `page_size` identifiers matching `prefix`,
+    /// starting after `continuation` (or from the beginning when `None`).
+    ///
+    /// Pass [`Page::next`] back in to fetch the following page; it is `None`
+    /// once the listing is exhausted. Tokens survive process restarts, so a
+    /// long listing can be checkpointed and resumed. Items are returned in
+    /// an order that is stable for a given backend but not necessarily the
+    /// same as [`list`](Self::list). A `page_size` of zero is treated as one.
+    ///
+    /// Adapters backed by a paginated API (S3, Azure, Dropbox) or a sorted
+    /// index (Local, Memory) fetch only the requested page. The default
+    /// implementation pages through [`list`](Self::list) by offset, which
+    /// re-lists from the start for every page and is only consistent while
+    /// the listing does not change.
+    fn list_page(
+        &self,
+        prefix: Option<&Self::Id>,
+        page_size: usize,
+        continuation: Option<ContinuationToken>,
+    ) -> impl std::future::Future<Output = Result<Page<Self::Id>>> + Send {
+        async move {
+            let offset = match &continuation {
+                None => 0,
+                Some(token) => token
+                    .as_str()
+                    .parse::<usize>()
+                    .map_err(|_| Error::Generic(format!("invalid continuation token: {token}")))?,
+            };
+            let page_size = page_size.max(1);
+
+            let mut ids = self.list(prefix).await?.skip(offset);
+            let mut page = Vec::new();
+            while page.len() < page_size {
+                match ids.next().await {
+                    Some(id) => page.push(id?),
+                    None => break,
+                }
+            }
+
+            // Only hand out a token if there really is another item.
+            let more = page.len() == page_size && ids.next().await.transpose()?.is_some();
+            let next = more.then(|| ContinuationToken::new((offset + page.len()).to_string()));
+            Ok(Page { ids: page, next })
+        }
+    }
+
+    /// List one level of the hierarchy: the items and subfolders directly
+    /// inside `folder`, or inside the root when `folder` is `None`.
+    ///
+    /// Unlike [`list`](Self::list) this does not recurse, so browsing a
+    /// large tree only fetches the level being shown. A folder that does not
+    /// exist yields an empty listing.
+    ///
+    /// **Path-based backends** treat `folder` as a path, with or without a
+    /// trailing `/`. **ID-based backends** take the folder's ID and return
+    /// item and folder IDs.
+    ///
+    /// The default implementation returns [`Error::Unsupported`]; adapters with
+    /// a native delimiter or directory listing override it.
+    fn list_dir(
+        &self,
+        folder: Option<&Self::Id>,
+    ) -> impl std::future::Future<Output = Result<DirListing<Self::Id>>> + Send {
+        let _ = folder;
+        async move {
+            Err(Error::Unsupported(
+                "hierarchical listing is not supported by this backend".to_string(),
+            ))
+        }
+    }
 }
 
 /// Convenience methods built on [`Storage`].
@@ -235,22 +1144,9 @@ pub trait StorageExt: Storage {
         id: &Self::Id,
     ) -> impl std::future::Future<Output = Result<Vec<u8>>> + Send {
         async move {
+            let mut reader = self.get_reader(id).await?;
             let mut buf: Vec<u8> = Vec::new();
-            let (mut client, mut server) = tokio::io::duplex(64 * 1024);
-
-            let download_fut = async {
-                let result = self.get_into(id, &mut server).await;
-                drop(server);
-                result
-            };
-
-            let read_fut = async {
-                client.read_to_end(&mut buf).await?;
-                Result::<()>::Ok(())
-            };
-
-            let (_written, _) = tokio::try_join!(download_fut, read_fut)?;
-
+            reader.read_to_end(&mut buf).await.map_err(Error::from_io)?;
             Ok(buf)
         }
     }
@@ -279,6 +1175,38 @@ pub trait StorageExt: Storage {
         }
     }
 
+    /// Upload an item by writing to it.
+    ///
+    /// For encoders (tar, zip, CSV, ...) that push into an [`AsyncWrite`]
+    /// rather than hand over an [`AsyncRead`]. Nothing is committed until
+    /// [`PutWriter::finish`] (or `shutdown`) completes; dropping the writer
+    /// before that aborts the upload. The length is not known up front, so
+    /// backends that need it buffer the content.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "memory")]
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use stowage::{MemoryStorage, StorageExt};
+    /// use tokio::io::AsyncWriteExt;
+    ///
+    /// let storage = MemoryStorage::new();
+    ///
+    /// let mut writer = storage.put_writer("rows.csv".to_string());
+    /// writer.write_all(b"id,name\n").await?;
+    /// writer.write_all(b"1,alice\n").await?;
+    /// writer.finish().await?;
+    ///
+    /// assert_eq!(storage.get_string(&"rows.csv".to_string()).await?, "id,name\n1,alice\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn put_writer(&self, id: Self::Id) -> PutWriter<'_> {
+        let (writer, reader) = tokio::io::duplex(streaming::PIPE_BUFFER_SIZE);
+        PutWriter::new(writer, Box::pin(self.put(id, reader, None)))
+    }
+
     /// Copy an item from this storage to another via streaming.
     fn copy_to<S2: Storage<Id = Self::Id>>(
         &self,
@@ -380,6 +1308,33 @@ pub trait StorageExt: Storage {
     {
         adapters::multi::migration::migrate(self, dest, options)
     }
+
+    /// Box this storage as a [`DynStorage`] trait object, so backends of
+    /// different types can be stored together or mixed in the multi
+    /// adapters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "memory")]
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// use std::collections::HashMap;
+    /// use stowage::{DynStorage, MemoryStorage, StorageExt};
+    ///
+    /// let mut tenants: HashMap<&str, Box<dyn DynStorage<Id = String>>> = HashMap::new();
+    /// tenants.insert("acme", MemoryStorage::new().boxed());
+    ///
+    /// let storage = &tenants["acme"];
+    /// storage.put_bytes("hello.txt".to_string(), b"hi").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn boxed(self) -> Box<dyn DynStorage<Id = Self::Id>>
+    where
+        Self: Sized + 'static,
+    {
+        Box::new(self)
+    }
 }
 
 impl<T: Storage + ?Sized> StorageExt for T {}
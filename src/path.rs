@@ -0,0 +1,529 @@
+//! Validated paths shared by the path-based adapters.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+/// A validated, normalized `/`-separated storage path such as
+/// `docs/2024/report.pdf`.
+///
+/// Path-based adapters (Local, S3, Azure, WebDAV, SFTP, FTP) validate every
+/// item identifier with [`StoragePath::parse`] and list only identifiers
+/// that parse, so a path that is valid on one of them is valid on all of
+/// them and names the same item everywhere: `./docs/a.txt` and `docs/a.txt`
+/// are one item.
+///
+/// Parsing drops `.` segments and trailing slashes, and rejects:
+///
+/// - empty paths and empty segments (`a//b`),
+/// - absolute paths (`/etc/passwd`) and `..` segments, with
+///   [`Error::PermissionDenied`],
+/// - control characters, and backslashes that don't start an escape.
+///
+/// Rejections other than the ones above return [`Error::InvalidId`].
+///
+/// # Escapes
+///
+/// Names the grammar can't hold as they are, such as object keys written by
+/// other tools, are escaped with a backslash and two uppercase hex digits
+/// per UTF-8 byte: `\5C` for a backslash, `\2F` for a `/` that doesn't
+/// separate two segments, `\2E` for the dots of a `.` or `..` segment, and
+/// `\0A` and so on for control characters. The key `a//b` is the path
+/// `a\2F/b` and `/x` is `\2Fx`. Every name has exactly one spelling, built
+/// by [`escape`](StoragePath::escape); paths with other escapes are
+/// rejected.
+///
+/// Object stores (S3, Azure) keep each item under its
+/// [`unescaped`](StoragePath::unescaped) name, so every key in a bucket
+/// lists as a path. File systems (Local, WebDAV, SFTP, FTP) keep the path
+/// as it is, escapes included, and list file names outside the grammar as
+/// [`Error::InvalidId`].
+///
+/// ```rust
+/// use stowage::StoragePath;
+///
+/// let path = StoragePath::parse("./docs/2024/report.pdf")?;
+/// assert_eq!(path.as_str(), "docs/2024/report.pdf");
+/// assert_eq!(path.file_name(), "report.pdf");
+/// assert_eq!(path.extension(), Some("pdf"));
+/// assert_eq!(path.parent().unwrap().as_str(), "docs/2024");
+///
+/// assert!(StoragePath::parse("docs/../secrets").is_err());
+///
+/// let key = StoragePath::escape("backup//C:\\data")?;
+/// assert_eq!(key.as_str(), r"backup\2F/C:\5Cdata");
+/// assert_eq!(key.unescaped(), "backup//C:\\data");
+/// # Ok::<(), stowage::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(try_from = "String")
+)]
+pub struct StoragePath(String);
+
+impl StoragePath {
+    /// Validate and normalize `path`.
+    pub fn parse(path: impl AsRef<str>) -> Result<Self> {
+        let raw = path.as_ref();
+        if raw.is_empty() {
+            return Err(Error::InvalidId("path cannot be empty".to_string()));
+        }
+        if raw.starts_with('/') {
+            return Err(Error::PermissionDenied(format!(
+                "absolute paths are not allowed: {raw}"
+            )));
+        }
+
+        let mut segments = Vec::new();
+        for segment in raw.trim_end_matches('/').split('/') {
+            match segment {
+                "." => {}
+                ".." => {
+                    return Err(Error::PermissionDenied(format!(
+                        "parent dir components ('..') are not allowed: {raw}"
+                    )));
+                }
+                "" => {
+                    return Err(Error::InvalidId(format!(
+                        "empty path segments are not allowed: {raw}"
+                    )));
+                }
+                segment => {
+                    if let Some(c) = segment.chars().find(|c| c.is_control()) {
+                        return Err(Error::InvalidId(format!(
+                            "invalid character {c:?} in path: {raw}"
+                        )));
+                    }
+                    segments.push(segment);
+                }
+            }
+        }
+
+        if segments.is_empty() {
+            return Err(Error::InvalidId("path cannot be empty".to_string()));
+        }
+        let path = segments.join("/");
+        if path.contains('\\') {
+            let canonical = unescape(&path).and_then(Self::escape)?;
+            if canonical.0 != path {
+                return Err(Error::InvalidId(format!(
+                    "escapes must be spelled as in {canonical}: {raw}"
+                )));
+            }
+        }
+        Ok(Self(path))
+    }
+
+    /// The path of the backend name `name`, escaping what the grammar can't
+    /// hold as it is (see [Escapes](StoragePath#escapes)). Only the empty
+    /// name has no path.
+    pub fn escape(name: impl AsRef<str>) -> Result<Self> {
+        let name = name.as_ref();
+        if name.is_empty() {
+            return Err(Error::InvalidId("path cannot be empty".to_string()));
+        }
+
+        // A `/` separates two segments unless one of them would be empty.
+        let mut segments = Vec::new();
+        let mut current = String::new();
+        for (i, part) in name.split('/').enumerate() {
+            if i > 0 {
+                if current.is_empty() || part.is_empty() {
+                    current.push('/');
+                } else {
+                    segments.push(std::mem::take(&mut current));
+                }
+            }
+            current.push_str(part);
+        }
+        segments.push(current);
+
+        let mut path = String::with_capacity(name.len());
+        for (i, segment) in segments.iter().enumerate() {
+            if i > 0 {
+                path.push('/');
+            }
+            if segment == "." || segment == ".." {
+                path.push_str(&segment.replace('.', "\\2E"));
+                continue;
+            }
+            for c in segment.chars() {
+                if c == '\\' || c == '/' || c.is_control() {
+                    for byte in c.to_string().bytes() {
+                        path.push_str(&format!("\\{byte:02X}"));
+                    }
+                } else {
+                    path.push(c);
+                }
+            }
+        }
+        Ok(Self(path))
+    }
+
+    /// The name the path stands for, with its escapes decoded. Object
+    /// stores keep the item under this key.
+    pub fn unescaped(&self) -> Cow<'_, str> {
+        match self.0.contains('\\') {
+            // Parsing checked the escapes.
+            true => Cow::Owned(unescape(&self.0).unwrap_or_else(|_| self.0.clone())),
+            false => Cow::Borrowed(&self.0),
+        }
+    }
+
+    /// The path as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Convert into the underlying string.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// The `/`-separated segments of the path.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split('/')
+    }
+
+    /// Append the relative path `child`, which is validated like
+    /// [`parse`](Self::parse).
+    pub fn join(&self, child: impl AsRef<str>) -> Result<Self> {
+        let child = Self::parse(child)?;
+        Ok(Self(format!("{}/{}", self.0, child.0)))
+    }
+
+    /// The path without its last segment, or `None` for a top-level path.
+    pub fn parent(&self) -> Option<Self> {
+        self.0
+            .rsplit_once('/')
+            .map(|(parent, _)| Self(parent.to_string()))
+    }
+
+    /// The last segment of the path.
+    pub fn file_name(&self) -> &str {
+        self.0.rsplit('/').next().unwrap_or(&self.0)
+    }
+
+    /// The extension of [`file_name`](Self::file_name), without the dot.
+    ///
+    /// Dotfiles such as `.env` have no extension.
+    pub fn extension(&self) -> Option<&str> {
+        match self.file_name().rsplit_once('.') {
+            Some(("", _)) | None => None,
+            Some((_, ext)) => Some(ext),
+        }
+    }
+}
+
+/// Identifier type of the path-based adapters.
+///
+/// The adapters take `String` ids by default, validating each one as it
+/// comes in; switch one to [`StoragePath`] ids with its `with_path_ids`
+/// method to have paths validated when they are built instead.
+///
+/// ```rust
+/// # #[cfg(feature = "local")]
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use stowage::adapters::local::LocalStorage;
+/// use stowage::{StorageExt, StoragePath};
+///
+/// let storage = LocalStorage::new("/tmp/stowage").with_path_ids();
+/// let path = StoragePath::parse("docs")?.join("report.txt")?;
+/// storage.put_bytes(path.clone(), b"hello").await?;
+/// assert_eq!(storage.get_string(&path).await?, "hello");
+/// # Ok(())
+/// # }
+/// ```
+pub trait PathId:
+    AsRef<str> + Clone + Ord + std::hash::Hash + fmt::Debug + fmt::Display + Send + Sync + 'static
+{
+    /// The id of `path`.
+    fn from_path(path: StoragePath) -> Self;
+}
+
+impl PathId for String {
+    fn from_path(path: StoragePath) -> Self {
+        path.into_string()
+    }
+}
+
+impl PathId for StoragePath {
+    fn from_path(path: StoragePath) -> Self {
+        path
+    }
+}
+
+/// Decode the `\XX` escapes in `text`.
+fn unescape(text: &str) -> Result<String> {
+    let invalid = || Error::InvalidId(format!("invalid escape in path: {text}"));
+    let mut name = Vec::with_capacity(text.len());
+    let mut bytes = text.bytes().enumerate();
+    while let Some((i, byte)) = bytes.next() {
+        if byte != b'\\' {
+            name.push(byte);
+            continue;
+        }
+        let hex = text.get(i + 1..i + 3).ok_or_else(invalid)?;
+        name.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+        bytes.nth(1);
+    }
+    String::from_utf8(name).map_err(|_| invalid())
+}
+
+/// Key of the item `id` in an object store (S3, Azure): the
+/// [`unescaped`](StoragePath::unescaped) path.
+#[cfg(any(feature = "s3", feature = "azure"))]
+pub(crate) fn object_key(id: &str) -> Result<String> {
+    StoragePath::parse(id).map(|path| path.unescaped().into_owned())
+}
+
+/// Key prefix in an object store for the id prefix `prefix`: its escapes
+/// decoded, leaving out one cut off at the end. Every key under an id
+/// starting with `prefix` starts with it; callers filter out the other keys.
+#[cfg(any(feature = "s3", feature = "azure"))]
+pub(crate) fn object_prefix(prefix: &str) -> String {
+    let mut name = Vec::with_capacity(prefix.len());
+    let mut bytes = prefix.bytes().enumerate();
+    while let Some((i, byte)) = bytes.next() {
+        if byte != b'\\' {
+            name.push(byte);
+            continue;
+        }
+        match prefix
+            .get(i + 1..i + 3)
+            .map(|hex| u8::from_str_radix(hex, 16))
+        {
+            Some(Ok(byte)) => {
+                name.push(byte);
+                bytes.nth(1);
+            }
+            Some(Err(_)) => name.push(byte),
+            None => break,
+        }
+    }
+    match String::from_utf8(name) {
+        Ok(name) => name,
+        Err(e) => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut name = e.into_bytes();
+            name.truncate(valid);
+            String::from_utf8(name).unwrap_or_default()
+        }
+    }
+}
+
+/// Id of the object store key `key`, see [Escapes](StoragePath#escapes).
+#[cfg(any(feature = "s3", feature = "azure"))]
+pub(crate) fn object_id<Id: PathId>(key: &str) -> Result<Id> {
+    StoragePath::escape(key).map(Id::from_path)
+}
+
+/// Path of the item `id` relative to the root of a file system (Local,
+/// WebDAV, SFTP, FTP): the normalized [`StoragePath`], escapes included.
+#[cfg(any(feature = "webdav", feature = "sftp", feature = "ftp"))]
+pub(crate) fn file_key(id: &str) -> Result<String> {
+    StoragePath::parse(id).map(StoragePath::into_string)
+}
+
+/// Id of the file at `path`, relative to the root of a file system.
+/// Names outside the grammar, which no id could address, are rejected.
+#[cfg(any(
+    feature = "local",
+    feature = "webdav",
+    feature = "sftp",
+    feature = "ftp"
+))]
+pub(crate) fn file_id<Id: PathId>(path: &str) -> Result<Id> {
+    match StoragePath::parse(path) {
+        Ok(parsed) if parsed.as_str() == path => Ok(Id::from_path(parsed)),
+        _ => Err(Error::InvalidId(format!(
+            "file name outside the path grammar: {path}"
+        ))),
+    }
+}
+
+impl fmt::Display for StoragePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for StoragePath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for StoragePath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for StoragePath {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<&str> for StoragePath {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl From<StoragePath> for String {
+    fn from(path: StoragePath) -> Self {
+        path.0
+    }
+}
+
+impl PartialEq<str> for StoragePath {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for StoragePath {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_normalizes() {
+        assert_eq!(StoragePath::parse("a/b.txt").unwrap(), "a/b.txt");
+        assert_eq!(StoragePath::parse("./a/./b/").unwrap(), "a/b");
+        assert_eq!(StoragePath::parse("folder/").unwrap(), "folder");
+    }
+
+    #[test]
+    fn test_parse_rejects() {
+        for path in ["", ".", "a//b", "a\\b", "a\nb"] {
+            assert!(
+                matches!(StoragePath::parse(path), Err(Error::InvalidId(_))),
+                "{path:?}"
+            );
+        }
+        for path in ["/", "/etc/passwd", "..", "a/../b", "a/.."] {
+            assert!(
+                matches!(StoragePath::parse(path), Err(Error::PermissionDenied(_))),
+                "{path:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_components() {
+        let path = StoragePath::parse("docs/archive.tar.gz").unwrap();
+        assert_eq!(path.file_name(), "archive.tar.gz");
+        assert_eq!(path.extension(), Some("gz"));
+        assert_eq!(path.parent().unwrap(), "docs");
+        assert_eq!(
+            path.segments().collect::<Vec<_>>(),
+            ["docs", "archive.tar.gz"]
+        );
+
+        let top = StoragePath::parse(".env").unwrap();
+        assert_eq!(top.parent(), None);
+        assert_eq!(top.extension(), None);
+    }
+
+    #[test]
+    fn test_escape() {
+        for (name, path) in [
+            ("a/b", "a/b"),
+            ("a//b", r"a\2F/b"),
+            ("/x", r"\2Fx"),
+            ("docs/", r"docs\2F"),
+            ("/", r"\2F"),
+            ("a\\b", r"a\5Cb"),
+            ("a/../b", r"a/\2E\2E/b"),
+            ("./x", r"\2E/x"),
+            ("a\nb", r"a\0Ab"),
+            ("%20 #?", "%20 #?"),
+        ] {
+            let escaped = StoragePath::escape(name).unwrap();
+            assert_eq!(escaped, path, "{name:?}");
+            assert_eq!(StoragePath::parse(path).unwrap(), escaped);
+            assert_eq!(escaped.unescaped(), name);
+        }
+        assert!(StoragePath::escape("").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_other_escapes() {
+        for path in [
+            r"a\b", r"a\2", r"a\2f/b", r"a\2Fb", r"\61", r"a/\2F", r"\C3",
+        ] {
+            assert!(
+                matches!(StoragePath::parse(path), Err(Error::InvalidId(_))),
+                "{path:?}"
+            );
+        }
+    }
+
+    #[cfg(any(feature = "s3", feature = "azure"))]
+    #[test]
+    fn test_object_key() {
+        assert_eq!(object_key("./a/b.txt/").unwrap(), "a/b.txt");
+        assert_eq!(object_key(r"a\2F/b").unwrap(), "a//b");
+        for rejected in ["", "a//b", "/x", "a\\b", "a/../b"] {
+            assert!(object_key(rejected).is_err(), "{rejected:?}");
+        }
+
+        assert_eq!(object_prefix(r"a/\2F"), "a//");
+        assert_eq!(object_prefix(r"a\2"), "a");
+        assert_eq!(object_prefix(r"a\C3\A"), "a");
+        assert_eq!(object_id::<String>("a//b").unwrap(), r"a\2F/b");
+    }
+
+    #[cfg(any(feature = "webdav", feature = "sftp", feature = "ftp"))]
+    #[test]
+    fn test_file_key() {
+        assert_eq!(file_key("./a/b.txt/").unwrap(), "a/b.txt");
+        assert_eq!(file_key(r"a\5Cb").unwrap(), r"a\5Cb");
+        for rejected in ["", "a//b", "/x", "a\\b"] {
+            assert!(file_key(rejected).is_err(), "{rejected:?}");
+        }
+    }
+
+    #[cfg(any(
+        feature = "local",
+        feature = "webdav",
+        feature = "sftp",
+        feature = "ftp"
+    ))]
+    #[test]
+    fn test_file_id() {
+        assert_eq!(file_id::<String>(r"a/\2E\2E").unwrap(), r"a/\2E\2E");
+        for rejected in ["a\\b", r"a/b\2Ec", "./a", "a/b/"] {
+            assert!(file_id::<String>(rejected).is_err(), "{rejected:?}");
+        }
+    }
+
+    #[test]
+    fn test_join() {
+        let base = StoragePath::parse("docs").unwrap();
+        assert_eq!(
+            base.join("2024/report.pdf").unwrap(),
+            "docs/2024/report.pdf"
+        );
+        assert!(base.join("../etc").is_err());
+        assert!(base.join("/etc").is_err());
+    }
+}
@@ -0,0 +1,77 @@
+//! Time-limited URLs for direct access to stored items.
+
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+use crate::{Result, Storage};
+
+/// The operation a presigned URL grants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PresignMethod {
+    /// Download the item with an HTTP `GET`.
+    Get,
+    /// Upload the item with an HTTP `PUT`, replacing any existing content.
+    Put,
+}
+
+impl PresignMethod {
+    /// The HTTP method to send the request with.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PresignMethod::Get => "GET",
+            PresignMethod::Put => "PUT",
+        }
+    }
+}
+
+/// A request that can be sent without further credentials until it expires,
+/// returned by [`Presign::presign`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresignedRequest {
+    /// The operation the URL grants.
+    pub method: PresignMethod,
+    /// The signed URL. Anyone holding it can perform the operation, so treat
+    /// it like a credential.
+    pub url: String,
+    /// Headers the request must be sent with, e.g. Azure's `x-ms-blob-type`
+    /// for uploads.
+    pub headers: Vec<(String, String)>,
+    /// When the URL stops being accepted.
+    pub expires_at: SystemTime,
+}
+
+/// Storage that can hand out time-limited URLs, so clients such as browsers
+/// can transfer an item directly instead of through the application.
+///
+/// Signing happens locally; nothing is sent to the backend and the item does
+/// not need to exist.
+///
+/// ```no_run
+/// # #[cfg(feature = "s3")]
+/// # async fn example(storage: stowage::adapters::s3::S3Storage) -> stowage::Result<()> {
+/// use std::time::Duration;
+/// use stowage::{Presign, PresignMethod};
+///
+/// let upload = storage
+///     .presign(
+///         &"uploads/avatar.png".to_string(),
+///         PresignMethod::Put,
+///         Duration::from_secs(15 * 60),
+///     )
+///     .await?;
+/// // Hand `upload.url` and `upload.headers` to the client.
+/// # Ok(())
+/// # }
+/// ```
+pub trait Presign: Storage {
+    /// Sign a `method` request for `id`, valid for `expires_in` from now.
+    ///
+    /// Backends limit how long a URL may stay valid and return
+    /// [`Error::Generic`](crate::Error::Generic) for longer durations.
+    fn presign(
+        &self,
+        id: &Self::Id,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> impl Future<Output = Result<PresignedRequest>> + Send;
+}
@@ -0,0 +1,264 @@
+//! Writer- and reader-style transfers built on [`Storage`](crate::Storage).
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Mutex, PoisonError};
+use std::task::{Context, Poll, ready};
+
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream, ReadBuf};
+
+use crate::{Error, Result};
+
+/// Capacity of the pipe between a [`PutWriter`] or [`GetReader`] and the
+/// transfer behind it.
+pub(crate) const PIPE_BUFFER_SIZE: usize = 64 * 1024;
+
+type Upload<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// An upload that is written to, returned by
+/// [`StorageExt::put_writer`](crate::StorageExt::put_writer).
+///
+/// Bytes written are piped into [`Storage::put`](crate::Storage::put), which
+/// runs as part of the writes themselves, so no task is spawned. Call
+/// [`finish`](Self::finish) to commit the upload and get its result;
+/// `shutdown` does the same but reports errors as [`io::Error`], from which
+/// [`Error::from_io`] recovers the storage error. Dropping the writer
+/// before that aborts the upload.
+///
+/// An aborted upload stops where it is, without any clean-up that needs
+/// the backend: an S3 multipart upload that had already started is left
+/// incomplete (a bucket lifecycle rule can expire those), and the local
+/// adapter can leave its temp file, `<name>.<pid>-<n>.tmp.stowage` (with the
+/// writing process's ID and a counter), next to the item.
+pub struct PutWriter<'a> {
+    pipe: DuplexStream,
+    /// Only accessed through `&mut self`; the mutex makes the writer `Sync`
+    /// without requiring the same of the backend's upload future.
+    upload: Mutex<Upload<'a>>,
+    outcome: Option<Result<()>>,
+}
+
+impl<'a> PutWriter<'a> {
+    pub(crate) fn new(pipe: DuplexStream, upload: Upload<'a>) -> Self {
+        Self {
+            pipe,
+            upload: Mutex::new(upload),
+            outcome: None,
+        }
+    }
+
+    /// Close the input and wait for the upload to be committed.
+    pub async fn finish(mut self) -> Result<()> {
+        std::future::poll_fn(|cx| {
+            ready!(Pin::new(&mut self.pipe).poll_shutdown(cx))?;
+            self.poll_upload(cx).map(Ok::<_, io::Error>)
+        })
+        .await?;
+        self.outcome.take().unwrap_or(Ok(()))
+    }
+
+    /// Drive the upload; ready once it has ended.
+    fn poll_upload(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.outcome.is_none() {
+            let upload = self
+                .upload
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner);
+            self.outcome = Some(ready!(upload.as_mut().poll(cx)));
+        }
+        Poll::Ready(())
+    }
+
+    /// Error for a write that arrives after the upload has ended.
+    ///
+    /// A failed upload's error is handed over whole, so [`Error::from_io`]
+    /// can recover it; [`finish`](Self::finish) still reports a copy.
+    fn ended(&mut self) -> io::Error {
+        match &mut self.outcome {
+            Some(Err(e)) => {
+                let copy = e.replicate();
+                io::Error::other(std::mem::replace(e, copy))
+            }
+            _ => io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "upload ended before all data was written",
+            ),
+        }
+    }
+}
+
+impl AsyncWrite for PutWriter<'_> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.poll_upload(cx).is_ready() {
+            return Poll::Ready(Err(this.ended()));
+        }
+        Pin::new(&mut this.pipe).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.poll_upload(cx).is_ready() && matches!(this.outcome, Some(Err(_))) {
+            return Poll::Ready(Err(this.ended()));
+        }
+        Pin::new(&mut this.pipe).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        ready!(Pin::new(&mut this.pipe).poll_shutdown(cx))?;
+        ready!(this.poll_upload(cx));
+        match &this.outcome {
+            Some(Err(_)) => Poll::Ready(Err(this.ended())),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl std::fmt::Debug for PutWriter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PutWriter")
+            .field("finished", &self.outcome.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A download that is read from, returned by
+/// [`Storage::get_reader`](crate::Storage::get_reader).
+///
+/// Errors while reading are [`io::Error`]s; [`Error::from_io`] recovers the
+/// original storage error where there is one.
+pub struct GetReader<'a> {
+    /// Only accessed through `&mut self`; the mutex makes the reader `Sync`,
+    /// so it can be handed straight to [`Storage::put`](crate::Storage::put).
+    inner: Mutex<Pin<Box<dyn AsyncRead + Send + 'a>>>,
+}
+
+impl<'a> GetReader<'a> {
+    /// Wrap a reader, e.g. for a [`Storage`](crate::Storage) implementation
+    /// that overrides [`get_reader`](crate::Storage::get_reader).
+    pub fn new(reader: impl AsyncRead + Send + 'a) -> Self {
+        Self {
+            inner: Mutex::new(Box::pin(reader)),
+        }
+    }
+
+    /// Read what `download` writes into the other end of `pipe`.
+    ///
+    /// Waits until the download has produced data or ended, so a failure to
+    /// open the item is returned here with its original error.
+    pub(crate) async fn download(pipe: DuplexStream, download: Download<'a>) -> Result<Self> {
+        let reader = DownloadReader {
+            pipe,
+            download: Some(download),
+            error: None,
+            head: Vec::new(),
+            pos: 0,
+        };
+        Ok(Self::new(reader.start().await?))
+    }
+}
+
+impl AsyncRead for GetReader<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.inner
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .poll_read(cx, buf)
+    }
+}
+
+impl std::fmt::Debug for GetReader<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GetReader").finish_non_exhaustive()
+    }
+}
+
+type Download<'a> = Pin<Box<dyn Future<Output = Result<u64>> + Send + 'a>>;
+
+/// Reading end of a pipe whose writing end is filled by a download that runs
+/// as part of the reads.
+struct DownloadReader<'a> {
+    pipe: DuplexStream,
+    /// `None` once the download has ended.
+    download: Option<Download<'a>>,
+    error: Option<Error>,
+    /// Data read by [`start`](Self::start), handed out before the pipe.
+    head: Vec<u8>,
+    pos: usize,
+}
+
+impl DownloadReader<'_> {
+    /// Size of the first read, which [`start`](Self::start) buffers.
+    const HEAD_SIZE: usize = 8 * 1024;
+
+    /// Drive the download; ready once it has ended.
+    fn poll_download(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(download) = &mut self.download {
+            let result = ready!(download.as_mut().poll(cx));
+            self.download = None;
+            self.error = result.err();
+        }
+        Poll::Ready(())
+    }
+
+    async fn start(mut self) -> Result<Self> {
+        let mut head = vec![0; Self::HEAD_SIZE];
+        let n = std::future::poll_fn(|cx| {
+            let _ = self.poll_download(cx);
+            let mut buf = ReadBuf::new(&mut head);
+            ready!(Pin::new(&mut self.pipe).poll_read(cx, &mut buf))?;
+            Poll::Ready(Ok::<_, io::Error>(buf.filled().len()))
+        })
+        .await?;
+
+        if n == 0 {
+            std::future::poll_fn(|cx| self.poll_download(cx)).await;
+            if let Some(error) = self.error.take() {
+                return Err(error);
+            }
+        }
+        head.truncate(n);
+        self.head = head;
+        Ok(self)
+    }
+}
+
+impl AsyncRead for DownloadReader<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.pos < this.head.len() {
+            let n = buf.remaining().min(this.head.len() - this.pos);
+            buf.put_slice(&this.head[this.pos..this.pos + n]);
+            this.pos += n;
+            return Poll::Ready(Ok(()));
+        }
+
+        let _ = this.poll_download(cx);
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut this.pipe).poll_read(cx, buf))?;
+        if buf.filled().len() == filled && buf.remaining() > 0 {
+            // The pipe closes when the download drops its end; report how
+            // the download ended.
+            ready!(this.poll_download(cx));
+            if let Some(error) = this.error.take() {
+                return Poll::Ready(Err(io::Error::other(error)));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,213 @@
+//! Minimal timestamp handling for backend metadata.
+//!
+//! Backends report modification times either as RFC 3339 strings (JSON APIs)
+//! or as RFC 1123 HTTP dates (`Last-Modified` and `Retry-After` headers).
+//! Both are converted to [`SystemTime`] without pulling in a full date/time
+//! crate; so are the expiry times of Azure user delegation keys. Azure SAS
+//! tokens need the reverse, formatting a [`SystemTime`] as ISO 8601.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Days since the Unix epoch for a proleptic Gregorian calendar date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian calendar date for a number of days since the Unix
+/// epoch; the inverse of [`days_from_civil`].
+#[cfg(feature = "azure")]
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn to_system_time(secs: i64, nanos: u32) -> Option<SystemTime> {
+    if secs >= 0 {
+        UNIX_EPOCH.checked_add(Duration::new(secs as u64, nanos))
+    } else {
+        UNIX_EPOCH
+            .checked_sub(Duration::from_secs(secs.unsigned_abs()))?
+            .checked_add(Duration::from_nanos(nanos as u64))
+    }
+}
+
+fn timestamp(year: i64, month: u32, day: u32, h: u32, m: u32, s: u32) -> Option<i64> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || h > 23 || m > 59 || s > 60 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86_400 + (h * 3600 + m * 60 + s) as i64)
+}
+
+/// Parse an RFC 3339 timestamp such as `2015-05-12T15:50:38Z` or
+/// `2012-12-12T10:55:30.123-08:00`.
+#[cfg(any(
+    feature = "azure",
+    feature = "gdrive",
+    feature = "onedrive",
+    feature = "dropbox",
+    feature = "box_storage"
+))]
+pub(crate) fn parse_rfc3339(s: &str) -> Option<SystemTime> {
+    let s = s.trim();
+    let (date, rest) = s.split_at_checked(10)?;
+    let rest = rest.strip_prefix(['T', 't', ' '])?;
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+
+    let (clock, zone) = rest.split_at_checked(8)?;
+    let mut clock_parts = clock.splitn(3, ':');
+    let h: u32 = clock_parts.next()?.parse().ok()?;
+    let m: u32 = clock_parts.next()?.parse().ok()?;
+    let sec: u32 = clock_parts.next()?.parse().ok()?;
+
+    // Optional fractional seconds.
+    let (nanos, zone) = match zone.strip_prefix('.') {
+        Some(frac) => {
+            let digits = frac
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(frac.len());
+            let (frac, zone) = frac.split_at(digits);
+            let mut nanos: u32 = 0;
+            for (i, c) in frac.chars().take(9).enumerate() {
+                nanos += c.to_digit(10)? * 10u32.pow(8 - i as u32);
+            }
+            (nanos, zone)
+        }
+        None => (0, zone),
+    };
+
+    let offset_secs: i64 = match zone {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match zone.chars().next()? {
+                '+' => 1,
+                '-' => -1,
+                _ => return None,
+            };
+            let (oh, om) = zone[1..].split_once(':')?;
+            sign * (oh.parse::<i64>().ok()? * 3600 + om.parse::<i64>().ok()? * 60)
+        }
+    };
+
+    let secs = timestamp(year, month, day, h, m, sec)? - offset_secs;
+    to_system_time(secs, nanos)
+}
+
+/// Parse an RFC 1123 HTTP date such as `Sun, 06 Nov 1994 08:49:37 GMT`.
+#[cfg(any(
+    feature = "azure",
+    feature = "webdav",
+    feature = "gdrive",
+    feature = "onedrive",
+    feature = "box_storage"
+))]
+pub(crate) fn parse_http_date(s: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let (_weekday, rest) = s.trim().split_once(", ")?;
+    let mut parts = rest.split_whitespace();
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month_name = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month_name)? as u32 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+
+    let mut clock = parts.next()?.splitn(3, ':');
+    let h: u32 = clock.next()?.parse().ok()?;
+    let m: u32 = clock.next()?.parse().ok()?;
+    let sec: u32 = clock.next()?.parse().ok()?;
+
+    if parts.next()? != "GMT" {
+        return None;
+    }
+
+    to_system_time(timestamp(year, month, day, h, m, sec)?, 0)
+}
+
+/// Format a time as a UTC ISO 8601 timestamp with whole seconds, such as
+/// `2015-05-12T15:50:38Z`. Times before the Unix epoch are clamped to it.
+#[cfg(feature = "azure")]
+pub(crate) fn format_iso8601(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let clock = secs.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        clock / 3600,
+        clock / 60 % 60,
+        clock % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(
+        feature = "azure",
+        feature = "gdrive",
+        feature = "onedrive",
+        feature = "dropbox",
+        feature = "box_storage"
+    ))]
+    #[test]
+    fn test_parse_rfc3339() {
+        let t = parse_rfc3339("2015-05-12T15:50:38Z").unwrap();
+        assert_eq!(t, UNIX_EPOCH + Duration::from_secs(1_431_445_838));
+
+        let t = parse_rfc3339("2015-05-12T15:50:38.250Z").unwrap();
+        assert_eq!(t, UNIX_EPOCH + Duration::from_millis(1_431_445_838_250));
+
+        let t = parse_rfc3339("2015-05-12T08:50:38-07:00").unwrap();
+        assert_eq!(t, UNIX_EPOCH + Duration::from_secs(1_431_445_838));
+
+        assert!(parse_rfc3339("not a date").is_none());
+    }
+
+    #[cfg(any(
+        feature = "azure",
+        feature = "webdav",
+        feature = "gdrive",
+        feature = "onedrive",
+        feature = "box_storage"
+    ))]
+    #[test]
+    fn test_parse_http_date() {
+        let t = parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(t, UNIX_EPOCH + Duration::from_secs(784_111_777));
+
+        assert!(parse_http_date("Sun, 06 Nov 1994 08:49:37 PST").is_none());
+    }
+
+    #[cfg(feature = "azure")]
+    #[test]
+    fn test_format_iso8601() {
+        let t = UNIX_EPOCH + Duration::from_millis(1_431_445_838_250);
+        assert_eq!(format_iso8601(t), "2015-05-12T15:50:38Z");
+        assert_eq!(format_iso8601(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+
+        let t = UNIX_EPOCH + Duration::from_secs(951_782_400);
+        assert_eq!(format_iso8601(t), "2000-02-29T00:00:00Z");
+    }
+}
@@ -44,10 +44,13 @@ fn memory_storage_implements_storage() {
 #[test]
 fn local_storage_implements_storage() {
     use std::path::PathBuf;
+    use stowage::StoragePath;
     use stowage::adapters::local::LocalStorage;
 
-    assert!(LocalStorage::check_id_bounds());
-    assert!(LocalStorage::check_storage_bounds());
+    assert!(<LocalStorage>::check_id_bounds());
+    assert!(LocalStorage::<StoragePath>::check_id_bounds());
+    assert!(<LocalStorage>::check_storage_bounds());
+    assert!(LocalStorage::<StoragePath>::check_storage_bounds());
 
     let _storage = LocalStorage::new(PathBuf::from("/tmp"));
     // Storage trait is not dyn-compatible due to impl Trait in return types
@@ -57,20 +60,26 @@ fn local_storage_implements_storage() {
 #[cfg(feature = "s3")]
 #[test]
 fn s3_storage_implements_storage() {
+    use stowage::StoragePath;
     use stowage::adapters::s3::S3Storage;
 
-    assert!(S3Storage::check_id_bounds());
-    assert!(S3Storage::check_storage_bounds());
+    assert!(<S3Storage>::check_id_bounds());
+    assert!(S3Storage::<StoragePath>::check_id_bounds());
+    assert!(<S3Storage>::check_storage_bounds());
+    assert!(S3Storage::<StoragePath>::check_storage_bounds());
 }
 
 /// Test that AzureStorage implements Storage correctly
 #[cfg(feature = "azure")]
 #[test]
 fn azure_storage_implements_storage() {
+    use stowage::StoragePath;
     use stowage::adapters::azure::AzureStorage;
 
-    assert!(AzureStorage::check_id_bounds());
-    assert!(AzureStorage::check_storage_bounds());
+    assert!(<AzureStorage>::check_id_bounds());
+    assert!(AzureStorage::<StoragePath>::check_id_bounds());
+    assert!(<AzureStorage>::check_storage_bounds());
+    assert!(AzureStorage::<StoragePath>::check_storage_bounds());
 
     let _storage = AzureStorage::new("account", "container", "sas_token");
     // Storage trait is not dyn-compatible due to impl Trait in return types
@@ -123,10 +132,13 @@ fn dropbox_storage_implements_storage() {
 #[cfg(feature = "webdav")]
 #[test]
 fn webdav_storage_implements_storage() {
+    use stowage::StoragePath;
     use stowage::adapters::webdav::WebDAVStorage;
 
-    assert!(WebDAVStorage::check_id_bounds());
-    assert!(WebDAVStorage::check_storage_bounds());
+    assert!(<WebDAVStorage>::check_id_bounds());
+    assert!(WebDAVStorage::<StoragePath>::check_id_bounds());
+    assert!(<WebDAVStorage>::check_storage_bounds());
+    assert!(WebDAVStorage::<StoragePath>::check_storage_bounds());
 
     let _storage = WebDAVStorage::new("https://example.com/dav", "username", "password");
     // Storage trait is not dyn-compatible due to impl Trait in return types
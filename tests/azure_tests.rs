@@ -0,0 +1,88 @@
+//! Tests for AzureStorage against a mock Blob service
+
+#![cfg(feature = "azure")]
+
+#[path = "test_common/mod.rs"]
+mod test_common;
+
+use std::time::{Duration, SystemTime};
+use stowage::adapters::azure::{AzureStorage, UserDelegationKey};
+use stowage::{Error, Presign, PresignMethod, Storage};
+use test_common::{MockRequest, MockResponse, MockServer};
+
+/// Get User Delegation Key for the bearer token `token`; blob requests
+/// succeed.
+fn delegation_service(req: &MockRequest) -> MockResponse {
+    if req.target == "/?restype=service&comp=userdelegationkey" {
+        if req.header("authorization") != Some("Bearer token") {
+            return MockResponse::new(403);
+        }
+        let mut response = MockResponse::new(200);
+        response.body = "<?xml version=\"1.0\" encoding=\"utf-8\"?><UserDelegationKey>\
+             <SignedOid>oid</SignedOid><SignedTid>tid</SignedTid>\
+             <SignedStart>2026-01-01T00:00:00Z</SignedStart>\
+             <SignedExpiry>2099-01-01T00:00:00Z</SignedExpiry>\
+             <SignedService>b</SignedService><SignedVersion>2022-11-02</SignedVersion>\
+             <Value>ZGVsZWdhdGlvbiBrZXk=</Value></UserDelegationKey>"
+            .into();
+        return response;
+    }
+    MockResponse::new(200)
+}
+
+#[tokio::test]
+async fn test_user_delegation_key_signs_requests_and_presigned_urls() {
+    let server = MockServer::start(delegation_service).await;
+    let expiry = SystemTime::now() + Duration::from_secs(3600);
+    let key = UserDelegationKey::fetch(server.url(), "token", expiry)
+        .await
+        .unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.header("x-ms-version"), Some("2022-11-02"));
+    let body = String::from_utf8(request.body.clone()).unwrap();
+    assert!(body.contains("<KeyInfo><Start>"));
+
+    let storage = AzureStorage::with_user_delegation_key_and_endpoint(
+        "account",
+        "container",
+        key,
+        server.url(),
+    );
+    assert!(storage.exists(&"a b.txt".to_string()).await.unwrap());
+    let request = &server.requests()[1];
+    let (path, query) = request.target.split_once('?').unwrap();
+    assert_eq!(path, "/container/a%20b.txt");
+    assert!(query.contains("&sr=c&sp=racwdl&skoid=oid&sktid=tid&"));
+
+    let presigned = storage
+        .presign(
+            &"a b.txt".to_string(),
+            PresignMethod::Get,
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+    assert!(
+        presigned
+            .url
+            .starts_with(&format!("{}/container/a%20b.txt?", server.url()))
+    );
+    assert!(presigned.url.contains("&sr=b&sp=r&skoid=oid&"));
+}
+
+#[tokio::test]
+async fn test_user_delegation_key_needs_authorization() {
+    let server = MockServer::start(delegation_service).await;
+    let result = UserDelegationKey::fetch(
+        server.url(),
+        "wrong",
+        SystemTime::now() + Duration::from_secs(3600),
+    )
+    .await;
+    assert!(
+        matches!(result, Err(Error::PermissionDenied(_))),
+        "{result:?}"
+    );
+}
@@ -0,0 +1,344 @@
+//! Checks that uploads stream with bounded memory.
+//!
+//! The counting allocator sees the whole process, so these checks have a
+//! test binary of their own and run one after another from a single test:
+//! nothing else allocates during a measurement or resets the peak in the
+//! middle of one. The S3 check needs MinIO (see `s3_tests.rs`) and is
+//! ignored; `cargo test --features s3 --test bounded_memory_tests --
+//! --ignored` runs it alone.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use stowage::{Error, PutOptions, Storage};
+
+#[path = "test_common/mod.rs"]
+mod test_common;
+use test_common::PatternReader;
+#[cfg(feature = "onedrive")]
+use test_common::{MockRequest, MockResponse, MockServer};
+
+const SIZE: u64 = 64 * 1024 * 1024;
+const LIMIT: usize = 32 * 1024 * 1024;
+
+/// Allocator that records the peak number of live heap bytes, so tests can
+/// check that uploads don't buffer their whole input.
+struct PeakAlloc;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let now = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(now, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAlloc = PeakAlloc;
+
+/// Reset the peak to the current number of live bytes and return it.
+fn reset_peak() -> usize {
+    let now = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(now, Ordering::Relaxed);
+    now
+}
+
+/// Peak number of live heap bytes since the last [`reset_peak`].
+fn peak() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
+
+/// Storage that reads uploads to the end and keeps only their sizes, so the
+/// memory used by an upload is whatever the caller's pipeline holds.
+#[derive(Debug, Default)]
+struct SinkStorage {
+    sizes: std::sync::Mutex<std::collections::HashMap<String, u64>>,
+}
+
+impl Storage for SinkStorage {
+    type Id = String;
+
+    async fn exists(&self, id: &Self::Id) -> stowage::Result<bool> {
+        Ok(self.sizes.lock().unwrap().contains_key(id))
+    }
+
+    async fn folder_exists(&self, _id: &Self::Id) -> stowage::Result<bool> {
+        Ok(false)
+    }
+
+    async fn stat(&self, id: &Self::Id) -> stowage::Result<stowage::ObjectMeta> {
+        let size = self.sizes.lock().unwrap().get(id).copied();
+        let size = size.ok_or_else(|| Error::NotFound(id.clone()))?;
+        Ok(stowage::ObjectMeta {
+            size,
+            ..Default::default()
+        })
+    }
+
+    async fn put<R: tokio::io::AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        _len: Option<u64>,
+    ) -> stowage::Result<()> {
+        let mut input = input;
+        let size = tokio::io::copy(&mut input, &mut tokio::io::sink()).await?;
+        self.sizes.lock().unwrap().insert(id, size);
+        Ok(())
+    }
+
+    async fn put_with<R: tokio::io::AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> stowage::Result<()> {
+        match options.if_none_match.as_deref() {
+            _ if options.if_match.is_some() => Err(Error::Unsupported("if_match".to_string())),
+            Some("*") if self.exists(&id).await? => Err(Error::PreconditionFailed(id)),
+            Some("*") | None => self.put(id, input, len).await,
+            Some(_) => Err(Error::Unsupported("if_none_match".to_string())),
+        }
+    }
+
+    /// Streams back generated content of the recorded size.
+    async fn get_into<W: tokio::io::AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        mut output: W,
+    ) -> stowage::Result<u64> {
+        let size = self.stat(id).await?.size;
+        let mut input = PatternReader::new(size);
+        Ok(tokio::io::copy(&mut input, &mut output).await?)
+    }
+
+    async fn delete(&self, id: &Self::Id) -> stowage::Result<()> {
+        self.sizes.lock().unwrap().remove(id);
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        _prefix: Option<&Self::Id>,
+    ) -> stowage::Result<futures::stream::BoxStream<'_, stowage::Result<Self::Id>>> {
+        let ids: Vec<_> = self.sizes.lock().unwrap().keys().cloned().collect();
+        Ok(Box::pin(futures::stream::iter(ids.into_iter().map(Ok))))
+    }
+}
+
+/// Uploads a large generated input and checks that memory use stays well
+/// below its size.
+async fn check_put<S>(storage: S)
+where
+    S: Storage,
+    S::Id: From<String>,
+{
+    let id = S::Id::from("streamed.bin".to_string());
+
+    let baseline = reset_peak();
+    storage
+        .put(id.clone(), PatternReader::new(SIZE), Some(SIZE))
+        .await
+        .unwrap();
+    let growth = peak().saturating_sub(baseline);
+    assert!(
+        growth < LIMIT,
+        "uploading {SIZE} bytes to {storage:?} grew the heap by {growth} bytes"
+    );
+
+    assert_eq!(storage.stat(&id).await.unwrap().size, SIZE);
+    storage.delete(&id).await.unwrap();
+}
+
+/// Like [`check_put`], for a create-only conditional write.
+async fn check_conditional_put<S>(storage: S)
+where
+    S: Storage,
+    S::Id: From<String>,
+{
+    let id = S::Id::from("streamed.bin".to_string());
+
+    let baseline = reset_peak();
+    storage
+        .put_with(
+            id.clone(),
+            PatternReader::new(SIZE),
+            Some(SIZE),
+            PutOptions::new().if_none_match("*"),
+        )
+        .await
+        .unwrap();
+    let growth = peak().saturating_sub(baseline);
+    assert!(
+        growth < LIMIT,
+        "conditionally uploading {SIZE} bytes to {storage:?} grew the heap by {growth} bytes"
+    );
+
+    assert_eq!(storage.stat(&id).await.unwrap().size, SIZE);
+    storage.delete(&id).await.unwrap();
+}
+
+/// The plain sink, and the wrappers that fan an upload out to several
+/// backends.
+async fn check_multi() {
+    use stowage::multi::{FallbackStorage, MirrorStorage, ReturnPolicy, WriteStrategy};
+
+    check_put(SinkStorage::default()).await;
+    for policy in [
+        ReturnPolicy::WaitAll,
+        ReturnPolicy::Optimistic,
+        ReturnPolicy::FastFail,
+    ] {
+        check_put(
+            MirrorStorage::builder()
+                .add_backend(SinkStorage::default())
+                .add_backend(SinkStorage::default())
+                .write_strategy(WriteStrategy::AllOrFail { rollback: true })
+                .return_policy(policy)
+                .build(),
+        )
+        .await;
+    }
+    check_conditional_put(
+        MirrorStorage::builder()
+            .add_backend(SinkStorage::default())
+            .add_backend(SinkStorage::default())
+            .add_backend(SinkStorage::default())
+            .write_strategy(WriteStrategy::AllOrFail { rollback: true })
+            .build(),
+    )
+    .await;
+    check_put(
+        FallbackStorage::new(SinkStorage::default(), SinkStorage::default())
+            .with_write_through(true),
+    )
+    .await;
+}
+
+#[cfg(feature = "local")]
+async fn check_local() {
+    use stowage::adapters::local::LocalStorage;
+
+    let dir = tempfile::tempdir().unwrap();
+    check_put(LocalStorage::new(dir.path())).await;
+    check_conditional_put(LocalStorage::new(dir.path())).await;
+}
+
+/// Upload sessions: hand out an upload URL, then acknowledge each chunk
+/// until the one ending at the total size.
+#[cfg(feature = "onedrive")]
+fn upload_session(req: &MockRequest) -> MockResponse {
+    if req.target.ends_with("/createUploadSession") {
+        return MockResponse::json(
+            200,
+            serde_json::json!({ "uploadUrl": format!("{}/upload", req.base_url()) }),
+        );
+    }
+    if req.method == "PUT" && req.target == "/upload" {
+        let range = req.header("content-range").unwrap_or_default();
+        let (span, total) = range.trim_start_matches("bytes ").split_once('/').unwrap();
+        let end: u64 = span.split_once('-').unwrap().1.parse().unwrap();
+        if end + 1 == total.parse::<u64>().unwrap() {
+            return MockResponse::json(201, serde_json::json!({ "id": "new-id", "name": "f" }));
+        }
+        return MockResponse::new(202);
+    }
+    MockResponse::new(404)
+}
+
+/// Uploads of unknown length go to OneDrive in chunks spooled to disk.
+#[cfg(feature = "onedrive")]
+async fn check_onedrive() {
+    use reqwest::{Client, Url};
+    use stowage::adapters::onedrive::{OneDriveStorage, TokenProvider};
+
+    let server = MockServer::start(upload_session).await;
+    let storage = OneDriveStorage::new(Client::new(), TokenProvider::Static("token".into()))
+        .unwrap()
+        .with_base_url(Url::parse(&format!("{}/v1.0/", server.url())).unwrap());
+
+    let baseline = reset_peak();
+    storage
+        .put("item-id".to_string(), PatternReader::new(SIZE), None)
+        .await
+        .unwrap();
+    let growth = peak().saturating_sub(baseline);
+    assert!(
+        growth < LIMIT,
+        "uploading {SIZE} bytes of unknown length grew the heap by {growth} bytes"
+    );
+
+    let chunks: Vec<_> = server
+        .requests()
+        .into_iter()
+        .filter(|req| req.target == "/upload")
+        .collect();
+    assert!(chunks.len() > 1);
+    assert_eq!(chunks.iter().map(|req| req.body_len).sum::<u64>(), SIZE);
+    assert!(chunks.iter().all(|req| {
+        req.header("content-range")
+            .unwrap()
+            .ends_with(&format!("/{SIZE}"))
+    }));
+}
+
+/// Uploads to the MinIO instance the S3 integration tests use.
+#[cfg(feature = "s3")]
+async fn check_s3() {
+    use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+    use stowage::adapters::s3::S3Storage;
+
+    let config = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new("us-east-1"))
+        .endpoint_url("http://localhost:9000")
+        .credentials_provider(Credentials::new(
+            "minioadmin",
+            "minioadmin",
+            None,
+            None,
+            "test",
+        ))
+        .force_path_style(true)
+        .build();
+    let client = aws_sdk_s3::Client::from_conf(config);
+    let bucket = format!("stowage-bounded-memory-{}", std::process::id());
+    client
+        .create_bucket()
+        .bucket(&bucket)
+        .send()
+        .await
+        .expect("Failed to create test bucket. Is MinIO running on localhost:9000?");
+
+    check_put(S3Storage::new(client.clone(), &bucket)).await;
+    client.delete_bucket().bucket(&bucket).send().await.unwrap();
+}
+
+/// The only test that runs by default, so the checks run in sequence.
+#[tokio::test]
+async fn test_uploads_stream_with_bounded_memory() {
+    check_multi().await;
+    #[cfg(feature = "local")]
+    check_local().await;
+    #[cfg(feature = "onedrive")]
+    check_onedrive().await;
+}
+
+#[cfg(feature = "s3")]
+#[tokio::test]
+#[ignore]
+async fn test_s3_upload_streams_with_bounded_memory() {
+    check_s3().await;
+}
@@ -0,0 +1,79 @@
+//! Tests for BoxStorage against a mock Box API
+
+#![cfg(feature = "box_storage")]
+
+#[path = "test_common/mod.rs"]
+mod test_common;
+
+use stowage::Error;
+use stowage::adapters::box_storage::BoxStorage;
+use test_common::{MockRequest, MockResponse, MockServer};
+
+fn storage(server: &MockServer) -> BoxStorage {
+    BoxStorage::new("token").with_base_urls(
+        format!("{}/2.0", server.url()),
+        format!("{}/api/2.0", server.url()),
+    )
+}
+
+/// Uploads: create a file unless the name is `taken.txt`, which the folder
+/// already holds.
+fn upload(req: &MockRequest) -> MockResponse {
+    if req.method != "POST" || req.target != "/api/2.0/files/content" {
+        return MockResponse::new(404);
+    }
+    if String::from_utf8_lossy(&req.body).contains("\"name\":\"taken.txt\"") {
+        return MockResponse::json(
+            409,
+            serde_json::json!({
+                "type": "error",
+                "status": 409,
+                "code": "item_name_in_use",
+                "message": "Item with the same name already exists",
+            }),
+        );
+    }
+    MockResponse::json(
+        201,
+        serde_json::json!({
+            "total_count": 1,
+            "entries": [{ "type": "file", "id": "12345", "name": "notes.txt" }],
+        }),
+    )
+}
+
+#[tokio::test]
+async fn test_create_uploads_attributes_and_content() {
+    let server = MockServer::start(upload).await;
+    let storage = storage(&server);
+
+    let id = storage
+        .create("678", "notes.txt", &b"hello"[..], Some(5))
+        .await
+        .unwrap();
+    assert_eq!(id, "12345");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].header("authorization"), Some("Bearer token"));
+    assert!(
+        requests[0]
+            .header("content-type")
+            .unwrap()
+            .starts_with("multipart/form-data; boundary=")
+    );
+    let body = String::from_utf8_lossy(&requests[0].body);
+    assert!(body.contains(r#"{"name":"notes.txt","parent":{"id":"678"}}"#));
+    assert!(body.contains("\r\n\r\nhello\r\n"));
+}
+
+#[tokio::test]
+async fn test_create_reports_existing_names() {
+    let server = MockServer::start(upload).await;
+    let storage = storage(&server);
+
+    let result = storage
+        .create("678", "taken.txt", &b"hello"[..], None)
+        .await;
+    assert!(matches!(result, Err(Error::AlreadyExists(_))), "{result:?}");
+}
@@ -0,0 +1,300 @@
+//! Tests for CachedStorage
+
+#![cfg(feature = "memory")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use futures::stream::BoxStream;
+use stowage::multi::CachedStorage;
+use stowage::{Error, MemoryStorage, ObjectMeta, Result, Storage, StorageExt};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+/// Counts downloads and existence checks, optionally taking `delay` for
+/// each download.
+#[derive(Debug, Default)]
+struct Origin {
+    inner: MemoryStorage,
+    downloads: AtomicUsize,
+    lookups: AtomicUsize,
+    delay: Option<Duration>,
+}
+
+impl Origin {
+    fn downloads(&self) -> usize {
+        self.downloads.load(Ordering::SeqCst)
+    }
+
+    fn lookups(&self) -> usize {
+        self.lookups.load(Ordering::SeqCst)
+    }
+}
+
+impl Storage for Origin {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        self.lookups.fetch_add(1, Ordering::SeqCst);
+        self.inner.exists(id).await
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        self.inner.folder_exists(id).await
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        self.inner.stat(id).await
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        self.inner.put(id, input, len).await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        mut output: W,
+    ) -> Result<u64> {
+        self.downloads.fetch_add(1, Ordering::SeqCst);
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        let data = self.inner.get_bytes(id)?;
+        output.write_all(&data).await?;
+        Ok(data.len() as u64)
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        self.inner.delete(id).await
+    }
+
+    async fn list(&self, prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        self.inner.list(prefix).await
+    }
+}
+
+fn cached(origin: Origin) -> CachedStorage<Origin, MemoryStorage> {
+    CachedStorage::new(origin, MemoryStorage::new())
+}
+
+#[tokio::test]
+async fn test_cached_read_through() {
+    let storage = cached(Origin::default());
+    storage
+        .put_bytes("file.txt".to_string(), b"data")
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        assert_eq!(
+            storage.get_string(&"file.txt".to_string()).await.unwrap(),
+            "data"
+        );
+    }
+    assert_eq!(storage.origin().downloads(), 1);
+    assert!(
+        storage
+            .cache()
+            .exists(&"file.txt".to_string())
+            .await
+            .unwrap()
+    );
+    assert_eq!(storage.cached_bytes(), 4);
+
+    // Cached items do not need the origin for exists either
+    assert!(storage.exists(&"file.txt".to_string()).await.unwrap());
+    assert_eq!(storage.origin().lookups(), 0);
+
+    let mut output = Vec::new();
+    storage
+        .get_range(&"file.txt".to_string(), 1..3, &mut output)
+        .await
+        .unwrap();
+    assert_eq!(output, b"at");
+    assert_eq!(storage.origin().downloads(), 1);
+}
+
+#[tokio::test]
+async fn test_cached_writes_invalidate() {
+    let storage = cached(Origin::default());
+    let id = "file.txt".to_string();
+    storage.put_bytes(id.clone(), b"old").await.unwrap();
+    assert_eq!(storage.get_string(&id).await.unwrap(), "old");
+
+    storage.put_bytes(id.clone(), b"new").await.unwrap();
+    assert!(!storage.cache().exists(&id).await.unwrap());
+    assert_eq!(storage.get_string(&id).await.unwrap(), "new");
+
+    storage.delete(&id).await.unwrap();
+    assert!(!storage.exists(&id).await.unwrap());
+    assert!(matches!(
+        storage.get_bytes(&id).await,
+        Err(Error::NotFound(_))
+    ));
+    assert_eq!(storage.cached_bytes(), 0);
+
+    // Renames drop both names
+    storage.put_bytes(id.clone(), b"moved").await.unwrap();
+    assert_eq!(storage.get_string(&id).await.unwrap(), "moved");
+    storage.rename(&id, "dst.txt".to_string()).await.unwrap();
+    assert!(!storage.cache().exists(&id).await.unwrap());
+    assert_eq!(
+        storage.get_string(&"dst.txt".to_string()).await.unwrap(),
+        "moved"
+    );
+}
+
+#[tokio::test]
+async fn test_cached_evicts_least_recently_used() {
+    let storage = cached(Origin::default()).with_capacity(10);
+    for id in ["a", "b", "c"] {
+        storage.put_bytes(id.to_string(), b"1234").await.unwrap();
+    }
+
+    storage.get_bytes(&"a".to_string()).await.unwrap();
+    storage.get_bytes(&"b".to_string()).await.unwrap();
+    storage.get_bytes(&"a".to_string()).await.unwrap();
+    // Caching "c" goes over capacity, and "b" was read least recently
+    storage.get_bytes(&"c".to_string()).await.unwrap();
+
+    assert_eq!(storage.cached_bytes(), 8);
+    assert!(storage.cache().exists(&"a".to_string()).await.unwrap());
+    assert!(!storage.cache().exists(&"b".to_string()).await.unwrap());
+    assert!(storage.cache().exists(&"c".to_string()).await.unwrap());
+    assert_eq!(storage.origin().downloads(), 3);
+}
+
+#[tokio::test]
+async fn test_cached_skips_oversized_items() {
+    let storage = cached(Origin::default()).with_capacity(4);
+    let id = "big.bin".to_string();
+    storage.put_bytes(id.clone(), &[7u8; 64]).await.unwrap();
+
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), vec![7u8; 64]);
+    assert!(!storage.cache().exists(&id).await.unwrap());
+    assert_eq!(storage.cached_bytes(), 0);
+    // One download stopped at the capacity, one served the read
+    assert_eq!(storage.origin().downloads(), 2);
+
+    // Later reads go straight to the origin
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), vec![7u8; 64]);
+    assert_eq!(storage.origin().downloads(), 3);
+    assert!(!storage.cache().exists(&id).await.unwrap());
+
+    // Until the item changes
+    storage.put_bytes(id.clone(), b"tiny").await.unwrap();
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), b"tiny");
+    assert!(storage.cache().exists(&id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_cached_ttl() {
+    let storage = cached(Origin::default()).with_ttl(Duration::from_millis(20));
+    let id = "file.txt".to_string();
+    storage.put_bytes(id.clone(), b"data").await.unwrap();
+
+    storage.get_bytes(&id).await.unwrap();
+    storage.get_bytes(&id).await.unwrap();
+    assert_eq!(storage.origin().downloads(), 1);
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    storage.get_bytes(&id).await.unwrap();
+    assert_eq!(storage.origin().downloads(), 2);
+
+    // An expired copy is deleted even if fetching it again fails
+    storage.origin().inner.delete(&id).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert!(matches!(
+        storage.get_bytes(&id).await,
+        Err(Error::NotFound(_))
+    ));
+    assert!(!storage.cache().exists(&id).await.unwrap());
+    assert_eq!(storage.cached_bytes(), 0);
+}
+
+#[tokio::test]
+async fn test_cached_negative_lookups() {
+    let storage = cached(Origin::default()).with_negative_ttl(Duration::from_secs(60));
+    let id = "missing.txt".to_string();
+
+    assert!(!storage.exists(&id).await.unwrap());
+    assert!(!storage.exists(&id).await.unwrap());
+    assert_eq!(storage.origin().lookups(), 1);
+
+    // Downloads trust the negative entry too
+    assert!(matches!(
+        storage.get_bytes(&id).await,
+        Err(Error::NotFound(_))
+    ));
+    assert_eq!(storage.origin().downloads(), 0);
+
+    // Writing the item clears it
+    storage.put_bytes(id.clone(), b"data").await.unwrap();
+    assert!(storage.exists(&id).await.unwrap());
+    assert_eq!(storage.get_string(&id).await.unwrap(), "data");
+}
+
+#[tokio::test]
+async fn test_cached_without_negative_ttl_asks_origin() {
+    let storage = cached(Origin::default());
+    let id = "missing.txt".to_string();
+
+    assert!(!storage.exists(&id).await.unwrap());
+    assert!(!storage.exists(&id).await.unwrap());
+    assert_eq!(storage.origin().lookups(), 2);
+}
+
+#[tokio::test]
+async fn test_cached_deduplicates_concurrent_misses() {
+    let storage = cached(Origin {
+        delay: Some(Duration::from_millis(50)),
+        ..Default::default()
+    });
+    let id = "file.txt".to_string();
+    storage.put_bytes(id.clone(), b"shared").await.unwrap();
+
+    let reads = (0..8).map(|_| storage.get_bytes(&id));
+    for result in futures::future::join_all(reads).await {
+        assert_eq!(result.unwrap(), b"shared");
+    }
+    assert_eq!(storage.origin().downloads(), 1);
+}
+
+#[cfg(feature = "local")]
+#[tokio::test]
+async fn test_cached_on_local_disk() {
+    use stowage::adapters::local::LocalStorage;
+
+    let dir = tempfile::tempdir().unwrap();
+    let storage =
+        CachedStorage::new(Origin::default(), LocalStorage::new(dir.path())).with_capacity(16);
+    storage
+        .put_bytes("small.txt".to_string(), b"fits")
+        .await
+        .unwrap();
+    storage
+        .put_bytes("big.bin".to_string(), &[1u8; 1024])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        storage.get_string(&"small.txt".to_string()).await.unwrap(),
+        "fits"
+    );
+    assert_eq!(
+        storage.get_bytes(&"big.bin".to_string()).await.unwrap(),
+        vec![1u8; 1024]
+    );
+
+    // Only the item that fits was written to disk
+    let files: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(files, vec!["small.txt"]);
+}
@@ -0,0 +1,210 @@
+//! Tests for declarative storage configuration
+
+#![cfg(feature = "serde")]
+
+use std::time::Duration;
+
+use stowage::config::MirrorConfig;
+use stowage::multi::{ReturnPolicy, WriteStrategy};
+use stowage::{Error, SecretSource, Storage, StorageConfig, StorageExt};
+
+#[test]
+fn test_config_parses_mirror_toml() {
+    let config: MirrorConfig = toml::from_str(
+        r#"
+        write_strategy = { type = "quorum", rollback = true }
+        return_policy = "fast_fail"
+        backend_timeout_ms = 1500
+        primary_index = 1
+
+        [[backends]]
+        type = "memory"
+
+        [[backends]]
+        type = "url"
+        url = "memory://"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.backends.len(), 2);
+    assert_eq!(
+        config.write_strategy,
+        Some(WriteStrategy::Quorum { rollback: true })
+    );
+    assert_eq!(config.return_policy, Some(ReturnPolicy::FastFail));
+    assert_eq!(
+        config.backend_timeout_ms.map(Duration::from_millis),
+        Some(Duration::from_millis(1500))
+    );
+    assert_eq!(config.primary_index, Some(1));
+}
+
+#[test]
+fn test_config_rejects_unknown_fields() {
+    let result: Result<StorageConfig, _> = serde_json::from_str(
+        r#"{ "type": "fallback", "primary": { "type": "memory" },
+             "secondary": { "type": "memory" }, "write_thru": true }"#,
+    );
+    assert!(result.is_err());
+
+    let result: Result<StorageConfig, _> = serde_json::from_str(r#"{ "type": "nosuch" }"#);
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_config_builds_layered_topology() {
+    let config: StorageConfig = toml::from_str(
+        r#"
+        type = "fallback"
+        write_through = true
+
+        [primary]
+        type = "mirror"
+        write_strategy = { type = "at_least_one", rollback = false }
+        backends = [{ type = "memory" }, { type = "memory" }]
+
+        [secondary]
+        type = "prefixed"
+        prefix = "backup"
+        inner = { type = "memory" }
+        "#,
+    )
+    .unwrap();
+
+    let storage = config.build().await.unwrap();
+    storage
+        .put_bytes("file.txt".to_string(), b"hello")
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.get_string(&"file.txt".to_string()).await.unwrap(),
+        "hello"
+    );
+}
+
+#[tokio::test]
+async fn test_config_builds_read_only() {
+    let config: StorageConfig =
+        serde_json::from_str(r#"{ "type": "read_only", "inner": { "type": "memory" } }"#).unwrap();
+
+    let storage = config.build().await.unwrap();
+    let result = storage.put_bytes("file.txt".to_string(), b"data").await;
+    assert!(matches!(result, Err(Error::PermissionDenied(_))));
+    assert!(!storage.exists(&"file.txt".to_string()).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_config_builds_retry() {
+    let config: StorageConfig = toml::from_str(
+        r#"
+        type = "retry"
+        max_attempts = 5
+        initial_backoff_ms = 50
+        deadline_ms = 10000
+        inner = { type = "memory" }
+        "#,
+    )
+    .unwrap();
+
+    let StorageConfig::Retry(retry) = &config else {
+        panic!("Expected a retry config");
+    };
+    assert_eq!(retry.max_attempts, Some(5));
+    assert_eq!(retry.max_backoff_ms, None);
+
+    let storage = config.build().await.unwrap();
+    storage
+        .put_bytes("file.txt".to_string(), b"data")
+        .await
+        .unwrap();
+    assert!(storage.exists(&"file.txt".to_string()).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_config_builds_cached() {
+    let config: StorageConfig = toml::from_str(
+        r#"
+        type = "cached"
+        capacity_bytes = 1048576
+        ttl_ms = 60000
+        origin = { type = "memory" }
+        cache = { type = "memory" }
+        "#,
+    )
+    .unwrap();
+
+    let storage = config.build().await.unwrap();
+    storage
+        .put_bytes("file.txt".to_string(), b"data")
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.get_string(&"file.txt".to_string()).await.unwrap(),
+        "data"
+    );
+}
+
+#[cfg(feature = "encryption")]
+#[tokio::test]
+async fn test_config_builds_encrypted() {
+    let dir = tempfile::tempdir().unwrap();
+    let key = dir.path().join("key");
+    std::fs::write(&key, format!("{}\n", "ab".repeat(32))).unwrap();
+    let config: StorageConfig = toml::from_str(&format!(
+        r#"
+        type = "encrypted"
+        keys = [{{ id = "k1", key = {{ file = "{0}" }} }}]
+        id_key = {{ file = "{0}" }}
+        inner = {{ type = "memory" }}
+        "#,
+        key.display()
+    ))
+    .unwrap();
+
+    let storage = config.build().await.unwrap();
+    storage
+        .put_bytes("file.txt".to_string(), b"data")
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.get_string(&"file.txt".to_string()).await.unwrap(),
+        "data"
+    );
+
+    let config: StorageConfig =
+        toml::from_str("type = \"encrypted\"\nkeys = []\ninner = { type = \"memory\" }").unwrap();
+    assert!(matches!(config.build().await, Err(Error::InvalidId(_))));
+}
+
+#[tokio::test]
+async fn test_config_invalid_mirror() {
+    let config: StorageConfig =
+        serde_json::from_str(r#"{ "type": "mirror", "backends": [] }"#).unwrap();
+    assert!(matches!(config.build().await, Err(Error::InvalidId(_))));
+
+    let config: StorageConfig = serde_json::from_str(
+        r#"{ "type": "mirror", "backends": [{ "type": "memory" }], "primary_index": 1 }"#,
+    )
+    .unwrap();
+    assert!(matches!(config.build().await, Err(Error::InvalidId(_))));
+}
+
+#[test]
+fn test_secret_source_file() {
+    use secrecy::ExposeSecret;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("token");
+    std::fs::write(&path, "s3cr3t\n").unwrap();
+
+    let source: SecretSource =
+        toml::from_str::<toml::Table>(&format!("file = {:?}", path.display().to_string()))
+            .unwrap()
+            .try_into()
+            .unwrap();
+    assert_eq!(source.resolve().unwrap().expose_secret(), "s3cr3t");
+
+    let missing = SecretSource::Env("STOWAGE_TEST_UNSET_SECRET".to_string());
+    assert!(matches!(missing.resolve(), Err(Error::InvalidId(_))));
+}
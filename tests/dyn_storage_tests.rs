@@ -0,0 +1,111 @@
+//! Tests for DynStorage trait objects
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use stowage::multi::{FallbackStorage, MirrorStorage, ReadOnlyStorage};
+use stowage::{DynStorage, Error, MemoryStorage, PutOptions, Storage, StorageExt};
+
+#[path = "test_common/mod.rs"]
+mod test_common;
+
+#[tokio::test]
+async fn test_boxed_storage_passes_common_suite() {
+    test_common::run_all_tests(|| async { MemoryStorage::new().boxed() }).await;
+}
+
+#[tokio::test]
+async fn test_arc_storage_passes_common_suite() {
+    test_common::run_all_tests(|| async {
+        Arc::new(MemoryStorage::new()) as Arc<dyn DynStorage<Id = String>>
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_boxed_storage_keeps_native_behaviour() {
+    let storage = MemoryStorage::new().boxed();
+    storage
+        .put_bytes("file.txt".to_string(), b"hello world")
+        .await
+        .unwrap();
+
+    // Native preconditions are forwarded, not replaced by the trait default
+    let result = storage
+        .put_with(
+            "file.txt".to_string(),
+            &b"new"[..],
+            Some(3),
+            PutOptions::new().if_none_match("*"),
+        )
+        .await;
+    assert!(matches!(result, Err(Error::PreconditionFailed(_))));
+
+    let mut buf = Vec::new();
+    let n = storage
+        .get_range(&"file.txt".to_string(), 6..11, &mut buf)
+        .await
+        .unwrap();
+    assert_eq!(n, 5);
+    assert_eq!(buf, b"world");
+
+    // Native hierarchical listing is forwarded too
+    let listing = storage.list_dir(None).await.unwrap();
+    assert_eq!(listing.files, vec!["file.txt".to_string()]);
+}
+
+#[tokio::test]
+async fn test_heterogeneous_backends_in_map() {
+    let mut tenants: HashMap<&str, Box<dyn DynStorage<Id = String>>> = HashMap::new();
+    tenants.insert("plain", MemoryStorage::new().boxed());
+    tenants.insert(
+        "fallback",
+        FallbackStorage::new(MemoryStorage::new(), MemoryStorage::new()).boxed(),
+    );
+    tenants.insert(
+        "readonly",
+        ReadOnlyStorage::new(MemoryStorage::new()).boxed(),
+    );
+
+    for name in ["plain", "fallback"] {
+        let storage = &tenants[name];
+        storage
+            .put_bytes("a.txt".to_string(), name.as_bytes())
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_string(&"a.txt".to_string()).await.unwrap(),
+            name
+        );
+    }
+
+    let result = tenants["readonly"]
+        .put_bytes("a.txt".to_string(), b"data")
+        .await;
+    assert!(matches!(result, Err(Error::PermissionDenied(_))));
+}
+
+#[tokio::test]
+async fn test_mirror_of_mixed_backends() {
+    let backends: Vec<Box<dyn DynStorage<Id = String>>> = vec![
+        MemoryStorage::new().boxed(),
+        FallbackStorage::new(MemoryStorage::new(), MemoryStorage::new()).boxed(),
+    ];
+    let mirror = MirrorStorage::new(backends);
+
+    mirror
+        .put_bytes("mirrored.txt".to_string(), b"data")
+        .await
+        .unwrap();
+
+    for index in 0..2 {
+        let backend = mirror.backend(index).unwrap();
+        assert_eq!(
+            backend
+                .get_bytes(&"mirrored.txt".to_string())
+                .await
+                .unwrap(),
+            b"data"
+        );
+    }
+}
@@ -0,0 +1,434 @@
+//! Tests for EncryptedStorage
+
+#![cfg(all(feature = "memory", feature = "encryption"))]
+
+use std::sync::Mutex;
+
+use futures::TryStreamExt;
+use futures::stream::BoxStream;
+use stowage::multi::{EncryptedStorage, Keyring};
+use stowage::{Error, MemoryStorage, ObjectMeta, PutOptions, Result, Storage, StorageExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+
+const KEY_1: [u8; 32] = [1; 32];
+const KEY_2: [u8; 32] = [2; 32];
+
+fn encrypted(inner: MemoryStorage) -> EncryptedStorage<MemoryStorage> {
+    EncryptedStorage::new(inner, Keyring::new("k1", KEY_1).unwrap())
+}
+
+/// Overwrites an item with `pending` right after it is stat'ed, as a
+/// concurrent writer would. Rejects conditional writes unless `conditional`.
+#[derive(Debug, Default)]
+struct Racing {
+    inner: MemoryStorage,
+    pending: Mutex<Option<Vec<u8>>>,
+    conditional: bool,
+}
+
+impl Storage for Racing {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        self.inner.exists(id).await
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        self.inner.folder_exists(id).await
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        let meta = self.inner.stat(id).await?;
+        let pending = self.pending.lock().unwrap().take();
+        if let Some(data) = pending {
+            StorageExt::put_bytes(&self.inner, id.clone(), &data).await?;
+        }
+        Ok(meta)
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        self.inner.put(id, input, len).await
+    }
+
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        if !self.conditional && options.has_preconditions() {
+            return Err(Error::Unsupported("conditional writes".to_string()));
+        }
+        self.inner.put_with(id, input, len, options).await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        output: W,
+    ) -> Result<u64> {
+        self.inner.get_into(id, output).await
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        self.inner.delete(id).await
+    }
+
+    async fn list(&self, prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        self.inner.list(prefix).await
+    }
+}
+
+/// Deterministic content spanning several 64 KiB chunks.
+fn content(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[tokio::test]
+async fn test_encrypted_round_trip() {
+    let storage = encrypted(MemoryStorage::new());
+    let id = "file.txt".to_string();
+    storage
+        .put_bytes(id.clone(), b"attack at dawn")
+        .await
+        .unwrap();
+
+    assert_eq!(storage.get_string(&id).await.unwrap(), "attack at dawn");
+    assert_eq!(storage.stat(&id).await.unwrap().size, 14);
+
+    let sealed = storage.inner().get_bytes(&id).unwrap();
+    assert!(sealed.len() > 14);
+    assert!(!sealed.windows(6).any(|w| w == b"attack"));
+
+    // Every item gets its own data key
+    storage
+        .put_bytes("copy.txt".to_string(), b"attack at dawn")
+        .await
+        .unwrap();
+    assert_ne!(storage.inner().get_bytes("copy.txt").unwrap(), sealed);
+}
+
+#[tokio::test]
+async fn test_encrypted_streams_large_items() {
+    let storage = encrypted(MemoryStorage::new());
+    for len in [0, 1, 64 * 1024, 128 * 1024, 200_000] {
+        let id = format!("item-{len}");
+        let data = content(len);
+        storage
+            .put(id.clone(), &data[..], Some(len as u64))
+            .await
+            .unwrap();
+
+        assert_eq!(storage.get_bytes(&id).await.unwrap(), data);
+        assert_eq!(storage.stat(&id).await.unwrap().size, len as u64);
+
+        let mut read = Vec::new();
+        let mut reader = storage.get_reader(&id).await.unwrap();
+        reader.read_to_end(&mut read).await.unwrap();
+        assert_eq!(read, data);
+    }
+}
+
+#[tokio::test]
+async fn test_encrypted_ranges() {
+    let storage = encrypted(MemoryStorage::new());
+    let id = "big.bin".to_string();
+    let data = content(200_000);
+    storage.put_bytes(id.clone(), &data).await.unwrap();
+
+    for range in [0..10, 65_530..65_550, 100_000..200_000, 1..199_999] {
+        let mut output = Vec::new();
+        let written = storage
+            .get_range(&id, range.clone(), &mut output)
+            .await
+            .unwrap();
+        assert_eq!(written, range.end - range.start);
+        assert_eq!(output, &data[range.start as usize..range.end as usize]);
+    }
+
+    // Ranges past the end are truncated
+    let mut output = Vec::new();
+    storage
+        .get_range(&id, 199_990..300_000, &mut output)
+        .await
+        .unwrap();
+    assert_eq!(output, &data[199_990..]);
+    let mut output = Vec::new();
+    assert_eq!(
+        storage
+            .get_range(&id, 300_000..300_010, &mut output)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_encrypted_detects_tampering() {
+    let storage = encrypted(MemoryStorage::new());
+    let id = "file.bin".to_string();
+    storage
+        .put_bytes(id.clone(), &content(100_000))
+        .await
+        .unwrap();
+    let sealed = storage.inner().get_bytes(&id).unwrap();
+
+    let mut altered = sealed.clone();
+    *altered.last_mut().unwrap() ^= 1;
+    StorageExt::put_bytes(storage.inner(), id.clone(), &altered)
+        .await
+        .unwrap();
+    assert!(matches!(
+        storage.get_bytes(&id).await,
+        Err(Error::Generic(_))
+    ));
+
+    // Dropping the final chunk leaves a well-formed but truncated item
+    let header_and_first_chunk = sealed.len() - (100_000 - 65_536) - 16;
+    StorageExt::put_bytes(
+        storage.inner(),
+        id.clone(),
+        &sealed[..header_and_first_chunk],
+    )
+    .await
+    .unwrap();
+    assert!(matches!(
+        storage.get_bytes(&id).await,
+        Err(Error::Generic(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_encrypted_needs_the_right_key() {
+    let storage = encrypted(MemoryStorage::new());
+    let id = "file.txt".to_string();
+    storage.put_bytes(id.clone(), b"secret").await.unwrap();
+    let inner = storage.into_inner();
+
+    let other = EncryptedStorage::new(inner.clone(), Keyring::new("k2", KEY_2).unwrap());
+    assert!(matches!(other.get_bytes(&id).await, Err(Error::Generic(_))));
+
+    // Same key ID, different key
+    let wrong = EncryptedStorage::new(inner, Keyring::new("k1", KEY_2).unwrap());
+    assert!(matches!(wrong.get_bytes(&id).await, Err(Error::Generic(_))));
+}
+
+#[tokio::test]
+async fn test_encrypted_key_rotation() {
+    let inner = MemoryStorage::new();
+    let id = "report.pdf".to_string();
+    encrypted(inner.clone())
+        .put_with(
+            id.clone(),
+            &b"quarterly numbers"[..],
+            None,
+            PutOptions::new().content_type("application/pdf"),
+        )
+        .await
+        .unwrap();
+
+    let keyring = Keyring::new("k2", KEY_2)
+        .unwrap()
+        .with_key("k1", KEY_1)
+        .unwrap();
+    let rotated = EncryptedStorage::new(inner.clone(), keyring);
+    assert_eq!(rotated.key_id(&id).await.unwrap(), "k1");
+    assert_eq!(rotated.get_string(&id).await.unwrap(), "quarterly numbers");
+
+    assert!(rotated.rewrap(&id).await.unwrap());
+    assert!(!rotated.rewrap(&id).await.unwrap());
+    assert_eq!(rotated.key_id(&id).await.unwrap(), "k2");
+    assert_eq!(
+        rotated.stat(&id).await.unwrap().content_type.as_deref(),
+        Some("application/pdf")
+    );
+
+    // The old key is no longer needed
+    let current = EncryptedStorage::new(inner, Keyring::new("k2", KEY_2).unwrap());
+    assert_eq!(current.get_string(&id).await.unwrap(), "quarterly numbers");
+}
+
+#[tokio::test]
+async fn test_encrypted_rewrap_keeps_concurrent_writes() {
+    let id = "notes.txt".to_string();
+    let old = encrypted(MemoryStorage::new());
+    old.put_bytes(id.clone(), b"first").await.unwrap();
+    let first = old.inner().get_bytes(&id).unwrap();
+    old.put_bytes(id.clone(), b"second").await.unwrap();
+    let second = old.inner().get_bytes(&id).unwrap();
+
+    let keyring = Keyring::new("k2", KEY_2)
+        .unwrap()
+        .with_key("k1", KEY_1)
+        .unwrap();
+    for conditional in [true, false] {
+        let racing = Racing {
+            conditional,
+            ..Racing::default()
+        };
+        StorageExt::put_bytes(&racing.inner, id.clone(), &first)
+            .await
+            .unwrap();
+        if conditional {
+            *racing.pending.lock().unwrap() = Some(second.clone());
+        }
+        let rotated = EncryptedStorage::new(racing, keyring.clone());
+
+        if conditional {
+            // The second version is kept, and is re-wrapped on retry
+            let result = rotated.rewrap(&id).await;
+            assert!(matches!(result, Err(Error::PreconditionFailed(_))));
+            assert_eq!(rotated.key_id(&id).await.unwrap(), "k1");
+            assert_eq!(rotated.get_string(&id).await.unwrap(), "second");
+            assert!(rotated.rewrap(&id).await.unwrap());
+            assert_eq!(rotated.get_string(&id).await.unwrap(), "second");
+        } else {
+            // Backends without conditional writes are written back as before
+            assert!(rotated.rewrap(&id).await.unwrap());
+            assert_eq!(rotated.get_string(&id).await.unwrap(), "first");
+        }
+        assert_eq!(rotated.key_id(&id).await.unwrap(), "k2");
+    }
+}
+
+#[tokio::test]
+async fn test_encrypted_ids() {
+    let storage = encrypted(MemoryStorage::new()).with_encrypted_ids([9; 32]);
+    assert!(storage.encrypts_ids());
+    for id in ["docs/a.txt", "docs/b.txt", "photos/2026/c.jpg"] {
+        storage.put_bytes(id.to_string(), b"data").await.unwrap();
+    }
+
+    let stored: Vec<String> = storage
+        .inner()
+        .list(None)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 3);
+    assert!(
+        stored
+            .iter()
+            .all(|id| !id.contains("docs") && !id.contains("photos"))
+    );
+
+    let mut docs: Vec<String> = storage
+        .list(Some(&"docs/".to_string()))
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    docs.sort();
+    assert_eq!(docs, vec!["docs/a.txt", "docs/b.txt"]);
+
+    let entries: Vec<_> = storage
+        .list_with_meta(Some(&"photos".to_string()))
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, "photos/2026/c.jpg");
+    assert_eq!(entries[0].size, 4);
+
+    storage
+        .rename(&"docs/a.txt".to_string(), "docs/moved.txt".to_string())
+        .await
+        .unwrap();
+    assert!(!storage.exists(&"docs/a.txt".to_string()).await.unwrap());
+    assert_eq!(
+        storage
+            .get_string(&"docs/moved.txt".to_string())
+            .await
+            .unwrap(),
+        "data"
+    );
+
+    let deleted = storage.delete_prefix(&"docs/".to_string()).await.unwrap();
+    let mut succeeded = deleted.succeeded;
+    succeeded.sort();
+    assert_eq!(succeeded, vec!["docs/b.txt", "docs/moved.txt"]);
+
+    // Items the wrapper did not write cannot be listed through it
+    StorageExt::put_bytes(storage.inner(), "plain.txt".to_string(), b"x")
+        .await
+        .unwrap();
+    let listed: stowage::Result<Vec<String>> =
+        storage.list(None).await.unwrap().try_collect().await;
+    assert!(matches!(listed, Err(Error::InvalidId(_))));
+}
+
+#[test]
+fn test_keyring_validation() {
+    assert!(Keyring::new("", KEY_1).is_err());
+    assert!(Keyring::new("k".repeat(65), KEY_1).is_err());
+    assert!(
+        Keyring::new("k1", KEY_1)
+            .unwrap()
+            .with_key("k1", KEY_2)
+            .is_err()
+    );
+
+    let keyring = Keyring::new("k1", KEY_1)
+        .unwrap()
+        .with_key("k0", KEY_2)
+        .unwrap();
+    assert!(keyring.contains("k0"));
+    assert!(!format!("{keyring:?}").contains("[1, 1"));
+
+    let hex = "01".repeat(32);
+    assert_eq!(Keyring::key_from_hex(&hex).unwrap(), KEY_1);
+    assert!(Keyring::key_from_hex("0102").is_err());
+    assert!(Keyring::key_from_hex(&"zz".repeat(32)).is_err());
+    assert_ne!(Keyring::generate_key(), Keyring::generate_key());
+}
+
+#[cfg(feature = "local")]
+#[tokio::test]
+async fn test_encrypted_ids_on_local_disk() {
+    use stowage::adapters::local::LocalStorage;
+
+    let dir = tempfile::tempdir().unwrap();
+    let storage = EncryptedStorage::new(
+        LocalStorage::new(dir.path()),
+        Keyring::new("k1", KEY_1).unwrap(),
+    )
+    .with_encrypted_ids([9; 32]);
+    storage
+        .put_bytes("reports/2026/q3.csv".to_string(), b"a,b,c")
+        .await
+        .unwrap();
+
+    let root = storage.list_dir(None).await.unwrap();
+    assert_eq!(root.prefixes, vec!["reports"]);
+    let year = storage
+        .list_dir(Some(&"reports/2026".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(year.files, vec!["reports/2026/q3.csv"]);
+    assert_eq!(
+        storage
+            .get_string(&"reports/2026/q3.csv".to_string())
+            .await
+            .unwrap(),
+        "a,b,c"
+    );
+
+    // Nothing on disk reveals the names
+    let names: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(names.len(), 1);
+    assert!(!names[0].contains("reports"));
+}
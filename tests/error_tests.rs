@@ -42,6 +42,52 @@ async fn test_permission_denied_error_formatting() {
     assert!(formatted.contains("Access denied"));
 }
 
+#[tokio::test]
+async fn test_precondition_failed_error_formatting() {
+    let err = Error::PreconditionFailed("manifest.json".to_string());
+    let formatted = format!("{}", err);
+    assert!(formatted.contains("Precondition failed"));
+    assert!(formatted.contains("manifest.json"));
+}
+
+#[tokio::test]
+async fn test_rate_limited_error_formatting() {
+    let err = Error::RateLimited {
+        message: "slow down".to_string(),
+        retry_after: Some(std::time::Duration::from_secs(5)),
+    };
+    let formatted = format!("{}", err);
+    assert!(formatted.contains("Rate limited"));
+    assert!(formatted.contains("slow down"));
+    assert_eq!(err.retry_after(), Some(std::time::Duration::from_secs(5)));
+}
+
+#[tokio::test]
+async fn test_is_retryable() {
+    use std::io::ErrorKind;
+
+    assert!(
+        Error::RateLimited {
+            message: "busy".to_string(),
+            retry_after: None,
+        }
+        .is_retryable()
+    );
+    assert!(Error::Timeout("read".to_string()).is_retryable());
+    assert!(Error::Unavailable("503".to_string()).is_retryable());
+    assert!(Error::Io(std::io::Error::from(ErrorKind::ConnectionReset)).is_retryable());
+
+    assert!(!Error::NotFound("a.txt".to_string()).is_retryable());
+    assert!(!Error::PermissionDenied("no".to_string()).is_retryable());
+    assert!(!Error::PreconditionFailed("a.txt".to_string()).is_retryable());
+    assert!(!Error::AlreadyExists("a.txt".to_string()).is_retryable());
+    assert!(!Error::QuotaExceeded("full".to_string()).is_retryable());
+    assert!(!Error::InvalidId("".to_string()).is_retryable());
+    assert!(!Error::Unsupported("list_dir".to_string()).is_retryable());
+    assert!(!Error::Io(std::io::Error::from(ErrorKind::NotFound)).is_retryable());
+    assert_eq!(Error::Timeout("read".to_string()).retry_after(), None);
+}
+
 #[tokio::test]
 async fn test_io_error_conversion() {
     let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
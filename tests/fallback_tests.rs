@@ -119,6 +119,24 @@ async fn test_exists_checks_both_storages() {
     assert!(!storage.exists(&id3).await.unwrap());
 }
 
+#[tokio::test]
+async fn test_stat_falls_back_to_secondary() {
+    let primary = MemoryStorage::new();
+    let secondary = MemoryStorage::new();
+    let storage = FallbackStorage::new(primary.clone(), secondary.clone());
+
+    let id = "in_secondary.txt".to_string();
+    StorageExt::put_bytes(&secondary, id.clone(), b"secondary data")
+        .await
+        .unwrap();
+
+    let meta = storage.stat(&id).await.unwrap();
+    assert_eq!(meta.size, 14);
+
+    let result = storage.stat(&"nowhere.txt".to_string()).await;
+    assert!(matches!(result, Err(Error::NotFound(_))));
+}
+
 #[tokio::test]
 async fn test_delete_removes_from_both() {
     let primary = MemoryStorage::new();
@@ -366,7 +384,6 @@ async fn test_copy_to_fallback_storage() {
 #[tokio::test]
 async fn test_concurrent_reads() {
     let primary = MemoryStorage::new();
-    let secondary = MemoryStorage::new();
 
     // Prepare data in primary
     for i in 0..10 {
@@ -396,7 +413,6 @@ async fn test_concurrent_reads() {
 #[tokio::test]
 async fn test_concurrent_writes() {
     let primary = MemoryStorage::new();
-    let secondary = MemoryStorage::new();
 
     let handles: Vec<_> = (0..10)
         .map(|i| {
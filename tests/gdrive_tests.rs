@@ -0,0 +1,129 @@
+//! Tests for GoogleDriveStorage against a mock Drive endpoint
+
+#![cfg(feature = "gdrive")]
+
+#[path = "test_common/mod.rs"]
+mod test_common;
+
+use reqwest::{Client, Url};
+use stowage::Error;
+use stowage::adapters::gdrive::{GoogleDriveStorage, TokenProvider};
+use test_common::{MockRequest, MockResponse, MockServer};
+
+fn storage(server: &MockServer) -> GoogleDriveStorage {
+    GoogleDriveStorage::new(Client::new(), TokenProvider::Static("token".into()))
+        .unwrap()
+        .with_base_url(Url::parse(&format!("{}/drive/v3/", server.url())).unwrap())
+}
+
+/// Parts of a `multipart/related` request body, split on the boundary its
+/// content type names.
+fn multipart_parts(req: &MockRequest) -> Vec<String> {
+    let content_type = req.header("content-type").unwrap();
+    let boundary = content_type
+        .strip_prefix("multipart/related; boundary=")
+        .unwrap();
+    String::from_utf8(req.body.clone())
+        .unwrap()
+        .split(&format!("--{boundary}"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Multipart uploads: answer with a new ID for every file, since Drive
+/// allows several files with the same name in a folder.
+fn multipart_upload(req: &MockRequest) -> MockResponse {
+    static CREATED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    if req.method == "POST" && req.target.contains("uploadType=multipart") {
+        let n = CREATED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        return MockResponse::json(200, serde_json::json!({ "id": format!("file-{n}") }));
+    }
+    MockResponse::new(404)
+}
+
+#[tokio::test]
+async fn test_create_uploads_metadata_and_content() {
+    let server = MockServer::start(multipart_upload).await;
+    let storage = storage(&server);
+
+    let id = storage
+        .create("folder-id", "notes.txt", &b"hello"[..], Some(5))
+        .await
+        .unwrap();
+    assert!(id.starts_with("file-"));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].target.starts_with("/upload/drive/v3/files?"));
+    let parts = multipart_parts(&requests[0]);
+    assert_eq!(parts.len(), 4);
+    let metadata: serde_json::Value =
+        serde_json::from_str(parts[1].rsplit("\r\n\r\n").next().unwrap().trim_end()).unwrap();
+    assert_eq!(
+        metadata,
+        serde_json::json!({ "name": "notes.txt", "parents": ["folder-id"] })
+    );
+    assert!(parts[2].ends_with("\r\n\r\nhello\r\n"));
+    assert_eq!(parts[3], "--\r\n");
+}
+
+#[tokio::test]
+async fn test_create_content_cannot_end_a_part() {
+    let server = MockServer::start(multipart_upload).await;
+    let storage = storage(&server);
+
+    // Content carrying the boundary of an earlier upload
+    storage
+        .create("folder-id", "a.txt", &b"first"[..], None)
+        .await
+        .unwrap();
+    let earlier = server.requests()[0]
+        .header("content-type")
+        .unwrap()
+        .split("boundary=")
+        .nth(1)
+        .unwrap()
+        .to_string();
+    let content = format!("before\r\n--{earlier}--\r\nafter");
+    storage
+        .create("folder-id", "b.txt", content.as_bytes(), None)
+        .await
+        .unwrap();
+
+    let parts = multipart_parts(&server.requests()[1]);
+    assert_eq!(parts.len(), 4);
+    assert!(parts[2].ends_with(&format!("\r\n\r\n{content}\r\n")));
+}
+
+#[tokio::test]
+async fn test_create_allows_duplicate_names() {
+    let server = MockServer::start(multipart_upload).await;
+    let storage = storage(&server);
+
+    let first = storage
+        .create("folder-id", "same.txt", &b"1"[..], Some(1))
+        .await
+        .unwrap();
+    let second = storage
+        .create("folder-id", "same.txt", &b"2"[..], Some(1))
+        .await
+        .unwrap();
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn test_create_maps_conflicts() {
+    let server = MockServer::start(|_| {
+        MockResponse::json(
+            409,
+            serde_json::json!({ "error": { "code": 409, "message": "conflict" } }),
+        )
+    })
+    .await;
+    let storage = storage(&server);
+
+    let result = storage
+        .create("folder-id", "a.txt", &b"data"[..], Some(4))
+        .await;
+    assert!(matches!(result, Err(Error::Conflict(_))), "{result:?}");
+}
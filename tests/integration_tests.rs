@@ -2,7 +2,7 @@
 //! Tests the trait contract across different storage implementations
 
 use futures::stream::StreamExt;
-use stowage::{Error, MemoryStorage, Storage, StorageExt};
+use stowage::{Error, MemoryStorage, PutOptions, Storage, StorageExt};
 
 #[path = "test_common/mod.rs"]
 mod test_common;
@@ -76,6 +76,61 @@ async fn test_special_characters() {
     test_common::test_special_characters(&mut || async { MemoryStorage::new() }).await;
 }
 
+#[tokio::test]
+async fn test_stat() {
+    test_common::test_stat(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_get_range() {
+    test_common::test_get_range(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_copy() {
+    test_common::test_copy(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_rename() {
+    test_common::test_rename(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_put_with_preconditions() {
+    test_common::test_put_with_preconditions(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_list_dir() {
+    test_common::test_list_dir(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_list_with_meta() {
+    test_common::test_list_with_meta(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_list_page() {
+    test_common::test_list_page(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_delete_many() {
+    test_common::test_delete_many(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_delete_prefix() {
+    test_common::test_delete_prefix(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_delete_folder() {
+    test_common::test_delete_folder(&mut || async { MemoryStorage::new() }).await;
+}
+
 // ============================================================================
 // Integration-specific tests (StorageExt methods, edge cases, etc.)
 // ============================================================================
@@ -417,3 +472,180 @@ async fn test_empty_prefix_lists_all() {
     // Empty prefix should behave similarly to no prefix
     assert_eq!(items_with_empty.len(), items_without.len());
 }
+
+// ============================================================================
+// Default trait method implementations
+// ============================================================================
+
+/// Storage that implements only the required trait methods, so every
+/// provided method runs its default implementation.
+#[derive(Debug, Clone, Default)]
+struct MinimalStorage {
+    inner: MemoryStorage,
+}
+
+impl Storage for MinimalStorage {
+    type Id = String;
+
+    async fn exists(&self, id: &Self::Id) -> stowage::Result<bool> {
+        self.inner.exists(id).await
+    }
+
+    async fn folder_exists(&self, id: &Self::Id) -> stowage::Result<bool> {
+        self.inner.folder_exists(id).await
+    }
+
+    async fn put<R: tokio::io::AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: Self::Id,
+        input: R,
+        len: Option<u64>,
+    ) -> stowage::Result<()> {
+        self.inner.put(id, input, len).await
+    }
+
+    async fn get_into<W: tokio::io::AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &Self::Id,
+        output: W,
+    ) -> stowage::Result<u64> {
+        self.inner.get_into(id, output).await
+    }
+
+    async fn delete(&self, id: &Self::Id) -> stowage::Result<()> {
+        self.inner.delete(id).await
+    }
+
+    async fn list(
+        &self,
+        prefix: Option<&Self::Id>,
+    ) -> stowage::Result<futures::stream::BoxStream<'_, stowage::Result<Self::Id>>> {
+        self.inner.list(prefix).await
+    }
+}
+
+#[tokio::test]
+async fn test_default_stat() {
+    let storage = MinimalStorage::default();
+    let id = "stat.txt".to_string();
+    assert!(matches!(storage.stat(&id).await, Err(Error::NotFound(_))));
+
+    storage.put_bytes(id.clone(), b"hello world").await.unwrap();
+    let meta = storage.stat(&id).await.unwrap();
+    assert_eq!(meta.size, 11);
+    assert_eq!(meta.etag, None);
+}
+
+#[tokio::test]
+async fn test_default_get_range() {
+    test_common::test_get_range(&mut || async { MinimalStorage::default() }).await;
+}
+
+#[tokio::test]
+async fn test_default_copy() {
+    test_common::test_copy(&mut || async { MinimalStorage::default() }).await;
+}
+
+#[tokio::test]
+async fn test_default_rename() {
+    test_common::test_rename(&mut || async { MinimalStorage::default() }).await;
+}
+
+#[tokio::test]
+async fn test_default_put_with() {
+    let storage = MinimalStorage::default();
+    let id = "conditional.txt".to_string();
+
+    // Without preconditions the default behaves like put
+    storage
+        .put_with(id.clone(), &b"data"[..], None, PutOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), b"data");
+
+    // Preconditions can't be honoured, so they are refused rather than ignored
+    let result = storage
+        .put_with(
+            id.clone(),
+            &b"new"[..],
+            None,
+            PutOptions::new().if_none_match("*"),
+        )
+        .await;
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), b"data");
+}
+
+#[tokio::test]
+async fn test_default_list_with_meta() {
+    test_common::test_list_with_meta(&mut || async { MinimalStorage::default() }).await;
+}
+
+#[tokio::test]
+async fn test_default_list_page() {
+    test_common::test_list_page(&mut || async { MinimalStorage::default() }).await;
+}
+
+#[tokio::test]
+async fn test_default_list_page_rejects_foreign_token() {
+    let storage = MinimalStorage::default();
+    let token = stowage::ContinuationToken::new("not-an-offset");
+    let result = storage.list_page(None, 10, Some(token)).await;
+    assert!(matches!(result, Err(Error::Generic(_))));
+}
+
+#[tokio::test]
+async fn test_default_list_dir() {
+    let storage = MinimalStorage::default();
+    storage
+        .put_bytes("dir/file.txt".to_string(), b"data")
+        .await
+        .unwrap();
+
+    // Without a native listing the default refuses rather than recursing
+    let result = storage.list_dir(None).await;
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+}
+
+#[tokio::test]
+async fn test_default_delete_many() {
+    let storage = MinimalStorage::default();
+    let ids: Vec<String> = (0..20).map(|i| format!("bulk/{i}.txt")).collect();
+    for id in &ids {
+        storage.put_bytes(id.clone(), b"data").await.unwrap();
+    }
+
+    let result = storage.delete_many(ids.clone()).await.unwrap();
+    assert!(result.is_complete());
+    let mut deleted = result.succeeded;
+    deleted.sort();
+    let mut expected = ids.clone();
+    expected.sort();
+    assert_eq!(deleted, expected);
+    for id in &ids {
+        assert!(!storage.exists(id).await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn test_default_delete_prefix_and_folder() {
+    let storage = MinimalStorage::default();
+    storage
+        .put_bytes("t/a.txt".to_string(), b"a")
+        .await
+        .unwrap();
+    storage
+        .put_bytes("t/b/c.txt".to_string(), b"c")
+        .await
+        .unwrap();
+    storage.put_bytes("u.txt".to_string(), b"u").await.unwrap();
+
+    let result = storage.delete_prefix(&"t/".to_string()).await.unwrap();
+    assert_eq!(result.success_count(), 2);
+    assert!(!storage.exists(&"t/a.txt".to_string()).await.unwrap());
+    assert!(storage.exists(&"u.txt".to_string()).await.unwrap());
+
+    // Folders need native support
+    let result = storage.delete_folder(&"t".to_string()).await;
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+}
@@ -0,0 +1,276 @@
+//! Tests that the path-based adapters map an item ID to the same object.
+
+#[path = "test_common/mod.rs"]
+mod test_common;
+
+#[cfg(any(feature = "s3", feature = "azure", feature = "webdav"))]
+use stowage::Storage;
+#[cfg(any(feature = "s3", feature = "azure", feature = "webdav"))]
+use test_common::{MockResponse, MockServer};
+
+/// Spellings of `docs/report.txt` that every path-based adapter normalizes.
+#[cfg(any(
+    feature = "local",
+    feature = "s3",
+    feature = "azure",
+    feature = "webdav"
+))]
+const EQUIVALENT_IDS: [&str; 4] = [
+    "docs/report.txt",
+    "./docs/report.txt",
+    "docs/./report.txt",
+    "docs/report.txt/",
+];
+
+/// Object keys other tools can create that are not paths themselves, with
+/// the paths that name them.
+#[cfg(any(feature = "s3", feature = "azure"))]
+const ESCAPED_KEYS: [(&str, &str); 4] = [
+    ("a//b", r"a\2F/b"),
+    ("/x", r"\2Fx"),
+    ("a\\b", r"a\5Cb"),
+    ("docs/", r"docs\2F"),
+];
+
+/// Paths of the requests `server` received, without their query strings.
+#[cfg(any(feature = "s3", feature = "azure", feature = "webdav"))]
+fn request_paths(server: &MockServer) -> Vec<String> {
+    server
+        .requests()
+        .into_iter()
+        .map(|req| req.target.split('?').next().unwrap_or_default().to_string())
+        .collect()
+}
+
+#[cfg(feature = "local")]
+#[tokio::test]
+async fn test_local_normalizes_ids() {
+    use stowage::StorageExt;
+    use stowage::adapters::local::LocalStorage;
+
+    let dir = tempfile::tempdir().unwrap();
+    let storage = LocalStorage::new(dir.path());
+    for (i, id) in EQUIVALENT_IDS.iter().enumerate() {
+        storage
+            .put_bytes(id.to_string(), i.to_string().as_bytes())
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("docs/report.txt")).unwrap(),
+        (EQUIVALENT_IDS.len() - 1).to_string()
+    );
+}
+
+#[cfg(feature = "s3")]
+fn mock_s3(server: &MockServer) -> stowage::adapters::s3::S3Storage {
+    use aws_sdk_s3::config::{BehaviorVersion, Credentials, Region};
+    use stowage::adapters::s3::S3Storage;
+
+    let config = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new("us-east-1"))
+        .endpoint_url(server.url())
+        .credentials_provider(Credentials::new("key", "secret", None, None, "test"))
+        .force_path_style(true)
+        .build();
+    S3Storage::new(aws_sdk_s3::Client::from_conf(config), "bucket")
+}
+
+#[cfg(feature = "s3")]
+#[tokio::test]
+async fn test_s3_normalizes_ids_and_unescapes_keys() {
+    let server = MockServer::start(|_| MockResponse::new(200)).await;
+    let storage = mock_s3(&server);
+
+    for id in EQUIVALENT_IDS {
+        assert!(storage.exists(&id.to_string()).await.unwrap(), "{id:?}");
+    }
+    for (_, id) in ESCAPED_KEYS {
+        assert!(storage.exists(&id.to_string()).await.unwrap(), "{id:?}");
+    }
+    // The keys themselves are not valid ids on any backend
+    for key in ["a//b", "/x", "a\\b"] {
+        assert!(storage.exists(&key.to_string()).await.is_err(), "{key:?}");
+    }
+    assert!(storage.exists(&"a/../b".to_string()).await.is_err());
+    // S3 keys can have dot segments
+    assert!(storage.exists(&r"a/\2E\2E/b".to_string()).await.unwrap());
+
+    let mut expected = vec!["/bucket/docs/report.txt"; EQUIVALENT_IDS.len()];
+    expected.extend([
+        "/bucket/a//b",
+        "/bucket//x",
+        "/bucket/a%5Cb",
+        "/bucket/docs/",
+        "/bucket/a/../b",
+    ]);
+    assert_eq!(request_paths(&server), expected);
+}
+
+#[cfg(all(feature = "s3", feature = "local"))]
+#[tokio::test]
+async fn test_s3_keys_migrate_to_local() {
+    use stowage::adapters::local::LocalStorage;
+    use stowage::{MigrateOptions, StorageExt};
+
+    let server = MockServer::start(|req| {
+        if req.target.contains("list-type=2") {
+            let contents: String = ESCAPED_KEYS
+                .iter()
+                .map(|(key, _)| format!("<Contents><Key>{key}</Key><Size>1</Size></Contents>"))
+                .collect();
+            let mut response = MockResponse::new(200);
+            response.body = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+                 <ListBucketResult><Name>bucket</Name><IsTruncated>false</IsTruncated>\
+                 {contents}</ListBucketResult>"
+            )
+            .into_bytes();
+            return response;
+        }
+        match req.method.as_str() {
+            "GET" => {
+                let mut response = MockResponse::new(200);
+                let path = req.target.split('?').next().unwrap_or_default();
+                response.body = path.as_bytes().to_vec();
+                response
+            }
+            _ => MockResponse::new(404),
+        }
+    })
+    .await;
+    let source = mock_s3(&server);
+
+    let dir = tempfile::tempdir().unwrap();
+    let dest = LocalStorage::new(dir.path());
+    let result = source
+        .migrate_to(&dest, MigrateOptions::default())
+        .await
+        .unwrap();
+    assert!(result.is_complete(), "{result:?}");
+    assert_eq!(result.transferred_count(), ESCAPED_KEYS.len());
+
+    let mut ids: Vec<String> = dest.list_page(None, 10, None).await.unwrap().ids;
+    ids.sort();
+    let mut expected: Vec<&str> = ESCAPED_KEYS.iter().map(|(_, id)| *id).collect();
+    expected.sort();
+    assert_eq!(ids, expected);
+    assert_eq!(
+        dest.get_string(&r"a\2F/b".to_string()).await.unwrap(),
+        "/bucket/a//b"
+    );
+}
+
+#[cfg(feature = "azure")]
+#[tokio::test]
+async fn test_azure_normalizes_ids_and_unescapes_names() {
+    use stowage::adapters::azure::AzureStorage;
+
+    let server = MockServer::start(|_| MockResponse::new(200)).await;
+    let storage = AzureStorage::with_endpoint("account", "container", "sv=x", server.url());
+
+    for id in EQUIVALENT_IDS {
+        assert!(storage.exists(&id.to_string()).await.unwrap(), "{id:?}");
+    }
+    for (_, id) in ESCAPED_KEYS {
+        assert!(storage.exists(&id.to_string()).await.unwrap(), "{id:?}");
+    }
+    for key in ["a//b", "/x", "a\\b"] {
+        assert!(storage.exists(&key.to_string()).await.is_err(), "{key:?}");
+    }
+    assert!(
+        storage
+            .exists(&"my docs/a#1?.txt".to_string())
+            .await
+            .unwrap()
+    );
+    // URLs would resolve the dots away
+    for id in ["a/../b", r"a/\2E\2E/b"] {
+        assert!(storage.exists(&id.to_string()).await.is_err(), "{id:?}");
+    }
+
+    let mut expected = vec!["/container/docs/report.txt"; EQUIVALENT_IDS.len()];
+    expected.extend([
+        "/container/a//b",
+        "/container//x",
+        "/container/a%5Cb",
+        "/container/docs/",
+        "/container/my%20docs/a%231%3F.txt",
+    ]);
+    assert_eq!(request_paths(&server), expected);
+
+    // The root always exists; folders are checked by prefix
+    assert!(storage.folder_exists(&String::new()).await.unwrap());
+    assert_eq!(server.requests().len(), expected.len());
+}
+
+#[cfg(feature = "azure")]
+#[tokio::test]
+async fn test_azure_lists_names_as_escaped_paths() {
+    use futures::TryStreamExt;
+    use stowage::adapters::azure::AzureStorage;
+
+    let server = MockServer::start(|_| {
+        let blobs: String = ESCAPED_KEYS
+            .iter()
+            .map(|(key, _)| format!("<Blob><Name>{key}</Name></Blob>"))
+            .collect();
+        let mut response = MockResponse::new(200);
+        response.body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+             <EnumerationResults><Blobs>{blobs}</Blobs><NextMarker /></EnumerationResults>"
+        )
+        .into_bytes();
+        response
+    })
+    .await;
+    let storage = AzureStorage::with_endpoint("account", "container", "sv=x", server.url());
+
+    let ids: Vec<String> = storage
+        .list(None)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    let expected: Vec<&str> = ESCAPED_KEYS.iter().map(|(_, id)| *id).collect();
+    assert_eq!(ids, expected);
+
+    // Names are matched against the escaped prefix
+    let ids: Vec<String> = storage
+        .list(Some(&"a\\".to_string()))
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(ids, [r"a\2F/b", r"a\5Cb"]);
+    assert!(server.requests()[1].target.ends_with("&prefix=a"));
+}
+
+#[cfg(feature = "webdav")]
+#[tokio::test]
+async fn test_webdav_normalizes_ids() {
+    use stowage::adapters::webdav::WebDAVStorage;
+
+    let server = MockServer::start(|_| MockResponse::new(200)).await;
+    let storage = WebDAVStorage::new(format!("{}/dav", server.url()), "user", "pass");
+
+    for id in EQUIVALENT_IDS {
+        assert!(storage.exists(&id.to_string()).await.unwrap(), "{id:?}");
+    }
+    // Escaped paths are file names of their own
+    assert!(storage.exists(&r"a\5Cb".to_string()).await.unwrap());
+    for id in ["a//b", "/x", "a\\b", "a\\..\\b"] {
+        assert!(storage.exists(&id.to_string()).await.is_err(), "{id:?}");
+    }
+
+    let mut expected = vec!["/dav/docs/report.txt"; EQUIVALENT_IDS.len()];
+    expected.push("/dav/a%5C5Cb");
+    assert_eq!(request_paths(&server), expected);
+
+    // The root is the base URL itself
+    storage.folder_exists(&String::new()).await.unwrap();
+    assert_eq!(request_paths(&server).last().unwrap(), "/dav/");
+}
@@ -3,7 +3,7 @@
 
 use futures::stream::StreamExt;
 use stowage::adapters::local::LocalStorage;
-use stowage::{Error, Storage, StorageExt};
+use stowage::{Error, PutOptions, Storage, StorageExt, StoragePath};
 use tempfile::TempDir;
 use tokio::io::AsyncWriteExt;
 
@@ -139,6 +139,114 @@ async fn test_special_characters() {
     .await;
 }
 
+#[tokio::test]
+async fn test_stat() {
+    test_common::test_stat(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_get_range() {
+    test_common::test_get_range(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_copy() {
+    test_common::test_copy(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_rename() {
+    test_common::test_rename(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_put_with_preconditions() {
+    test_common::test_put_with_preconditions(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_put_with_attributes() {
+    test_common::test_put_with_attributes(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_list_dir() {
+    test_common::test_list_dir(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_list_with_meta() {
+    test_common::test_list_with_meta(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_list_page() {
+    test_common::test_list_page(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_delete_many() {
+    test_common::test_delete_many(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_delete_prefix() {
+    test_common::test_delete_prefix(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_delete_folder() {
+    test_common::test_delete_folder(&mut || async {
+        let (storage, _temp) = create_temp_storage();
+        storage
+    })
+    .await;
+}
+
 // ============================================================================
 // LocalStorage-specific tests
 // ============================================================================
@@ -166,6 +274,103 @@ async fn test_put_and_get_into() {
     assert_eq!(output, data);
 }
 
+#[tokio::test]
+async fn test_concurrent_create_only_has_one_winner() {
+    let (storage, _temp) = create_temp_storage();
+    let id = "lock.json".to_string();
+
+    let attempts = (0..8u8).map(|i| {
+        let storage = storage.clone();
+        let id = id.clone();
+        tokio::spawn(async move {
+            let data = [i; 16];
+            storage
+                .put_with(id, &data[..], None, PutOptions::new().if_none_match("*"))
+                .await
+        })
+    });
+    let results = futures::future::join_all(attempts).await;
+
+    let winners = results.iter().filter(|r| matches!(r, Ok(Ok(())))).count();
+    let losers = results
+        .iter()
+        .filter(|r| matches!(r, Ok(Err(Error::PreconditionFailed(_)))))
+        .count();
+    assert_eq!(winners, 1);
+    assert_eq!(losers, 7);
+    assert_eq!(storage.get_bytes(&id).await.unwrap().len(), 16);
+}
+
+#[tokio::test]
+async fn test_concurrent_if_match_has_one_winner() {
+    let (storage, temp) = create_temp_storage();
+    let id = "manifest.json".to_string();
+    storage.put_bytes(id.clone(), b"v0").await.unwrap();
+    let etag = storage.stat(&id).await.unwrap().etag.unwrap();
+
+    // Every writer read the same version; sizes differ so each write
+    // changes the ETag even within one timestamp tick
+    let attempts = (1..=8usize).map(|i| {
+        let storage = storage.clone();
+        let id = id.clone();
+        let etag = etag.clone();
+        tokio::spawn(async move {
+            let data = vec![b'x'; i * 1000];
+            storage
+                .put_with(id, &data[..], None, PutOptions::new().if_match(etag))
+                .await
+                .map(|()| i)
+        })
+    });
+    let results = futures::future::join_all(attempts).await;
+
+    let winners: Vec<usize> = results
+        .iter()
+        .filter_map(|r| r.as_ref().unwrap().as_ref().ok().copied())
+        .collect();
+    assert_eq!(winners.len(), 1);
+    assert!(
+        results
+            .iter()
+            .all(|r| matches!(r, Ok(Ok(_)) | Ok(Err(Error::PreconditionFailed(_)))))
+    );
+    assert_eq!(
+        storage.get_bytes(&id).await.unwrap(),
+        vec![b'x'; winners[0] * 1000]
+    );
+
+    // No temp files are left behind
+    let names: Vec<_> = std::fs::read_dir(temp.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(names, vec!["manifest.json"]);
+}
+
+#[tokio::test]
+async fn test_create_only_never_exposes_partial_content() {
+    let (storage, _temp) = create_temp_storage();
+    let id = "big.bin".to_string();
+
+    let (mut writer, reader) = tokio::io::duplex(1024);
+    let upload = storage.put_with(
+        id.clone(),
+        reader,
+        None,
+        PutOptions::new().if_none_match("*"),
+    );
+    let feed = async {
+        writer.write_all(&[7u8; 4096]).await.unwrap();
+        // Halfway through the upload nothing is visible yet
+        assert!(!storage.exists(&id).await.unwrap());
+        writer.write_all(&[7u8; 4096]).await.unwrap();
+        drop(writer);
+    };
+    let (result, ()) = tokio::join!(upload, feed);
+    result.unwrap();
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), vec![7u8; 8192]);
+}
+
 #[tokio::test]
 async fn test_put_creates_parent_directories() {
     let (storage, _temp) = create_temp_storage();
@@ -492,7 +697,34 @@ async fn test_path_validation_empty_id() {
 
     let result = storage.put_bytes(id, b"data").await;
     assert!(result.is_err());
-    assert!(matches!(result.unwrap_err(), Error::Generic(_)));
+    assert!(matches!(result.unwrap_err(), Error::InvalidId(_)));
+}
+
+#[tokio::test]
+async fn test_path_validation_empty_segment() {
+    let (storage, _temp) = create_temp_storage();
+    let id = "foo//bar.txt".to_string();
+
+    let result = storage.put_bytes(id, b"data").await;
+    assert!(matches!(result.unwrap_err(), Error::InvalidId(_)));
+}
+
+#[tokio::test]
+async fn test_storage_path_id() {
+    let (storage, _temp) = create_temp_storage();
+    let path = StoragePath::parse("./docs/report.txt").unwrap();
+
+    storage
+        .put_bytes(path.clone().into(), b"data")
+        .await
+        .unwrap();
+    assert!(
+        storage
+            .exists(&"docs/report.txt".to_string())
+            .await
+            .unwrap()
+    );
+    assert_eq!(storage.get_string(&path.into()).await.unwrap(), "data");
 }
 
 #[tokio::test]
@@ -612,6 +844,19 @@ async fn test_delete_does_not_remove_empty_directories() {
     assert!(storage.folder_exists(&"dir".to_string()).await.unwrap());
 }
 
+#[tokio::test]
+async fn test_stat_directory_is_not_found() {
+    let (storage, _temp) = create_temp_storage();
+
+    storage
+        .put_bytes("dir/file.txt".to_string(), b"data")
+        .await
+        .unwrap();
+
+    let result = storage.stat(&"dir".to_string()).await;
+    assert!(matches!(result, Err(Error::NotFound(_))));
+}
+
 #[tokio::test]
 async fn test_clone_storage() {
     let (storage1, _temp) = create_temp_storage();
@@ -629,3 +874,217 @@ async fn test_clone_storage() {
         .unwrap();
     assert_eq!(data, b"data");
 }
+
+#[tokio::test]
+async fn test_list_page_resumes_after_cursor_item_is_deleted() {
+    let (storage, _temp) = create_temp_storage();
+    for name in ["a/1.txt", "a/2.txt", "b/3.txt", "c.txt"] {
+        storage.put_bytes(name.to_string(), b"x").await.unwrap();
+    }
+
+    let first = storage.list_page(None, 2, None).await.unwrap();
+    assert_eq!(first.ids, vec!["a/1.txt", "a/2.txt"]);
+
+    // The token records a position rather than an offset, so removing
+    // already-listed items doesn't skip anything.
+    storage.delete(&"a/1.txt".to_string()).await.unwrap();
+    storage.delete(&"a/2.txt".to_string()).await.unwrap();
+
+    let second = storage.list_page(None, 2, first.next).await.unwrap();
+    assert_eq!(second.ids, vec!["b/3.txt", "c.txt"]);
+    assert!(second.next.is_none());
+}
+
+#[tokio::test]
+async fn test_attributes_follow_rename_and_delete() {
+    let (storage, _temp) = create_temp_storage();
+    let options = PutOptions::new()
+        .content_type("text/csv")
+        .user_metadata("source", "import");
+    storage
+        .put_with("a.csv".to_string(), &b"x,y"[..], Some(3), options)
+        .await
+        .unwrap();
+
+    storage
+        .rename(&"a.csv".to_string(), "b.csv".to_string())
+        .await
+        .unwrap();
+    let meta = storage.stat(&"b.csv".to_string()).await.unwrap();
+    assert_eq!(meta.content_type.as_deref(), Some("text/csv"));
+
+    // Attributes live on disk, so another storage on the same root sees them
+    let reopened = LocalStorage::new(storage.root());
+    let meta = reopened.stat(&"b.csv".to_string()).await.unwrap();
+    assert_eq!(meta.metadata["source"], "import");
+
+    // Deleting drops them, so a file created at the same path later has none
+    storage.delete(&"b.csv".to_string()).await.unwrap();
+    std::fs::write(storage.root().join("b.csv"), b"z").unwrap();
+    let meta = storage.stat(&"b.csv".to_string()).await.unwrap();
+    assert!(meta.content_type.is_none());
+    assert!(meta.metadata.is_empty());
+}
+
+#[tokio::test]
+async fn test_attributes_are_stored_by_normalized_path() {
+    let (storage, _temp) = create_temp_storage();
+    storage
+        .put_with(
+            "./docs/a.txt".to_string(),
+            &b"a"[..],
+            Some(1),
+            PutOptions::new()
+                .content_type("text/plain")
+                .user_metadata("note", "tab\there\nnewline \\ done"),
+        )
+        .await
+        .unwrap();
+
+    let meta = storage.stat(&"docs/./a.txt".to_string()).await.unwrap();
+    assert_eq!(meta.content_type.as_deref(), Some("text/plain"));
+    assert_eq!(meta.metadata["note"], "tab\there\nnewline \\ done");
+
+    storage
+        .copy(&"docs/a.txt".to_string(), "docs/b.txt".to_string())
+        .await
+        .unwrap();
+    let meta = storage.stat(&"docs/b.txt".to_string()).await.unwrap();
+    assert_eq!(meta.content_type.as_deref(), Some("text/plain"));
+
+    // A plain put clears them
+    storage
+        .put_bytes("docs/b.txt".to_string(), b"b")
+        .await
+        .unwrap();
+    let meta = storage.stat(&"docs/b.txt".to_string()).await.unwrap();
+    assert!(meta.content_type.is_none());
+}
+
+#[tokio::test]
+async fn test_listings_hide_attribute_sidecars() {
+    let (storage, temp) = create_temp_storage();
+    let options = PutOptions::new().content_type("text/plain");
+    for id in ["a.txt", "dir/b.txt"] {
+        storage
+            .put_with(id.to_string(), &b"x"[..], Some(1), options.clone())
+            .await
+            .unwrap();
+    }
+    assert!(temp.path().join("a.txt.meta.stowage").is_file());
+
+    let mut ids: Vec<String> = storage
+        .list(None)
+        .await
+        .unwrap()
+        .map(|id| id.unwrap())
+        .collect()
+        .await;
+    ids.sort();
+    assert_eq!(ids, vec!["a.txt", "dir/b.txt"]);
+
+    let page = storage.list_page(None, 10, None).await.unwrap();
+    assert_eq!(page.ids, vec!["a.txt", "dir/b.txt"]);
+
+    let listing = storage.list_dir(Some(&"dir".to_string())).await.unwrap();
+    assert_eq!(listing.files, vec!["dir/b.txt"]);
+
+    // Deleting an item removes its sidecar, so its folder can be emptied
+    storage.delete(&"dir/b.txt".to_string()).await.unwrap();
+    assert_eq!(
+        std::fs::read_dir(temp.path().join("dir")).unwrap().count(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_items_named_like_sidecars() {
+    let (storage, temp) = create_temp_storage();
+    let options = PutOptions::new().content_type("text/plain");
+    storage
+        .put_with("a.txt".to_string(), &b"a"[..], Some(1), options)
+        .await
+        .unwrap();
+
+    let ids = ["a.txt.meta.stowage", "dir.stowage/x.1-2.tmp.stowage"];
+    for id in ids {
+        storage
+            .put_bytes(id.to_string(), id.as_bytes())
+            .await
+            .unwrap();
+    }
+    assert!(temp.path().join("a.txt.meta.stowage.stowage").is_file());
+    assert!(
+        temp.path()
+            .join("dir.stowage.stowage/x.1-2.tmp.stowage.stowage")
+            .is_file()
+    );
+
+    // The item did not replace the sidecar of a.txt
+    let meta = storage.stat(&"a.txt".to_string()).await.unwrap();
+    assert_eq!(meta.content_type.as_deref(), Some("text/plain"));
+    for id in ids {
+        let content = storage.get_string(&id.to_string()).await.unwrap();
+        assert_eq!(content, id);
+    }
+
+    let listed: Vec<String> = storage
+        .list(None)
+        .await
+        .unwrap()
+        .map(|id| id.unwrap())
+        .collect()
+        .await;
+    assert_eq!(
+        listed,
+        [
+            "a.txt",
+            "a.txt.meta.stowage",
+            "dir.stowage/x.1-2.tmp.stowage"
+        ]
+    );
+    let page = storage.list_page(None, 2, None).await.unwrap();
+    assert_eq!(page.ids, ["a.txt", "a.txt.meta.stowage"]);
+    let page = storage.list_page(None, 2, page.next).await.unwrap();
+    assert_eq!(page.ids, ["dir.stowage/x.1-2.tmp.stowage"]);
+    let listing = storage.list_dir(None).await.unwrap();
+    assert_eq!(listing.files, ["a.txt", "a.txt.meta.stowage"]);
+    assert_eq!(listing.prefixes, ["dir.stowage"]);
+}
+
+// Windows rejects escaped ids, since `\` separates folders there
+#[cfg(unix)]
+#[tokio::test]
+async fn test_escaped_ids_are_stored_as_they_are() {
+    let (storage, temp) = create_temp_storage();
+    // The keys `a//b` and `x\y` of an object store
+    for id in [r"a\2F/b", r"x\5Cy"] {
+        storage.put_bytes(id.to_string(), b"x").await.unwrap();
+        assert!(temp.path().join(id).is_file(), "{id}");
+    }
+
+    // Names no id can address make the listing fail
+    std::fs::write(temp.path().join("odd\\name"), b"x").unwrap();
+    assert!(matches!(
+        storage.list_page(None, 10, None).await,
+        Err(Error::InvalidId(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_path_ids() {
+    let temp = TempDir::new().unwrap();
+    let storage = LocalStorage::new(temp.path()).with_path_ids();
+    let docs = StoragePath::parse("docs").unwrap();
+    let path = docs.join("report.txt").unwrap();
+
+    storage.put_bytes(path.clone(), b"hello").await.unwrap();
+    assert_eq!(storage.get_string(&path).await.unwrap(), "hello");
+    let page = storage.list_page(None, 10, None).await.unwrap();
+    assert_eq!(page.ids, [StoragePath::parse("docs/report.txt").unwrap()]);
+    let listing = storage.list_dir(None).await.unwrap();
+    assert_eq!(listing.prefixes, [StoragePath::parse("docs").unwrap()]);
+
+    storage.delete_folder(&docs).await.unwrap();
+    assert!(!storage.exists(&path).await.unwrap());
+}
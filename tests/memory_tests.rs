@@ -74,6 +74,66 @@ async fn test_special_characters() {
     test_common::test_special_characters(&mut || async { MemoryStorage::new() }).await;
 }
 
+#[tokio::test]
+async fn test_stat() {
+    test_common::test_stat(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_get_range() {
+    test_common::test_get_range(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_copy() {
+    test_common::test_copy(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_rename() {
+    test_common::test_rename(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_put_with_preconditions() {
+    test_common::test_put_with_preconditions(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_put_with_attributes() {
+    test_common::test_put_with_attributes(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_list_dir() {
+    test_common::test_list_dir(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_list_with_meta() {
+    test_common::test_list_with_meta(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_list_page() {
+    test_common::test_list_page(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_delete_many() {
+    test_common::test_delete_many(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_delete_prefix() {
+    test_common::test_delete_prefix(&mut || async { MemoryStorage::new() }).await;
+}
+
+#[tokio::test]
+async fn test_delete_folder() {
+    test_common::test_delete_folder(&mut || async { MemoryStorage::new() }).await;
+}
+
 // MemoryStorage-specific tests
 
 #[tokio::test]
@@ -1,7 +1,7 @@
 //! Integration tests for multi-storage patterns.
 
 use stowage::multi::{FallbackStorage, MirrorStorage, ReadOnlyStorage, WriteStrategy};
-use stowage::{MemoryStorage, Storage, StorageExt};
+use stowage::{Error, MemoryStorage, PutOptions, Storage, StorageExt};
 
 #[tokio::test]
 async fn test_fallback_basic() {
@@ -60,6 +60,127 @@ async fn test_mirror_basic() {
     );
 }
 
+#[tokio::test]
+async fn test_mirror_copy_and_rename() {
+    let storage = MirrorStorage::builder()
+        .add_backend(MemoryStorage::new())
+        .add_backend(MemoryStorage::new())
+        .write_strategy(WriteStrategy::AllOrFail { rollback: true })
+        .build();
+
+    storage
+        .put_bytes("a.txt".to_string(), b"mirrored")
+        .await
+        .unwrap();
+
+    storage
+        .copy(&"a.txt".to_string(), "b.txt".to_string())
+        .await
+        .unwrap();
+    storage
+        .rename(&"a.txt".to_string(), "c.txt".to_string())
+        .await
+        .unwrap();
+
+    for i in 0..2 {
+        let backend = storage.backend(i).unwrap();
+        assert!(!backend.exists(&"a.txt".to_string()).await.unwrap());
+        assert_eq!(backend.get_bytes("b.txt").unwrap(), b"mirrored");
+        assert_eq!(backend.get_bytes("c.txt").unwrap(), b"mirrored");
+    }
+}
+
+#[tokio::test]
+async fn test_mirror_conditional_put() {
+    let storage = MirrorStorage::builder()
+        .add_backend(MemoryStorage::new())
+        .add_backend(MemoryStorage::new())
+        .write_strategy(WriteStrategy::AllOrFail { rollback: false })
+        .build();
+    let id = "manifest.json".to_string();
+
+    storage
+        .put_with(
+            id.clone(),
+            &b"v1"[..],
+            None,
+            PutOptions::new().if_none_match("*"),
+        )
+        .await
+        .unwrap();
+    let etag = storage.stat(&id).await.unwrap().etag.unwrap();
+
+    // A second create-only write is rejected by the primary
+    let result = storage
+        .put_with(
+            id.clone(),
+            &b"v2"[..],
+            None,
+            PutOptions::new().if_none_match("*"),
+        )
+        .await;
+    assert!(matches!(result, Err(Error::PreconditionFailed(_))));
+
+    storage
+        .put_with(
+            id.clone(),
+            &b"v2"[..],
+            None,
+            PutOptions::new().if_match(etag),
+        )
+        .await
+        .unwrap();
+
+    for i in 0..2 {
+        assert_eq!(storage.backend(i).unwrap().get_bytes(&id).unwrap(), b"v2");
+    }
+}
+
+#[tokio::test]
+async fn test_put_with_attributes_reach_every_backend() {
+    let primary = MemoryStorage::new();
+    let secondary = MemoryStorage::new();
+    let mirror = MirrorStorage::builder()
+        .add_backend(primary.clone())
+        .add_backend(secondary.clone())
+        .write_strategy(WriteStrategy::AllOrFail { rollback: false })
+        .build();
+    let id = "page.html".to_string();
+    let options = PutOptions::new().content_type("text/html");
+
+    mirror
+        .put_with(id.clone(), &b"<p>"[..], None, options.clone())
+        .await
+        .unwrap();
+    // Conditional writes bring the other backends in line with attributes too
+    mirror
+        .put_with(
+            id.clone(),
+            &b"<p>!"[..],
+            None,
+            options.clone().if_none_match("stale"),
+        )
+        .await
+        .unwrap();
+    for backend in [&primary, &secondary] {
+        let meta = backend.stat(&id).await.unwrap();
+        assert_eq!(meta.content_type.as_deref(), Some("text/html"));
+    }
+
+    let primary = MemoryStorage::new();
+    let secondary = MemoryStorage::new();
+    let fallback =
+        FallbackStorage::new(primary.clone(), secondary.clone()).with_write_through(true);
+    fallback
+        .put_with(id.clone(), &b"<p>"[..], None, options)
+        .await
+        .unwrap();
+    for backend in [&primary, &secondary] {
+        let meta = backend.stat(&id).await.unwrap();
+        assert_eq!(meta.content_type.as_deref(), Some("text/html"));
+    }
+}
+
 #[tokio::test]
 async fn test_readonly_basic() {
     let inner = MemoryStorage::new();
@@ -187,29 +308,74 @@ async fn test_mirror_rollback_on_failure() {
 
 #[tokio::test]
 async fn test_mirror_error_contains_details() {
+    // The second backend rejects every write
     let storage = MirrorStorage::builder()
-        .add_backend(MemoryStorage::new())
-        .add_backend(MemoryStorage::new())
-        .write_strategy(WriteStrategy::AllOrFail { rollback: false })
+        .add_backend(MemoryStorage::new().boxed())
+        .add_backend(ReadOnlyStorage::new(MemoryStorage::new()).boxed())
+        .write_strategy(WriteStrategy::AllOrFail { rollback: true })
         .build();
 
-    // Normal write succeeds
+    let result = storage.put_bytes("test.txt".to_string(), b"data").await;
+    let Err(Error::MirrorFailure(details)) = result else {
+        panic!("Expected MirrorFailure, got {result:?}");
+    };
+
+    assert_eq!(details.successful_indices(), &[0]);
+    assert_eq!(details.failed_indices(), vec![1]);
+    assert!(!details.has_rollback_errors());
+
+    // The backend's own error is kept, not flattened into a message
+    assert!(matches!(
+        details.error_for(1),
+        Some(Error::PermissionDenied(_))
+    ));
+    assert!(details.error_for(0).is_none());
+    let source = std::error::Error::source(&details).unwrap();
+    assert!(matches!(
+        source.downcast_ref::<Error>(),
+        Some(Error::PermissionDenied(_))
+    ));
+
+    // Every backend that finished has its timing recorded
+    assert!(details.elapsed_for(0).is_some());
+    assert!(details.elapsed_for(1).is_some());
+    assert!(details.elapsed_for(2).is_none());
+
+    // The successful write was rolled back
+    assert!(
+        !storage
+            .backend(0)
+            .unwrap()
+            .exists(&"test.txt".to_string())
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_mirror_failure_keeps_errors_for_copy() {
+    let storage = MirrorStorage::builder()
+        .add_backend(MemoryStorage::new().boxed())
+        .add_backend(MemoryStorage::new().boxed())
+        .write_strategy(WriteStrategy::AllOrFail { rollback: false })
+        .build();
     storage
-        .put_bytes("test.txt".to_string(), b"data")
+        .backend(0)
+        .unwrap()
+        .put_bytes("only-first.txt".to_string(), b"data")
         .await
         .unwrap();
 
-    // The structure supports detailed error reporting
-    // In a real scenario where one backend fails, Error::MirrorFailure(details) would contain:
-    // - details.successes: Vec<usize> of backend indices that succeeded
-    // - details.failures: Vec<(usize, Box<Error>)> with full error objects
-    // - details.rollback_errors: Vec<(usize, Box<Error>)> if rollback attempted
-    //
-    // Helpful methods available:
-    // - details.success_count(), details.failure_count(), details.total_backends()
-    // - details.has_successes(), details.has_failures(), details.has_rollback_errors()
-    // - details.failed_indices(), details.successful_indices()
-    assert_eq!(storage.backend_count(), 2);
+    // The copy fails on the backend that lacks the source
+    let result = storage
+        .copy(&"only-first.txt".to_string(), "copy.txt".to_string())
+        .await;
+    let Err(Error::MirrorFailure(details)) = result else {
+        panic!("Expected MirrorFailure, got {result:?}");
+    };
+    assert!(matches!(details.error_for(1), Some(Error::NotFound(_))));
+    assert_eq!(details.elapsed.len(), 2);
+    assert!(!Error::MirrorFailure(details).is_retryable());
 }
 
 #[tokio::test]
@@ -0,0 +1,109 @@
+//! Tests for OneDriveStorage against a mock Graph endpoint
+
+#![cfg(feature = "onedrive")]
+
+#[path = "test_common/mod.rs"]
+mod test_common;
+
+use reqwest::{Client, Url};
+use stowage::adapters::onedrive::{OneDriveStorage, TokenProvider};
+use test_common::{MockRequest, MockResponse, MockServer};
+
+fn storage(server: &MockServer) -> OneDriveStorage {
+    OneDriveStorage::new(Client::new(), TokenProvider::Static("token".into()))
+        .unwrap()
+        .with_base_url(Url::parse(&format!("{}/v1.0/", server.url())).unwrap())
+}
+
+/// Simple uploads: create the item unless the name is `taken.txt`, which
+/// the folder already holds.
+fn simple_upload(req: &MockRequest) -> MockResponse {
+    if req.method != "PUT" || !req.target.contains(":/content?") {
+        return MockResponse::new(404);
+    }
+    if req.target.contains("/taken.txt:/") {
+        return MockResponse::json(
+            409,
+            serde_json::json!({
+                "error": { "code": "nameAlreadyExists", "message": "Name already exists" }
+            }),
+        );
+    }
+    MockResponse::json(
+        201,
+        serde_json::json!({ "id": "new-id", "name": "notes.txt" }),
+    )
+}
+
+#[tokio::test]
+async fn test_create_uploads_into_the_parent() {
+    let server = MockServer::start(simple_upload).await;
+    let storage = storage(&server);
+
+    let id = storage
+        .create("parent-id", "notes.txt", &b"hello"[..], Some(5))
+        .await
+        .unwrap();
+    assert_eq!(id, "new-id");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(
+        requests[0].target,
+        "/v1.0/me/drive/items/parent-id:/notes.txt:/content\
+         ?%40microsoft.graph.conflictBehavior=fail"
+    );
+    assert_eq!(requests[0].body, b"hello");
+}
+
+#[tokio::test]
+async fn test_create_reports_existing_names() {
+    let server = MockServer::start(simple_upload).await;
+    let storage = storage(&server);
+
+    let result = storage
+        .create("parent-id", "taken.txt", &b"hello"[..], Some(5))
+        .await;
+    assert!(
+        matches!(result, Err(stowage::Error::AlreadyExists(_))),
+        "{result:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_large_create_fails_on_existing_names() {
+    let server = MockServer::start(|req| {
+        if req.target.ends_with("/createUploadSession") {
+            return MockResponse::json(
+                409,
+                serde_json::json!({
+                    "error": { "code": "nameAlreadyExists", "message": "Name already exists" }
+                }),
+            );
+        }
+        MockResponse::new(404)
+    })
+    .await;
+    let storage = storage(&server);
+
+    let result = storage
+        .create(
+            "parent-id",
+            "big.bin",
+            test_common::PatternReader::new(16 * 1024 * 1024),
+            Some(16 * 1024 * 1024),
+        )
+        .await;
+    assert!(
+        matches!(result, Err(stowage::Error::AlreadyExists(_))),
+        "{result:?}"
+    );
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(
+        body.pointer("/item/@microsoft.graph.conflictBehavior"),
+        Some(&serde_json::json!("fail"))
+    );
+}
@@ -0,0 +1,376 @@
+//! Tests for PathResolvingStorage over an in-memory ID-based drive
+
+use futures::stream::{BoxStream, StreamExt};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use stowage::multi::{DriveItem, DriveTree, PathResolvingStorage};
+use stowage::{Error, MemoryStorage, ObjectMeta, Result, Storage, StorageExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A drive that stores contents by opaque ID and keeps a parent/child tree,
+/// like Google Drive or OneDrive.
+#[derive(Debug, Default)]
+struct FakeDrive {
+    blobs: MemoryStorage,
+    /// Item ID -> (parent ID, item).
+    items: Mutex<HashMap<String, (String, DriveItem)>>,
+    next_id: AtomicUsize,
+    lookups: AtomicUsize,
+}
+
+impl FakeDrive {
+    fn add(&self, parent_id: &str, name: &str, is_folder: bool) -> String {
+        let id = format!("id{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let item = DriveItem {
+            id: id.clone(),
+            name: name.to_string(),
+            is_folder,
+        };
+        self.items
+            .lock()
+            .unwrap()
+            .insert(id.clone(), (parent_id.to_string(), item));
+        id
+    }
+
+    /// Remove an item and everything below it.
+    async fn remove(&self, id: &str) -> Result<()> {
+        let mut removed = Vec::new();
+        {
+            let mut items = self.items.lock().unwrap();
+            let mut pending = vec![id.to_string()];
+            while let Some(id) = pending.pop() {
+                items.remove(&id);
+                pending.extend(
+                    items
+                        .iter()
+                        .filter(|(_, (parent, _))| *parent == id)
+                        .map(|(child, _)| child.clone()),
+                );
+                removed.push(id);
+            }
+        }
+        for id in removed {
+            self.blobs.delete(&id).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Storage for FakeDrive {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        self.blobs.exists(id).await
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        Ok(id == "root"
+            || self
+                .items
+                .lock()
+                .unwrap()
+                .get(id)
+                .is_some_and(|(_, item)| item.is_folder))
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        self.blobs.stat(id).await
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        self.blobs.put(id, input, len).await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        output: W,
+    ) -> Result<u64> {
+        self.blobs.get_into(id, output).await
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        self.remove(id).await
+    }
+
+    async fn delete_folder(&self, folder: &String) -> Result<()> {
+        self.remove(folder).await
+    }
+
+    async fn list(&self, _prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        self.blobs.list(None).await
+    }
+}
+
+impl DriveTree for FakeDrive {
+    fn root_folder_id(&self) -> String {
+        "root".to_string()
+    }
+
+    async fn list_children(&self, parent_id: &str) -> Result<Vec<DriveItem>> {
+        self.lookups.fetch_add(1, Ordering::SeqCst);
+        // Let concurrent calls interleave, as network round trips would.
+        tokio::task::yield_now().await;
+        Ok(self
+            .items
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|(parent, _)| parent == parent_id)
+            .map(|(_, item)| item.clone())
+            .collect())
+    }
+
+    async fn create_folder(&self, parent_id: &str, name: &str) -> Result<String> {
+        tokio::task::yield_now().await;
+        Ok(self.add(parent_id, name, true))
+    }
+
+    async fn create_file<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        parent_id: &str,
+        name: &str,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<String> {
+        tokio::task::yield_now().await;
+        let id = self.add(parent_id, name, false);
+        self.blobs.put(id.clone(), input, len).await?;
+        Ok(id)
+    }
+}
+
+fn path(p: &str) -> String {
+    p.to_string()
+}
+
+#[tokio::test]
+async fn test_put_creates_folders_and_get_resolves() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+
+    storage
+        .put_bytes(path("reports/2024/q1.csv"), b"a,b")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        storage
+            .get_string(&path("reports/2024/q1.csv"))
+            .await
+            .unwrap(),
+        "a,b"
+    );
+    assert!(storage.folder_exists(&path("reports")).await.unwrap());
+    assert!(storage.folder_exists(&path("reports/2024")).await.unwrap());
+    assert!(!storage.exists(&path("reports/2024")).await.unwrap());
+
+    let id = storage
+        .resolve("reports/2024/q1.csv")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(storage.inner().get_string(&id).await.unwrap(), "a,b");
+    assert_eq!(storage.resolve("reports/missing.csv").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_put_existing_updates_in_place() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+
+    storage.put_bytes(path("docs/a.txt"), b"one").await.unwrap();
+    let first = storage.resolve("docs/a.txt").await.unwrap();
+    storage.put_bytes(path("docs/a.txt"), b"two").await.unwrap();
+
+    assert_eq!(storage.resolve("docs/a.txt").await.unwrap(), first);
+    assert_eq!(
+        storage.get_string(&path("docs/a.txt")).await.unwrap(),
+        "two"
+    );
+    assert_eq!(storage.inner().items.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_invalid_paths_rejected() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+
+    let result = storage.put_bytes(path("../escape.txt"), b"x").await;
+    assert!(matches!(result, Err(Error::PermissionDenied(_))));
+    let result = storage.put_bytes(path("a//b.txt"), b"x").await;
+    assert!(matches!(result, Err(Error::InvalidId(_))));
+}
+
+#[tokio::test]
+async fn test_get_missing_and_folder_is_not_found() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+    storage.put_bytes(path("dir/file.txt"), b"x").await.unwrap();
+
+    assert!(matches!(
+        storage.get_bytes(&path("dir/other.txt")).await,
+        Err(Error::NotFound(_))
+    ));
+    assert!(matches!(
+        storage.get_bytes(&path("dir")).await,
+        Err(Error::NotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_list_and_list_dir_return_paths() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+    for p in ["a.txt", "dir/b.txt", "dir/sub/c.txt"] {
+        storage.put_bytes(path(p), b"x").await.unwrap();
+    }
+
+    let all: Vec<String> = storage
+        .list(None)
+        .await
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect()
+        .await;
+    assert_eq!(all, ["a.txt", "dir/b.txt", "dir/sub/c.txt"]);
+
+    let nested: Vec<String> = storage
+        .list(Some(&path("dir")))
+        .await
+        .unwrap()
+        .map(|r| r.unwrap())
+        .collect()
+        .await;
+    assert_eq!(nested, ["dir/b.txt", "dir/sub/c.txt"]);
+
+    let listing = storage.list_dir(Some(&path("dir"))).await.unwrap();
+    assert_eq!(listing.files, ["dir/b.txt"]);
+    assert_eq!(listing.prefixes, ["dir/sub"]);
+}
+
+#[tokio::test]
+async fn test_list_matches_string_prefixes() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+    for p in [
+        "reports/q1.csv",
+        "reports/2024/q2.csv",
+        "repo.txt",
+        "data/r.txt",
+    ] {
+        storage.put_bytes(path(p), b"x").await.unwrap();
+    }
+
+    for (prefix, expected) in [
+        (
+            "rep",
+            vec!["repo.txt", "reports/2024/q2.csv", "reports/q1.csv"],
+        ),
+        ("reports/", vec!["reports/2024/q2.csv", "reports/q1.csv"]),
+        ("reports/q", vec!["reports/q1.csv"]),
+        ("reports/2024/q2.csv", vec!["reports/2024/q2.csv"]),
+        ("missing/", vec![]),
+    ] {
+        let mut listed: Vec<String> = storage
+            .list(Some(&path(prefix)))
+            .await
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+            .await;
+        listed.sort();
+        assert_eq!(listed, expected, "{prefix:?}");
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_puts_create_each_item_once() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+
+    let puts = (0..8).map(|i| {
+        let id = match i % 2 {
+            0 => format!("shared/new/file{i}.txt"),
+            _ => "shared/same.txt".to_string(),
+        };
+        let storage = &storage;
+        async move { storage.put_bytes(id, b"x").await }
+    });
+    for result in futures::future::join_all(puts).await {
+        result.unwrap();
+    }
+
+    let items = storage.inner().items.lock().unwrap().clone();
+    let count = |name: &str| items.values().filter(|(_, i)| i.name == name).count();
+    assert_eq!(count("shared"), 1);
+    assert_eq!(count("new"), 1);
+    assert_eq!(count("same.txt"), 1);
+    assert_eq!(items.len(), 3 + 4);
+}
+
+#[tokio::test]
+async fn test_lookups_are_cached() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+    storage.put_bytes(path("a/b/c.txt"), b"x").await.unwrap();
+
+    let before = storage.inner().lookups.load(Ordering::SeqCst);
+    storage.get_bytes(&path("a/b/c.txt")).await.unwrap();
+    storage.stat(&path("a/b/c.txt")).await.unwrap();
+    assert_eq!(storage.inner().lookups.load(Ordering::SeqCst), before);
+
+    storage.clear_cache();
+    storage.get_bytes(&path("a/b/c.txt")).await.unwrap();
+    assert!(storage.inner().lookups.load(Ordering::SeqCst) > before);
+}
+
+#[tokio::test]
+async fn test_delete_invalidates_cache() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+    storage.put_bytes(path("dir/a.txt"), b"x").await.unwrap();
+    storage.put_bytes(path("dir/b.txt"), b"y").await.unwrap();
+
+    storage.delete(&path("dir/a.txt")).await.unwrap();
+    assert!(!storage.exists(&path("dir/a.txt")).await.unwrap());
+    // Deleting again is a no-op
+    storage.delete(&path("dir/a.txt")).await.unwrap();
+
+    storage.delete_folder(&path("dir")).await.unwrap();
+    assert!(!storage.folder_exists(&path("dir")).await.unwrap());
+    assert_eq!(storage.resolve("dir/b.txt").await.unwrap(), None);
+
+    // Recreating the path after a folder delete creates fresh folders
+    storage.put_bytes(path("dir/b.txt"), b"z").await.unwrap();
+    assert_eq!(storage.get_string(&path("dir/b.txt")).await.unwrap(), "z");
+}
+
+#[tokio::test]
+async fn test_out_of_band_delete_drops_stale_entry() {
+    let storage = PathResolvingStorage::new(FakeDrive::default());
+    storage.put_bytes(path("dir/a.txt"), b"x").await.unwrap();
+    let id = storage.resolve("dir/a.txt").await.unwrap().unwrap();
+
+    // Delete directly on the drive, leaving the wrapper's cache stale
+    storage.inner().delete(&id).await.unwrap();
+
+    assert!(matches!(
+        storage.get_bytes(&path("dir/a.txt")).await,
+        Err(Error::NotFound(_))
+    ));
+    storage.put_bytes(path("dir/a.txt"), b"new").await.unwrap();
+    assert_ne!(storage.resolve("dir/a.txt").await.unwrap(), Some(id));
+    assert_eq!(storage.get_string(&path("dir/a.txt")).await.unwrap(), "new");
+}
+
+#[tokio::test]
+async fn test_with_root_scopes_paths() {
+    let drive = FakeDrive::default();
+    let scoped = drive.add("root", "scoped", true);
+    let storage = PathResolvingStorage::with_root(drive, scoped.clone());
+
+    storage.put_bytes(path("file.txt"), b"x").await.unwrap();
+
+    let items = storage.inner().items.lock().unwrap().clone();
+    let (parent, _) = items.values().find(|(_, i)| i.name == "file.txt").unwrap();
+    assert_eq!(parent, &scoped);
+}
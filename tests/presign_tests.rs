@@ -0,0 +1,112 @@
+//! Tests for presigned URLs through the wrapping storages
+
+#![cfg(feature = "memory")]
+
+use futures::stream::BoxStream;
+use std::time::{Duration, SystemTime};
+use stowage::multi::{PrefixedStorage, ReadOnlyStorage};
+use stowage::{
+    Error, MemoryStorage, ObjectMeta, Presign, PresignMethod, PresignedRequest, Result, Storage,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Signs fake URLs naming the item and method.
+#[derive(Debug, Default)]
+struct FakeSigner {
+    inner: MemoryStorage,
+}
+
+impl Storage for FakeSigner {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        self.inner.exists(id).await
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        self.inner.folder_exists(id).await
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        self.inner.stat(id).await
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        self.inner.put(id, input, len).await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        output: W,
+    ) -> Result<u64> {
+        self.inner.get_into(id, output).await
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        self.inner.delete(id).await
+    }
+
+    async fn list(&self, prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        self.inner.list(prefix).await
+    }
+}
+
+impl Presign for FakeSigner {
+    async fn presign(
+        &self,
+        id: &String,
+        method: PresignMethod,
+        expires_in: Duration,
+    ) -> Result<PresignedRequest> {
+        Ok(PresignedRequest {
+            method,
+            url: format!("https://example.com/{id}?op={}", method.as_str()),
+            headers: Vec::new(),
+            expires_at: SystemTime::now() + expires_in,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_prefixed_presigns_full_key() {
+    let storage = PrefixedStorage::new(FakeSigner::default(), "tenants/acme");
+
+    let request = storage
+        .presign(
+            &"report.csv".to_string(),
+            PresignMethod::Put,
+            Duration::from_secs(60),
+        )
+        .await
+        .unwrap();
+    assert_eq!(request.method, PresignMethod::Put);
+    assert_eq!(
+        request.url,
+        "https://example.com/tenants/acme/report.csv?op=PUT"
+    );
+}
+
+#[tokio::test]
+async fn test_readonly_presigns_downloads_only() {
+    let storage = ReadOnlyStorage::new(FakeSigner::default());
+    let id = "file.txt".to_string();
+
+    let request = storage
+        .presign(&id, PresignMethod::Get, Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert_eq!(request.url, "https://example.com/file.txt?op=GET");
+
+    assert!(matches!(
+        storage
+            .presign(&id, PresignMethod::Put, Duration::from_secs(60))
+            .await,
+        Err(Error::PermissionDenied(_))
+    ));
+}
@@ -2,7 +2,7 @@
 
 use futures::stream::StreamExt;
 use stowage::multi::ReadOnlyStorage;
-use stowage::{Error, MemoryStorage, Storage, StorageExt};
+use stowage::{Error, MemoryStorage, PutOptions, Storage, StorageExt};
 
 #[tokio::test]
 async fn test_new_readonly_storage() {
@@ -163,6 +163,23 @@ async fn test_put_is_rejected() {
     assert!(matches!(result.unwrap_err(), Error::PermissionDenied(_)));
 }
 
+#[tokio::test]
+async fn test_put_with_is_rejected() {
+    let inner = MemoryStorage::new();
+    let storage = ReadOnlyStorage::new(inner.clone());
+
+    let result = storage
+        .put_with(
+            "test.txt".to_string(),
+            &b"data"[..],
+            None,
+            PutOptions::new().if_none_match("*"),
+        )
+        .await;
+    assert!(matches!(result.unwrap_err(), Error::PermissionDenied(_)));
+    assert!(inner.is_empty());
+}
+
 #[tokio::test]
 async fn test_delete_is_rejected() {
     let inner = MemoryStorage::new();
@@ -187,6 +204,27 @@ async fn test_delete_nonexistent_is_rejected() {
     assert!(matches!(result.unwrap_err(), Error::PermissionDenied(_)));
 }
 
+#[tokio::test]
+async fn test_copy_and_rename_are_rejected() {
+    let inner = MemoryStorage::new();
+    StorageExt::put_bytes(&inner, "test.txt".to_string(), b"data")
+        .await
+        .unwrap();
+
+    let storage = ReadOnlyStorage::new(inner.clone());
+    let src = "test.txt".to_string();
+
+    let result = storage.copy(&src, "copy.txt".to_string()).await;
+    assert!(matches!(result.unwrap_err(), Error::PermissionDenied(_)));
+
+    let result = storage.rename(&src, "moved.txt".to_string()).await;
+    assert!(matches!(result.unwrap_err(), Error::PermissionDenied(_)));
+
+    // Inner storage is unchanged
+    assert!(inner.exists(&src).await.unwrap());
+    assert_eq!(inner.len(), 1);
+}
+
 #[tokio::test]
 async fn test_get_nonexistent_returns_error() {
     let inner = MemoryStorage::new();
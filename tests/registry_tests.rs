@@ -0,0 +1,110 @@
+//! Tests for URL-based storage construction
+
+use stowage::multi::{PrefixedStorage, ReadOnlyStorage};
+use stowage::{Error, MemoryStorage, Storage, StorageExt, StorageRegistry};
+
+#[path = "test_common/mod.rs"]
+mod test_common;
+
+#[tokio::test]
+async fn test_registry_opens_memory() {
+    let registry = StorageRegistry::new();
+    assert!(registry.supports("memory"));
+
+    let storage = registry.open("memory://").await.unwrap();
+    storage
+        .put_bytes("file.txt".to_string(), b"hello")
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.get_string(&"file.txt".to_string()).await.unwrap(),
+        "hello"
+    );
+}
+
+#[tokio::test]
+async fn test_registry_unknown_scheme() {
+    let registry = StorageRegistry::new();
+    let result = registry.open("nosuch://host/path").await;
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+
+    let result = StorageRegistry::empty().open("memory://").await;
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+}
+
+#[tokio::test]
+async fn test_registry_invalid_url() {
+    let registry = StorageRegistry::new();
+    for url in [
+        "memory",
+        "://host",
+        "memory://host:notaport",
+        "memory://%zz",
+    ] {
+        let result = registry.open(url).await;
+        assert!(matches!(result, Err(Error::InvalidId(_))), "{url}");
+    }
+}
+
+#[cfg(not(feature = "s3"))]
+#[tokio::test]
+async fn test_registry_disabled_feature() {
+    let registry = StorageRegistry::new();
+    assert!(!registry.supports("s3"));
+    let result = registry.open("s3://bucket").await;
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+}
+
+#[tokio::test]
+async fn test_registry_custom_scheme() {
+    let mut registry = StorageRegistry::empty();
+    registry.register("Tenant", |url| async move {
+        assert_eq!(url.host(), "acme");
+        Ok(PrefixedStorage::new(MemoryStorage::new(), url.host()))
+    });
+    registry.register("fixtures", |_url| async {
+        Ok(ReadOnlyStorage::new(MemoryStorage::new()))
+    });
+    assert_eq!(registry.schemes(), vec!["fixtures", "tenant"]);
+
+    let storage = registry.open("TENANT://acme").await.unwrap();
+    storage
+        .put_bytes("file.txt".to_string(), b"data")
+        .await
+        .unwrap();
+    assert!(storage.exists(&"file.txt".to_string()).await.unwrap());
+
+    let storage = registry.open("fixtures://").await.unwrap();
+    let result = storage.put_bytes("file.txt".to_string(), b"data").await;
+    assert!(matches!(result, Err(Error::PermissionDenied(_))));
+}
+
+#[tokio::test]
+async fn test_prefixed_storage_passes_common_suite() {
+    test_common::run_all_tests(|| async {
+        PrefixedStorage::new(MemoryStorage::new(), "scoped/prefix")
+    })
+    .await;
+}
+
+#[cfg(feature = "local")]
+#[tokio::test]
+async fn test_registry_opens_local() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = format!("file://{}", dir.path().display());
+
+    let storage = StorageRegistry::new().open(&url).await.unwrap();
+    storage
+        .put_bytes("nested/file.txt".to_string(), b"local")
+        .await
+        .unwrap();
+    assert!(dir.path().join("nested/file.txt").exists());
+
+    assert!(StorageRegistry::new().open("file://").await.is_err());
+    assert!(
+        StorageRegistry::new()
+            .open("file://remote/var/data")
+            .await
+            .is_err()
+    );
+}
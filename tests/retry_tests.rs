@@ -0,0 +1,280 @@
+//! Tests for RetryStorage
+
+#![cfg(feature = "memory")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use futures::stream::BoxStream;
+use stowage::multi::RetryStorage;
+use stowage::{Error, MemoryStorage, ObjectMeta, PutOptions, Result, Storage, StorageExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Fails the next `failures` calls, with `RateLimited` if `retry_after` is
+/// set and `Unavailable` otherwise, and counts every call.
+#[derive(Debug, Default)]
+struct Flaky {
+    inner: MemoryStorage,
+    failures: AtomicUsize,
+    calls: AtomicUsize,
+    retry_after: Option<Duration>,
+    /// Write this many bytes of the item before a failing download fails.
+    partial: usize,
+}
+
+impl Flaky {
+    fn failing(failures: usize) -> Self {
+        Self {
+            failures: AtomicUsize::new(failures),
+            ..Default::default()
+        }
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    fn check(&self) -> Result<()> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let failed = self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if !failed {
+            return Ok(());
+        }
+        Err(match self.retry_after {
+            Some(delay) => Error::RateLimited {
+                message: "slow down".to_string(),
+                retry_after: Some(delay),
+            },
+            None => Error::Unavailable("503".to_string()),
+        })
+    }
+}
+
+impl Storage for Flaky {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        self.check()?;
+        self.inner.exists(id).await
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        self.check()?;
+        self.inner.folder_exists(id).await
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        self.check()?;
+        self.inner.stat(id).await
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        mut input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data).await?;
+        self.check()?;
+        self.inner.put(id, &data[..], len).await
+    }
+
+    async fn put_with<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        mut input: R,
+        len: Option<u64>,
+        options: PutOptions,
+    ) -> Result<()> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data).await?;
+        self.check()?;
+        self.inner.put_with(id, &data[..], len, options).await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        mut output: W,
+    ) -> Result<u64> {
+        let data = self.inner.get_bytes(id)?;
+        if let Err(e) = self.check() {
+            output.write_all(&data[..self.partial]).await?;
+            return Err(e);
+        }
+        output.write_all(&data).await?;
+        Ok(data.len() as u64)
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        self.check()?;
+        self.inner.delete(id).await
+    }
+
+    async fn list(&self, prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        self.check()?;
+        self.inner.list(prefix).await
+    }
+}
+
+fn retrying(inner: Flaky) -> RetryStorage<Flaky> {
+    RetryStorage::new(inner).with_backoff(Duration::from_millis(1), Duration::from_millis(5))
+}
+
+#[tokio::test]
+async fn test_retry_recovers_from_transient_errors() {
+    let storage = retrying(Flaky::failing(2));
+    storage
+        .inner()
+        .inner
+        .put_bytes("file.txt".to_string(), b"data")
+        .await
+        .unwrap();
+
+    assert!(storage.exists(&"file.txt".to_string()).await.unwrap());
+    assert_eq!(storage.inner().calls(), 3);
+}
+
+#[tokio::test]
+async fn test_retry_gives_up_after_max_attempts() {
+    let storage = retrying(Flaky::failing(10)).with_max_attempts(4);
+
+    let result = storage.stat(&"file.txt".to_string()).await;
+    assert!(matches!(result, Err(Error::Unavailable(_))));
+    assert_eq!(storage.inner().calls(), 4);
+}
+
+#[tokio::test]
+async fn test_retry_skips_permanent_errors() {
+    let storage = retrying(Flaky::default());
+
+    let result = storage.stat(&"missing.txt".to_string()).await;
+    assert!(matches!(result, Err(Error::NotFound(_))));
+    assert_eq!(storage.inner().calls(), 1);
+}
+
+#[tokio::test]
+async fn test_retry_replays_put_input() {
+    let storage = retrying(Flaky::failing(1));
+
+    // The first attempt consumes the input before failing
+    storage
+        .put_bytes("file.txt".to_string(), b"replayed")
+        .await
+        .unwrap();
+    assert_eq!(storage.inner().calls(), 2);
+    assert_eq!(
+        storage.get_string(&"file.txt".to_string()).await.unwrap(),
+        "replayed"
+    );
+}
+
+#[tokio::test]
+async fn test_retry_streams_large_put_once() {
+    let storage = retrying(Flaky::failing(1)).with_replay_limit(4);
+
+    let result = storage
+        .put_bytes("file.txt".to_string(), b"too large")
+        .await;
+    assert!(matches!(result, Err(Error::Unavailable(_))));
+    assert_eq!(storage.inner().calls(), 1);
+
+    // Everything was still sent on the single attempt
+    storage
+        .put("file.txt".to_string(), &b"too large"[..], None)
+        .await
+        .unwrap();
+    assert_eq!(
+        storage.get_string(&"file.txt".to_string()).await.unwrap(),
+        "too large"
+    );
+}
+
+#[tokio::test]
+async fn test_retry_skips_conditional_put() {
+    let storage = retrying(Flaky::failing(1));
+
+    let result = storage
+        .put_with(
+            "file.txt".to_string(),
+            &b"data"[..],
+            None,
+            PutOptions::new().if_none_match("*"),
+        )
+        .await;
+    assert!(matches!(result, Err(Error::Unavailable(_))));
+    assert_eq!(storage.inner().calls(), 1);
+}
+
+#[tokio::test]
+async fn test_retry_download_only_before_output() {
+    let storage = retrying(Flaky::failing(1));
+    storage
+        .inner()
+        .inner
+        .put_bytes("file.txt".to_string(), b"content")
+        .await
+        .unwrap();
+
+    let mut output = Vec::new();
+    storage
+        .get_into(&"file.txt".to_string(), &mut output)
+        .await
+        .unwrap();
+    assert_eq!(output, b"content");
+    assert_eq!(storage.inner().calls(), 2);
+
+    // A failure after data was written is returned as is
+    let storage = retrying(Flaky {
+        failures: AtomicUsize::new(1),
+        partial: 3,
+        ..Default::default()
+    });
+    storage
+        .inner()
+        .inner
+        .put_bytes("file.txt".to_string(), b"content")
+        .await
+        .unwrap();
+
+    let mut output = Vec::new();
+    let result = storage.get_into(&"file.txt".to_string(), &mut output).await;
+    assert!(matches!(result, Err(Error::Unavailable(_))));
+    assert_eq!(output, b"con");
+    assert_eq!(storage.inner().calls(), 1);
+}
+
+#[tokio::test]
+async fn test_retry_honors_retry_after() {
+    let storage = retrying(Flaky {
+        failures: AtomicUsize::new(1),
+        retry_after: Some(Duration::from_millis(50)),
+        ..Default::default()
+    });
+
+    let started = Instant::now();
+    storage.delete(&"file.txt".to_string()).await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(50));
+    assert_eq!(storage.inner().calls(), 2);
+}
+
+#[tokio::test]
+async fn test_retry_respects_deadline() {
+    let storage = retrying(Flaky {
+        failures: AtomicUsize::new(1),
+        retry_after: Some(Duration::from_secs(60)),
+        ..Default::default()
+    })
+    .with_deadline(Duration::from_millis(100));
+
+    // Waiting as asked would pass the deadline, so the error comes back now
+    let started = Instant::now();
+    let result = storage.list(None).await;
+    assert!(matches!(result, Err(Error::RateLimited { .. })));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(storage.inner().calls(), 1);
+}
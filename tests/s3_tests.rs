@@ -194,6 +194,78 @@ mod s3_integration_tests {
         test_common::test_special_characters(&mut setup_test_storage).await;
     }
 
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_stat() {
+        test_common::test_stat(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_get_range() {
+        test_common::test_get_range(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_copy() {
+        test_common::test_copy(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_rename() {
+        test_common::test_rename(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_put_with_preconditions() {
+        test_common::test_put_with_preconditions(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_put_with_attributes() {
+        test_common::test_put_with_attributes(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_list_dir() {
+        test_common::test_list_dir(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_list_with_meta() {
+        test_common::test_list_with_meta(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_list_page() {
+        test_common::test_list_page(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_delete_many() {
+        test_common::test_delete_many(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_delete_prefix() {
+        test_common::test_delete_prefix(&mut setup_test_storage).await;
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_delete_folder() {
+        test_common::test_delete_folder(&mut setup_test_storage).await;
+    }
+
     // S3-specific tests
 
     #[tokio::test]
@@ -204,7 +276,7 @@ mod s3_integration_tests {
 
         let result = storage.put_bytes(empty_key.clone(), b"data").await;
         assert!(result.is_err());
-        assert!(matches!(result.unwrap_err(), Error::Generic(_)));
+        assert!(matches!(result.unwrap_err(), Error::InvalidId(_)));
 
         cleanup_storage(&storage).await;
     }
@@ -381,4 +453,40 @@ mod s3_integration_tests {
 
         cleanup_storage(&storage).await;
     }
+
+    // Presigning is done locally, so it needs no running service
+
+    #[tokio::test]
+    async fn test_s3_presign() {
+        use std::time::{Duration, SystemTime};
+        use stowage::{Presign, PresignMethod};
+
+        let storage = S3Storage::new(create_minio_client().await, "presign-bucket");
+        let id = "dir/file.txt".to_string();
+
+        let get = storage
+            .presign(&id, PresignMethod::Get, Duration::from_secs(300))
+            .await
+            .unwrap();
+        assert_eq!(get.method, PresignMethod::Get);
+        assert!(
+            get.url
+                .starts_with("http://localhost:9000/presign-bucket/dir/file.txt?")
+        );
+        assert!(get.url.contains("X-Amz-Expires=300"));
+        assert!(get.url.contains("X-Amz-Signature="));
+        assert!(get.expires_at > SystemTime::now());
+
+        let put = storage
+            .presign(&id, PresignMethod::Put, Duration::from_secs(300))
+            .await
+            .unwrap();
+        assert_ne!(put.url, get.url);
+
+        // SigV4 caps presigned URLs at a week
+        let result = storage
+            .presign(&id, PresignMethod::Get, Duration::from_secs(8 * 24 * 3600))
+            .await;
+        assert!(matches!(result, Err(Error::Generic(_))));
+    }
 }
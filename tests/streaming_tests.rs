@@ -0,0 +1,258 @@
+//! Tests for the writer- and reader-style transfer APIs
+
+#![cfg(feature = "memory")]
+
+use futures::stream::BoxStream;
+use stowage::multi::{FallbackStorage, ReadOnlyStorage};
+use stowage::{Error, MemoryStorage, ObjectMeta, Result, Storage, StorageExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+#[tokio::test]
+async fn test_put_writer_commits_on_finish() {
+    let storage = MemoryStorage::new();
+
+    let mut writer = storage.put_writer("out.txt".to_string());
+    writer.write_all(b"hello ").await.unwrap();
+    writer.write_all(b"world").await.unwrap();
+    assert!(!storage.exists(&"out.txt".to_string()).await.unwrap());
+
+    writer.finish().await.unwrap();
+    assert_eq!(
+        storage.get_string(&"out.txt".to_string()).await.unwrap(),
+        "hello world"
+    );
+}
+
+#[tokio::test]
+async fn test_put_writer_large_content() {
+    let storage = MemoryStorage::new();
+    let data: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+
+    let mut writer = storage.put_writer("big.bin".to_string());
+    for chunk in data.chunks(10_000) {
+        writer.write_all(chunk).await.unwrap();
+    }
+    writer.finish().await.unwrap();
+
+    assert_eq!(
+        StorageExt::get_bytes(&storage, &"big.bin".to_string())
+            .await
+            .unwrap(),
+        data
+    );
+}
+
+#[tokio::test]
+async fn test_put_writer_shutdown_commits() {
+    let storage = MemoryStorage::new();
+
+    let mut writer = storage.put_writer("out.txt".to_string());
+    writer.write_all(b"data").await.unwrap();
+    writer.shutdown().await.unwrap();
+    drop(writer);
+
+    assert_eq!(
+        storage.get_string(&"out.txt".to_string()).await.unwrap(),
+        "data"
+    );
+}
+
+#[tokio::test]
+async fn test_put_writer_drop_aborts() {
+    let storage = MemoryStorage::new();
+
+    let mut writer = storage.put_writer("out.txt".to_string());
+    writer.write_all(b"partial").await.unwrap();
+    drop(writer);
+
+    assert!(!storage.exists(&"out.txt".to_string()).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_put_writer_reports_upload_error() {
+    let storage = ReadOnlyStorage::new(MemoryStorage::new());
+
+    let mut writer = storage.put_writer("out.txt".to_string());
+    // The upload fails straight away, so writes fail too, with its error
+    let error = writer.write_all(b"data").await.unwrap_err();
+    assert!(matches!(Error::from_io(error), Error::PermissionDenied(_)));
+    let error = writer.shutdown().await.unwrap_err();
+    assert!(matches!(Error::from_io(error), Error::PermissionDenied(_)));
+    assert!(matches!(
+        writer.finish().await,
+        Err(Error::PermissionDenied(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_put_writer_as_download_target() {
+    let source = MemoryStorage::new();
+    let dest = MemoryStorage::new();
+    source
+        .put_bytes("file.txt".to_string(), b"copied")
+        .await
+        .unwrap();
+
+    let mut writer = dest.put_writer("file.txt".to_string());
+    source
+        .get_into(&"file.txt".to_string(), &mut writer)
+        .await
+        .unwrap();
+    writer.finish().await.unwrap();
+
+    assert_eq!(
+        dest.get_string(&"file.txt".to_string()).await.unwrap(),
+        "copied"
+    );
+}
+
+/// Implements only the required `Storage` methods, so `get_reader` uses the
+/// default that drives `get_into` through a pipe.
+#[derive(Debug, Default)]
+struct PushOnly {
+    inner: MemoryStorage,
+    /// Fail downloads after writing this many bytes.
+    fail_after: Option<usize>,
+}
+
+impl Storage for PushOnly {
+    type Id = String;
+
+    async fn exists(&self, id: &String) -> Result<bool> {
+        self.inner.exists(id).await
+    }
+
+    async fn folder_exists(&self, id: &String) -> Result<bool> {
+        self.inner.folder_exists(id).await
+    }
+
+    async fn stat(&self, id: &String) -> Result<ObjectMeta> {
+        self.inner.stat(id).await
+    }
+
+    async fn put<R: AsyncRead + Send + Sync + Unpin>(
+        &self,
+        id: String,
+        input: R,
+        len: Option<u64>,
+    ) -> Result<()> {
+        self.inner.put(id, input, len).await
+    }
+
+    async fn get_into<W: AsyncWrite + Send + Sync + Unpin>(
+        &self,
+        id: &String,
+        mut output: W,
+    ) -> Result<u64> {
+        let data = self.inner.get_bytes(id)?;
+        match self.fail_after {
+            Some(n) => {
+                output.write_all(&data[..n]).await?;
+                Err(Error::Generic("download interrupted".to_string()))
+            }
+            None => {
+                output.write_all(&data).await?;
+                Ok(data.len() as u64)
+            }
+        }
+    }
+
+    async fn delete(&self, id: &String) -> Result<()> {
+        self.inner.delete(id).await
+    }
+
+    async fn list(&self, prefix: Option<&String>) -> Result<BoxStream<'_, Result<String>>> {
+        self.inner.list(prefix).await
+    }
+}
+
+#[tokio::test]
+async fn test_get_reader_default_streams() {
+    let storage = PushOnly::default();
+    let data: Vec<u8> = (0..500_000u32).map(|i| (i % 249) as u8).collect();
+    storage
+        .inner
+        .put_bytes("big.bin".to_string(), &data)
+        .await
+        .unwrap();
+
+    let mut reader = storage.get_reader(&"big.bin".to_string()).await.unwrap();
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output).await.unwrap();
+    assert_eq!(output, data);
+
+    // Errors opening the item are returned before any read
+    assert!(matches!(
+        storage.get_reader(&"missing".to_string()).await,
+        Err(Error::NotFound(_))
+    ));
+
+    // Dropping an unread reader abandons the download
+    drop(storage.get_reader(&"big.bin".to_string()).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_get_reader_default_reports_error_after_data() {
+    let storage = PushOnly {
+        fail_after: Some(100_000),
+        ..Default::default()
+    };
+    storage
+        .inner
+        .put_bytes("file.bin".to_string(), &[7u8; 200_000])
+        .await
+        .unwrap();
+
+    // Data arrived before the failure, so opening succeeds
+    let mut reader = storage.get_reader(&"file.bin".to_string()).await.unwrap();
+    let mut output = Vec::new();
+    let error = reader.read_to_end(&mut output).await.unwrap_err();
+
+    assert!(matches!(Error::from_io(error), Error::Generic(_)));
+    assert_eq!(output.len(), 100_000);
+
+    // get_bytes recovers the original error too
+    assert!(matches!(
+        storage.get_bytes(&"file.bin".to_string()).await,
+        Err(Error::Generic(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_get_reader_feeds_put() {
+    let source = MemoryStorage::new();
+    let dest = MemoryStorage::new();
+    source
+        .put_bytes("file.txt".to_string(), b"piped")
+        .await
+        .unwrap();
+
+    let reader = source.get_reader(&"file.txt".to_string()).await.unwrap();
+    dest.put("file.txt".to_string(), reader, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        dest.get_string(&"file.txt".to_string()).await.unwrap(),
+        "piped"
+    );
+}
+
+#[tokio::test]
+async fn test_get_reader_falls_back() {
+    let primary = MemoryStorage::new();
+    let secondary = MemoryStorage::new();
+    secondary
+        .put_bytes("only-secondary.txt".to_string(), b"backup")
+        .await
+        .unwrap();
+    let storage = FallbackStorage::new(primary, secondary);
+
+    let mut reader = storage
+        .get_reader(&"only-secondary.txt".to_string())
+        .await
+        .unwrap();
+    let mut output = String::new();
+    reader.read_to_string(&mut output).await.unwrap();
+    assert_eq!(output, "backup");
+}
@@ -3,7 +3,42 @@
 //! This module provides generic test functions that can be called from any
 //! storage adapter's test file to avoid code duplication.
 
-use stowage::{Error, Storage, StorageExt};
+#![allow(dead_code)]
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use stowage::{ContinuationToken, Error, PutOptions, Storage, StorageExt};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// Reader that generates `len` bytes of a repeating pattern without holding
+/// them in memory.
+pub struct PatternReader {
+    remaining: u64,
+}
+
+impl PatternReader {
+    pub fn new(len: u64) -> Self {
+        Self { remaining: len }
+    }
+}
+
+impl AsyncRead for PatternReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let n = (buf.remaining() as u64).min(self.remaining) as usize;
+        let offset = self.remaining;
+        let chunk = buf.initialize_unfilled_to(n);
+        for (i, b) in chunk.iter_mut().enumerate() {
+            *b = (offset.wrapping_sub(i as u64) % 251) as u8;
+        }
+        buf.advance(n);
+        self.remaining -= n as u64;
+        Poll::Ready(Ok(()))
+    }
+}
 
 /// Run all common storage tests
 pub async fn run_all_tests<S, F, Fut>(mut setup: F)
@@ -23,9 +58,22 @@ where
     test_large_data(&mut setup).await;
     test_binary_data(&mut setup).await;
     test_get_into(&mut setup).await;
+    test_get_reader(&mut setup).await;
     test_folder_exists(&mut setup).await;
     test_folder_exists_nested(&mut setup).await;
     test_special_characters(&mut setup).await;
+    test_stat(&mut setup).await;
+    test_get_range(&mut setup).await;
+    test_copy(&mut setup).await;
+    test_rename(&mut setup).await;
+    test_put_with_preconditions(&mut setup).await;
+    test_put_with_attributes(&mut setup).await;
+    test_list_dir(&mut setup).await;
+    test_list_with_meta(&mut setup).await;
+    test_list_page(&mut setup).await;
+    test_delete_many(&mut setup).await;
+    test_delete_prefix(&mut setup).await;
+    test_delete_folder(&mut setup).await;
 }
 
 pub async fn test_put_and_exists<S, F, Fut>(setup: &mut F)
@@ -204,6 +252,33 @@ where
     assert_eq!(output, data);
 }
 
+pub async fn test_get_reader<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    use tokio::io::AsyncReadExt;
+
+    let storage = setup().await;
+    let id = S::Id::from("reader.bin".to_string());
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 253) as u8).collect();
+
+    storage.put_bytes(id.clone(), &data).await.unwrap();
+
+    let mut reader = storage.get_reader(&id).await.unwrap();
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output).await.unwrap();
+    assert_eq!(output, data);
+
+    let missing = S::Id::from("missing.bin".to_string());
+    assert!(matches!(
+        storage.get_reader(&missing).await,
+        Err(Error::NotFound(_))
+    ));
+}
+
 pub async fn test_folder_exists<S, F, Fut>(setup: &mut F)
 where
     S: Storage,
@@ -309,3 +384,758 @@ where
         assert_eq!(data, b"data");
     }
 }
+
+pub async fn test_stat<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let id = S::Id::from("stat.txt".to_string());
+
+    match storage.stat(&id).await {
+        Err(Error::NotFound(_)) => {}
+        other => panic!("Expected NotFound for missing item, got: {:?}", other),
+    }
+
+    storage.put_bytes(id.clone(), b"hello world").await.unwrap();
+
+    let meta = storage.stat(&id).await.unwrap();
+    assert_eq!(meta.size, 11);
+    assert!(meta.last_modified.is_some());
+
+    // Overwriting with different content must be reflected in size and etag.
+    storage.put_bytes(id.clone(), b"hello").await.unwrap();
+    let updated = storage.stat(&id).await.unwrap();
+    assert_eq!(updated.size, 5);
+    if meta.etag.is_some() {
+        assert_ne!(meta.etag, updated.etag);
+    }
+}
+
+pub async fn test_get_range<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let id = S::Id::from("range.bin".to_string());
+    let data: Vec<u8> = (0..100).collect();
+
+    storage.put_bytes(id.clone(), &data).await.unwrap();
+
+    // Range inside the item
+    let mut buf = Vec::new();
+    let n = storage.get_range(&id, 10..20, &mut buf).await.unwrap();
+    assert_eq!(n, 10);
+    assert_eq!(buf, &data[10..20]);
+
+    // Range running past the end is truncated
+    let mut buf = Vec::new();
+    let n = storage.get_range(&id, 90..200, &mut buf).await.unwrap();
+    assert_eq!(n, 10);
+    assert_eq!(buf, &data[90..]);
+
+    // Range entirely past the end and empty ranges yield nothing
+    let mut buf = Vec::new();
+    assert_eq!(storage.get_range(&id, 200..300, &mut buf).await.unwrap(), 0);
+    assert_eq!(storage.get_range(&id, 5..5, &mut buf).await.unwrap(), 0);
+    assert!(buf.is_empty());
+
+    // Missing items are reported as NotFound
+    let missing = S::Id::from("missing.bin".to_string());
+    let result = storage.get_range(&missing, 0..10, &mut Vec::new()).await;
+    match result {
+        Err(Error::NotFound(_)) | Err(Error::Connection(_)) => {}
+        other => panic!("Expected NotFound or Connection error, got: {:?}", other),
+    }
+}
+
+pub async fn test_copy<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let src = S::Id::from("copy/src.txt".to_string());
+    let dst = S::Id::from("copy/nested/dst.txt".to_string());
+
+    storage
+        .put_bytes(src.clone(), b"copied data")
+        .await
+        .unwrap();
+    storage.put_bytes(dst.clone(), b"old").await.unwrap();
+
+    // Copy replaces the destination and keeps the source
+    storage.copy(&src, dst.clone()).await.unwrap();
+    assert_eq!(storage.get_bytes(&src).await.unwrap(), b"copied data");
+    assert_eq!(storage.get_bytes(&dst).await.unwrap(), b"copied data");
+
+    let missing = S::Id::from("copy/missing.txt".to_string());
+    match storage.copy(&missing, dst.clone()).await {
+        Err(Error::NotFound(_)) => {}
+        other => panic!("Expected NotFound for missing source, got: {:?}", other),
+    }
+    // A failed copy leaves the destination untouched
+    assert_eq!(storage.get_bytes(&dst).await.unwrap(), b"copied data");
+}
+
+pub async fn test_rename<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let src = S::Id::from("rename/src.txt".to_string());
+    let dst = S::Id::from("rename/nested/dst.txt".to_string());
+
+    storage.put_bytes(src.clone(), b"moved data").await.unwrap();
+    storage.put_bytes(dst.clone(), b"old").await.unwrap();
+
+    // Rename replaces the destination and removes the source
+    storage.rename(&src, dst.clone()).await.unwrap();
+    assert!(!storage.exists(&src).await.unwrap());
+    assert_eq!(storage.get_bytes(&dst).await.unwrap(), b"moved data");
+
+    match storage.rename(&src, dst.clone()).await {
+        Err(Error::NotFound(_)) => {}
+        other => panic!("Expected NotFound for missing source, got: {:?}", other),
+    }
+    assert_eq!(storage.get_bytes(&dst).await.unwrap(), b"moved data");
+}
+
+pub async fn test_put_with_preconditions<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let id = S::Id::from("conditional.txt".to_string());
+
+    // Create-only succeeds once, then fails without touching the item
+    storage
+        .put_with(
+            id.clone(),
+            &b"v1"[..],
+            Some(2),
+            PutOptions::new().if_none_match("*"),
+        )
+        .await
+        .unwrap();
+    match storage
+        .put_with(
+            id.clone(),
+            &b"v2"[..],
+            Some(2),
+            PutOptions::new().if_none_match("*"),
+        )
+        .await
+    {
+        Err(Error::PreconditionFailed(_)) => {}
+        other => panic!("Expected PreconditionFailed, got: {:?}", other),
+    }
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), b"v1");
+
+    let etag = match storage.stat(&id).await.unwrap().etag {
+        Some(etag) => etag,
+        None => return,
+    };
+
+    // A stale ETag is rejected
+    match storage
+        .put_with(
+            id.clone(),
+            &b"v2"[..],
+            Some(2),
+            PutOptions::new().if_match("stale"),
+        )
+        .await
+    {
+        Err(Error::PreconditionFailed(_)) => {}
+        other => panic!("Expected PreconditionFailed, got: {:?}", other),
+    }
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), b"v1");
+
+    // The current ETag is accepted
+    storage
+        .put_with(
+            id.clone(),
+            &b"v2!"[..],
+            Some(3),
+            PutOptions::new().if_match(etag),
+        )
+        .await
+        .unwrap();
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), b"v2!");
+
+    // if_match on a missing item fails
+    let missing = S::Id::from("conditional-missing.txt".to_string());
+    match storage
+        .put_with(
+            missing.clone(),
+            &b"x"[..],
+            Some(1),
+            PutOptions::new().if_match("*"),
+        )
+        .await
+    {
+        Err(Error::PreconditionFailed(_)) => {}
+        other => panic!("Expected PreconditionFailed, got: {:?}", other),
+    }
+    assert!(!storage.exists(&missing).await.unwrap());
+}
+
+pub async fn test_put_with_attributes<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let id = S::Id::from("attributes.html".to_string());
+
+    storage
+        .put_with(
+            id.clone(),
+            &b"<p>hello</p>"[..],
+            Some(12),
+            PutOptions::new()
+                .content_type("text/html")
+                .cache_control("max-age=60")
+                .content_disposition("inline")
+                .user_metadata("owner", "tests"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(storage.get_bytes(&id).await.unwrap(), b"<p>hello</p>");
+
+    let meta = storage.stat(&id).await.unwrap();
+    assert_eq!(meta.content_type.as_deref(), Some("text/html"));
+    assert_eq!(meta.cache_control.as_deref(), Some("max-age=60"));
+    assert_eq!(meta.content_disposition.as_deref(), Some("inline"));
+    assert_eq!(
+        meta.metadata.get("owner").map(String::as_str),
+        Some("tests")
+    );
+
+    // Copies keep the attributes
+    let copy = S::Id::from("attributes-copy.html".to_string());
+    storage.copy(&id, copy.clone()).await.unwrap();
+    let meta = storage.stat(&copy).await.unwrap();
+    assert_eq!(meta.content_type.as_deref(), Some("text/html"));
+    assert_eq!(
+        meta.metadata.get("owner").map(String::as_str),
+        Some("tests")
+    );
+
+    // A plain put replaces the item along with its attributes
+    storage.put_bytes(id.clone(), b"plain").await.unwrap();
+    let meta = storage.stat(&id).await.unwrap();
+    assert_ne!(meta.content_type.as_deref(), Some("text/html"));
+    assert!(meta.cache_control.is_none());
+    assert!(meta.metadata.is_empty());
+}
+
+pub async fn test_list_dir<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug + PartialEq,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let ids = |names: &[&str]| -> Vec<S::Id> {
+        names.iter().map(|n| S::Id::from(n.to_string())).collect()
+    };
+
+    for name in [
+        "a.txt",
+        "dir/b.txt",
+        "dir/sub/c.txt",
+        "dir/sub/d.txt",
+        "other/e.txt",
+    ] {
+        storage
+            .put_bytes(S::Id::from(name.to_string()), b"data")
+            .await
+            .unwrap();
+    }
+
+    let root = storage.list_dir(None).await.unwrap();
+    assert_eq!(root.files, ids(&["a.txt"]));
+    assert_eq!(root.prefixes, ids(&["dir", "other"]));
+
+    let dir = storage
+        .list_dir(Some(&S::Id::from("dir".to_string())))
+        .await
+        .unwrap();
+    assert_eq!(dir.files, ids(&["dir/b.txt"]));
+    assert_eq!(dir.prefixes, ids(&["dir/sub"]));
+
+    // A trailing slash names the same folder
+    let with_slash = storage
+        .list_dir(Some(&S::Id::from("dir/".to_string())))
+        .await
+        .unwrap();
+    assert_eq!(with_slash, dir);
+
+    let sub = storage.list_dir(Some(&dir.prefixes[0])).await.unwrap();
+    assert_eq!(sub.files, ids(&["dir/sub/c.txt", "dir/sub/d.txt"]));
+    assert!(sub.prefixes.is_empty());
+
+    let missing = storage
+        .list_dir(Some(&S::Id::from("missing".to_string())))
+        .await
+        .unwrap();
+    assert!(missing.files.is_empty());
+    assert!(missing.prefixes.is_empty());
+}
+
+pub async fn test_list_with_meta<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug + PartialEq,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    use futures::StreamExt;
+
+    let storage = setup().await;
+    let a = S::Id::from("meta/a.txt".to_string());
+    let b = S::Id::from("meta/b.txt".to_string());
+    storage.put_bytes(a.clone(), b"hello").await.unwrap();
+    storage.put_bytes(b.clone(), b"abc").await.unwrap();
+
+    let prefix = S::Id::from("meta/".to_string());
+    let mut entries: Vec<_> = storage
+        .list_with_meta(Some(&prefix))
+        .await
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect()
+        .await;
+    entries.sort_by_key(|entry| format!("{:?}", entry.id));
+
+    assert_eq!(entries.len(), 2);
+    for (entry, id, size) in [(&entries[0], &a, 5), (&entries[1], &b, 3)] {
+        assert_eq!(&entry.id, id);
+        assert_eq!(entry.size, size);
+        assert!(!entry.is_dir);
+
+        // The listing reports the same metadata as stat
+        let meta = storage.stat(id).await.unwrap();
+        assert_eq!(entry.etag, meta.etag);
+        assert_eq!(entry.modified.is_some(), meta.last_modified.is_some());
+    }
+}
+
+pub async fn test_list_page<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug + PartialEq,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let names = [
+        "page/a.txt",
+        "page/b.txt",
+        "page/c/d.txt",
+        "page/c/e.txt",
+        "page/c/f/g.txt",
+        "page/h.txt",
+        "page/i.txt",
+    ];
+    for name in names {
+        storage
+            .put_bytes(S::Id::from(name.to_string()), b"data")
+            .await
+            .unwrap();
+    }
+    storage
+        .put_bytes(S::Id::from("other.txt".to_string()), b"data")
+        .await
+        .unwrap();
+
+    let prefix = S::Id::from("page/".to_string());
+    let mut seen = Vec::new();
+    let mut continuation = None;
+    loop {
+        let page = storage
+            .list_page(Some(&prefix), 3, continuation)
+            .await
+            .unwrap();
+        assert!(page.ids.len() <= 3);
+        seen.extend(page.ids);
+        match page.next {
+            // Tokens round-trip through their string form, as when resuming
+            // from a checkpoint
+            Some(token) => continuation = Some(ContinuationToken::new(token.as_str())),
+            None => break,
+        }
+    }
+
+    let mut seen: Vec<String> = seen.iter().map(|id| format!("{id:?}")).collect();
+    seen.sort();
+    let mut expected: Vec<String> = names
+        .iter()
+        .map(|n| format!("{:?}", S::Id::from(n.to_string())))
+        .collect();
+    expected.sort();
+    assert_eq!(seen, expected);
+
+    // A page size of zero still makes progress
+    let page = storage.list_page(Some(&prefix), 0, None).await.unwrap();
+    assert_eq!(page.ids.len(), 1);
+    assert!(page.next.is_some());
+
+    let empty = storage
+        .list_page(Some(&S::Id::from("missing/".to_string())), 3, None)
+        .await
+        .unwrap();
+    assert!(empty.ids.is_empty());
+    assert!(empty.next.is_none());
+}
+
+pub async fn test_delete_many<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug + PartialEq,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let ids: Vec<S::Id> = (0..25)
+        .map(|i| S::Id::from(format!("batch/item-{i:02}.txt")))
+        .collect();
+    for id in &ids {
+        storage.put_bytes(id.clone(), b"data").await.unwrap();
+    }
+    let keep = S::Id::from("batch-keep.txt".to_string());
+    storage.put_bytes(keep.clone(), b"keep").await.unwrap();
+
+    // Deleting a missing item counts as success, like delete
+    let mut request = ids.clone();
+    request.push(S::Id::from("batch/missing.txt".to_string()));
+
+    let result = storage.delete_many(request).await.unwrap();
+    assert!(result.is_complete(), "{:?}", result.failed);
+    assert_eq!(result.success_count(), 26);
+
+    for id in &ids {
+        assert!(!storage.exists(id).await.unwrap());
+    }
+    assert!(storage.exists(&keep).await.unwrap());
+
+    let empty = storage.delete_many(Vec::new()).await.unwrap();
+    assert_eq!(empty.success_count(), 0);
+    assert!(empty.is_complete());
+
+    storage.delete(&keep).await.unwrap();
+}
+
+pub async fn test_delete_prefix<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug + PartialEq,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let doomed = ["purge/a.txt", "purge/b.txt", "purge/nested/c.txt"];
+    for name in doomed {
+        storage
+            .put_bytes(S::Id::from(name.to_string()), b"data")
+            .await
+            .unwrap();
+    }
+    let keep = S::Id::from("purge-keep/d.txt".to_string());
+    storage.put_bytes(keep.clone(), b"keep").await.unwrap();
+
+    let result = storage
+        .delete_prefix(&S::Id::from("purge/".to_string()))
+        .await
+        .unwrap();
+    assert!(result.is_complete(), "{:?}", result.failed);
+    assert_eq!(result.success_count(), doomed.len());
+
+    for name in doomed {
+        assert!(
+            !storage
+                .exists(&S::Id::from(name.to_string()))
+                .await
+                .unwrap()
+        );
+    }
+    assert!(storage.exists(&keep).await.unwrap());
+
+    storage.delete(&keep).await.unwrap();
+}
+
+pub async fn test_delete_folder<S, F, Fut>(setup: &mut F)
+where
+    S: Storage,
+    S::Id: From<String> + std::fmt::Debug + PartialEq,
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = S>,
+{
+    let storage = setup().await;
+    let doomed = [
+        "tenant/a.txt",
+        "tenant/sub/b.txt",
+        "tenant/sub/deeper/c.txt",
+    ];
+    for name in doomed {
+        storage
+            .put_bytes(S::Id::from(name.to_string()), b"data")
+            .await
+            .unwrap();
+    }
+    let keep = S::Id::from("tenant-other/d.txt".to_string());
+    storage.put_bytes(keep.clone(), b"keep").await.unwrap();
+
+    let folder = S::Id::from("tenant".to_string());
+    storage.delete_folder(&folder).await.unwrap();
+
+    for name in doomed {
+        assert!(
+            !storage
+                .exists(&S::Id::from(name.to_string()))
+                .await
+                .unwrap()
+        );
+    }
+    assert!(!storage.folder_exists(&folder).await.unwrap());
+    assert!(storage.exists(&keep).await.unwrap());
+
+    // Idempotent, like delete
+    storage.delete_folder(&folder).await.unwrap();
+
+    // The root is never deleted by accident
+    for root in ["", "/"] {
+        assert!(matches!(
+            storage.delete_folder(&S::Id::from(root.to_string())).await,
+            Err(Error::InvalidId(_))
+        ));
+    }
+    assert!(storage.exists(&keep).await.unwrap());
+
+    storage.delete(&keep).await.unwrap();
+}
+
+/// A request received by a [`MockServer`].
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    pub method: String,
+    /// Path and query, e.g. `/upload?part=1`.
+    pub target: String,
+    /// Headers with lowercase names.
+    pub headers: Vec<(String, String)>,
+    /// The body, or its first [`MockServer::MAX_KEPT_BODY`] bytes.
+    pub body: Vec<u8>,
+    /// Full length of the body.
+    pub body_len: u64,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Base URL of the server the request was sent to.
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.header("host").unwrap_or_default())
+    }
+}
+
+/// A response for a [`MockServer`] to send.
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn json(status: u16, value: serde_json::Value) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: value.to_string().into_bytes(),
+        }
+    }
+}
+
+/// Minimal HTTP/1.1 server for exercising the HTTP adapters offline.
+///
+/// Every request is answered by the handler and recorded. Large bodies are
+/// counted but only their start is kept, so the server itself does not
+/// distort memory measurements.
+pub struct MockServer {
+    url: String,
+    requests: std::sync::Arc<std::sync::Mutex<Vec<MockRequest>>>,
+}
+
+impl MockServer {
+    pub const MAX_KEPT_BODY: usize = 1024 * 1024;
+
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler = std::sync::Arc::new(handler);
+
+        let recorded = std::sync::Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let handler = std::sync::Arc::clone(&handler);
+                let recorded = std::sync::Arc::clone(&recorded);
+                tokio::spawn(async move {
+                    let _ = Self::serve(socket, handler.as_ref(), &recorded).await;
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    async fn serve<F>(
+        socket: tokio::net::TcpStream,
+        handler: &F,
+        recorded: &std::sync::Mutex<Vec<MockRequest>>,
+    ) -> std::io::Result<()>
+    where
+        F: Fn(&MockRequest) -> MockResponse,
+    {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+        let mut socket = tokio::io::BufReader::new(socket);
+        loop {
+            let mut line = String::new();
+            if socket.read_line(&mut line).await? == 0 {
+                return Ok(());
+            }
+            let mut parts = line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let target = parts.next().unwrap_or_default().to_string();
+
+            let mut headers = Vec::new();
+            loop {
+                line.clear();
+                socket.read_line(&mut line).await?;
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+                }
+            }
+
+            let mut request = MockRequest {
+                method,
+                target,
+                headers,
+                body: Vec::new(),
+                body_len: 0,
+            };
+            if request.header("expect") == Some("100-continue") {
+                socket
+                    .get_mut()
+                    .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+                    .await?;
+            }
+            if request.header("transfer-encoding") == Some("chunked") {
+                loop {
+                    line.clear();
+                    socket.read_line(&mut line).await?;
+                    let size = u64::from_str_radix(line.trim(), 16).unwrap_or(0);
+                    Self::read_body(&mut socket, size, &mut request).await?;
+                    line.clear();
+                    socket.read_line(&mut line).await?;
+                    if size == 0 {
+                        break;
+                    }
+                }
+            } else {
+                let size = request
+                    .header("content-length")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0);
+                Self::read_body(&mut socket, size, &mut request).await?;
+            }
+
+            let response = handler(&request);
+            recorded.lock().unwrap().push(request);
+
+            let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+            for (name, value) in &response.headers {
+                head.push_str(&format!("{name}: {value}\r\n"));
+            }
+            head.push_str(&format!("content-length: {}\r\n\r\n", response.body.len()));
+            let stream = socket.get_mut();
+            stream.write_all(head.as_bytes()).await?;
+            stream.write_all(&response.body).await?;
+            stream.flush().await?;
+        }
+    }
+
+    async fn read_body<R>(
+        input: &mut R,
+        size: u64,
+        request: &mut MockRequest,
+    ) -> std::io::Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        use tokio::io::AsyncReadExt;
+
+        let mut remaining = size;
+        let mut buf = vec![0; 64 * 1024];
+        while remaining > 0 {
+            let want = buf.len().min(remaining as usize);
+            let n = input.read(&mut buf[..want]).await?;
+            if n == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            let keep = n.min(Self::MAX_KEPT_BODY.saturating_sub(request.body.len()));
+            request.body.extend_from_slice(&buf[..keep]);
+            remaining -= n as u64;
+        }
+        request.body_len += size;
+        Ok(())
+    }
+}